@@ -0,0 +1,369 @@
+
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::cell::RefCell;
+
+use serde::de::DeserializeOwned;
+
+use local_registry::Registry;
+use provider::Provider;
+use logic::context::Context;
+use logic::facade::default::{ConcurrentFacade, DefaultFacade};
+use json_registry::JSONRegistry;
+use sqlite_registry::SQLiteRegistry;
+
+use crate::{Config, RegistryConfig, Settings, Error, Result, expand_path};
+
+pub type ProviderFactory = fn(&str, &Settings) -> Result<Box<dyn Provider>>;
+pub type RegistryFactory = fn(&RegistryConfig) -> Result<Box<dyn Registry>>;
+
+pub struct Factory {
+    providers: HashMap<String, ProviderFactory>,
+    registries: HashMap<String, RegistryFactory>,
+}
+
+// Settings are kept as a raw map, so every implementation decides itself
+// what it expects
+pub fn settings<T: DeserializeOwned>(name: &str, settings: &Settings) -> Result<T> {
+    serde_json::from_value(serde_json::Value::Object(settings.clone()))
+        .map_err(|err| Error::InvalidSettings(name.to_string(), Box::new(err)))
+}
+
+fn mock_provider(name: &str, raw: &Settings) -> Result<Box<dyn Provider>> {
+    settings::<mock_provider::Settings>(name, raw)?
+        .build(name)
+        .map(|provider| Box::new(provider) as Box<dyn Provider>)
+        .map_err(|err| Error::InvalidSettings(name.to_string(), Box::new(err)))
+}
+
+fn hue_provider(name: &str, raw: &Settings) -> Result<Box<dyn Provider>> {
+    settings::<hue_provider::Settings>(name, raw)
+        .map(|settings| Box::new(settings.build(name)) as Box<dyn Provider>)
+}
+
+fn yeelight_provider(name: &str, raw: &Settings) -> Result<Box<dyn Provider>> {
+    settings::<yeelight_provider::Settings>(name, raw)
+        .map(|settings| Box::new(settings.build(name)) as Box<dyn Provider>)
+}
+
+fn mqtt_provider(name: &str, raw: &Settings) -> Result<Box<dyn Provider>> {
+    settings::<mqtt_provider::Settings>(name, raw)
+        .map(|settings| Box::new(settings.build(name)) as Box<dyn Provider>)
+}
+
+fn json_registry(config: &RegistryConfig) -> Result<Box<dyn Registry>> {
+    let settings = settings::<json_registry::Settings>("registry",
+                                                       &config.settings)?;
+    let registry = JSONRegistry::new(expand_path(&config.path));
+
+    Ok(Box::new(match settings.revisions {
+        Some(revisions) => registry.with_revisions(revisions),
+        None => registry,
+    }))
+}
+
+fn sqlite_registry(config: &RegistryConfig) -> Result<Box<dyn Registry>> {
+    let settings = settings::<sqlite_registry::Settings>("registry",
+                                                         &config.settings)?;
+    let registry = SQLiteRegistry::open(expand_path(&config.path))
+        .map_err(Error::Registry)?;
+
+    Ok(Box::new(match settings.revisions {
+        Some(revisions) => registry.with_revisions(revisions),
+        None => registry,
+    }))
+}
+
+impl Default for Factory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Factory {
+    pub fn empty() -> Self {
+        Self {
+            providers: HashMap::new(),
+            registries: HashMap::new(),
+        }
+    }
+
+    pub fn new() -> Self {
+        Self::empty()
+            .with_provider("mock", mock_provider)
+            .with_provider("hue", hue_provider)
+            .with_provider("yeelight", yeelight_provider)
+            .with_provider("zigbee2mqtt", mqtt_provider)
+            .with_registry("json", json_registry)
+            .with_registry("sqlite", sqlite_registry)
+    }
+
+    pub fn with_provider(mut self: Self, kind: &str,
+                         factory: ProviderFactory) -> Self {
+        self.providers.insert(kind.to_string(), factory);
+        self
+    }
+
+    pub fn with_registry(mut self: Self, backend: &str,
+                         factory: RegistryFactory) -> Self {
+        self.registries.insert(backend.to_string(), factory);
+        self
+    }
+
+    pub fn registry(self: &Self, config: &RegistryConfig) -> Result<Box<dyn Registry>> {
+        match self.registries.get(&config.backend) {
+            Some(factory) => factory(config),
+            None => Err(Error::UnknownRegistry(config.backend.clone())),
+        }
+    }
+
+    pub fn providers(self: &Self, config: &Config) -> Result<Vec<Box<dyn Provider>>> {
+        let mut out: Vec<Box<dyn Provider>> = Vec::new();
+
+        for (name, provider) in config.providers.iter() {
+            if !provider.enabled {
+                continue;
+            }
+
+            let kind = provider.kind(name);
+            let factory = self.providers.get(kind).ok_or_else(|| {
+                Error::UnknownProvider(name.clone(), kind.to_string())
+            })?;
+            let created = factory(name, &provider.settings)?;
+
+            if out.iter().any(|item| item.name() == created.name()) {
+                return Err(Error::DuplicateProvider(created.name().to_string()));
+            }
+
+            out.push(created);
+        }
+
+        Ok(out)
+    }
+
+    pub fn context(self: &Self, config: &Config) -> Result<Context> {
+        Ok(Context::new(self.providers(config)?,
+                        self.registry(&config.registry)?))
+    }
+
+    pub fn facade(self: &Self, config: &Config) -> Result<DefaultFacade> {
+        self.context(config)
+            .map(|context| DefaultFacade::new(Rc::new(RefCell::new(context))))
+    }
+
+    pub fn concurrent_facade(
+        self: &Self,
+        config: &Config
+    ) -> Result<ConcurrentFacade> {
+        self.context(config).map(ConcurrentFacade::shared)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::light::Light;
+
+    struct Dummy(String);
+
+    impl Provider for Dummy {
+        fn name(self: &Self) -> &str {
+            &self.0
+        }
+
+        fn list(self: &Self) -> provider::Result<Vec<Light>> {
+            Ok(Vec::new())
+        }
+
+        fn get(self: &Self, id: &str) -> provider::Result<Light> {
+            provider::Error::not_found(self.name(), id)
+        }
+
+        fn sync(self: &Self, light: &Light) -> provider::Result<()> {
+            provider::Error::foreign_light(self.name(), light)
+        }
+    }
+
+    #[derive(serde::Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct DummySettings {
+        #[serde(default)]
+        suffix: String,
+    }
+
+    fn dummy(name: &str, raw: &Settings) -> Result<Box<dyn Provider>> {
+        let parsed: DummySettings = settings(name, raw)?;
+        Ok(Box::new(Dummy(format!("{}{}", name, parsed.suffix))))
+    }
+
+    fn factory() -> Factory {
+        Factory::new().with_provider("dummy", dummy)
+    }
+
+    fn config(content: &str) -> Config {
+        Config::parse(content, false).expect("Correct config")
+    }
+
+    #[test]
+    fn providers() {
+        let providers = factory().providers(&config(r#"
+            [providers.first]
+            kind = "dummy"
+
+            [providers.second]
+            kind = "dummy"
+            suffix = "_x"
+
+            [providers.third]
+            kind = "dummy"
+            enabled = false
+        "#)).expect("Created");
+
+        let names: Vec<&str> = providers.iter().map(|p| p.name()).collect();
+        assert_eq!(names, vec!["first", "second_x"]);
+    }
+
+    #[test]
+    fn unknown_provider() {
+        let result = factory().providers(&config("[providers.lifx]"));
+
+        assert!(matches!(result,
+                         Err(Error::UnknownProvider(name, kind))
+                         if name == "lifx" && kind == "lifx"));
+    }
+
+    #[test]
+    fn invalid_settings() {
+        let result = factory().providers(&config(r#"
+            [providers.dummy]
+            suffix = 12
+        "#));
+
+        assert!(matches!(result, Err(Error::InvalidSettings(..))));
+    }
+
+    #[test]
+    fn duplicate_provider() {
+        let result = factory().providers(&config(r#"
+            [providers.a]
+            kind = "dummy"
+            suffix = "b"
+
+            [providers.ab]
+            kind = "dummy"
+        "#));
+
+        assert!(matches!(result, Err(Error::DuplicateProvider(name))
+                                 if name == "ab"));
+    }
+
+    #[test]
+    fn builtin_mock() {
+        let providers = Factory::new().providers(&config(r#"
+            [providers.virtual]
+            kind = "mock"
+
+            [[providers.virtual.lights]]
+            id = "1"
+            capabilities = ["Brightness"]
+            brightness = 0.5
+        "#)).expect("Created");
+
+        let light = providers[0].get("1").expect("Exists");
+        assert_eq!(light.provider.name, "virtual");
+    }
+
+    #[test]
+    fn builtin_hue() {
+        let providers = Factory::new().providers(&config(r#"
+            [providers.kitchen]
+            kind = "hue"
+            address = "192.168.1.2"
+            username = "user"
+        "#)).expect("Created");
+
+        assert_eq!(providers[0].name(), "kitchen");
+        assert!(matches!(Factory::new().providers(&config("[providers.hue]")),
+                         Err(Error::InvalidSettings(..))));
+    }
+
+    #[test]
+    fn builtin_yeelight() {
+        let providers = Factory::new().providers(&config(r#"
+            [providers.bedroom]
+            kind = "yeelight"
+
+            [[providers.bedroom.lights]]
+            id = "ceiling"
+            address = "192.168.1.5"
+        "#)).expect("Created");
+
+        assert_eq!(providers[0].name(), "bedroom");
+    }
+
+    #[test]
+    fn builtin_zigbee2mqtt() {
+        let providers = Factory::new().providers(&config(r#"
+            [providers.zigbee]
+            kind = "zigbee2mqtt"
+            host = "localhost"
+        "#)).expect("Created");
+
+        assert_eq!(providers[0].name(), "zigbee");
+    }
+
+    #[test]
+    fn unknown_registry() {
+        let result = factory().registry(&config(r#"
+            [registry]
+            backend = "nosql"
+        "#).registry);
+
+        assert!(matches!(result, Err(Error::UnknownRegistry(_))));
+    }
+
+    #[test]
+    fn registry_revisions() {
+        let registry = config(r#"
+            [registry]
+            revisions = 3
+        "#).registry;
+        assert!(factory().registry(&registry).is_ok());
+
+        let result = factory().registry(&config(r#"
+            [registry]
+            revisions = "all"
+        "#).registry);
+        assert!(matches!(result, Err(Error::InvalidSettings(..))));
+    }
+
+    #[test]
+    fn sqlite_backend() {
+        let dir = tempfile::tempdir().expect("Temporary directory");
+        let mut registry = config(r#"
+            [registry]
+            backend = "sqlite"
+            revisions = 3
+        "#).registry;
+        registry.path = dir.path().join("registry");
+
+        let registry = factory().registry(&registry).expect("Created");
+        assert_eq!(registry.name(), "sqlite");
+        assert!(dir.path().join("registry")
+                .join(sqlite_registry::FILE).is_file());
+    }
+
+    #[test]
+    fn context() {
+        let dir = tempfile::tempdir().expect("Temporary directory");
+        let mut config = config(r#"
+            [providers.dummy]
+        "#);
+        config.registry.path = dir.path().to_path_buf();
+
+        let context = factory().context(&config).expect("Created");
+
+        assert!(context.get_provider_by_name("dummy").is_some());
+        assert_eq!(context.registry.name(), "json");
+    }
+}
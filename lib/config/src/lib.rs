@@ -0,0 +1,264 @@
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Serialize, Deserialize};
+
+pub mod factory;
+
+pub use factory::Factory;
+
+pub type Result<T> = std::result::Result<T, Error>;
+pub type Settings = serde_json::Map<String, serde_json::Value>;
+
+const CONFIG_FILE: &str = "config.toml";
+const APPLICATION: &str = "lighting";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub registry: RegistryConfig,
+    #[serde(default)]
+    pub providers: BTreeMap<String, ProviderConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryConfig {
+    #[serde(default = "RegistryConfig::default_backend")]
+    pub backend: String,
+    #[serde(default = "default_data_dir")]
+    pub path: PathBuf,
+    #[serde(flatten)]
+    pub settings: Settings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderConfig {
+    pub kind: Option<String>, // Provider implementation, section name if unset
+    #[serde(default = "ProviderConfig::default_enabled")]
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub settings: Settings,
+}
+
+impl RegistryConfig {
+    fn default_backend() -> String {
+        "json".to_string()
+    }
+}
+
+impl Default for RegistryConfig {
+    fn default() -> Self {
+        Self {
+            backend: Self::default_backend(),
+            path: default_data_dir(),
+            settings: Settings::new(),
+        }
+    }
+}
+
+impl ProviderConfig {
+    fn default_enabled() -> bool {
+        true
+    }
+
+    pub fn kind<'a>(self: &'a Self, name: &'a str) -> &'a str {
+        self.kind.as_deref().unwrap_or(name)
+    }
+}
+
+fn home() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(PathBuf::from)
+}
+
+fn xdg_dir(variable: &str, fallback: &str) -> PathBuf {
+    if let Some(dir) = std::env::var_os(variable) {
+        PathBuf::from(dir).join(APPLICATION)
+    } else if let Some(home) = home() {
+        home.join(fallback).join(APPLICATION)
+    } else {
+        PathBuf::from(APPLICATION)
+    }
+}
+
+pub fn default_data_dir() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME", ".local/share")
+}
+
+pub fn default_path() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", ".config").join(CONFIG_FILE)
+}
+
+pub fn expand_path(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), home()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+impl Config {
+    pub fn parse(content: &str, json: bool) -> std::result::Result<Self, Box<dyn std::error::Error>> {
+        if json {
+            Ok(serde_json::from_str(content)?)
+        } else {
+            Ok(toml::from_str(content)?)
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let json = path.extension().is_some_and(|ext| ext == "json");
+
+        let content = std::fs::read_to_string(path)
+            .map_err(|err| Error::Io(path.to_path_buf(), err))?;
+
+        Self::parse(&content, json)
+            .map_err(|err| Error::Parse(path.to_path_buf(), err))
+    }
+
+    // Missing file at the default location isn't an error, as nothing
+    // was configured yet
+    pub fn load_default() -> Result<Self> {
+        let path = default_path();
+
+        if path.exists() {
+            Self::load(path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, std::io::Error),
+    Parse(PathBuf, Box<dyn std::error::Error>),
+    UnknownProvider(String, String),
+    UnknownRegistry(String),
+    InvalidSettings(String, Box<dyn std::error::Error>),
+    DuplicateProvider(String),
+    Registry(local_registry::Error),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(_, err) => Some(err),
+            Error::Parse(_, err) => Some(err.as_ref()),
+            Error::InvalidSettings(_, err) => Some(err.as_ref()),
+            Error::Registry(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(path, err) => {
+                write!(f, "Can't read config \"{}\": {}", path.display(), err)
+            },
+            Error::Parse(path, err) => {
+                write!(f, "Can't parse config \"{}\": {}", path.display(), err)
+            },
+            Error::UnknownProvider(name, kind) => {
+                write!(f, "Provider \"{}\": unknown kind \"{}\"", name, kind)
+            },
+            Error::UnknownRegistry(backend) => {
+                write!(f, "Unknown registry backend \"{}\"", backend)
+            },
+            Error::InvalidSettings(name, err) => {
+                write!(f, "Invalid settings for \"{}\": {}", name, err)
+            },
+            Error::DuplicateProvider(name) => {
+                write!(f, "Provider named \"{}\" configured twice", name)
+            },
+            Error::Registry(err) => err.fmt(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty() {
+        let config = Config::parse("", false).expect("Empty config is valid");
+
+        assert_eq!(config.registry.backend, "json");
+        assert!(config.providers.is_empty());
+    }
+
+    #[test]
+    fn toml() {
+        let config = Config::parse(r#"
+            [registry]
+            backend = "json"
+            path = "/tmp/lighting"
+
+            [providers.kitchen]
+            kind = "hue"
+            address = "192.168.1.2"
+
+            [providers.mock]
+            enabled = false
+        "#, false).expect("Correct config");
+
+        assert_eq!(config.registry.path, PathBuf::from("/tmp/lighting"));
+        assert_eq!(config.providers["kitchen"].kind("kitchen"), "hue");
+        assert_eq!(config.providers["kitchen"].settings["address"],
+                   "192.168.1.2");
+        assert!(config.providers["kitchen"].enabled);
+        assert_eq!(config.providers["mock"].kind("mock"), "mock");
+        assert!(!config.providers["mock"].enabled);
+    }
+
+    #[test]
+    fn json() {
+        let config = Config::parse(r#"{
+            "registry": { "path": "/tmp/lighting" },
+            "providers": { "mock": { "lights": [] } }
+        }"#, true).expect("Correct config");
+
+        assert_eq!(config.registry.backend, "json");
+        assert!(config.providers["mock"].settings["lights"].is_array());
+    }
+
+    #[test]
+    fn unknown_section() {
+        assert!(Config::parse("[something]\nvalue = 1", false).is_err());
+    }
+
+    #[test]
+    fn load_by_extension() {
+        let dir = tempfile::tempdir().expect("Temporary directory");
+        let path = dir.path().join("config.json");
+        std::fs::write(&path, r#"{ "registry": { "backend": "test" } }"#)
+            .expect("Written");
+
+        let config = Config::load(&path).expect("Loaded");
+
+        assert_eq!(config.registry.backend, "test");
+    }
+
+    #[test]
+    fn load_missing() {
+        let dir = tempfile::tempdir().expect("Temporary directory");
+
+        assert!(matches!(Config::load(dir.path().join("config.toml")),
+                         Err(Error::Io(..))));
+    }
+
+    #[test]
+    fn expand_home() {
+        if let Some(home) = home() {
+            assert_eq!(expand_path(Path::new("~/lighting")),
+                       home.join("lighting"));
+        }
+
+        assert_eq!(expand_path(Path::new("/lighting")),
+                   PathBuf::from("/lighting"));
+    }
+}
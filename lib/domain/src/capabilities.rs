@@ -1,6 +1,9 @@
 
-#[derive(Debug, Copy, Clone)]
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Capability {
+    Power,
     Color,
     Brightness,
     Mode,
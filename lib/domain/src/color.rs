@@ -6,8 +6,10 @@ use super::misc::Uf64;
 pub mod rgb;
 pub mod temperature;
 pub mod hsv;
+pub mod xy;
+pub mod oklab;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Color { // Default color in XYZ space
     pub x: Uf64,
     pub y: Uf64,
@@ -70,14 +70,14 @@ impl From<RGB> for HSV {
         Self::new(
             hue,
             saturation,
-            max / (u8::max_value() as f64),
+            max / (u8::MAX as f64),
         )
     }
 }
 
 impl From<HSV> for RGB {
     fn from(value: HSV) -> Self {
-        let max = *value.value * (u8::max_value() as f64);
+        let max = *value.value * (u8::MAX as f64);
         let diff = *value.saturation * max;
         let diff6 = 6.0 * diff;
 
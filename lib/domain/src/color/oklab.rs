@@ -0,0 +1,107 @@
+use super::Color;
+
+// Perceptual space by Björn Ottosson, so straight lines in it are seen as
+// even color changes (https://bottosson.github.io/posts/oklab/)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OKLab {
+    pub l: f64,
+    pub a: f64,
+    pub b: f64,
+}
+
+impl OKLab {
+    pub fn new(l: f64, a: f64, b: f64) -> Self {
+        Self {
+            l,
+            a,
+            b,
+        }
+    }
+
+    // Point at t between self (0) and other (1)
+    pub fn mix(self: &Self, other: &Self, t: f64) -> Self {
+        Self::new(self.l + (other.l - self.l) * t,
+                  self.a + (other.a - self.a) * t,
+                  self.b + (other.b - self.b) * t)
+    }
+}
+
+impl From<Color> for OKLab {
+    fn from(value: Color) -> Self {
+        let x: f64 = *value.x;
+        let y: f64 = *value.y;
+        let z: f64 = *value.z;
+
+        // XYZ(D65) to cone response
+        let l = 0.8189330101 * x + 0.3618667424 * y - 0.1288597137 * z;
+        let m = 0.0329845436 * x + 0.9293118715 * y + 0.0361456387 * z;
+        let s = 0.0482003018 * x + 0.2643662691 * y + 0.6338517070 * z;
+
+        let (l, m, s) = (l.cbrt(), m.cbrt(), s.cbrt());
+
+        Self::new(0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+                  1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+                  0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s)
+    }
+}
+
+impl From<OKLab> for Color {
+    fn from(value: OKLab) -> Self {
+        let l = value.l + 0.3963377774 * value.a + 0.2158037573 * value.b;
+        let m = value.l - 0.1055613458 * value.a - 0.0638541728 * value.b;
+        let s = value.l - 0.0894841775 * value.a - 1.2914855480 * value.b;
+
+        let (l, m, s) = (l.powi(3), m.powi(3), s.powi(3));
+
+        Self::new( 1.2270138511 * l - 0.5577999807 * m + 0.2812561490 * s,
+                  -0.0405801784 * l + 1.1122568696 * m - 0.0716766787 * s,
+                  -0.0763812845 * l - 0.4214819784 * m + 1.5861632204 * s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_float_eq;
+
+    #[test]
+    fn reference() {
+        // Values from the original post
+        for ((x, y, z), (l, a, b)) in [
+            ((0.950, 1.000, 1.089), (1.000, 0.000, 0.000)),
+            ((1.000, 0.000, 0.000), (0.450, 1.236, -0.019)),
+            ((0.000, 1.000, 0.000), (0.922, -0.671, 0.263)),
+            ((0.000, 0.000, 1.000), (0.153, -1.415, -0.449)),
+        ] {
+            let lab = OKLab::from(Color::new(x, y, z));
+
+            assert_float_eq!(lab.l, l, 1e-3);
+            assert_float_eq!(lab.a, a, 1e-3);
+            assert_float_eq!(lab.b, b, 1e-3);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let color = Color::new(0.4124, 0.2126, 0.0193);
+        let back = Color::from(OKLab::from(color.clone()));
+
+        assert_float_eq!(*back.x, *color.x);
+        assert_float_eq!(*back.y, *color.y);
+        assert_float_eq!(*back.z, *color.z);
+    }
+
+    #[test]
+    fn mix() {
+        let from = OKLab::new(0.2, -0.1, 0.1);
+        let to = OKLab::new(0.6, 0.1, -0.1);
+
+        assert_eq!(from.mix(&to, 0.0), from);
+        assert_eq!(from.mix(&to, 1.0), to);
+
+        let half = from.mix(&to, 0.5);
+        assert_float_eq!(half.l, 0.4);
+        assert_float_eq!(half.a, 0.0);
+        assert_float_eq!(half.b, 0.0);
+    }
+}
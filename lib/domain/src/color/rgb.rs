@@ -54,7 +54,7 @@ impl From<RGB> for Color {
     fn from(value: RGB) -> Self {
         // sRGB to linear sRGB
         fn linear(v: u8) -> f64 {
-            let v = v as f64 / u8::max_value() as f64;
+            let v = v as f64 / u8::MAX as f64;
 
             if 0.04045 >= v {
                 v / 12.92
@@ -337,7 +337,7 @@ mod tests {
 //         Some(HSV {
 //             hue,
 //             saturation,
-//             value: max / u8::max_value() as f64,
+//             value: max / u8::MAX as f64,
 //         })
 //     }
 //  
@@ -345,7 +345,7 @@ mod tests {
 //     fn to_temperature(self: &Self) -> Option<Temperature> {
 //         // sRGB to linear sRGB
 //         fn linear(v: u8) -> f64 {
-//             let v = v as f64 / u8::max_value() as f64;
+//             let v = v as f64 / u8::MAX as f64;
 //  
 //             if 0.04045 >= v {
 //                 v / 12.92
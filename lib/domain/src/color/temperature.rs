@@ -1,26 +1,205 @@
 
 use super::Color;
+use super::xy::XY;
 
 use crate::misc::Uf64;
 
 pub type Temperature = Uf64;
 
+// Range where Planckian locus approximations hold, temperatures outside
+// of it are clamped
+pub const MIN: f64 = 1000.0;
+pub const MAX: f64 = 25000.0;
+
+// Kim et al. approximation holds from here, Krystek is used below
+const KIM_MIN: f64 = 1667.0;
+
+// Chromaticity of a black body radiator
+pub fn planckian(temperature: f64) -> XY {
+    let t = temperature.clamp(MIN, MAX);
+
+    if KIM_MIN > t {
+        krystek(t)
+    } else {
+        kim(t)
+    }
+}
+
+// Krystek (1985), rational approximation in CIE 1960 UCS
+fn krystek(t: f64) -> XY {
+    let t2 = t * t;
+    let u = (0.860117757 + 1.54118254e-4 * t + 1.28641212e-7 * t2)
+        / (1.0 + 8.42420235e-4 * t + 7.08145163e-7 * t2);
+    let v = (0.317398726 + 4.22806245e-5 * t + 4.20481691e-8 * t2)
+        / (1.0 - 2.89741816e-5 * t + 1.61456053e-7 * t2);
+
+    from_uv(u, v)
+}
+
+// Kim et al. (2002), cubic spline
+fn kim(t: f64) -> XY {
+    let (t2, t3) = (t * t, t * t * t);
+
+    let x = if 4000.0 >= t {
+        -0.2661239e9 / t3 - 0.2343589e6 / t2 + 0.8776956e3 / t + 0.179910
+    } else {
+        -3.0258469e9 / t3 + 2.1070379e6 / t2 + 0.2226347e3 / t + 0.240390
+    };
+
+    let (x2, x3) = (x * x, x * x * x);
+    let y = if 2222.0 >= t {
+        -1.1063814 * x3 - 1.34811020 * x2 + 2.18555832 * x - 0.20219683
+    } else if 4000.0 >= t {
+        -0.9549476 * x3 - 1.37418593 * x2 + 2.09137015 * x - 0.16748867
+    } else {
+        3.0817580 * x3 - 5.87338670 * x2 + 3.75112997 * x - 0.37001483
+    };
+
+    XY::new(x, y)
+}
+
+fn from_uv(u: f64, v: f64) -> XY {
+    let d = 2.0 * u - 8.0 * v + 4.0;
+    XY::new(3.0 * u / d, 2.0 * v / d)
+}
+
+fn to_uv(xy: &XY) -> (f64, f64) {
+    let d = -2.0 * xy.x + 12.0 * xy.y + 3.0;
+    (4.0 * xy.x / d, 6.0 * xy.y / d)
+}
+
+// Squared distance to the locus in CIE 1960 UCS, where isotemperature
+// lines are normal to it
+fn distance(uv: (f64, f64), temperature: f64) -> f64 {
+    let locus = to_uv(&planckian(temperature));
+    (uv.0 - locus.0).powi(2) + (uv.1 - locus.1).powi(2)
+}
+
 impl From<Color> for Temperature {
+    // Temperature of the closest point on the locus, searched in mireds
+    // as they are close to perceptually uniform
     fn from(value: Color) -> Self {
-        // XYZ to xy
-        let s = *value.x + *value.y + *value.z;
-        let xc = *value.x / s;
-        let yc = *value.y / s;
+        const STEPS: usize = 100;
+        const ITERATIONS: usize = 40;
+
+        let uv = to_uv(&XY::from(value));
+        let (low, high) = (1e6 / MAX, 1e6 / MIN);
+        let step = (high - low) / STEPS as f64;
+        let f = |mired: f64| distance(uv, 1e6 / mired);
+
+        let best = (0..=STEPS)
+            .map(|i| low + step * i as f64)
+            .min_by(|a, b| f(*a).total_cmp(&f(*b)))
+            .unwrap_or(low);
+
+        // Golden section refinement around the coarse minimum
+        let ratio = (5f64.sqrt() - 1.0) / 2.0;
+        let (mut a, mut b) = ((best - step).max(low), (best + step).min(high));
+
+        for _ in 0..ITERATIONS {
+            let c = b - ratio * (b - a);
+            let d = a + ratio * (b - a);
 
-        // xyz to cct
-        let n = (xc - 0.3320) / (0.1858 - yc);
-        Self::new(449.0 * n.powi(3) + 3525.0 * n.powi(2) + 6823.3 * n + 5520.33)
+            if f(c) < f(d) {
+                b = d;
+            } else {
+                a = c;
+            }
+        }
+
+        Self::new(1e6 / ((a + b) / 2.0))
     }
 }
 
 impl From<Temperature> for Color {
-    fn from(_value: Temperature) -> Self {
-        Self::new(1.009794293297943, 1.0, 0.6444857332448575)
+    // Unit luminance, same as for other chromaticity only conversions
+    fn from(value: Temperature) -> Self {
+        Self::from(planckian(*value))
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // CIE 1931 chromaticity of the Planckian locus
+    const REFERENCE: [(f64, f64, f64); 11] = [
+        (1000.0, 0.6528, 0.3444),
+        (1500.0, 0.5857, 0.3931),
+        (2000.0, 0.5267, 0.4133),
+        (2700.0, 0.4599, 0.4106),
+        (3000.0, 0.4369, 0.4041),
+        (4000.0, 0.3805, 0.3768),
+        (5000.0, 0.3451, 0.3516),
+        (6500.0, 0.3135, 0.3237),
+        (10000.0, 0.2807, 0.2884),
+        (15000.0, 0.2637, 0.2673),
+        (25000.0, 0.2525, 0.2523),
+    ];
+
+    macro_rules! assert_float_eq {
+        ($x:expr, $y:expr, $d:expr) => {
+            if (($x - $y).abs() > $d) {
+                panic!("Left: {}\nRight: {}", $x, $y);
+            }
+        };
+    }
+
+    #[test]
+    fn to_xy() {
+        for (t, x, y) in REFERENCE {
+            let xy = XY::from(Color::from(Temperature::new(t)));
+
+            assert_float_eq!(xy.x, x, 1e-3);
+            assert_float_eq!(xy.y, y, 1e-3);
+        }
+    }
+
+    #[test]
+    fn from_xy() {
+        for (t, x, y) in REFERENCE {
+            let temperature = Temperature::from(Color::from(XY::new(x, y)));
+
+            // 1% is far below what is noticeable in a light
+            assert_float_eq!(*temperature, t, t * 0.01);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        for t in [1000.0, 1200.0, 1666.0, 1700.0, 2700.0, 6500.0, 12000.0,
+                  25000.0] {
+            let back = Temperature::from(Color::from(Temperature::new(t)));
+
+            assert_float_eq!(*back, t, t * 1e-3);
+        }
+    }
+
+    #[test]
+    fn unit_luminance() {
+        let color = Color::from(Temperature::new(2700.0));
+
+        assert_float_eq!(*color.y, 1.0, 1e-9);
+    }
+
+    #[test]
+    fn clamped() {
+        assert_eq!(planckian(500.0), planckian(MIN));
+        assert_eq!(planckian(40000.0), planckian(MAX));
+
+        let low = Temperature::from(Color::from(Temperature::new(500.0)));
+        let high = Temperature::from(Color::from(Temperature::new(40000.0)));
+
+        assert_float_eq!(*low, MIN, 1.0);
+        assert_float_eq!(*high, MAX, 25.0);
+    }
+
+    #[test]
+    fn black() {
+        let color: Color = crate::color::rgb::RGB::new(0, 0, 0).into();
+        let temperature = Temperature::from(color);
+
+        // D65 white point is used for black
+        assert_float_eq!(*temperature, 6504.0, 10.0);
+    }
+}
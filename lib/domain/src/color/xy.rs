@@ -0,0 +1,92 @@
+
+use super::Color;
+
+// CIE 1931 chromaticity, luminance is dropped
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct XY {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl XY {
+    pub fn new(x: f64, y: f64) -> Self {
+        Self {
+            x,
+            y,
+        }
+    }
+}
+
+impl From<Color> for XY {
+    fn from(value: Color) -> Self {
+        let s = *value.x + *value.y + *value.z;
+
+        if 0f64 == s {
+            // Black has no chromaticity, so use D65 white point
+            Self::new(0.3127, 0.3290)
+        } else {
+            Self::new(*value.x / s, *value.y / s)
+        }
+    }
+}
+
+impl From<XY> for Color {
+    // Restored with unit luminance
+    fn from(value: XY) -> Self {
+        if 0f64 >= value.y {
+            Self::new(0f64, 0f64, 0f64)
+        } else {
+            Self::new(value.x / value.y, 1f64,
+                      (1f64 - value.x - value.y) / value.y)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPS: f64 = 1e-5;
+
+    macro_rules! assert_float_eq {
+        ($x:expr, $y:expr) => {
+            if (($x - $y).abs() > EPS) {
+                panic!("Left: {}\nRight: {}", $x, $y);
+            }
+        };
+    }
+
+    #[test]
+    fn from_color() {
+        let xy: XY = Color::new(0.950470, 1f64, 1.088830).into();
+
+        assert_float_eq!(xy.x, 0.312727);
+        assert_float_eq!(xy.y, 0.329023);
+    }
+
+    #[test]
+    fn from_black() {
+        let xy: XY = Color::new(0f64, 0f64, 0f64).into();
+
+        assert_float_eq!(xy.x, 0.3127);
+        assert_float_eq!(xy.y, 0.3290);
+    }
+
+    #[test]
+    fn to_color() {
+        let color: Color = XY::new(0.64, 0.33).into();
+
+        assert_float_eq!(*color.x, 1.939394);
+        assert_float_eq!(*color.y, 1f64);
+        assert_float_eq!(*color.z, 0.090909);
+    }
+
+    #[test]
+    fn round_trip() {
+        let xy = XY::new(0.1532, 0.0475);
+        let back: XY = Color::from(xy).into();
+
+        assert_float_eq!(back.x, xy.x);
+        assert_float_eq!(back.y, xy.y);
+    }
+}
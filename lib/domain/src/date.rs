@@ -0,0 +1,103 @@
+// Calendar arithmetic shared by the scheduler and whatever shows timestamps
+
+pub const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    pub fn new(year: i32, month: u32, day: u32) -> Self {
+        Self { year, month, day }
+    }
+
+    // Proleptic gregorian calendar, see
+    // http://howardhinnant.github.io/date_algorithms.html
+    pub fn from_days(days: i64) -> Self {
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z.rem_euclid(146097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        Self::new(year as i32, month, day)
+    }
+
+    // Days since unix epoch
+    pub fn days(self: &Self) -> i64 {
+        let year = self.year as i64 - if self.month <= 2 { 1 } else { 0 };
+        let era = year.div_euclid(400);
+        let yoe = year.rem_euclid(400);
+        let month = self.month as i64;
+        let mp = if month > 2 { month - 3 } else { month + 9 };
+        let doy = (153 * mp + 2) / 5 + self.day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+        era * 146097 + doe - 719468
+    }
+
+    // 0 is sunday, the way cron counts
+    pub fn weekday(self: &Self) -> u32 {
+        (self.days() + 4).rem_euclid(7) as u32
+    }
+
+    // 1 for the first of january
+    pub fn ordinal(self: &Self) -> u32 {
+        (self.days() - Self::new(self.year, 1, 1).days()) as u32 + 1
+    }
+}
+
+impl std::fmt::Display for Date {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+// Seconds since unix epoch as date and time in UTC
+pub fn timestamp(at: i64) -> String {
+    let date = Date::from_days(at.div_euclid(SECONDS_PER_DAY));
+    let of_day = at.rem_euclid(SECONDS_PER_DAY);
+
+    format!("{} {:02}:{:02}:{:02}", date, of_day / 3600, of_day / 60 % 60,
+            of_day % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn epoch() {
+        assert_eq!(Date::from_days(0), Date::new(1970, 1, 1));
+        assert_eq!(Date::new(1970, 1, 1).weekday(), 4);
+    }
+
+    #[test]
+    fn round_trip() {
+        for days in -800000..800000 {
+            if days % 997 == 0 {
+                assert_eq!(Date::from_days(days).days(), days);
+            }
+        }
+
+        assert_eq!(Date::new(2024, 2, 29).days() + 1,
+                   Date::new(2024, 3, 1).days());
+        assert_eq!(Date::new(2024, 12, 31).ordinal(), 366);
+        assert_eq!(Date::new(2025, 3, 10).weekday(), 1);
+    }
+
+    #[test]
+    fn utc_timestamp() {
+        let day = Date::new(2025, 1, 1).days() * SECONDS_PER_DAY;
+
+        assert_eq!(timestamp(day + 3723), "2025-01-01 01:02:03");
+        assert_eq!(timestamp(day - 1), "2024-12-31 23:59:59");
+    }
+}
@@ -0,0 +1,88 @@
+
+use serde::{Serialize, Deserialize};
+
+use crate::light::ProviderID;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Member {
+    Light(ProviderID),
+    Saved(String), // Name of a saved light
+    Group(String),
+}
+
+// Several lights addressed as one, e.g. a room. Groups may include other
+// groups, but not themselves
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Group {
+    pub name: String,
+    pub members: Vec<Member>,
+}
+
+impl Group {
+    pub fn new(name: String, members: Vec<Member>) -> Self {
+        Self {
+            name,
+            members,
+        }
+    }
+
+    // Points members saved under the old name to the new one
+    pub fn rename_saved(self: &mut Self, old: &str, new: &str) -> bool {
+        let mut changed = false;
+
+        for member in self.members.iter_mut() {
+            if let Member::Saved(name) = member {
+                if name == old {
+                    *name = new.to_string();
+                    changed = true;
+                }
+            }
+        }
+
+        changed
+    }
+}
+
+impl std::fmt::Display for Member {
+    fn fmt(self: &Self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Member::Light(id) => write!(f, "{}", id),
+            Member::Saved(name) => write!(f, "saved \"{}\"", name),
+            Member::Group(name) => write!(f, "group \"{}\"", name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group() -> Group {
+        Group::new("kitchen".to_string(), vec![
+            Member::Light(ProviderID::new("hue".to_string(), "1".to_string())),
+            Member::Saved("desk".to_string()),
+            Member::Group("desk".to_string()),
+        ])
+    }
+
+    #[test]
+    fn rename_saved() {
+        let mut group = group();
+
+        assert!(group.rename_saved("desk", "table"));
+        assert_eq!(group.members[1], Member::Saved("table".to_string()));
+        // Groups have own names
+        assert_eq!(group.members[2], Member::Group("desk".to_string()));
+        assert!(!group.rename_saved("desk", "table"));
+    }
+
+    #[test]
+    fn serialized() {
+        let value = serde_json::to_value(group()).expect("Serialized");
+
+        assert_eq!(value["members"][0]["light"]["id"], "1");
+        assert_eq!(value["members"][1]["saved"], "desk");
+        assert_eq!(value["members"][2]["group"], "desk");
+    }
+}
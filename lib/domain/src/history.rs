@@ -0,0 +1,139 @@
+
+use serde::{Serialize, Deserialize};
+
+use crate::light::{Light, ProviderID};
+
+// Oldest entries are dropped once there are more of them
+pub const LIMIT: usize = 32;
+
+// State a light had until it was replaced at the given moment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    // Seconds since unix epoch
+    pub at: i64,
+    pub light: Light,
+}
+
+// Previous states of a single light, newest last. Undone states are kept
+// for redo until the light is synced again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct History {
+    pub id: ProviderID,
+    pub undo: Vec<Entry>,
+    pub redo: Vec<Entry>,
+}
+
+impl Entry {
+    pub fn new(at: i64, light: Light) -> Self {
+        Self {
+            at,
+            light,
+        }
+    }
+}
+
+impl History {
+    pub fn new(id: ProviderID) -> Self {
+        Self {
+            id,
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    pub fn is_empty(self: &Self) -> bool {
+        self.undo.is_empty() && self.redo.is_empty()
+    }
+
+    fn push(stack: &mut Vec<Entry>, entry: Entry) {
+        stack.push(entry);
+
+        if stack.len() > LIMIT {
+            stack.drain(..stack.len() - LIMIT);
+        }
+    }
+
+    // New change makes undone states unreachable
+    pub fn record(self: &mut Self, entry: Entry) {
+        Self::push(&mut self.undo, entry);
+        self.redo.clear();
+    }
+
+    // State to be synced, current one is kept for redo
+    pub fn undo(self: &mut Self, current: Entry) -> Option<Entry> {
+        let entry = self.undo.pop()?;
+        Self::push(&mut self.redo, current);
+
+        Some(entry)
+    }
+
+    pub fn redo(self: &mut Self, current: Entry) -> Option<Entry> {
+        let entry = self.redo.pop()?;
+        Self::push(&mut self.undo, current);
+
+        Some(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capabilities::Capability;
+    use crate::brightness::Brightness;
+
+    fn entry(at: i64, brightness: f64) -> Entry {
+        let mut light = Light::new("mock".to_string(), "1".to_string(),
+                                   vec![Capability::Brightness]);
+        light.set_brightness(Brightness::new(brightness)).expect("Capable");
+
+        Entry::new(at, light)
+    }
+
+    fn brightness(entry: &Entry) -> f64 {
+        **entry.light.get_brightness().expect("Set")
+    }
+
+    fn history() -> History {
+        History::new(ProviderID::new("mock".to_string(), "1".to_string()))
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut history = history();
+        history.record(entry(1, 0.1));
+        history.record(entry(2, 0.2));
+
+        let undone = history.undo(entry(3, 0.3)).expect("Recorded");
+        assert_eq!(brightness(&undone), 0.2);
+        assert_eq!(history.redo.len(), 1);
+
+        let redone = history.redo(entry(4, 0.2)).expect("Undone");
+        assert_eq!(brightness(&redone), 0.3);
+        assert_eq!(history.undo.len(), 2);
+        assert!(history.redo(entry(5, 0.3)).is_none());
+    }
+
+    #[test]
+    fn record_drops_redo() {
+        let mut history = history();
+        history.record(entry(1, 0.1));
+        history.undo(entry(2, 0.2)).expect("Recorded");
+
+        history.record(entry(3, 0.1));
+
+        assert!(history.redo.is_empty());
+        assert!(!history.is_empty());
+    }
+
+    #[test]
+    fn bounded() {
+        let mut history = history();
+
+        for at in 0..(LIMIT as i64 + 5) {
+            history.record(entry(at, 0.5));
+        }
+
+        assert_eq!(history.undo.len(), LIMIT);
+        assert_eq!(history.undo[0].at, 5);
+    }
+}
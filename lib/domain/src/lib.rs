@@ -5,6 +5,11 @@ pub mod capabilities;
 pub mod brightness;
 
 pub mod light;
+pub mod patch;
+pub mod scene;
+pub mod group;
+pub mod history;
+pub mod date;
 
 mod misc;
 
@@ -6,9 +6,9 @@ use crate::mode::Mode;
 use crate::brightness::Brightness;
 use crate::capabilities::Capability;
 
-type Result<T> = std::result::Result<T, Error>;
+pub type Result<T> = std::result::Result<T, Error>;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ProviderID {
     pub name: String, // Provider name
     pub id: String,   // Light id for provider
@@ -29,8 +29,33 @@ impl std::fmt::Display for ProviderID {
     }
 }
 
+impl std::str::FromStr for ProviderID {
+    type Err = ParseProviderIDError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.rsplit_once('@') {
+            Some((id, name)) if !id.is_empty() && !name.is_empty() => {
+                Ok(Self::new(name.to_string(), id.to_string()))
+            },
+            _ => Err(ParseProviderIDError(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseProviderIDError(String);
+
+impl std::error::Error for ParseProviderIDError {}
+
+impl std::fmt::Display for ParseProviderIDError {
+    fn fmt(self: &Self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "\"{}\" doesn't match \"<id>@<provider>\"", self.0)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 enum State {
+    Power(Option<bool>),
     Color(Option<Color>),
     Brightness(Option<Brightness>),
     Mode(Option<Mode>),
@@ -38,11 +63,23 @@ enum State {
 
 impl std::cmp::PartialEq<Capability> for State {
     fn eq(self: &Self, other: &Capability) -> bool {
-        match (self, other) {
-            (State::Color(_), Capability::Color) => true,
-            (State::Brightness(_), Capability::Brightness) => true,
-            (State::Mode(_), Capability::Mode) => true,
-            _ => false,
+        matches!(
+            (self, other),
+            (State::Power(_), Capability::Power)
+            | (State::Color(_), Capability::Color)
+            | (State::Brightness(_), Capability::Brightness)
+            | (State::Mode(_), Capability::Mode)
+        )
+    }
+}
+
+impl From<&State> for Capability {
+    fn from(value: &State) -> Self {
+        match value {
+            State::Power(_) => Self::Power,
+            State::Color(_) => Self::Color,
+            State::Brightness(_) => Self::Brightness,
+            State::Mode(_) => Self::Mode,
         }
     }
 }
@@ -50,6 +87,7 @@ impl std::cmp::PartialEq<Capability> for State {
 impl From<Capability> for State {
     fn from(value: Capability) -> Self {
         match value {
+            Capability::Power => Self::Power(None),
             Capability::Color => Self::Color(None),
             Capability::Brightness => Self::Brightness(None),
             Capability::Mode => Self::Mode(None),
@@ -72,6 +110,7 @@ impl<'a> FromIterator<&'a Capability> for Vec<State> {
 impl std::fmt::Display for Capability {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            Capability::Power => write!(f, "Power"),
             Capability::Color => write!(f, "Color"),
             Capability::Brightness => write!(f, "Brightness"),
             Capability::Mode => write!(f, "Mode"),
@@ -80,20 +119,47 @@ impl std::fmt::Display for Capability {
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(from = "Stored")]
 pub struct Light {
     pub provider: ProviderID, // Light id for Provider
     pub name: String,         // Local name
-    pub power: bool,
     state: Vec<State>,
 }
 
+// Power used to be a plain field, saved lights still may have it
+#[derive(Deserialize)]
+struct Stored {
+    provider: ProviderID,
+    name: String,
+    #[serde(default)]
+    power: Option<bool>,
+    state: Vec<State>,
+}
+
+impl From<Stored> for Light {
+    fn from(value: Stored) -> Self {
+        let mut state = value.state;
+
+        if let (Some(power), false) = (value.power, state.iter().any(|item| {
+            matches!(item, State::Power(_))
+        })) {
+            state.insert(0, State::Power(Some(power)));
+        }
+
+        Self {
+            provider: value.provider,
+            name: value.name,
+            state,
+        }
+    }
+}
+
 impl Light {
     pub fn new(provider: String, provider_id: String,
                capabilities: Vec<Capability>) -> Self {
         Self {
             provider: ProviderID::new(provider, provider_id),
             name: String::new(),
-            power: false,
             state: Vec::from_iter(capabilities.iter()),
         }
     }
@@ -104,7 +170,6 @@ impl Light {
         Self {
             provider: ProviderID::new(provider, provider_id),
             name,
-            power: false,
             state: Vec::from_iter(capabilities.iter()),
         }
     }
@@ -115,12 +180,43 @@ impl Light {
         })
     }
 
+    pub fn capabilities(self: &Self) -> Vec<Capability> {
+        self.state.iter().map(Capability::from).collect()
+    }
+
+    pub fn get_power(self: &Self) -> Result<bool> {
+        if let Some(State::Power(power)) = self.state.iter().find(|item| {
+            matches!(item, State::Power(_))
+        }) {
+            if let Some(power) = power {
+                Ok(*power)
+            } else {
+                Error::unset(self, Capability::Power)
+            }
+        } else {
+            Error::incapable(self, Capability::Power)
+        }
+    }
+
+    pub fn set_power(self: &mut Self, power: bool) -> Result<()> {
+        if let Some(State::Power(in_power)) = self.state.iter_mut().find(|item| {
+            matches!(item, State::Power(_))
+        }) {
+            *in_power = Some(power);
+            Ok(())
+        } else {
+            Error::incapable(self, Capability::Power)
+        }
+    }
+
+    // Unknown power is treated as off
+    pub fn is_on(self: &Self) -> bool {
+        self.get_power().unwrap_or(false)
+    }
+
     pub fn get_color(self: &Self) -> Result<&Color> {
         if let Some(State::Color(color)) = self.state.iter().find(|item| {
-            match item {
-                State::Color(_) => true,
-                _ => false,
-            }
+            matches!(item, State::Color(_))
         }) {
             if let Some(color) = color {
                 Ok(color)
@@ -134,10 +230,7 @@ impl Light {
 
     pub fn set_color(self: &mut Self, color: Color) -> Result<()> {
         if let Some(State::Color(in_color)) = self.state.iter_mut().find(|item| {
-            match item {
-                State::Color(_) => true,
-                _ => false,
-            }
+            matches!(item, State::Color(_))
         }) {
             *in_color = Some(color);
             Ok(())
@@ -148,10 +241,7 @@ impl Light {
 
     pub fn get_brightness(self: &Self) -> Result<&Brightness> {
         if let Some(State::Brightness(brightness)) = self.state.iter().find(|item| {
-            match item {
-                State::Brightness(_) => true,
-                _ => false,
-            }
+            matches!(item, State::Brightness(_))
         }) {
             if let Some(brightness) = brightness {
                 Ok(brightness)
@@ -165,10 +255,7 @@ impl Light {
 
     pub fn set_brightness(self: &mut Self, brightness: Brightness) -> Result<()> {
         if let Some(State::Brightness(in_brightness)) = self.state.iter_mut().find(|item| {
-            match item {
-                State::Brightness(_) => true,
-                _ => false,
-            }
+            matches!(item, State::Brightness(_))
         }) {
             *in_brightness = Some(brightness);
             Ok(())
@@ -179,10 +266,7 @@ impl Light {
 
     pub fn get_mode(self: &Self) -> Result<&Mode> {
         if let Some(State::Mode(mode)) = self.state.iter().find(|item| {
-            match item {
-                State::Mode(_) => true,
-                _ => false,
-            }
+            matches!(item, State::Mode(_))
         }) {
             if let Some(mode) = mode {
                 Ok(mode)
@@ -196,10 +280,7 @@ impl Light {
 
     pub fn set_mode(self: &mut Self, mode: Mode) -> Result<()> {
         if let Some(State::Mode(in_mode)) = self.state.iter_mut().find(|item| {
-            match item {
-                State::Mode(_) => true,
-                _ => false,
-            }
+            matches!(item, State::Mode(_))
         }) {
             if mode.provider == self.provider.name {
                 *in_mode = Some(mode);
@@ -222,25 +303,27 @@ pub enum Error {
 
 impl Error {
     fn incapable<T>(light: &Light, capability: Capability) -> Result<T> {
-        Err(Error::Incapable(String::from(light.name.clone()),
+        Err(Error::Incapable(light.name.clone(),
                              light.provider.clone(),
                              capability))
     }
 
     fn unset<T>(light: &Light, capability: Capability) -> Result<T> {
-        Err(Error::Unset(String::from(light.name.clone()),
+        Err(Error::Unset(light.name.clone(),
                          light.provider.clone(),
                          capability))
     }
 
     fn unsuitable_mode<T>(light: &Light, wrong: String) -> Result<T> {
-        Err(Error::UnsuitableMode(String::from(light.name.clone()),
+        Err(Error::UnsuitableMode(light.name.clone(),
                                   light.provider.clone(),
                                   wrong))
     }
 
 }
 
+impl std::error::Error for Error {}
+
 impl std::fmt::Display for Error {
     fn fmt(self: &Self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
@@ -260,4 +343,106 @@ impl std::fmt::Display for Error {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod provider_id {
+        use super::*;
+
+        #[test]
+        fn display() {
+            let id = ProviderID::new("hue".to_string(), "3".to_string());
+
+            assert_eq!(id.to_string(), "3@hue");
+        }
+
+        #[test]
+        fn parse() {
+            let id: ProviderID = "3@hue".parse().expect("Correct id");
+
+            assert_eq!(id.name, "hue");
+            assert_eq!(id.id, "3");
+        }
+
+        #[test]
+        fn parse_at_in_id() {
+            let id: ProviderID = "a@b@mqtt".parse().expect("Correct id");
+
+            assert_eq!(id.name, "mqtt");
+            assert_eq!(id.id, "a@b");
+        }
+
+        #[test]
+        fn parse_incorrect() {
+            assert!("3".parse::<ProviderID>().is_err());
+            assert!("@hue".parse::<ProviderID>().is_err());
+            assert!("3@".parse::<ProviderID>().is_err());
+        }
+    }
+
+    #[test]
+    fn capabilities() {
+        let light = Light::new("test".to_string(), "1".to_string(),
+                               vec![Capability::Mode, Capability::Color]);
+
+        assert_eq!(light.capabilities(),
+                   vec![Capability::Mode, Capability::Color]);
+        assert!(light.is_capable(&[Capability::Color]));
+        assert!(!light.is_capable(&[Capability::Brightness]));
+    }
+
+    mod power {
+        use super::*;
+
+        #[test]
+        fn set() {
+            let mut light = Light::new("test".to_string(), "1".to_string(),
+                                       vec![Capability::Power]);
+
+            assert!(matches!(light.get_power(), Err(Error::Unset(..))));
+            assert!(!light.is_on());
+
+            light.set_power(true).expect("Capable");
+
+            assert!(light.get_power().expect("Set"));
+            assert!(light.is_on());
+        }
+
+        #[test]
+        fn incapable() {
+            let mut light = Light::new("test".to_string(), "1".to_string(),
+                                       vec![Capability::Brightness]);
 
+            assert!(matches!(light.set_power(true), Err(Error::Incapable(..))));
+            assert!(matches!(light.get_power(), Err(Error::Incapable(..))));
+        }
+
+        #[test]
+        fn stored_as_field() {
+            let light: Light = serde_json::from_str(r#"{
+                "provider": { "name": "hue", "id": "1" },
+                "name": "desk",
+                "power": true,
+                "state": [{ "Brightness": 0.5 }]
+            }"#).expect("Correct light");
+
+            assert_eq!(light.capabilities(), vec![Capability::Power,
+                                                  Capability::Brightness]);
+            assert!(light.get_power().expect("Set"));
+        }
+
+        #[test]
+        fn serialized_as_state() {
+            let mut light = Light::new("test".to_string(), "1".to_string(),
+                                       vec![Capability::Power]);
+            light.set_power(false).expect("Capable");
+
+            let value = serde_json::to_value(&light).expect("Serialized");
+            assert!(value.get("power").is_none());
+
+            let back: Light = serde_json::from_value(value).expect("Deserialized");
+            assert!(!back.get_power().expect("Set"));
+        }
+    }
+}
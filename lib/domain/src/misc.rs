@@ -43,7 +43,7 @@ impl<C: FloatChecker> ConstraintedF64<C> {
     }
 
     pub fn new(value: f64) -> Self {
-        Self(Self::apply_check(value), PhantomData::default())
+        Self(Self::apply_check(value), PhantomData)
     }
 }
 
@@ -63,6 +63,13 @@ where C: FloatChecker {
     }
 }
 
+impl<C> PartialEq for ConstraintedF64<C>
+where C: FloatChecker {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
 impl<C> Serialize for ConstraintedF64<C>
 where C: FloatChecker {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -77,7 +84,7 @@ where C: FloatChecker {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de> {
-        f64::deserialize(deserializer).and_then(|value| Ok(Self::new(value)))
+        f64::deserialize(deserializer).map(|value| Self::new(value))
     }
 }
 
@@ -2,10 +2,12 @@
 use serde::{Serialize, Deserialize};
 
 pub mod parameter;
+pub mod descriptor;
+pub mod schema;
 
 use parameter::{Parameter, Value};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Mode {
     pub provider: String,
     pub name: String,
@@ -87,10 +89,7 @@ mod tests {
                              ]);
 
         assert!(mode.parameter("name2").is_some_and(|param| {
-            match param {
-                Value::Int(2) => true,
-                _ => false,
-            }
+            matches!(param, Value::Int(2))
         }));
     }
 
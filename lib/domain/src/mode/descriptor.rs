@@ -0,0 +1,270 @@
+
+use serde::{Serialize, Deserialize};
+
+use super::Mode;
+use super::parameter::Value;
+use super::schema::{Field, ParameterSchema, Violation};
+
+// Mirrors variants of parameter::Value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParameterType {
+    String,
+    Int,
+    UInt,
+    Float,
+    Group,
+    Array,
+}
+
+impl ParameterType {
+    pub fn of(value: &Value) -> Self {
+        match value {
+            Value::String(_) => Self::String,
+            Value::Int(_) => Self::Int,
+            Value::UInt(_) => Self::UInt,
+            Value::Float(_) => Self::Float,
+            Value::Group(_) => Self::Group,
+            Value::Array(_) => Self::Array,
+        }
+    }
+
+    // Integers are interchangeable while they fit, and both pass for floats
+    pub fn accepts(self: &Self, value: &Value) -> bool {
+        match (self, value) {
+            (Self::Int, Value::UInt(value)) => i64::try_from(*value).is_ok(),
+            (Self::UInt, Value::Int(value)) => 0 <= *value,
+            (Self::Float, Value::Int(_) | Value::UInt(_)) => true,
+            (expected, value) => *expected == Self::of(value),
+        }
+    }
+}
+
+impl std::fmt::Display for ParameterType {
+    fn fmt(self: &Self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            Self::String => "string",
+            Self::Int => "int",
+            Self::UInt => "uint",
+            Self::Float => "float",
+            Self::Group => "group",
+            Self::Array => "array",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+// What a provider accepts as a mode of its lights, parameters are described
+// as fields of a group
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModeDescriptor {
+    pub provider: String,
+    pub name: String,
+    #[serde(default)]
+    pub doc: String,
+    #[serde(default = "no_parameters")]
+    pub parameters: ParameterSchema,
+}
+
+fn no_parameters() -> ParameterSchema {
+    ParameterSchema::group(Vec::new())
+}
+
+impl ModeDescriptor {
+    pub fn new(provider: &str, name: &str, doc: &str) -> Self {
+        Self {
+            provider: provider.to_string(),
+            name: name.to_string(),
+            doc: doc.to_string(),
+            parameters: no_parameters(),
+        }
+    }
+
+    pub fn with_parameter(mut self: Self, field: Field) -> Self {
+        if let ParameterSchema::Group { fields } = &mut self.parameters {
+            fields.push(field);
+        }
+
+        self
+    }
+
+    pub fn fields(self: &Self) -> &[Field] {
+        match &self.parameters {
+            ParameterSchema::Group { fields } => fields,
+            _ => &[],
+        }
+    }
+
+    pub fn parameter(self: &Self, name: &str) -> Option<&Field> {
+        self.fields().iter().find(|field| field.name == name)
+    }
+
+    // Every violation of the parameters is reported at once
+    pub fn validate(self: &Self, mode: &Mode) -> Result<()> {
+        if mode.name != self.name {
+            return Err(Error::UnknownMode(mode.name.clone()));
+        }
+
+        mode.validate(&self.parameters).map_err(Error::Invalid)
+    }
+}
+
+// Checks mode against the catalog of its provider
+pub fn validate(catalog: &[ModeDescriptor], mode: &Mode) -> Result<()> {
+    match catalog.iter().find(|item| item.name == mode.name) {
+        Some(descriptor) => descriptor.validate(mode),
+        None => Err(Error::UnknownMode(mode.name.clone())),
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    UnknownMode(String),
+    Invalid(Vec<Violation>),
+}
+
+impl std::error::Error for Error {}
+
+impl std::fmt::Display for Error {
+    fn fmt(self: &Self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::UnknownMode(name) => {
+                write!(f, "Mode \"{}\" isn't supported", name)
+            },
+            Error::Invalid(violations) => {
+                let violations: Vec<String> = violations.iter()
+                    .map(Violation::to_string)
+                    .collect();
+                write!(f, "{}", violations.join("; "))
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mode::parameter::Parameter;
+    use crate::mode::schema::ViolationKind;
+
+    fn catalog() -> Vec<ModeDescriptor> {
+        vec![
+            ModeDescriptor::new("mock", "colorloop", "Cycles through colors"),
+            ModeDescriptor::new("mock", "pulse", "Pulses brightness")
+                .with_parameter(Field::new(
+                    "period", ParameterSchema::uint(Some(100), Some(10000)),
+                    "Period in ms"
+                ))
+                .with_parameter(Field::new(
+                    "speed", ParameterSchema::float(None, None),
+                    "Speed multiplier"
+                ).with_default(Value::Float(1.0))),
+        ]
+    }
+
+    fn pulse(parameters: Vec<(&str, Value)>) -> Mode {
+        Mode::new("mock".to_string(), "pulse".to_string(),
+                  parameters.into_iter()
+                      .map(|(name, value)| Parameter::new(name.to_string(), value))
+                      .collect())
+    }
+
+    fn violations(result: Result<()>) -> Vec<(String, ViolationKind)> {
+        match result {
+            Err(Error::Invalid(violations)) => violations.into_iter()
+                .map(|violation| (violation.path, violation.kind))
+                .collect(),
+            other => panic!("Violations expected, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn valid() {
+        let catalog = catalog();
+
+        assert!(validate(&catalog, &pulse(vec![("period", Value::UInt(500))])).is_ok());
+        assert!(validate(&catalog, &pulse(vec![("period", Value::Int(500)),
+                                               ("speed", Value::UInt(2))])).is_ok());
+        assert!(validate(&catalog, &Mode::new_empty("mock".to_string(),
+                                                    "colorloop".to_string())).is_ok());
+    }
+
+    #[test]
+    fn unknown() {
+        let catalog = catalog();
+
+        assert_eq!(validate(&catalog, &Mode::new_empty("mock".to_string(),
+                                                       "strobe".to_string())),
+                   Err(Error::UnknownMode("strobe".to_string())));
+        assert_eq!(violations(validate(&catalog, &pulse(vec![
+                       ("period", Value::UInt(500)),
+                       ("color", Value::UInt(1)),
+                   ]))),
+                   vec![("color".to_string(), ViolationKind::Unknown)]);
+    }
+
+    #[test]
+    fn missing() {
+        assert_eq!(violations(validate(&catalog(), &pulse(vec![]))),
+                   vec![("period".to_string(), ViolationKind::Missing)]);
+    }
+
+    #[test]
+    fn every_violation() {
+        let result = validate(&catalog(), &pulse(vec![
+            ("period", Value::UInt(50)),
+            ("speed", Value::String("fast".to_string())),
+        ]));
+
+        assert_eq!(violations(result), vec![
+            ("period".to_string(),
+             ViolationKind::OutOfRange(50.0, Some(100.0), Some(10000.0))),
+            ("speed".to_string(),
+             ViolationKind::WrongType(ParameterType::Float,
+                                      ParameterType::String)),
+        ]);
+    }
+
+    #[test]
+    fn nested_schema() {
+        let catalog = vec![
+            ModeDescriptor::new("mock", "party", "")
+                .with_parameter(Field::new("colors", ParameterSchema::array(
+                    ParameterSchema::uint(None, Some(0xFFFFFF))
+                ), "RGB values")),
+            ModeDescriptor::new("mock", "wave", "")
+                .with_parameter(Field::new("shape", ParameterSchema::group(vec![
+                    Field::new("width", ParameterSchema::uint(None, None), ""),
+                ]), "")),
+        ];
+        let party = Mode::new("mock".to_string(), "party".to_string(), vec![
+            Parameter::new("colors".to_string(),
+                           Value::Array(vec![Value::UInt(1),
+                                             Value::UInt(0x1000000)])),
+        ]);
+
+        let found = violations(validate(&catalog, &party));
+        assert_eq!(found[0].0, "colors[1]");
+        assert!(matches!(found[0].1, ViolationKind::OutOfRange(..)));
+
+        let wave = Mode::new("mock".to_string(), "wave".to_string(), vec![
+            Parameter::new("shape".to_string(), Value::Group(Default::default())),
+        ]);
+        assert_eq!(validate(&catalog, &wave).expect_err("Must fail").to_string(),
+                   "\"shape.width\" is missing");
+    }
+
+    #[test]
+    fn serde() {
+        let catalog = catalog();
+        let json = serde_json::to_string(&catalog).expect("Serialized");
+
+        assert!(json.contains("\"type\":\"uint\""));
+        assert!(json.contains("\"default\":"));
+        assert_eq!(serde_json::from_str::<Vec<ModeDescriptor>>(&json)
+                   .expect("Deserialized"), catalog);
+    }
+}
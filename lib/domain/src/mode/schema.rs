@@ -0,0 +1,498 @@
+
+use serde::{Serialize, Deserialize};
+
+use super::Mode;
+use super::parameter::{Parameter, Value};
+use super::descriptor::ParameterType;
+
+// Expected shape of a parameter value, numeric bounds are inclusive
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ParameterSchema {
+    String {
+        // Any string is accepted if empty
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        values: Vec<String>,
+    },
+    Int {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min: Option<i64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max: Option<i64>,
+    },
+    UInt {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max: Option<u64>,
+    },
+    Float {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max: Option<f64>,
+    },
+    Array {
+        items: Box<ParameterSchema>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min_len: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_len: Option<usize>,
+    },
+    Group {
+        fields: Vec<Field>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Field {
+    pub name: String,
+    #[serde(default = "required")]
+    pub required: bool,
+    // Used by the provider when the field is omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub doc: String,
+    #[serde(flatten)]
+    pub schema: ParameterSchema,
+}
+
+fn required() -> bool {
+    true
+}
+
+impl Field {
+    pub fn new(name: &str, schema: ParameterSchema, doc: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            required: true,
+            default: None,
+            doc: doc.to_string(),
+            schema,
+        }
+    }
+
+    pub fn optional(self: Self) -> Self {
+        Self {
+            required: false,
+            ..self
+        }
+    }
+
+    // Field with a default may be omitted
+    pub fn with_default(self: Self, value: Value) -> Self {
+        Self {
+            required: false,
+            default: Some(value),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    // E.g. "speed.colors[2]", empty for the value itself
+    pub path: String,
+    pub kind: ViolationKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ViolationKind {
+    Missing,
+    Unknown,
+    WrongType(ParameterType, ParameterType), // Expected, then got
+    OutOfRange(f64, Option<f64>, Option<f64>), // Value, min and max
+    NotAllowed(String, Vec<String>),          // Value, then allowed ones
+    Length(usize, Option<usize>, Option<usize>),
+    // Group key differs from the name of its parameter
+    KeyMismatch(String),
+}
+
+impl ParameterSchema {
+    pub fn string() -> Self {
+        Self::String { values: Vec::new() }
+    }
+
+    pub fn one_of(values: &[&str]) -> Self {
+        Self::String {
+            values: values.iter().map(|value| value.to_string()).collect(),
+        }
+    }
+
+    pub fn int(min: Option<i64>, max: Option<i64>) -> Self {
+        Self::Int { min, max }
+    }
+
+    pub fn uint(min: Option<u64>, max: Option<u64>) -> Self {
+        Self::UInt { min, max }
+    }
+
+    pub fn float(min: Option<f64>, max: Option<f64>) -> Self {
+        Self::Float { min, max }
+    }
+
+    pub fn array(items: ParameterSchema) -> Self {
+        Self::Array {
+            items: Box::new(items),
+            min_len: None,
+            max_len: None,
+        }
+    }
+
+    pub fn group(fields: Vec<Field>) -> Self {
+        Self::Group { fields }
+    }
+
+    // Only affects arrays
+    pub fn with_length(self: Self, min: Option<usize>, max: Option<usize>) -> Self {
+        match self {
+            Self::Array { items, .. } => Self::Array {
+                items,
+                min_len: min,
+                max_len: max,
+            },
+            other => other,
+        }
+    }
+
+    pub fn ptype(self: &Self) -> ParameterType {
+        match self {
+            Self::String { .. } => ParameterType::String,
+            Self::Int { .. } => ParameterType::Int,
+            Self::UInt { .. } => ParameterType::UInt,
+            Self::Float { .. } => ParameterType::Float,
+            Self::Array { .. } => ParameterType::Array,
+            Self::Group { .. } => ParameterType::Group,
+        }
+    }
+
+    // Every violation found, paths are relative to the value
+    pub fn validate(self: &Self, value: &Value) -> Vec<Violation> {
+        let mut out = Vec::new();
+        self.check("", value, &mut out);
+
+        out
+    }
+
+    pub fn validate_at(self: &Self, path: &str, value: &Value) -> Vec<Violation> {
+        let mut out = Vec::new();
+        self.check(path, value, &mut out);
+
+        out
+    }
+
+    fn check(self: &Self, path: &str, value: &Value, out: &mut Vec<Violation>) {
+        let violation = |kind| Violation { path: path.to_string(), kind };
+
+        if !self.ptype().accepts(value) {
+            out.push(violation(ViolationKind::WrongType(
+                self.ptype(), ParameterType::of(value)
+            )));
+            return;
+        }
+
+        match (self, value) {
+            (Self::String { values }, Value::String(value))
+                if !values.is_empty() && !values.contains(value) => {
+                out.push(violation(ViolationKind::NotAllowed(
+                    value.clone(), values.clone()
+                )));
+            },
+            (Self::Int { min, max }, value) => {
+                bounds(path, value, min.map(|v| v as f64), max.map(|v| v as f64), out)
+            },
+            (Self::UInt { min, max }, value) => {
+                bounds(path, value, min.map(|v| v as f64), max.map(|v| v as f64), out)
+            },
+            (Self::Float { min, max }, value) => bounds(path, value, *min, *max, out),
+            (Self::Array { items, min_len, max_len }, Value::Array(values)) => {
+                let len = values.len();
+
+                if min_len.is_some_and(|min| len < min)
+                    || max_len.is_some_and(|max| max < len) {
+                    out.push(violation(ViolationKind::Length(len, *min_len,
+                                                             *max_len)));
+                }
+
+                for (index, item) in values.iter().enumerate() {
+                    items.check(&format!("{}[{}]", path, index), item, out);
+                }
+            },
+            (Self::Group { fields }, Value::Group(group)) => {
+                let mut entries: Vec<(&str, &Parameter)> = group.iter()
+                    .map(|(key, parameter)| (key.as_str(), parameter))
+                    .collect();
+                entries.sort_by_key(|(key, _)| *key);
+
+                check_group(path, fields, entries, out);
+            },
+            _ => {},
+        }
+    }
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+fn bounds(path: &str, value: &Value, min: Option<f64>, max: Option<f64>,
+          out: &mut Vec<Violation>) {
+    let number = match value {
+        Value::Int(value) => *value as f64,
+        Value::UInt(value) => *value as f64,
+        Value::Float(value) => *value,
+        _ => return,
+    };
+
+    if min.is_some_and(|min| number < min) || max.is_some_and(|max| max < number) {
+        out.push(Violation {
+            path: path.to_string(),
+            kind: ViolationKind::OutOfRange(number, min, max),
+        });
+    }
+}
+
+fn check_group(path: &str, fields: &[Field], entries: Vec<(&str, &Parameter)>,
+               out: &mut Vec<Violation>) {
+    for (key, parameter) in entries.iter() {
+        let inner = join(path, key);
+
+        if *key != parameter.name {
+            out.push(Violation {
+                path: inner.clone(),
+                kind: ViolationKind::KeyMismatch(parameter.name.clone()),
+            });
+        }
+
+        match fields.iter().find(|field| field.name == *key) {
+            Some(field) => field.schema.check(&inner, &parameter.value, out),
+            None => out.push(Violation {
+                path: inner,
+                kind: ViolationKind::Unknown,
+            }),
+        }
+    }
+
+    for field in fields.iter().filter(|field| field.required) {
+        if !entries.iter().any(|(key, _)| *key == field.name) {
+            out.push(Violation {
+                path: join(path, &field.name),
+                kind: ViolationKind::Missing,
+            });
+        }
+    }
+}
+
+impl Mode {
+    // Parameters of the mode are taken as a group, so schema must be one
+    pub fn validate(self: &Self, schema: &ParameterSchema) -> Result<(), Vec<Violation>> {
+        let mut out = Vec::new();
+
+        match schema {
+            ParameterSchema::Group { fields } => {
+                let entries = self.parameters()
+                    .map(|parameter| (parameter.name.as_str(), parameter))
+                    .collect();
+                check_group("", fields, entries, &mut out);
+            },
+            other => out.push(Violation {
+                path: String::new(),
+                kind: ViolationKind::WrongType(other.ptype(),
+                                               ParameterType::Group),
+            }),
+        }
+
+        if out.is_empty() {
+            Ok(())
+        } else {
+            Err(out)
+        }
+    }
+}
+
+fn limits<T: std::fmt::Display>(min: &Option<T>, max: &Option<T>) -> String {
+    match (min, max) {
+        (Some(min), Some(max)) => format!("within [{}, {}]", min, max),
+        (Some(min), None) => format!("at least {}", min),
+        (None, Some(max)) => format!("at most {}", max),
+        (None, None) => "unbounded".to_string(),
+    }
+}
+
+impl std::fmt::Display for ViolationKind {
+    fn fmt(self: &Self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Missing => write!(f, "is missing"),
+            Self::Unknown => write!(f, "isn't expected"),
+            Self::WrongType(expected, got) => {
+                write!(f, "must be {}, got {}", expected, got)
+            },
+            Self::OutOfRange(value, min, max) => {
+                write!(f, "is {}, must be {}", value, limits(min, max))
+            },
+            Self::NotAllowed(value, allowed) => {
+                write!(f, "is \"{}\", must be one of {}", value, allowed.join(", "))
+            },
+            Self::Length(len, min, max) => {
+                write!(f, "has {} items, must have {}", len, limits(min, max))
+            },
+            Self::KeyMismatch(name) => {
+                write!(f, "holds parameter named \"{}\"", name)
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(self: &Self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "Value {}", self.kind)
+        } else {
+            write!(f, "\"{}\" {}", self.path, self.kind)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn group(parameters: Vec<(&str, Value)>) -> Value {
+        Value::Group(parameters.into_iter()
+            .map(|(name, value)| {
+                (name.to_string(), Parameter::new(name.to_string(), value))
+            })
+            .collect())
+    }
+
+    fn mode(parameters: Vec<(&str, Value)>) -> Mode {
+        Mode::new("mock".to_string(), "party".to_string(),
+                  parameters.into_iter()
+                      .map(|(name, value)| Parameter::new(name.to_string(), value))
+                      .collect())
+    }
+
+    // Speed is a group holding a rate and a list of colors
+    fn schema() -> ParameterSchema {
+        ParameterSchema::group(vec![
+            Field::new("speed", ParameterSchema::group(vec![
+                Field::new("rate", ParameterSchema::float(Some(0.0), Some(10.0)),
+                           "Changes per second"),
+                Field::new("colors", ParameterSchema::array(
+                    ParameterSchema::uint(None, Some(0xFFFFFF))
+                ).with_length(Some(1), None), "RGB values"),
+            ]), ""),
+            Field::new("style", ParameterSchema::one_of(&["smooth", "jump"]), "")
+                .optional(),
+        ])
+    }
+
+    fn paths(result: Result<(), Vec<Violation>>) -> Vec<(String, ViolationKind)> {
+        result.expect_err("Must fail").into_iter()
+            .map(|violation| (violation.path, violation.kind))
+            .collect()
+    }
+
+    #[test]
+    fn valid() {
+        let mode = mode(vec![
+            ("speed", group(vec![("rate", Value::UInt(2)),
+                                 ("colors", Value::Array(vec![Value::UInt(0xFF)]))])),
+            ("style", Value::String("jump".to_string())),
+        ]);
+
+        assert!(mode.validate(&schema()).is_ok());
+    }
+
+    #[test]
+    fn nested_paths() {
+        let mode = mode(vec![
+            ("speed", group(vec![
+                ("rate", Value::Float(20.0)),
+                ("colors", Value::Array(vec![Value::UInt(1), Value::UInt(2),
+                                            Value::String("red".to_string())])),
+            ])),
+        ]);
+
+        assert_eq!(paths(mode.validate(&schema())), vec![
+            ("speed.colors[2]".to_string(),
+             ViolationKind::WrongType(ParameterType::UInt, ParameterType::String)),
+            ("speed.rate".to_string(),
+             ViolationKind::OutOfRange(20.0, Some(0.0), Some(10.0))),
+        ]);
+    }
+
+    #[test]
+    fn fields() {
+        let mode = mode(vec![
+            ("style", Value::String("wave".to_string())),
+            ("extra", Value::Int(1)),
+        ]);
+
+        assert_eq!(paths(mode.validate(&schema())), vec![
+            ("style".to_string(),
+             ViolationKind::NotAllowed("wave".to_string(),
+                                       vec!["smooth".to_string(),
+                                            "jump".to_string()])),
+            ("extra".to_string(), ViolationKind::Unknown),
+            ("speed".to_string(), ViolationKind::Missing),
+        ]);
+    }
+
+    #[test]
+    fn array_length() {
+        let value = group(vec![("rate", Value::Float(1.0)),
+                               ("colors", Value::Array(Vec::new()))]);
+        let ParameterSchema::Group { fields } = schema() else {
+            panic!("Group expected");
+        };
+
+        assert_eq!(fields[0].schema.validate_at("speed", &value), vec![Violation {
+            path: "speed.colors".to_string(),
+            kind: ViolationKind::Length(0, Some(1), None),
+        }]);
+    }
+
+    #[test]
+    fn key_mismatch() {
+        let value = Value::Group(HashMap::from([
+            ("rate".to_string(),
+             Parameter::new("speed".to_string(), Value::Float(1.0))),
+        ]));
+        let schema = ParameterSchema::group(vec![
+            Field::new("rate", ParameterSchema::float(None, None), ""),
+        ]);
+
+        assert_eq!(schema.validate(&value), vec![Violation {
+            path: "rate".to_string(),
+            kind: ViolationKind::KeyMismatch("speed".to_string()),
+        }]);
+    }
+
+    #[test]
+    fn not_a_group() {
+        let result = mode(Vec::new()).validate(&ParameterSchema::string());
+
+        assert_eq!(paths(result), vec![
+            (String::new(),
+             ViolationKind::WrongType(ParameterType::String, ParameterType::Group)),
+        ]);
+    }
+
+    #[test]
+    fn serde() {
+        let json = serde_json::to_value(schema()).expect("Serialized");
+
+        assert_eq!(json["type"], "group");
+        assert_eq!(json["fields"][0]["fields"][1]["items"]["type"], "uint");
+        assert_eq!(json["fields"][1]["values"][0], "smooth");
+        assert_eq!(serde_json::from_value::<ParameterSchema>(json)
+                   .expect("Deserialized"), schema());
+    }
+}
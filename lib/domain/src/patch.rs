@@ -0,0 +1,189 @@
+
+use serde::{Serialize, Deserialize};
+
+use crate::light::{Light, Result};
+use crate::color::Color;
+use crate::brightness::Brightness;
+use crate::mode::Mode;
+use crate::capabilities::Capability;
+
+// Part of the state to be changed, unset fields are left as they are
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LightPatch {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub power: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<Color>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub brightness: Option<Brightness>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<Mode>,
+}
+
+impl LightPatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_power(self: Self, power: bool) -> Self {
+        Self {
+            power: Some(power),
+            ..self
+        }
+    }
+
+    pub fn with_color(self: Self, color: Color) -> Self {
+        Self {
+            color: Some(color),
+            ..self
+        }
+    }
+
+    pub fn with_brightness(self: Self, brightness: Brightness) -> Self {
+        Self {
+            brightness: Some(brightness),
+            ..self
+        }
+    }
+
+    pub fn with_mode(self: Self, mode: Mode) -> Self {
+        Self {
+            mode: Some(mode),
+            ..self
+        }
+    }
+
+    pub fn is_empty(self: &Self) -> bool {
+        self.capabilities().is_empty()
+    }
+
+    // Capabilities the light needs for the patch to be applied
+    pub fn capabilities(self: &Self) -> Vec<Capability> {
+        [
+            (self.power.is_some(), Capability::Power),
+            (self.color.is_some(), Capability::Color),
+            (self.brightness.is_some(), Capability::Brightness),
+            (self.mode.is_some(), Capability::Mode),
+        ].into_iter()
+            .filter_map(|(set, capability)| set.then_some(capability))
+            .collect()
+    }
+
+    // Nothing is changed if the light lacks any of the capabilities
+    pub fn apply(self: &Self, light: &mut Light) -> Result<()> {
+        let mut patched = light.clone();
+
+        if let Some(power) = self.power {
+            patched.set_power(power)?;
+        }
+
+        if let Some(color) = &self.color {
+            patched.set_color(color.clone())?;
+        }
+
+        if let Some(brightness) = &self.brightness {
+            patched.set_brightness(brightness.clone())?;
+        }
+
+        if let Some(mode) = &self.mode {
+            patched.set_mode(mode.clone())?;
+        }
+
+        *light = patched;
+        Ok(())
+    }
+}
+
+impl Light {
+    // Changes turning this light into the other one, parts the other light
+    // doesn't know are left out
+    pub fn diff(self: &Self, other: &Light) -> LightPatch {
+        fn changed<T: PartialEq + Clone>(
+            from: Result<T>,
+            to: Result<T>
+        ) -> Option<T> {
+            match (from, to) {
+                (Ok(from), Ok(to)) if from == to => None,
+                (_, to) => to.ok(),
+            }
+        }
+
+        LightPatch {
+            power: changed(self.get_power(), other.get_power()),
+            color: changed(self.get_color(), other.get_color()).cloned(),
+            brightness: changed(self.get_brightness(), other.get_brightness())
+                .cloned(),
+            mode: changed(self.get_mode(), other.get_mode()).cloned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::temperature::Temperature;
+
+    fn light() -> Light {
+        let mut light = Light::new("test".to_string(), "1".to_string(),
+                                   vec![Capability::Power, Capability::Color,
+                                        Capability::Brightness]);
+        light.set_power(true).expect("Capable");
+        light.set_brightness(Brightness::new(0.5)).expect("Capable");
+
+        light
+    }
+
+    #[test]
+    fn diff() {
+        let light = light();
+        let mut other = light.clone();
+        other.set_brightness(Brightness::new(0.3)).expect("Capable");
+        other.set_color(Temperature::new(4000.0).into()).expect("Capable");
+
+        let patch = light.diff(&other);
+
+        assert_eq!(patch.power, None);
+        assert_eq!(patch.brightness, Some(Brightness::new(0.3)));
+        assert!(patch.color.is_some());
+        assert_eq!(patch.capabilities(),
+                   vec![Capability::Color, Capability::Brightness]);
+        assert!(light.diff(&light).is_empty());
+    }
+
+    #[test]
+    fn apply() {
+        let mut light = light();
+        let mut other = light.clone();
+        other.set_power(false).expect("Capable");
+
+        light.diff(&other).apply(&mut light).expect("Capable");
+
+        assert!(!light.get_power().expect("Set"));
+        assert_eq!(**light.get_brightness().expect("Set"), 0.5);
+    }
+
+    #[test]
+    fn incapable() {
+        let mut light = Light::new("test".to_string(), "1".to_string(),
+                                   vec![Capability::Brightness]);
+        let patch = LightPatch::new()
+            .with_brightness(Brightness::new(0.7))
+            .with_power(true);
+
+        assert!(patch.apply(&mut light).is_err());
+        assert!(light.get_brightness().is_err());
+    }
+
+    #[test]
+    fn serde() {
+        let patch: LightPatch = serde_json::from_str(r#"{ "power": false }"#)
+            .expect("Correct patch");
+
+        assert_eq!(patch, LightPatch::new().with_power(false));
+        assert_eq!(serde_json::to_string(&patch).expect("Serialized"),
+                   r#"{"power":false}"#);
+        assert!(serde_json::from_str::<LightPatch>(r#"{ "volume": 1 }"#)
+                .is_err());
+    }
+}
@@ -0,0 +1,70 @@
+
+use serde::{Serialize, Deserialize};
+
+use crate::light::{Light, ProviderID};
+
+// Named states of several lights, e.g. a room or a mood. Members are
+// identified by their provider ids, their own names don't matter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scene {
+    pub name: String,
+    pub lights: Vec<Light>,
+}
+
+impl Scene {
+    pub fn new(name: String, lights: Vec<Light>) -> Self {
+        Self {
+            name,
+            lights,
+        }
+    }
+
+    pub fn get(self: &Self, id: &ProviderID) -> Option<&Light> {
+        self.lights.iter().find(|light| {
+            light.provider.name == id.name && light.provider.id == id.id
+        })
+    }
+
+    pub fn ids(self: &Self) -> Vec<&ProviderID> {
+        self.lights.iter().map(|light| &light.provider).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capabilities::Capability;
+    use crate::brightness::Brightness;
+
+    fn light(id: &str, brightness: f64) -> Light {
+        let mut light = Light::new("mock".to_string(), id.to_string(),
+                                   vec![Capability::Brightness]);
+        light.set_brightness(Brightness::new(brightness)).expect("Capable");
+
+        light
+    }
+
+    #[test]
+    fn get() {
+        let scene = Scene::new("evening".to_string(),
+                               vec![light("1", 0.2), light("2", 0.8)]);
+        let id = ProviderID::new("mock".to_string(), "2".to_string());
+        let other = ProviderID::new("other".to_string(), "2".to_string());
+
+        assert_eq!(**scene.get(&id).expect("Member")
+                   .get_brightness().expect("Set"), 0.8);
+        assert!(scene.get(&other).is_none());
+        assert_eq!(scene.ids().len(), 2);
+    }
+
+    #[test]
+    fn serialized() {
+        let scene = Scene::new("evening".to_string(), vec![light("1", 0.2)]);
+        let back: Scene = serde_json::from_str(
+            &serde_json::to_string(&scene).expect("Serialized")
+        ).expect("Deserialized");
+
+        assert_eq!(back.name, "evening");
+        assert_eq!(back.lights[0].provider.id, "1");
+    }
+}
@@ -1,28 +1,88 @@
-use std::path::Path;
+
+use std::path::{Path, PathBuf};
 use std::io::Write;
+use std::time::UNIX_EPOCH;
+use serde::{Serialize, Deserialize, de::DeserializeOwned};
 use serde_json as json;
 
 use domain::light::Light;
+use domain::scene::Scene;
+use domain::group::Group;
+use domain::history::History;
+use domain::light::ProviderID;
 use local_registry::{
     Registry,
+    Revision,
+    Section,
     Error,
+    ErrorType,
     Result,
+    REVISIONS,
 };
 
 const DUMPS: &str = "dumps";
-const DEFULATS: &str = "defaults";
+const DEFAULTS: &str = "defaults";
+const SCENES: &str = "scenes";
+const GROUPS: &str = "groups";
+const HISTORY: &str = "history";
+const EXTENSION: &str = "json";
+
+// Every saved light is a directory of its revisions, "<number>.json" each.
+// Lights saved as plain "<name>.json" files by earlier versions aren't
+// read, listing or loading them fails until `migrate` moves them into
+// their directories
+pub struct JSONRegistry {
+    location: PathBuf,
+    revisions: usize,
+}
+
+// Files which couldn't be read as lights, or whose lights are saved with
+// revisions already, are left in place
+#[derive(Debug, Default)]
+pub struct Migration {
+    pub moved: usize,
+    pub skipped: Vec<(PathBuf, Error)>,
+}
 
-struct JSONRegistry {
-    location: Path,
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Settings {
+    pub revisions: Option<usize>,
+}
+
+fn subdir(section: Section) -> &'static str {
+    match section {
+        Section::Dumps => DUMPS,
+        Section::Defaults => DEFAULTS,
+    }
 }
 
 impl JSONRegistry {
+    pub fn new<P: AsRef<Path>>(location: P) -> Self {
+        Self {
+            location: location.as_ref().to_path_buf(),
+            revisions: REVISIONS,
+        }
+    }
+
+    // At least the latest revision is kept
+    pub fn with_revisions(self: Self, revisions: usize) -> Self {
+        Self {
+            revisions: revisions.max(1),
+            ..self
+        }
+    }
+
+    pub fn location(self: &Self) -> &Path {
+        &self.location
+    }
+
     fn ensure_path<P: AsRef<Path>>(self: &Self, path: P) -> Result<()> {
-        if self.location.exists() {
+        if path.as_ref().exists() {
             Ok(())
         } else {
             match std::fs::create_dir_all(path) {
-                Err(err) => return Error::internal(self.name(), Box::new(err)),
+                Err(err) => Error::internal(self.name(), Box::new(err)),
                 Ok(_) => Ok(()),
             }
         }
@@ -31,31 +91,50 @@ impl JSONRegistry {
     fn ensure_paths(self: &Self) -> Result<()> {
         self.ensure_path(&self.location)?;
         self.ensure_path(self.location.join(DUMPS))?;
-        self.ensure_path(self.location.join(DEFULATS))
+        self.ensure_path(self.location.join(DEFAULTS))?;
+        self.ensure_path(self.location.join(SCENES))?;
+        self.ensure_path(self.location.join(GROUPS))?;
+        self.ensure_path(self.location.join(HISTORY))
     }
 
-    fn check_light(self: &Self, light: &Light) -> Result<()> {
-        if "" == light.name {
+    // Names become file and directory names, so none may lead out of
+    // the registry
+    fn check_name(self: &Self, name: &str) -> Result<()> {
+        if name.is_empty() {
             Error::unnamed(self.name())
+        } else if name == "." || name == ".."
+            || name.contains(['/', '\\', '\0']) {
+            Error::incorrect_name(self.name(), name)
         } else {
             Ok(())
         }
     }
 
-    fn dump_to_file(self: &Self, subdir: &str, light: &Light) -> Result<()> {
-        self.check_light(light)?;
-        self.ensure_paths()?;
-
-        let path = self.location
+    fn file_path(self: &Self, subdir: &str, name: &str) -> PathBuf {
+        self.location
             .join(subdir)
-            .join(format!("{}.json", light.name));
+            .join(format!("{}.{}", name, EXTENSION))
+    }
 
+    fn io<T>(self: &Self, result: std::io::Result<T>) -> Result<T> {
+        result.or_else(|err| Error::internal(self.name(), Box::new(err)))
+    }
+
+    fn dump_to_file<T: Serialize>(self: &Self, subdir: &str, name: &str,
+                                  value: &T) -> Result<()> {
+        self.check_name(name)?;
+        self.ensure_paths()?;
+        self.write_file(&self.file_path(subdir, name), value)
+    }
+
+    fn write_file<T: Serialize>(self: &Self, path: &Path,
+                                value: &T) -> Result<()> {
         match std::fs::File::create(path) {
             Err(err) => Error::internal(self.name(), Box::new(err)),
             Ok(file) => {
                 let mut writer = std::io::BufWriter::new(file);
 
-                if let Err(err) = json::to_writer(&mut writer, &light) {
+                if let Err(err) = json::to_writer(&mut writer, value) {
                     Error::internal(self.name(), Box::new(err))
                 } else if let Err(err) = writer.flush() {
                     Error::internal(self.name(), Box::new(err))
@@ -65,6 +144,319 @@ impl JSONRegistry {
             }
         }
     }
+
+    fn read_file<T: DeserializeOwned>(self: &Self, path: &Path) -> Result<T> {
+        match std::fs::File::open(path) {
+            Err(err) => Error::internal(self.name(), Box::new(err)),
+            Ok(file) => {
+                let reader = std::io::BufReader::new(file);
+
+                json::from_reader(reader)
+                    .or_else(|err| Error::internal(self.name(), Box::new(err)))
+            }
+        }
+    }
+
+    fn load_from_file<T: DeserializeOwned>(self: &Self, subdir: &str,
+                                           name: &str) -> Result<T> {
+        self.check_name(name)?;
+        let path = self.file_path(subdir, name);
+
+        if path.is_file() {
+            self.read_file(&path)
+        } else {
+            Error::not_found(self.name(), name)
+        }
+    }
+
+    // Files directly inside of a directory, sorted by name
+    fn json_files(self: &Self, subdir: &str) -> Result<Vec<PathBuf>> {
+        let path = self.location.join(subdir);
+
+        if !path.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut paths = Vec::new();
+
+        for entry in self.io(std::fs::read_dir(path))? {
+            let path = self.io(entry)?.path();
+
+            if path.is_file()
+               && path.extension().is_some_and(|ext| ext == EXTENSION) {
+                paths.push(path);
+            }
+        }
+
+        // Directory order is platform dependent
+        paths.sort();
+        Ok(paths)
+    }
+
+    fn list_directory<T: DeserializeOwned>(self: &Self,
+                                           subdir: &str) -> Result<Vec<T>> {
+        self.json_files(subdir)?.iter()
+            .map(|path| self.read_file(path))
+            .collect()
+    }
+
+    fn remove_file(self: &Self, subdir: &str, name: &str) -> Result<bool> {
+        let path = self.file_path(subdir, name);
+
+        if !path.is_file() {
+            Ok(false)
+        } else if let Err(err) = std::fs::remove_file(path) {
+            Error::internal(self.name(), Box::new(err))
+        } else {
+            Ok(true)
+        }
+    }
+
+    fn revision_dir(self: &Self, section: Section, name: &str) -> PathBuf {
+        self.location.join(subdir(section)).join(name)
+    }
+
+    fn revision_path(self: &Self, section: Section, name: &str,
+                     number: u64) -> PathBuf {
+        self.revision_dir(section, name)
+            .join(format!("{}.{}", number, EXTENSION))
+    }
+
+    fn is_saved(self: &Self, section: Section, name: &str) -> bool {
+        self.revision_dir(section, name).is_dir()
+    }
+
+    // Ascending, files not named by a number are ignored
+    fn revision_numbers(self: &Self, section: Section,
+                        name: &str) -> Result<Vec<u64>> {
+        let dir = self.revision_dir(section, name);
+
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut numbers = Vec::new();
+
+        for entry in self.io(std::fs::read_dir(dir))? {
+            let path = self.io(entry)?.path();
+
+            if path.extension().is_some_and(|ext| ext == EXTENSION) {
+                if let Some(number) = path.file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(|stem| stem.parse().ok()) {
+                    numbers.push(number);
+                }
+            }
+        }
+
+        numbers.sort_unstable();
+        Ok(numbers)
+    }
+
+    fn latest(self: &Self, section: Section,
+              name: &str) -> Result<Option<Revision>> {
+        match self.revision_numbers(section, name)?.last() {
+            Some(&number) => {
+                self.read_file(&self.revision_path(section, name, number))
+                    .map(Some)
+            },
+            None => Ok(None),
+        }
+    }
+
+    // Flat files aren't read anymore, but they aren't hidden either
+    fn unmigrated<T>(self: &Self, path: &Path) -> Result<T> {
+        Error::internal(self.name(), format!(
+            "\"{}\" is saved by an earlier version, run `lighting migrate`",
+            path.display()
+        ).into())
+    }
+
+    // Nothing is saved next to a flat file of the same name, so migration
+    // doesn't find revisions newer than the file
+    fn check_migrated(self: &Self, section: Section, name: &str) -> Result<()> {
+        let flat = self.file_path(subdir(section), name);
+
+        if flat.is_file() {
+            self.unmigrated(&flat)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn load_latest(self: &Self, section: Section, name: &str) -> Result<Light> {
+        self.check_name(name)?;
+        self.check_migrated(section, name)?;
+
+        match self.latest(section, name)? {
+            Some(revision) => Ok(revision.light),
+            None => Error::not_found(self.name(), name),
+        }
+    }
+
+    fn list_latest(self: &Self, section: Section) -> Result<Vec<Light>> {
+        if let Some(flat) = self.list_flat(section)?.first() {
+            return self.unmigrated(flat);
+        }
+
+        let mut lights = Vec::new();
+
+        for name in self.list_names(section)? {
+            if let Some(revision) = self.latest(section, &name)? {
+                lights.push(revision.light);
+            }
+        }
+
+        Ok(lights)
+    }
+
+    // Writes the revision following the latest one and drops the oldest
+    // ones, time of the revision may be given
+    fn push_revision(self: &Self, section: Section, light: &Light,
+                     message: Option<&str>, at: Option<i64>) -> Result<()> {
+        let dir = self.revision_dir(section, &light.name);
+        self.ensure_path(&dir)?;
+
+        let latest = self.latest(section, &light.name)?;
+
+        if let Some(mut revision) = Revision::follow(latest.as_ref(), light,
+                                                     message) {
+            revision.at = at.unwrap_or(revision.at);
+            self.write_file(&self.revision_path(section, &light.name,
+                                                revision.number),
+                            &revision)?;
+        }
+
+        let numbers = self.revision_numbers(section, &light.name)?;
+
+        for number in numbers.iter().take(numbers.len()
+                                          .saturating_sub(self.revisions)) {
+            let path = self.revision_path(section, &light.name, *number);
+            self.io(std::fs::remove_file(path))?;
+        }
+
+        Ok(())
+    }
+
+    fn remove_revisions(self: &Self, section: Section,
+                        name: &str) -> Result<bool> {
+        if self.is_saved(section, name) {
+            self.io(std::fs::remove_dir_all(self.revision_dir(section, name)))
+                .map(|_| true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    // Name is stored inside of every revision too, so they are rewritten
+    // after the move
+    fn rename_revisions(self: &Self, section: Section, old: &str,
+                        new: &str) -> Result<()> {
+        if !self.is_saved(section, old) {
+            return Ok(());
+        }
+
+        self.io(std::fs::rename(self.revision_dir(section, old),
+                                self.revision_dir(section, new)))?;
+
+        for number in self.revision_numbers(section, new)? {
+            let path = self.revision_path(section, new, number);
+            let mut revision: Revision = self.read_file(&path)?;
+            revision.light.name = new.to_string();
+            self.write_file(&path, &revision)?;
+        }
+
+        Ok(())
+    }
+
+    // Registry trait only loads histories by id
+    pub fn list_histories(self: &Self) -> Result<Vec<History>> {
+        self.list_directory(HISTORY)
+    }
+
+    // Names of lights saved with revisions, flat files aside, directories
+    // without any revision aren't lights
+    pub fn list_names(self: &Self, section: Section) -> Result<Vec<String>> {
+        let path = self.location.join(subdir(section));
+
+        if !path.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut names = Vec::new();
+
+        for entry in self.io(std::fs::read_dir(path))? {
+            let path = self.io(entry)?.path();
+            let name = path.file_name().and_then(|name| name.to_str());
+
+            if let (true, Some(name)) = (path.is_dir(), name) {
+                if !self.revision_numbers(section, name)?.is_empty() {
+                    names.push(name.to_string());
+                }
+            }
+        }
+
+        names.sort();
+        Ok(names)
+    }
+
+    // Files of lights saved in the flat layout by earlier versions
+    pub fn list_flat(self: &Self, section: Section) -> Result<Vec<PathBuf>> {
+        self.json_files(subdir(section))
+    }
+
+    // Light saved in the flat layout, along with the time it was saved at.
+    // Revisions of the same name are newer than the file, so it's refused
+    // rather than put on top of them
+    pub fn read_flat(self: &Self, section: Section,
+                     path: &Path) -> Result<(Light, i64)> {
+        let light: Light = self.read_file(path)?;
+        self.check_name(&light.name)?;
+
+        if self.is_saved(section, &light.name) {
+            return Error::exists(self.name(), &light.name);
+        }
+
+        let at = self.io(std::fs::metadata(path)
+                         .and_then(|meta| meta.modified()))?
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs() as i64);
+
+        Ok((light, at))
+    }
+
+    // Moves lights saved in the flat layout into revision directories as
+    // their only revisions, never done implicitly
+    pub fn migrate(self: &mut Self) -> Result<Migration> {
+        let mut migration = Migration::default();
+
+        for section in [Section::Dumps, Section::Defaults] {
+            for path in self.list_flat(section)? {
+                match self.read_flat(section, &path) {
+                    Err(err) => migration.skipped.push((path, err)),
+                    Ok((light, at)) => {
+                        self.push_revision(section, &light, None, Some(at))?;
+                        self.io(std::fs::remove_file(path))?;
+                        migration.moved += 1;
+                    },
+                }
+            }
+        }
+
+        Ok(migration)
+    }
+}
+
+// Ids are given by providers and may contain anything, so everything but
+// a few safe characters is percent encoded
+fn history_name(id: &ProviderID) -> String {
+    id.to_string().bytes()
+        .map(|byte| match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9'
+            | b'-' | b'_' | b'@' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
 }
 
 impl Registry for JSONRegistry {
@@ -73,42 +465,466 @@ impl Registry for JSONRegistry {
     }
 
     fn list_defaults(self: &Self) -> Result<Vec<Light>> {
-        todo!()
+        self.list_latest(Section::Defaults)
     }
 
     fn list_dumps(self: &Self) -> Result<Vec<Light>> {
-        todo!()
+        self.list_latest(Section::Dumps)
     }
 
     fn load_default(self: &Self, name: &str) -> Result<Light> {
-        todo!()
+        self.load_latest(Section::Defaults, name)
     }
 
     fn load_dump(self: &Self, name: &str) -> Result<Light> {
-        todo!()
+        self.load_latest(Section::Dumps, name)
+    }
+
+    fn store(self: &mut Self, section: Section, light: &Light,
+             message: Option<&str>) -> Result<()> {
+        self.check_name(&light.name)?;
+        self.check_migrated(section, &light.name)?;
+        self.ensure_paths()?;
+        self.push_revision(section, light, message, None)
+    }
+
+    fn list_revisions(self: &Self, section: Section,
+                      name: &str) -> Result<Vec<Revision>> {
+        self.check_name(name)?;
+
+        let numbers = self.revision_numbers(section, name)?;
+
+        if numbers.is_empty() {
+            return Error::not_found(self.name(), name);
+        }
+
+        numbers.into_iter()
+            .map(|number| {
+                self.read_file(&self.revision_path(section, name, number))
+            })
+            .collect()
+    }
+
+    fn load_revision(self: &Self, section: Section, name: &str,
+                     number: u64) -> Result<Revision> {
+        self.check_name(name)?;
+
+        let path = self.revision_path(section, name, number);
+
+        if path.is_file() {
+            self.read_file(&path)
+        } else if self.is_saved(section, name) {
+            Error::no_revision(self.name(), name, number)
+        } else {
+            Error::not_found(self.name(), name)
+        }
+    }
+
+    fn remove(self: &mut Self, name: &str) -> Result<()> {
+        self.check_name(name)?;
+        let dump = self.remove_revisions(Section::Dumps, name)?;
+        let default = self.remove_revisions(Section::Defaults, name)?;
+
+        if dump || default {
+            Ok(())
+        } else {
+            Error::not_found(self.name(), name)
+        }
+    }
+
+    fn rename(self: &mut Self, old: &str, new: &str) -> Result<()> {
+        self.check_name(old)?;
+        self.check_name(new)?;
+
+        let exists = |name: &str| {
+            self.is_saved(Section::Dumps, name)
+            || self.is_saved(Section::Defaults, name)
+        };
+
+        if !exists(old) {
+            return Error::not_found(self.name(), old);
+        } else if old == new {
+            return Ok(());
+        } else if exists(new) {
+            return Error::exists(self.name(), new);
+        }
+
+        self.rename_revisions(Section::Dumps, old, new)?;
+        self.rename_revisions(Section::Defaults, old, new)
+    }
+
+    fn list_scenes(self: &Self) -> Result<Vec<Scene>> {
+        self.list_directory(SCENES)
+    }
+
+    fn load_scene(self: &Self, name: &str) -> Result<Scene> {
+        self.load_from_file(SCENES, name)
+    }
+
+    fn save_scene(self: &mut Self, scene: &Scene) -> Result<()> {
+        self.dump_to_file(SCENES, &scene.name, scene)
+    }
+
+    fn remove_scene(self: &mut Self, name: &str) -> Result<()> {
+        self.check_name(name)?;
+
+        if self.remove_file(SCENES, name)? {
+            Ok(())
+        } else {
+            Error::not_found(self.name(), name)
+        }
+    }
+
+    fn list_groups(self: &Self) -> Result<Vec<Group>> {
+        self.list_directory(GROUPS)
+    }
+
+    fn load_group(self: &Self, name: &str) -> Result<Group> {
+        self.load_from_file(GROUPS, name)
+    }
+
+    fn save_group(self: &mut Self, group: &Group) -> Result<()> {
+        self.dump_to_file(GROUPS, &group.name, group)
+    }
+
+    fn remove_group(self: &mut Self, name: &str) -> Result<()> {
+        self.check_name(name)?;
+
+        if self.remove_file(GROUPS, name)? {
+            Ok(())
+        } else {
+            Error::not_found(self.name(), name)
+        }
+    }
+
+    fn load_history(self: &Self, id: &ProviderID) -> Result<History> {
+        match self.load_from_file(HISTORY, &history_name(id)) {
+            Err(Error { etype: ErrorType::NotFound(_), .. }) => {
+                Ok(History::new(id.clone()))
+            },
+            result => result,
+        }
+    }
+
+    fn save_history(self: &mut Self, history: &History) -> Result<()> {
+        self.dump_to_file(HISTORY, &history_name(&history.id), history)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::capabilities::Capability;
+    use domain::brightness::Brightness;
+    use local_registry::ErrorType;
+
+    fn light(name: &str, id: &str) -> Light {
+        let mut light = Light::named("test".to_string(), id.to_string(),
+                                     vec![Capability::Brightness],
+                                     name.to_string());
+        light.set_brightness(Brightness::new(0.5)).expect("Capable");
+
+        light
+    }
+
+    fn registry() -> (tempfile::TempDir, JSONRegistry) {
+        let dir = tempfile::tempdir().expect("Temporary directory");
+        let registry = JSONRegistry::new(dir.path().join("registry"));
+
+        (dir, registry)
+    }
+
+    fn names(lights: Vec<Light>) -> Vec<String> {
+        lights.into_iter().map(|light| light.name).collect()
+    }
+
+    #[test]
+    fn empty_listing() {
+        let (_dir, registry) = registry();
+
+        assert!(registry.list_dumps().expect("Empty list").is_empty());
+        assert!(registry.list_defaults().expect("Empty list").is_empty());
+    }
+
+    #[test]
+    fn dump_and_load() {
+        let (_dir, mut registry) = registry();
+        registry.dump(&light("lamp", "1")).expect("Saved");
+
+        let loaded = registry.load_dump("lamp").expect("Loaded");
+
+        assert_eq!(loaded.name, "lamp");
+        assert_eq!(loaded.provider.id, "1");
+        assert_eq!(**loaded.get_brightness().expect("Set"), 0.5);
+        assert!(registry.location().join(DUMPS).is_dir());
+    }
+
+    #[test]
+    fn dump_is_not_default() {
+        let (_dir, mut registry) = registry();
+        registry.dump(&light("lamp", "1")).expect("Saved");
+
+        assert!(matches!(registry.load_default("lamp"),
+                         Err(Error { etype: ErrorType::NotFound(_), .. })));
     }
 
-    fn dump(self: &mut Self, light: &Light) -> Result<()> {
-        self.dump_to_file(DUMPS, light)
+    #[test]
+    fn list_sorted() {
+        let (_dir, mut registry) = registry();
+        registry.default(&light("b", "2")).expect("Saved");
+        registry.default(&light("a", "1")).expect("Saved");
+
+        assert_eq!(names(registry.list_defaults().expect("Listed")),
+                   vec!["a", "b"]);
+        assert!(registry.list_dumps().expect("Listed").is_empty());
     }
 
-    fn default(self: &mut Self, light: &Light) -> Result<()> {
-        self.dump_to_file(DEFULATS, light)
+    #[test]
+    fn unnamed() {
+        let (_dir, mut registry) = registry();
+
+        assert!(matches!(registry.dump(&light("", "1")),
+                         Err(Error { etype: ErrorType::Unnamed, .. })));
+        assert!(matches!(registry.load_dump(""),
+                         Err(Error { etype: ErrorType::Unnamed, .. })));
     }
 
-    fn remove_dump(self: &mut Self, name: &str) -> Result<()> {
-        todo!()
+    #[test]
+    fn outside_names() {
+        let (_dir, mut registry) = registry();
+        registry.dump(&light("lamp", "1")).expect("Saved");
+
+        for name in ["..", ".", "../lamp", "dumps\\lamp", "lamp\0"] {
+            assert!(matches!(registry.remove(name),
+                             Err(Error { etype: ErrorType::IncorrectName(_),
+                                         .. })));
+        }
+
+        assert!(matches!(registry.dump(&light("../lamp", "1")),
+                         Err(Error { etype: ErrorType::IncorrectName(_), .. })));
+        assert!(matches!(registry.rename("lamp", "../desk"),
+                         Err(Error { etype: ErrorType::IncorrectName(_), .. })));
+        assert!(matches!(registry.load_scene("../dumps/lamp/1"),
+                         Err(Error { etype: ErrorType::IncorrectName(_), .. })));
+        assert!(registry.location().join(DUMPS).join("lamp").is_dir());
     }
 
-    fn remove_default(self: &mut Self, name: &str) -> Result<()> {
-        todo!()
+    #[test]
+    fn load_missing() {
+        let (_dir, registry) = registry();
+
+        assert!(matches!(registry.load_dump("lamp"),
+                         Err(Error { etype: ErrorType::NotFound(_), .. })));
     }
 
-    fn rename_dump(self: &mut Self, old: &str, new: &str) -> Result<()> {
-        todo!()
+    #[test]
+    fn remove_both() {
+        let (_dir, mut registry) = registry();
+        registry.dump(&light("lamp", "1")).expect("Saved");
+        registry.default(&light("lamp", "1")).expect("Saved");
+
+        registry.remove("lamp").expect("Removed");
+
+        assert!(registry.list_dumps().expect("Listed").is_empty());
+        assert!(registry.list_defaults().expect("Listed").is_empty());
     }
 
-    fn rename_default(self: &mut Self, old: &str, new: &str) -> Result<()> {
-        todo!()
+    #[test]
+    fn remove_missing() {
+        let (_dir, mut registry) = registry();
+
+        assert!(matches!(registry.remove("lamp"),
+                         Err(Error { etype: ErrorType::NotFound(_), .. })));
+    }
+
+    #[test]
+    fn rename() {
+        let (_dir, mut registry) = registry();
+        registry.dump(&light("lamp", "1")).expect("Saved");
+        registry.default(&light("lamp", "1")).expect("Saved");
+
+        registry.rename("lamp", "desk").expect("Renamed");
+
+        assert_eq!(names(registry.list_dumps().expect("Listed")), vec!["desk"]);
+        assert_eq!(registry.load_default("desk").expect("Loaded").name, "desk");
+        assert!(registry.load_dump("lamp").is_err());
+    }
+
+    #[test]
+    fn rename_collision() {
+        let (_dir, mut registry) = registry();
+        registry.dump(&light("lamp", "1")).expect("Saved");
+        registry.default(&light("desk", "2")).expect("Saved");
+
+        assert!(matches!(registry.rename("lamp", "desk"),
+                         Err(Error { etype: ErrorType::Exists(_), .. })));
+        assert_eq!(registry.load_dump("lamp").expect("Untouched").name, "lamp");
+    }
+
+    #[test]
+    fn scenes() {
+        let (_dir, mut registry) = registry();
+        let scene = Scene::new("night".to_string(),
+                               vec![light("", "1"), light("", "2")]);
+        registry.save_scene(&scene).expect("Saved");
+        registry.dump(&light("night", "1")).expect("Saved");
+
+        let loaded = registry.load_scene("night").expect("Loaded");
+        assert_eq!(loaded.lights.len(), 2);
+        assert_eq!(loaded.lights[1].provider.id, "2");
+        assert_eq!(registry.list_scenes().expect("Listed").len(), 1);
+        // Scenes aren't listed as lights
+        assert_eq!(names(registry.list_dumps().expect("Listed")), vec!["night"]);
+
+        registry.remove_scene("night").expect("Removed");
+        assert!(registry.list_scenes().expect("Listed").is_empty());
+        assert!(matches!(registry.load_scene("night"),
+                         Err(Error { etype: ErrorType::NotFound(_), .. })));
+        assert!(registry.load_dump("night").is_ok());
+    }
+
+    #[test]
+    fn groups() {
+        use domain::group::Member;
+
+        let (_dir, mut registry) = registry();
+        let group = Group::new("kitchen".to_string(), vec![
+            Member::Saved("desk".to_string()),
+            Member::Group("hall".to_string()),
+        ]);
+        registry.save_group(&group).expect("Saved");
+
+        let loaded = registry.load_group("kitchen").expect("Loaded");
+        assert_eq!(loaded.members, group.members);
+        assert_eq!(registry.list_groups().expect("Listed").len(), 1);
+
+        registry.remove_group("kitchen").expect("Removed");
+        assert!(matches!(registry.remove_group("kitchen"),
+                         Err(Error { etype: ErrorType::NotFound(_), .. })));
+    }
+
+    #[test]
+    fn history() {
+        use domain::history::Entry;
+
+        let (_dir, mut registry) = registry();
+        let id = ProviderID::new("mqtt".to_string(),
+                                 "kitchen/ceiling".to_string());
+        assert!(registry.load_history(&id).expect("Empty").is_empty());
+
+        let mut history = History::new(id.clone());
+        history.record(Entry::new(1, light("", "1")));
+        registry.save_history(&history).expect("Saved");
+
+        let loaded = registry.load_history(&id).expect("Loaded");
+        assert_eq!(loaded.undo.len(), 1);
+        assert_eq!(loaded.id, id);
+        assert!(registry.location().join(HISTORY)
+                .join("kitchen%2Fceiling@mqtt.json").is_file());
+    }
+
+    #[test]
+    fn revisions() {
+        let (_dir, registry) = registry();
+        let mut registry = registry.with_revisions(2);
+        let lamp_path = registry.location().join(DUMPS).join("lamp");
+
+        for level in [0.1, 0.2, 0.3] {
+            let mut lamp = light("lamp", "1");
+            lamp.set_brightness(Brightness::new(level)).expect("Capable");
+            registry.store(Section::Dumps, &lamp, Some("step"))
+                .expect("Saved");
+        }
+
+        let revisions = registry.list_revisions(Section::Dumps, "lamp")
+            .expect("Listed");
+        let numbers: Vec<u64> = revisions.iter()
+            .map(|revision| revision.number)
+            .collect();
+        assert_eq!(numbers, vec![2, 3]);
+        assert!(lamp_path.join("3.json").is_file());
+        assert!(!lamp_path.join("1.json").exists());
+        assert_eq!(**registry.load_dump("lamp").expect("Loaded")
+                   .get_brightness().expect("Set"), 0.3);
+
+        let patch = registry.diff_revisions(Section::Dumps, "lamp", 2, 3)
+            .expect("Compared");
+        assert_eq!(patch.brightness, Some(Brightness::new(0.3)));
+        assert!(matches!(registry.load_revision(Section::Dumps, "lamp", 1),
+                         Err(Error { etype: ErrorType::NotFound(_), .. })));
+
+        registry.rename("lamp", "desk").expect("Renamed");
+        let revision = registry.load_revision(Section::Dumps, "desk", 2)
+            .expect("Moved");
+        assert_eq!(revision.light.name, "desk");
+        assert_eq!(revision.message.as_deref(), Some("step"));
+    }
+
+    #[test]
+    fn flat_layout_migrated() {
+        let (_dir, mut registry) = registry();
+        let defaults = registry.location().join(DEFAULTS);
+        std::fs::create_dir_all(&defaults).expect("Created");
+        std::fs::write(defaults.join("lamp.json"),
+                       json::to_string(&light("lamp", "1")).expect("JSON"))
+            .expect("Written");
+        std::fs::write(defaults.join("broken.json"), "{").expect("Written");
+
+        // Reading points to the migration and doesn't touch the flat layout
+        assert!(registry.list_defaults().is_err());
+        assert!(registry.load_default("lamp").is_err());
+        assert!(registry.default(&light("lamp", "1")).is_err());
+        assert!(defaults.join("lamp.json").is_file());
+
+        let migration = registry.migrate().expect("Migrated");
+        assert_eq!(migration.moved, 1);
+        assert_eq!(migration.skipped.len(), 1);
+        assert_eq!(migration.skipped[0].0, defaults.join("broken.json"));
+        assert!(defaults.join("broken.json").is_file());
+        assert!(!defaults.join("lamp.json").exists());
+
+        // Skipped files are left for the user
+        assert!(registry.list_defaults().is_err());
+        std::fs::remove_file(defaults.join("broken.json")).expect("Removed");
+
+        assert_eq!(names(registry.list_defaults().expect("Listed")),
+                   vec!["lamp"]);
+        let revisions = registry.list_revisions(Section::Defaults, "lamp")
+            .expect("Listed");
+        assert_eq!(revisions.len(), 1);
+        assert_eq!(revisions[0].number, 1);
+        assert!(revisions[0].at > 0);
+        assert_eq!(registry.migrate().expect("Nothing left").moved, 0);
+    }
+
+    #[test]
+    fn flat_behind_revisions() {
+        let (_dir, mut registry) = registry();
+        registry.dump(&light("lamp", "1")).expect("Saved");
+        let dumps = registry.location().join(DUMPS);
+        std::fs::write(dumps.join("lamp.json"),
+                       json::to_string(&light("lamp", "2")).expect("JSON"))
+            .expect("Written");
+
+        // File is older than the revisions, so it's not put on top of them
+        let migration = registry.migrate().expect("Migrated");
+        assert_eq!(migration.moved, 0);
+        assert!(matches!(migration.skipped[0].1,
+                         Error { etype: ErrorType::Exists(_), .. }));
+        assert!(dumps.join("lamp.json").is_file());
+
+        std::fs::remove_file(dumps.join("lamp.json")).expect("Removed");
+        let revisions = registry.list_revisions(Section::Dumps, "lamp")
+            .expect("Listed");
+        assert_eq!(revisions.len(), 1);
+        assert_eq!(revisions[0].light.provider.id, "1");
+    }
+
+    #[test]
+    fn rename_missing() {
+        let (_dir, mut registry) = registry();
+
+        assert!(matches!(registry.rename("lamp", "desk"),
+                         Err(Error { etype: ErrorType::NotFound(_), .. })));
     }
 }
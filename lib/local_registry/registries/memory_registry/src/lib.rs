@@ -0,0 +1,489 @@
+
+use std::collections::HashMap;
+
+use domain::light::Light;
+use domain::scene::Scene;
+use domain::group::Group;
+use domain::history::History;
+use domain::light::ProviderID;
+use local_registry::{
+    Registry,
+    Revision,
+    Section,
+    Error,
+    Result,
+    REVISIONS,
+};
+
+// Revisions of every saved light, oldest first
+type Revisions = HashMap<String, Vec<Revision>>;
+
+pub struct MemoryRegistry {
+    dumps: Revisions,
+    defaults: Revisions,
+    scenes: HashMap<String, Scene>,
+    groups: HashMap<String, Group>,
+    histories: HashMap<ProviderID, History>,
+    revisions: usize,
+    group_saves: usize,
+    fail_on_group: Option<usize>,
+    fail_on_history: bool,
+}
+
+impl Default for MemoryRegistry {
+    fn default() -> Self {
+        Self {
+            dumps: HashMap::new(),
+            defaults: HashMap::new(),
+            scenes: HashMap::new(),
+            groups: HashMap::new(),
+            histories: HashMap::new(),
+            revisions: REVISIONS,
+            group_saves: 0,
+            fail_on_group: None,
+            fail_on_history: false,
+        }
+    }
+}
+
+impl MemoryRegistry {
+    pub fn new() -> Self {
+        <Self as Default>::default()
+    }
+
+    // At least the latest revision is kept
+    pub fn with_revisions(self: Self, revisions: usize) -> Self {
+        Self {
+            revisions: revisions.max(1),
+            ..self
+        }
+    }
+
+    // Saves of groups are counted from 1, the given one fails
+    pub fn fail_on_group(self: Self, save: usize) -> Self {
+        Self {
+            fail_on_group: Some(save),
+            ..self
+        }
+    }
+
+    // Every save of a history fails
+    pub fn fail_on_history(self: Self) -> Self {
+        Self {
+            fail_on_history: true,
+            ..self
+        }
+    }
+
+    fn check_name(self: &Self, name: &str) -> Result<()> {
+        if name.is_empty() {
+            Error::unnamed(self.name())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn exists(self: &Self, name: &str) -> bool {
+        self.dumps.contains_key(name) || self.defaults.contains_key(name)
+    }
+
+    fn section(self: &Self, section: Section) -> &Revisions {
+        match section {
+            Section::Dumps => &self.dumps,
+            Section::Defaults => &self.defaults,
+        }
+    }
+
+    fn list(section: &Revisions) -> Vec<Light> {
+        let mut out: Vec<Light> = section.values()
+            .filter_map(|revisions| revisions.last())
+            .map(|revision| revision.light.clone())
+            .collect();
+        out.sort_by(|a, b| a.name.cmp(&b.name));
+
+        out
+    }
+
+    fn revisions(self: &Self, section: Section,
+                 name: &str) -> Result<&Vec<Revision>> {
+        self.check_name(name)?;
+
+        match self.section(section).get(name) {
+            Some(revisions) => Ok(revisions),
+            None => Error::not_found(self.name(), name),
+        }
+    }
+
+    fn load(self: &Self, section: Section, name: &str) -> Result<Light> {
+        self.revisions(section, name)
+            .map(|revisions| revisions.last().expect("Never empty"))
+            .map(|revision| revision.light.clone())
+    }
+
+    fn rename_in(section: &mut Revisions, old: &str, new: &str) {
+        if let Some(mut revisions) = section.remove(old) {
+            for revision in revisions.iter_mut() {
+                revision.light.name = new.to_string();
+            }
+
+            section.insert(new.to_string(), revisions);
+        }
+    }
+}
+
+impl Registry for MemoryRegistry {
+    fn name(self: &Self) -> &str {
+        "memory"
+    }
+
+    fn list_defaults(self: &Self) -> Result<Vec<Light>> {
+        Ok(Self::list(&self.defaults))
+    }
+
+    fn list_dumps(self: &Self) -> Result<Vec<Light>> {
+        Ok(Self::list(&self.dumps))
+    }
+
+    fn load_default(self: &Self, name: &str) -> Result<Light> {
+        self.load(Section::Defaults, name)
+    }
+
+    fn load_dump(self: &Self, name: &str) -> Result<Light> {
+        self.load(Section::Dumps, name)
+    }
+
+    fn store(self: &mut Self, section: Section, light: &Light,
+             message: Option<&str>) -> Result<()> {
+        self.check_name(&light.name)?;
+        let limit = self.revisions;
+        let revisions = match section {
+            Section::Dumps => &mut self.dumps,
+            Section::Defaults => &mut self.defaults,
+        }.entry(light.name.clone()).or_default();
+
+        if let Some(revision) = Revision::follow(revisions.last(), light,
+                                                 message) {
+            revisions.push(revision);
+        }
+
+        if revisions.len() > limit {
+            revisions.drain(..revisions.len() - limit);
+        }
+
+        Ok(())
+    }
+
+    fn list_revisions(self: &Self, section: Section,
+                      name: &str) -> Result<Vec<Revision>> {
+        self.revisions(section, name).cloned()
+    }
+
+    fn load_revision(self: &Self, section: Section, name: &str,
+                     number: u64) -> Result<Revision> {
+        match self.revisions(section, name)?.iter()
+            .find(|revision| revision.number == number) {
+            Some(revision) => Ok(revision.clone()),
+            None => Error::no_revision(self.name(), name, number),
+        }
+    }
+
+    fn remove(self: &mut Self, name: &str) -> Result<()> {
+        self.check_name(name)?;
+        let dump = self.dumps.remove(name);
+        let default = self.defaults.remove(name);
+
+        if dump.is_some() || default.is_some() {
+            Ok(())
+        } else {
+            Error::not_found(self.name(), name)
+        }
+    }
+
+    fn rename(self: &mut Self, old: &str, new: &str) -> Result<()> {
+        self.check_name(old)?;
+        self.check_name(new)?;
+
+        if !self.exists(old) {
+            return Error::not_found(self.name(), old);
+        } else if old == new {
+            return Ok(());
+        } else if self.exists(new) {
+            return Error::exists(self.name(), new);
+        }
+
+        Self::rename_in(&mut self.dumps, old, new);
+        Self::rename_in(&mut self.defaults, old, new);
+
+        Ok(())
+    }
+
+    fn list_scenes(self: &Self) -> Result<Vec<Scene>> {
+        let mut out: Vec<Scene> = self.scenes.values().cloned().collect();
+        out.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(out)
+    }
+
+    fn load_scene(self: &Self, name: &str) -> Result<Scene> {
+        self.check_name(name)?;
+
+        match self.scenes.get(name) {
+            Some(scene) => Ok(scene.clone()),
+            None => Error::not_found(self.name(), name),
+        }
+    }
+
+    fn save_scene(self: &mut Self, scene: &Scene) -> Result<()> {
+        self.check_name(&scene.name)?;
+        self.scenes.insert(scene.name.clone(), scene.clone());
+
+        Ok(())
+    }
+
+    fn remove_scene(self: &mut Self, name: &str) -> Result<()> {
+        self.check_name(name)?;
+
+        match self.scenes.remove(name) {
+            Some(_) => Ok(()),
+            None => Error::not_found(self.name(), name),
+        }
+    }
+
+    fn list_groups(self: &Self) -> Result<Vec<Group>> {
+        let mut out: Vec<Group> = self.groups.values().cloned().collect();
+        out.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(out)
+    }
+
+    fn load_group(self: &Self, name: &str) -> Result<Group> {
+        self.check_name(name)?;
+
+        match self.groups.get(name) {
+            Some(group) => Ok(group.clone()),
+            None => Error::not_found(self.name(), name),
+        }
+    }
+
+    fn save_group(self: &mut Self, group: &Group) -> Result<()> {
+        self.check_name(&group.name)?;
+        self.group_saves += 1;
+
+        if self.fail_on_group == Some(self.group_saves) {
+            return Error::internal(self.name(), "Injected failure".into());
+        }
+
+        self.groups.insert(group.name.clone(), group.clone());
+
+        Ok(())
+    }
+
+    fn remove_group(self: &mut Self, name: &str) -> Result<()> {
+        self.check_name(name)?;
+
+        match self.groups.remove(name) {
+            Some(_) => Ok(()),
+            None => Error::not_found(self.name(), name),
+        }
+    }
+
+    fn load_history(self: &Self, id: &ProviderID) -> Result<History> {
+        Ok(self.histories.get(id)
+            .cloned()
+            .unwrap_or_else(|| History::new(id.clone())))
+    }
+
+    fn save_history(self: &mut Self, history: &History) -> Result<()> {
+        if self.fail_on_history {
+            return Error::internal(self.name(), "Injected failure".into());
+        }
+
+        self.histories.insert(history.id.clone(), history.clone());
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::capabilities::Capability;
+    use local_registry::ErrorType;
+
+    fn light(name: &str) -> Light {
+        Light::named("test".to_string(), "1".to_string(),
+                     vec![Capability::Brightness], name.to_string())
+    }
+
+    fn names(lights: Vec<Light>) -> Vec<String> {
+        lights.into_iter().map(|light| light.name).collect()
+    }
+
+    #[test]
+    fn dump_and_load() {
+        let mut registry = MemoryRegistry::new();
+        registry.dump(&light("lamp")).expect("Saved");
+
+        assert_eq!(registry.load_dump("lamp").expect("Loaded").name, "lamp");
+        assert!(matches!(registry.load_default("lamp"),
+                         Err(Error { etype: ErrorType::NotFound(_), .. })));
+    }
+
+    #[test]
+    fn list_sorted() {
+        let mut registry = MemoryRegistry::new();
+        registry.default(&light("c")).expect("Saved");
+        registry.default(&light("a")).expect("Saved");
+        registry.default(&light("b")).expect("Saved");
+
+        assert_eq!(names(registry.list_defaults().expect("Listed")),
+                   vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn unnamed() {
+        let mut registry = MemoryRegistry::new();
+
+        assert!(matches!(registry.dump(&light("")),
+                         Err(Error { etype: ErrorType::Unnamed, .. })));
+        assert!(matches!(registry.default(&light("")),
+                         Err(Error { etype: ErrorType::Unnamed, .. })));
+        assert!(matches!(registry.load_dump(""),
+                         Err(Error { etype: ErrorType::Unnamed, .. })));
+        assert!(matches!(registry.remove(""),
+                         Err(Error { etype: ErrorType::Unnamed, .. })));
+        assert!(matches!(registry.rename("a", ""),
+                         Err(Error { etype: ErrorType::Unnamed, .. })));
+    }
+
+    #[test]
+    fn remove() {
+        let mut registry = MemoryRegistry::new();
+        registry.dump(&light("lamp")).expect("Saved");
+        registry.default(&light("lamp")).expect("Saved");
+
+        registry.remove("lamp").expect("Removed");
+
+        assert!(registry.list_dumps().expect("Listed").is_empty());
+        assert!(registry.list_defaults().expect("Listed").is_empty());
+        assert!(matches!(registry.remove("lamp"),
+                         Err(Error { etype: ErrorType::NotFound(_), .. })));
+    }
+
+    #[test]
+    fn rename() {
+        let mut registry = MemoryRegistry::new();
+        registry.dump(&light("lamp")).expect("Saved");
+
+        registry.rename("lamp", "desk").expect("Renamed");
+
+        assert_eq!(names(registry.list_dumps().expect("Listed")), vec!["desk"]);
+        assert!(registry.list_defaults().expect("Listed").is_empty());
+    }
+
+    #[test]
+    fn rename_same() {
+        let mut registry = MemoryRegistry::new();
+        registry.default(&light("lamp")).expect("Saved");
+
+        registry.rename("lamp", "lamp").expect("Nothing to do");
+
+        assert_eq!(names(registry.list_defaults().expect("Listed")),
+                   vec!["lamp"]);
+    }
+
+    #[test]
+    fn rename_collision() {
+        let mut registry = MemoryRegistry::new();
+        registry.dump(&light("lamp")).expect("Saved");
+        registry.default(&light("desk")).expect("Saved");
+
+        assert!(matches!(registry.rename("lamp", "desk"),
+                         Err(Error { etype: ErrorType::Exists(_), .. })));
+        assert!(registry.load_dump("lamp").is_ok());
+    }
+
+    #[test]
+    fn revisions() {
+        use domain::brightness::Brightness;
+
+        let mut registry = MemoryRegistry::new().with_revisions(2);
+        let mut lamp = light("lamp");
+        registry.dump(&lamp).expect("Saved");
+        registry.dump(&lamp).expect("Nothing new");
+        lamp.set_brightness(Brightness::new(0.5)).expect("Capable");
+        registry.store(Section::Dumps, &lamp, Some("dimmed")).expect("Saved");
+        lamp.set_brightness(Brightness::new(0.7)).expect("Capable");
+        registry.dump(&lamp).expect("Saved");
+
+        let numbers: Vec<u64> = registry.list_revisions(Section::Dumps, "lamp")
+            .expect("Listed")
+            .iter().map(|revision| revision.number).collect();
+        assert_eq!(numbers, vec![2, 3]);
+
+        let dimmed = registry.load_revision(Section::Dumps, "lamp", 2)
+            .expect("Loaded");
+        assert_eq!(dimmed.message.as_deref(), Some("dimmed"));
+        assert!(matches!(registry.load_revision(Section::Dumps, "lamp", 1),
+                         Err(Error { etype: ErrorType::NotFound(_), .. })));
+
+        let patch = registry.diff_revisions(Section::Dumps, "lamp", 2, 3)
+            .expect("Compared");
+        assert_eq!(patch.brightness, Some(Brightness::new(0.7)));
+        assert!(patch.power.is_none());
+
+        registry.rename("lamp", "desk").expect("Renamed");
+        assert!(registry.list_revisions(Section::Dumps, "desk")
+                .expect("Listed").iter()
+                .all(|revision| revision.light.name == "desk"));
+        assert!(registry.list_revisions(Section::Defaults, "desk").is_err());
+    }
+
+    #[test]
+    fn scenes() {
+        let mut registry = MemoryRegistry::new();
+        registry.save_scene(&Scene::new("night".to_string(), vec![light("")]))
+            .expect("Saved");
+        registry.save_scene(&Scene::new("day".to_string(), Vec::new()))
+            .expect("Saved");
+        registry.dump(&light("day")).expect("Saved");
+
+        let names: Vec<String> = registry.list_scenes().expect("Listed")
+            .into_iter().map(|scene| scene.name).collect();
+        assert_eq!(names, vec!["day", "night"]);
+        assert_eq!(registry.load_scene("night").expect("Loaded").lights.len(), 1);
+
+        // Lights and scenes don't share names
+        registry.remove_scene("day").expect("Removed");
+        assert!(registry.load_dump("day").is_ok());
+        assert!(matches!(registry.remove_scene("day"),
+                         Err(Error { etype: ErrorType::NotFound(_), .. })));
+        assert!(matches!(registry.save_scene(&Scene::new(String::new(),
+                                                         Vec::new())),
+                         Err(Error { etype: ErrorType::Unnamed, .. })));
+    }
+
+    #[test]
+    fn groups() {
+        let mut registry = MemoryRegistry::new();
+        registry.save_group(&Group::new("kitchen".to_string(), Vec::new()))
+            .expect("Saved");
+
+        assert_eq!(registry.list_groups().expect("Listed").len(), 1);
+        assert!(registry.load_group("kitchen").is_ok());
+        assert!(registry.load_scene("kitchen").is_err());
+
+        registry.remove_group("kitchen").expect("Removed");
+        assert!(matches!(registry.load_group("kitchen"),
+                         Err(Error { etype: ErrorType::NotFound(_), .. })));
+    }
+
+    #[test]
+    fn rename_missing() {
+        let mut registry = MemoryRegistry::new();
+
+        assert!(matches!(registry.rename("lamp", "desk"),
+                         Err(Error { etype: ErrorType::NotFound(_), .. })));
+    }
+}
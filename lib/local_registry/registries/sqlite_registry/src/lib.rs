@@ -0,0 +1,873 @@
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Serialize, Deserialize, de::DeserializeOwned};
+
+use domain::light::{Light, ProviderID};
+use domain::scene::Scene;
+use domain::group::Group;
+use domain::history::History;
+use json_registry::JSONRegistry;
+use local_registry::{
+    Registry,
+    Revision,
+    Section,
+    Error,
+    Result,
+    REVISIONS,
+};
+
+mod schema;
+
+// Database file inside of the registry location
+pub const FILE: &str = "registry.sqlite3";
+
+const NAME: &str = "sqlite";
+
+// Every saved light is a row per revision, states are kept as JSON, so the
+// schema doesn't follow changes of the domain
+pub struct SQLiteRegistry {
+    connection: Mutex<Connection>,
+    revisions: usize,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Settings {
+    pub revisions: Option<usize>,
+}
+
+// What was brought in by an import
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Imported {
+    pub lights: usize,
+    pub revisions: usize,
+    pub scenes: usize,
+    pub groups: usize,
+    pub histories: usize,
+}
+
+fn internal<T, E>(result: std::result::Result<T, E>) -> Result<T>
+where E: std::error::Error + Send + Sync + 'static {
+    result.or_else(|err| Error::internal(NAME, Box::new(err)))
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String> {
+    internal(serde_json::to_string(value))
+}
+
+fn from_json<T: DeserializeOwned>(text: &str) -> Result<T> {
+    internal(serde_json::from_str(text))
+}
+
+fn section_name(section: Section) -> &'static str {
+    match section {
+        Section::Dumps => "dumps",
+        Section::Defaults => "defaults",
+    }
+}
+
+fn check_name(name: &str) -> Result<()> {
+    if name.is_empty() {
+        Error::unnamed(NAME)
+    } else {
+        Ok(())
+    }
+}
+
+fn revision(row: &rusqlite::Row) -> rusqlite::Result<(u64, i64, Option<String>,
+                                                       String)> {
+    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+}
+
+fn into_revision(
+    (number, at, message, light): (u64, i64, Option<String>, String)
+) -> Result<Revision> {
+    Ok(Revision {
+        number,
+        at,
+        message,
+        light: from_json(&light)?,
+    })
+}
+
+fn revisions(connection: &Connection, section: Section,
+             name: &str) -> Result<Vec<Revision>> {
+    let mut statement = internal(connection.prepare(
+        "SELECT number, at, message, light FROM revisions
+         WHERE section = ?1 AND name = ?2 ORDER BY number"
+    ))?;
+    let rows = internal(statement.query_map(
+        params![section_name(section), name], revision
+    ))?;
+
+    rows.map(|row| internal(row).and_then(into_revision)).collect()
+}
+
+fn latest(connection: &Connection, section: Section,
+          name: &str) -> Result<Option<Revision>> {
+    internal(connection.query_row(
+        "SELECT number, at, message, light FROM revisions
+         WHERE section = ?1 AND name = ?2 ORDER BY number DESC LIMIT 1",
+        params![section_name(section), name],
+        revision
+    ).optional())?.map(into_revision).transpose()
+}
+
+fn insert(connection: &Connection, section: Section,
+          revision: &Revision) -> Result<()> {
+    let light = &revision.light;
+
+    internal(connection.execute(
+        "INSERT INTO revisions
+         (section, name, number, at, message, provider, light_id, light)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![section_name(section), light.name, revision.number,
+                revision.at, revision.message, light.provider.name,
+                light.provider.id, to_json(light)?]
+    )).map(|_| ())
+}
+
+fn is_saved(connection: &Connection, name: &str) -> Result<bool> {
+    internal(connection.query_row(
+        "SELECT EXISTS (SELECT 1 FROM revisions WHERE name = ?1)",
+        params![name],
+        |row| row.get(0)
+    ))
+}
+
+// Scenes and groups are whole JSON documents by name, table names are
+// never given from the outside
+fn list_documents<T: DeserializeOwned>(connection: &Connection,
+                                       table: &str) -> Result<Vec<T>> {
+    let mut statement = internal(connection.prepare(&format!(
+        "SELECT body FROM {} ORDER BY name", table
+    )))?;
+    let rows = internal(statement.query_map([], |row| row.get::<_, String>(0)))?;
+
+    rows.map(|row| internal(row).and_then(|body| from_json(&body))).collect()
+}
+
+fn load_document<T: DeserializeOwned>(connection: &Connection, table: &str,
+                                      name: &str) -> Result<T> {
+    check_name(name)?;
+
+    let body: Option<String> = internal(connection.query_row(
+        &format!("SELECT body FROM {} WHERE name = ?1", table),
+        params![name],
+        |row| row.get(0)
+    ).optional())?;
+
+    match body {
+        Some(body) => from_json(&body),
+        None => Error::not_found(NAME, name),
+    }
+}
+
+fn document_exists(connection: &Connection, table: &str,
+                   name: &str) -> Result<bool> {
+    internal(connection.query_row(
+        &format!("SELECT EXISTS (SELECT 1 FROM {} WHERE name = ?1)", table),
+        params![name],
+        |row| row.get(0)
+    ))
+}
+
+fn save_document<T: Serialize>(connection: &Connection, table: &str,
+                               name: &str, value: &T) -> Result<()> {
+    check_name(name)?;
+
+    internal(connection.execute(
+        &format!("INSERT OR REPLACE INTO {} (name, body) VALUES (?1, ?2)",
+                 table),
+        params![name, to_json(value)?]
+    )).map(|_| ())
+}
+
+fn remove_document(connection: &Connection, table: &str,
+                   name: &str) -> Result<()> {
+    check_name(name)?;
+
+    let removed = internal(connection.execute(
+        &format!("DELETE FROM {} WHERE name = ?1", table),
+        params![name]
+    ))?;
+
+    if removed == 0 {
+        Error::not_found(NAME, name)
+    } else {
+        Ok(())
+    }
+}
+
+fn save_history(connection: &Connection, history: &History) -> Result<()> {
+    internal(connection.execute(
+        "INSERT OR REPLACE INTO histories (provider, light_id, history)
+         VALUES (?1, ?2, ?3)",
+        params![history.id.name, history.id.id, to_json(history)?]
+    )).map(|_| ())
+}
+
+impl SQLiteRegistry {
+    // Database is created in the location if there is none, schema is
+    // brought to the latest version
+    pub fn open<P: AsRef<Path>>(location: P) -> Result<Self> {
+        internal(std::fs::create_dir_all(location.as_ref()))?;
+
+        Self::with_connection(
+            internal(Connection::open(location.as_ref().join(FILE)))?
+        )
+    }
+
+    pub fn in_memory() -> Result<Self> {
+        Self::with_connection(internal(Connection::open_in_memory())?)
+    }
+
+    fn with_connection(mut connection: Connection) -> Result<Self> {
+        let version = internal(schema::version(&connection))?;
+
+        if version > schema::latest() {
+            return Error::internal(NAME, format!(
+                "Schema version {} is newer than supported {}",
+                version, schema::latest()
+            ).into());
+        }
+
+        internal(schema::migrate(&mut connection))?;
+
+        Ok(Self {
+            connection: Mutex::new(connection),
+            revisions: REVISIONS,
+        })
+    }
+
+    // At least the latest revision is kept
+    pub fn with_revisions(self: Self, revisions: usize) -> Self {
+        Self {
+            revisions: revisions.max(1),
+            ..self
+        }
+    }
+
+    pub fn schema_version(self: &Self) -> Result<usize> {
+        internal(schema::version(&self.connection()))
+    }
+
+    // Statements are short, so a panic in one of them leaves nothing half
+    // done that a transaction wouldn't have rolled back
+    fn connection(self: &Self) -> MutexGuard<'_, Connection> {
+        self.connection.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn list_latest(self: &Self, section: Section) -> Result<Vec<Light>> {
+        let connection = self.connection();
+        let mut statement = internal(connection.prepare(
+            "SELECT light FROM revisions AS saved
+             WHERE section = ?1 AND number = (
+                 SELECT MAX(number) FROM revisions
+                 WHERE section = saved.section AND name = saved.name
+             )
+             ORDER BY name"
+        ))?;
+        let rows = internal(statement.query_map(
+            params![section_name(section)], |row| row.get::<_, String>(0)
+        ))?;
+
+        rows.map(|row| internal(row).and_then(|light| from_json(&light)))
+            .collect()
+    }
+
+    fn load_latest(self: &Self, section: Section, name: &str) -> Result<Light> {
+        check_name(name)?;
+
+        match latest(&self.connection(), section, name)? {
+            Some(revision) => Ok(revision.light),
+            None => Error::not_found(NAME, name),
+        }
+    }
+
+    // Latest states saved for the light under any name, sorted by name
+    pub fn find_by_id(self: &Self, section: Section,
+                      id: &ProviderID) -> Result<Vec<Light>> {
+        let names: Vec<String> = {
+            let connection = self.connection();
+            let mut statement = internal(connection.prepare(
+                "SELECT DISTINCT name FROM revisions
+                 WHERE section = ?1 AND provider = ?2 AND light_id = ?3
+                 ORDER BY name"
+            ))?;
+            let rows = internal(statement.query_map(
+                params![section_name(section), id.name, id.id],
+                |row| row.get(0)
+            ))?;
+
+            internal(rows.collect::<rusqlite::Result<Vec<String>>>())?
+        };
+
+        // Name may have been saved for another light later on
+        Ok(names.iter()
+            .map(|name| self.load_latest(section, name))
+            .collect::<Result<Vec<Light>>>()?
+            .into_iter()
+            .filter(|light| light.provider == *id)
+            .collect())
+    }
+
+    // Brings in everything saved in a JSON registry within one transaction,
+    // nothing is imported if any name (or light history) is taken already.
+    // Source is only read, lights of its flat layout are brought in as
+    // `JSONRegistry::migrate` would have saved them, so a flat file next to
+    // revisions of the same name fails the import as well
+    pub fn import_json<P: AsRef<Path>>(self: &mut Self,
+                                       location: P) -> Result<Imported> {
+        let location = location.as_ref();
+
+        if !location.is_dir() {
+            return Error::not_found(NAME, &location.display().to_string());
+        }
+
+        let source = JSONRegistry::new(location);
+        let mut imported = Imported::default();
+        let mut connection = self.connection();
+        let transaction = internal(connection.transaction())?;
+
+        for section in [Section::Dumps, Section::Defaults] {
+            let mut names = HashSet::new();
+
+            for name in source.list_names(section)? {
+                if latest(&transaction, section, &name)?.is_some() {
+                    return Error::exists(NAME, &name);
+                }
+
+                for revision in source.list_revisions(section, &name)? {
+                    insert(&transaction, section, &revision)?;
+                    imported.revisions += 1;
+                }
+
+                imported.lights += 1;
+                names.insert(name);
+            }
+
+            for path in source.list_flat(section)? {
+                let (light, at) = source.read_flat(section, &path)?;
+                let previous = latest(&transaction, section, &light.name)?;
+
+                if previous.is_some() && !names.contains(&light.name) {
+                    return Error::exists(NAME, &light.name);
+                }
+
+                if let Some(mut revision) = Revision::follow(previous.as_ref(),
+                                                             &light, None) {
+                    revision.at = at;
+                    insert(&transaction, section, &revision)?;
+                    imported.revisions += 1;
+                }
+
+                if names.insert(light.name) {
+                    imported.lights += 1;
+                }
+            }
+        }
+
+        for scene in source.list_scenes()? {
+            if document_exists(&transaction, "scenes", &scene.name)? {
+                return Error::exists(NAME, &scene.name);
+            }
+
+            save_document(&transaction, "scenes", &scene.name, &scene)?;
+            imported.scenes += 1;
+        }
+
+        for group in source.list_groups()? {
+            if document_exists(&transaction, "groups", &group.name)? {
+                return Error::exists(NAME, &group.name);
+            }
+
+            save_document(&transaction, "groups", &group.name, &group)?;
+            imported.groups += 1;
+        }
+
+        for history in source.list_histories()? {
+            let taken: bool = internal(transaction.query_row(
+                "SELECT EXISTS (SELECT 1 FROM histories
+                                WHERE provider = ?1 AND light_id = ?2)",
+                params![history.id.name, history.id.id],
+                |row| row.get(0)
+            ))?;
+
+            if taken {
+                return Error::exists(NAME, &history.id.to_string());
+            }
+
+            save_history(&transaction, &history)?;
+            imported.histories += 1;
+        }
+
+        internal(transaction.commit())?;
+        Ok(imported)
+    }
+}
+
+impl Registry for SQLiteRegistry {
+    fn name(self: &Self) -> &str {
+        NAME
+    }
+
+    fn list_defaults(self: &Self) -> Result<Vec<Light>> {
+        self.list_latest(Section::Defaults)
+    }
+
+    fn list_dumps(self: &Self) -> Result<Vec<Light>> {
+        self.list_latest(Section::Dumps)
+    }
+
+    fn load_default(self: &Self, name: &str) -> Result<Light> {
+        self.load_latest(Section::Defaults, name)
+    }
+
+    fn load_dump(self: &Self, name: &str) -> Result<Light> {
+        self.load_latest(Section::Dumps, name)
+    }
+
+    fn store(self: &mut Self, section: Section, light: &Light,
+             message: Option<&str>) -> Result<()> {
+        check_name(&light.name)?;
+
+        let limit = self.revisions;
+        let mut connection = self.connection();
+        let transaction = internal(connection.transaction())?;
+        let previous = latest(&transaction, section, &light.name)?;
+
+        if let Some(revision) = Revision::follow(previous.as_ref(), light,
+                                                 message) {
+            insert(&transaction, section, &revision)?;
+        }
+
+        internal(transaction.execute(
+            "DELETE FROM revisions
+             WHERE section = ?1 AND name = ?2 AND number NOT IN (
+                 SELECT number FROM revisions
+                 WHERE section = ?1 AND name = ?2
+                 ORDER BY number DESC LIMIT ?3
+             )",
+            params![section_name(section), light.name, limit]
+        ))?;
+
+        internal(transaction.commit())
+    }
+
+    fn list_revisions(self: &Self, section: Section,
+                      name: &str) -> Result<Vec<Revision>> {
+        check_name(name)?;
+
+        match revisions(&self.connection(), section, name)? {
+            revisions if revisions.is_empty() => Error::not_found(NAME, name),
+            revisions => Ok(revisions),
+        }
+    }
+
+    fn load_revision(self: &Self, section: Section, name: &str,
+                     number: u64) -> Result<Revision> {
+        check_name(name)?;
+
+        let connection = self.connection();
+        let found = internal(connection.query_row(
+            "SELECT number, at, message, light FROM revisions
+             WHERE section = ?1 AND name = ?2 AND number = ?3",
+            params![section_name(section), name, number],
+            revision
+        ).optional())?;
+
+        match found {
+            Some(found) => into_revision(found),
+            None if latest(&connection, section, name)?.is_some() => {
+                Error::no_revision(NAME, name, number)
+            },
+            None => Error::not_found(NAME, name),
+        }
+    }
+
+    fn remove(self: &mut Self, name: &str) -> Result<()> {
+        check_name(name)?;
+
+        let removed = internal(self.connection().execute(
+            "DELETE FROM revisions WHERE name = ?1", params![name]
+        ))?;
+
+        if removed == 0 {
+            Error::not_found(NAME, name)
+        } else {
+            Ok(())
+        }
+    }
+
+    // Name is stored inside of the light too, both are changed at once
+    fn rename(self: &mut Self, old: &str, new: &str) -> Result<()> {
+        check_name(old)?;
+        check_name(new)?;
+
+        let mut connection = self.connection();
+        let transaction = internal(connection.transaction())?;
+
+        if !is_saved(&transaction, old)? {
+            return Error::not_found(NAME, old);
+        } else if old == new {
+            return Ok(());
+        } else if is_saved(&transaction, new)? {
+            return Error::exists(NAME, new);
+        }
+
+        internal(transaction.execute(
+            "UPDATE revisions SET name = ?2, light = json_set(light, '$.name', ?2)
+             WHERE name = ?1",
+            params![old, new]
+        ))?;
+
+        internal(transaction.commit())
+    }
+
+    fn list_scenes(self: &Self) -> Result<Vec<Scene>> {
+        list_documents(&self.connection(), "scenes")
+    }
+
+    fn load_scene(self: &Self, name: &str) -> Result<Scene> {
+        load_document(&self.connection(), "scenes", name)
+    }
+
+    fn save_scene(self: &mut Self, scene: &Scene) -> Result<()> {
+        save_document(&self.connection(), "scenes", &scene.name, scene)
+    }
+
+    fn remove_scene(self: &mut Self, name: &str) -> Result<()> {
+        remove_document(&self.connection(), "scenes", name)
+    }
+
+    fn list_groups(self: &Self) -> Result<Vec<Group>> {
+        list_documents(&self.connection(), "groups")
+    }
+
+    fn load_group(self: &Self, name: &str) -> Result<Group> {
+        load_document(&self.connection(), "groups", name)
+    }
+
+    fn save_group(self: &mut Self, group: &Group) -> Result<()> {
+        save_document(&self.connection(), "groups", &group.name, group)
+    }
+
+    fn remove_group(self: &mut Self, name: &str) -> Result<()> {
+        remove_document(&self.connection(), "groups", name)
+    }
+
+    fn load_history(self: &Self, id: &ProviderID) -> Result<History> {
+        let history: Option<String> = internal(self.connection().query_row(
+            "SELECT history FROM histories
+             WHERE provider = ?1 AND light_id = ?2",
+            params![id.name, id.id],
+            |row| row.get(0)
+        ).optional())?;
+
+        match history {
+            Some(history) => from_json(&history),
+            None => Ok(History::new(id.clone())),
+        }
+    }
+
+    fn save_history(self: &mut Self, history: &History) -> Result<()> {
+        save_history(&self.connection(), history)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::capabilities::Capability;
+    use domain::brightness::Brightness;
+    use domain::history::Entry;
+    use local_registry::ErrorType;
+
+    fn light(name: &str, id: &str) -> Light {
+        let mut light = Light::named("test".to_string(), id.to_string(),
+                                     vec![Capability::Brightness],
+                                     name.to_string());
+        light.set_brightness(Brightness::new(0.5)).expect("Capable");
+
+        light
+    }
+
+    fn registry() -> SQLiteRegistry {
+        SQLiteRegistry::in_memory().expect("Opened")
+    }
+
+    fn names(lights: Vec<Light>) -> Vec<String> {
+        lights.into_iter().map(|light| light.name).collect()
+    }
+
+    #[test]
+    fn dump_and_load() {
+        let mut registry = registry();
+        assert!(registry.list_dumps().expect("Empty list").is_empty());
+        registry.dump(&light("lamp", "1")).expect("Saved");
+
+        let loaded = registry.load_dump("lamp").expect("Loaded");
+
+        assert_eq!(loaded.provider.id, "1");
+        assert_eq!(**loaded.get_brightness().expect("Set"), 0.5);
+        assert!(matches!(registry.load_default("lamp"),
+                         Err(Error { etype: ErrorType::NotFound(_), .. })));
+    }
+
+    #[test]
+    fn list_sorted() {
+        let mut registry = registry();
+        registry.default(&light("b", "2")).expect("Saved");
+        registry.default(&light("a", "1")).expect("Saved");
+        let mut a = light("a", "1");
+        a.set_brightness(Brightness::new(0.7)).expect("Capable");
+        registry.default(&a).expect("Saved");
+
+        let listed = registry.list_defaults().expect("Listed");
+        assert_eq!(names(listed.clone()), vec!["a", "b"]);
+        assert_eq!(**listed[0].get_brightness().expect("Set"), 0.7);
+        assert!(registry.list_dumps().expect("Listed").is_empty());
+    }
+
+    #[test]
+    fn unnamed() {
+        let mut registry = registry();
+
+        assert!(matches!(registry.dump(&light("", "1")),
+                         Err(Error { etype: ErrorType::Unnamed, .. })));
+        assert!(matches!(registry.load_scene(""),
+                         Err(Error { etype: ErrorType::Unnamed, .. })));
+    }
+
+    #[test]
+    fn remove_both() {
+        let mut registry = registry();
+        registry.dump(&light("lamp", "1")).expect("Saved");
+        registry.default(&light("lamp", "1")).expect("Saved");
+
+        registry.remove("lamp").expect("Removed");
+
+        assert!(registry.list_dumps().expect("Listed").is_empty());
+        assert!(registry.list_defaults().expect("Listed").is_empty());
+        assert!(matches!(registry.remove("lamp"),
+                         Err(Error { etype: ErrorType::NotFound(_), .. })));
+    }
+
+    #[test]
+    fn rename() {
+        let mut registry = registry();
+        registry.dump(&light("lamp", "1")).expect("Saved");
+        registry.default(&light("lamp", "1")).expect("Saved");
+        registry.default(&light("hall", "2")).expect("Saved");
+
+        assert!(matches!(registry.rename("lamp", "hall"),
+                         Err(Error { etype: ErrorType::Exists(_), .. })));
+        assert!(matches!(registry.rename("desk", "porch"),
+                         Err(Error { etype: ErrorType::NotFound(_), .. })));
+
+        registry.rename("lamp", "desk").expect("Renamed");
+
+        assert_eq!(names(registry.list_dumps().expect("Listed")), vec!["desk"]);
+        assert_eq!(registry.load_default("desk").expect("Loaded").name, "desk");
+        assert!(registry.load_dump("lamp").is_err());
+    }
+
+    #[test]
+    fn revisions() {
+        let mut registry = registry().with_revisions(2);
+
+        for level in [0.1, 0.2, 0.2, 0.3] {
+            let mut lamp = light("lamp", "1");
+            lamp.set_brightness(Brightness::new(level)).expect("Capable");
+            registry.dump(&lamp).expect("Saved");
+        }
+        registry.store(Section::Dumps, &light("lamp", "1"), Some("reset"))
+            .expect("Saved");
+
+        let revisions = registry.list_revisions(Section::Dumps, "lamp")
+            .expect("Listed");
+        let numbers: Vec<u64> = revisions.iter()
+            .map(|revision| revision.number)
+            .collect();
+        assert_eq!(numbers, vec![3, 4]);
+        assert_eq!(revisions[1].message.as_deref(), Some("reset"));
+
+        let patch = registry.diff_revisions(Section::Dumps, "lamp", 3, 4)
+            .expect("Compared");
+        assert_eq!(patch.brightness, Some(Brightness::new(0.5)));
+        assert!(matches!(registry.load_revision(Section::Dumps, "lamp", 1),
+                         Err(Error { etype: ErrorType::NotFound(_), .. })));
+        assert!(matches!(registry.list_revisions(Section::Defaults, "lamp"),
+                         Err(Error { etype: ErrorType::NotFound(_), .. })));
+    }
+
+    #[test]
+    fn find_by_id() {
+        let mut registry = registry();
+        registry.dump(&light("lamp", "1")).expect("Saved");
+        registry.dump(&light("desk", "1")).expect("Saved");
+        registry.dump(&light("hall", "2")).expect("Saved");
+        // Name taken over by another light
+        registry.dump(&light("hall", "1")).expect("Saved");
+        registry.dump(&light("hall", "2")).expect("Saved");
+
+        let found = registry.find_by_id(
+            Section::Dumps, &ProviderID::new("test".to_string(), "1".to_string())
+        ).expect("Found");
+
+        assert_eq!(names(found), vec!["desk", "lamp"]);
+    }
+
+    #[test]
+    fn scenes_and_groups() {
+        use domain::group::Member;
+
+        let mut registry = registry();
+        let scene = Scene::new("night".to_string(),
+                               vec![light("", "1"), light("", "2")]);
+        let group = Group::new("kitchen".to_string(), vec![
+            Member::Saved("desk".to_string()),
+        ]);
+        registry.save_scene(&scene).expect("Saved");
+        registry.save_group(&group).expect("Saved");
+
+        assert_eq!(registry.load_scene("night").expect("Loaded").lights.len(),
+                   2);
+        assert_eq!(registry.load_group("kitchen").expect("Loaded").members,
+                   group.members);
+        assert!(registry.list_dumps().expect("Listed").is_empty());
+
+        registry.remove_scene("night").expect("Removed");
+        assert!(registry.list_scenes().expect("Listed").is_empty());
+        assert!(matches!(registry.remove_group("hall"),
+                         Err(Error { etype: ErrorType::NotFound(_), .. })));
+    }
+
+    #[test]
+    fn history() {
+        let mut registry = registry();
+        let id = ProviderID::new("mqtt".to_string(),
+                                 "kitchen/ceiling".to_string());
+        assert!(registry.load_history(&id).expect("Empty").is_empty());
+
+        let mut history = History::new(id.clone());
+        history.record(Entry::new(1, light("", "1")));
+        registry.save_history(&history).expect("Saved");
+        history.record(Entry::new(2, light("", "1")));
+        registry.save_history(&history).expect("Replaced");
+
+        let loaded = registry.load_history(&id).expect("Loaded");
+        assert_eq!(loaded.undo.len(), 2);
+        assert_eq!(loaded.id, id);
+    }
+
+    #[test]
+    fn reopened() {
+        let dir = tempfile::tempdir().expect("Temporary directory");
+        let location = dir.path().join("registry");
+
+        let mut registry = SQLiteRegistry::open(&location).expect("Created");
+        assert_eq!(registry.schema_version().expect("Versioned"),
+                   schema::latest());
+        registry.dump(&light("lamp", "1")).expect("Saved");
+        drop(registry);
+
+        let registry = SQLiteRegistry::open(&location).expect("Opened");
+        assert_eq!(names(registry.list_dumps().expect("Listed")), vec!["lamp"]);
+    }
+
+    #[test]
+    fn newer_schema() {
+        let dir = tempfile::tempdir().expect("Temporary directory");
+        let connection = Connection::open(dir.path().join(FILE))
+            .expect("Created");
+        connection.pragma_update(None, "user_version", schema::latest() + 1)
+            .expect("Versioned");
+        drop(connection);
+
+        assert!(matches!(SQLiteRegistry::open(dir.path()),
+                         Err(Error { etype: ErrorType::Internal(_), .. })));
+    }
+
+    #[test]
+    fn import_json() {
+        let dir = tempfile::tempdir().expect("Temporary directory");
+        let mut source = JSONRegistry::new(dir.path());
+        let mut lamp = light("lamp", "1");
+        source.dump(&lamp).expect("Saved");
+        lamp.set_brightness(Brightness::new(0.8)).expect("Capable");
+        source.store(Section::Dumps, &lamp, Some("bright")).expect("Saved");
+        source.default(&light("desk", "2")).expect("Saved");
+        source.save_scene(&Scene::new("night".to_string(),
+                                      vec![light("", "1")]))
+            .expect("Saved");
+        let mut history = History::new(lamp.provider.clone());
+        history.record(Entry::new(1, light("", "1")));
+        source.save_history(&history).expect("Saved");
+
+        let mut registry = registry();
+        let imported = registry.import_json(dir.path()).expect("Imported");
+
+        assert_eq!(imported, Imported {
+            lights: 2,
+            revisions: 3,
+            scenes: 1,
+            groups: 0,
+            histories: 1,
+        });
+        let revision = registry.load_revision(Section::Dumps, "lamp", 2)
+            .expect("Loaded");
+        assert_eq!(revision.message.as_deref(), Some("bright"));
+        assert_eq!(registry.load_history(&lamp.provider).expect("Loaded")
+                   .undo.len(), 1);
+
+        // Second import would overwrite, nothing is brought in
+        registry.remove_scene("night").expect("Removed");
+        assert!(matches!(registry.import_json(dir.path()),
+                         Err(Error { etype: ErrorType::Exists(_), .. })));
+        assert!(registry.list_scenes().expect("Listed").is_empty());
+    }
+
+    #[test]
+    fn import_flat_json() {
+        let dir = tempfile::tempdir().expect("Temporary directory");
+        let mut source = JSONRegistry::new(dir.path());
+        source.dump(&light("lamp", "1")).expect("Saved");
+        let mut lamp = light("lamp", "1");
+        lamp.set_brightness(Brightness::new(0.8)).expect("Capable");
+        let flat = dir.path().join("dumps");
+        std::fs::write(flat.join("lamp.json"),
+                       serde_json::to_string(&lamp).expect("JSON"))
+            .expect("Written");
+        std::fs::write(flat.join("desk.json"),
+                       serde_json::to_string(&light("desk", "2"))
+                       .expect("JSON"))
+            .expect("Written");
+
+        // Flat file is older than the revisions of the same name
+        let mut registry = registry();
+        assert!(matches!(registry.import_json(dir.path()),
+                         Err(Error { etype: ErrorType::Exists(_), .. })));
+        assert!(registry.list_dumps().expect("Listed").is_empty());
+
+        std::fs::remove_file(flat.join("lamp.json")).expect("Removed");
+        let imported = registry.import_json(dir.path()).expect("Imported");
+
+        assert_eq!((imported.lights, imported.revisions), (2, 2));
+        assert_eq!(names(registry.list_dumps().expect("Listed")),
+                   vec!["desk", "lamp"]);
+        assert_eq!(registry.list_revisions(Section::Dumps, "lamp")
+                   .expect("Listed").len(), 1);
+
+        // Source is left as it was
+        assert!(flat.join("desk.json").is_file());
+        assert!(!flat.join("desk").exists());
+        assert_eq!(source.list_revisions(Section::Dumps, "lamp")
+                   .expect("Listed").len(), 1);
+    }
+}
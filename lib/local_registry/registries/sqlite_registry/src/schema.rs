@@ -0,0 +1,60 @@
+
+use rusqlite::Connection;
+
+// Schema version N is reached by applying the first N entries, applied ones
+// are never edited, changes go into a new entry
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE revisions (
+        section  TEXT    NOT NULL,
+        name     TEXT    NOT NULL,
+        number   INTEGER NOT NULL,
+        at       INTEGER NOT NULL,
+        message  TEXT,
+        provider TEXT    NOT NULL,
+        light_id TEXT    NOT NULL,
+        light    TEXT    NOT NULL,
+        PRIMARY KEY (section, name, number)
+    );
+    CREATE INDEX revisions_by_name ON revisions (name);
+    CREATE INDEX revisions_by_id ON revisions (provider, light_id);
+
+    CREATE TABLE scenes (
+        name  TEXT PRIMARY KEY,
+        body  TEXT NOT NULL
+    );
+
+    CREATE TABLE groups (
+        name  TEXT PRIMARY KEY,
+        body  TEXT NOT NULL
+    );
+
+    CREATE TABLE histories (
+        provider TEXT NOT NULL,
+        light_id TEXT NOT NULL,
+        history  TEXT NOT NULL,
+        PRIMARY KEY (provider, light_id)
+    );",
+];
+
+pub fn latest() -> usize {
+    MIGRATIONS.len()
+}
+
+pub fn version(connection: &Connection) -> rusqlite::Result<usize> {
+    connection.pragma_query_value(None, "user_version", |row| row.get(0))
+}
+
+// Each migration is applied in its own transaction along with the version
+// bump, so an interrupted upgrade resumes where it stopped
+pub fn migrate(connection: &mut Connection) -> rusqlite::Result<()> {
+    let current = version(connection)?;
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(current) {
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(migration)?;
+        transaction.pragma_update(None, "user_version", index + 1)?;
+        transaction.commit()?;
+    }
+
+    Ok(())
+}
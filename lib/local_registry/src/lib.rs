@@ -1,18 +1,126 @@
 
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Serialize, Deserialize};
+
 use domain::light::Light;
+use domain::scene::Scene;
+use domain::group::Group;
+use domain::history::History;
+use domain::light::ProviderID;
+use domain::patch::LightPatch;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-pub trait Registry {
+// Revisions kept per saved light unless configured otherwise
+pub const REVISIONS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    Dumps,
+    Defaults,
+}
+
+// Saved state of a light, numbers only grow, so a pruned revision number
+// is never reused
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Revision {
+    pub number: u64,
+    // Seconds since unix epoch
+    pub at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    pub light: Light,
+}
+
+impl Revision {
+    // Revision following the latest one, none if it would store the same
+    // light without saying anything new
+    pub fn follow(latest: Option<&Revision>, light: &Light,
+                  message: Option<&str>) -> Option<Self> {
+        let same = latest.is_some_and(|latest| {
+            message.is_none()
+            && serde_json::to_value(&latest.light).ok()
+                == serde_json::to_value(light).ok()
+        });
+
+        if same {
+            return None;
+        }
+
+        Some(Self {
+            number: latest.map_or(1, |latest| latest.number + 1),
+            at: SystemTime::now().duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs() as i64),
+            message: message.map(str::to_string),
+            light: light.clone(),
+        })
+    }
+}
+
+// Lights are stored by their local names in two independent sections:
+// dumps and defaults. Every method taking a name (or a light) fails with
+// `ErrorType::Unnamed` if it is empty. Registries keeping lights in files
+// refuse names leading out of their directories with
+// `ErrorType::IncorrectName`.
+pub trait Registry: Send + Sync {
     fn name(self: &Self) -> &str;
+    // Listings are sorted by light name
     fn list_defaults(self: &Self) -> Result<Vec<Light>>;
     fn list_dumps(self: &Self) -> Result<Vec<Light>>;
+    // `ErrorType::NotFound` if nothing is saved under the name
     fn load_default(self: &Self, name: &str) -> Result<Light>;
     fn load_dump(self: &Self, name: &str) -> Result<Light>;
-    fn dump(self: &mut Self, light: &Light) -> Result<()>;
-    fn default(self: &mut Self, light: &Light) -> Result<()>;
+    // Saved light becomes the latest revision of its name, the oldest ones
+    // are dropped once there are more of them than the registry keeps
+    fn store(self: &mut Self, section: Section, light: &Light,
+             message: Option<&str>) -> Result<()>;
+
+    fn dump(self: &mut Self, light: &Light) -> Result<()> {
+        self.store(Section::Dumps, light, None)
+    }
+
+    fn default(self: &mut Self, light: &Light) -> Result<()> {
+        self.store(Section::Defaults, light, None)
+    }
+
+    // Oldest first, `ErrorType::NotFound` if nothing is saved under the name
+    fn list_revisions(self: &Self, section: Section,
+                      name: &str) -> Result<Vec<Revision>>;
+    fn load_revision(self: &Self, section: Section, name: &str,
+                     number: u64) -> Result<Revision>;
+
+    // Changes turning the older revision into the newer one
+    fn diff_revisions(self: &Self, section: Section, name: &str, from: u64,
+                      to: u64) -> Result<LightPatch> {
+        let from = self.load_revision(section, name, from)?;
+        let to = self.load_revision(section, name, to)?;
+
+        Ok(from.light.diff(&to.light))
+    }
+    // Affects both sections along with every revision, `ErrorType::NotFound`
+    // if name is in neither
     fn remove(self: &mut Self, name: &str) -> Result<()>;
+    // Affects both sections, `ErrorType::NotFound` if old name is in
+    // neither, `ErrorType::Exists` if new name is taken in any of them
     fn rename(self: &mut Self, old: &str, new: &str) -> Result<()>;
+
+    // Scenes have their own namespace and follow the same rules
+    fn list_scenes(self: &Self) -> Result<Vec<Scene>>;
+    fn load_scene(self: &Self, name: &str) -> Result<Scene>;
+    fn save_scene(self: &mut Self, scene: &Scene) -> Result<()>;
+    fn remove_scene(self: &mut Self, name: &str) -> Result<()>;
+
+    // Groups too, members are stored as is and aren't checked
+    fn list_groups(self: &Self) -> Result<Vec<Group>>;
+    fn load_group(self: &Self, name: &str) -> Result<Group>;
+    fn save_group(self: &mut Self, group: &Group) -> Result<()>;
+    fn remove_group(self: &mut Self, name: &str) -> Result<()>;
+
+    // Histories are kept by provider ids, so they outlive renames. Empty
+    // history is loaded if nothing was recorded for the light
+    fn load_history(self: &Self, id: &ProviderID) -> Result<History>;
+    fn save_history(self: &mut Self, history: &History) -> Result<()>;
 }
 
 #[derive(Debug)]
@@ -24,9 +132,11 @@ pub struct Error {
 #[derive(Debug)]
 pub enum ErrorType {
     NotFound(String),
+    Exists(String),
     IncorrectLight(Light),
     Unnamed,
-    Internal(Box<dyn std::error::Error>),
+    IncorrectName(String),
+    Internal(Box<dyn std::error::Error + Send + Sync>),
 }
 
 impl Error {
@@ -37,6 +147,13 @@ impl Error {
         })
     }
 
+    pub fn exists<T>(registry: &str, name: &str) -> Result<T> {
+        Err(Self {
+            registry: registry.to_string(),
+            etype: ErrorType::Exists(name.to_string()),
+        })
+    }
+
     pub fn incorrect_light<T>(registry: &str, light: &Light) -> Result<T> {
         Err(Self {
             registry: registry.to_string(),
@@ -44,19 +161,30 @@ impl Error {
         })
     }
 
-    pub fn internal<T>(registry: &str, err: Box<dyn std::error::Error>) -> Result<T> {
+    pub fn internal<T>(registry: &str, err: Box<dyn std::error::Error + Send + Sync>) -> Result<T> {
         Err(Self {
             registry: registry.to_string(),
             etype: ErrorType::Internal(err),
         })
     }
 
+    pub fn no_revision<T>(registry: &str, name: &str, number: u64) -> Result<T> {
+        Self::not_found(registry, &format!("{} (revision {})", name, number))
+    }
+
     pub fn unnamed<T>(registry: &str) -> Result<T> {
         Err(Self {
             registry: registry.to_string(),
             etype: ErrorType::Unnamed,
         })
     }
+
+    pub fn incorrect_name<T>(registry: &str, name: &str) -> Result<T> {
+        Err(Self {
+            registry: registry.to_string(),
+            etype: ErrorType::IncorrectName(name.to_string()),
+        })
+    }
 }
 
 impl From<Error> for ErrorType {
@@ -78,7 +206,10 @@ impl std::fmt::Display for ErrorType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::NotFound(name) => {
-                write!(f, "Can't find light named \"{}\"", name)
+                write!(f, "Nothing is saved under name \"{}\"", name)
+            },
+            Self::Exists(name) => {
+                write!(f, "Light named \"{}\" already exists", name)
             },
             Self::IncorrectLight(light) => {
                 write!(f, "Incorrect light occured --- {:?}", light)
@@ -86,6 +217,9 @@ impl std::fmt::Display for ErrorType {
             Self::Unnamed => {
                 write!(f, "Local registry can't manage unnamed lights")
             }
+            Self::IncorrectName(name) => {
+                write!(f, "Name \"{}\" can't be used by the registry", name)
+            },
             Self::Internal(err) => {
                 write!(f, "Internal error occured\n{}", err)
             },
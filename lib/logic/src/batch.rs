@@ -0,0 +1,179 @@
+
+use domain::light::Light;
+use local_registry::ErrorType;
+use crate::facade::Managers;
+use crate::managers::local::{self, LocalStateManager};
+
+// What a strategy working on several items does when one of them fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Policy {
+    // Stop, items after the failed one are skipped
+    #[default]
+    FailFast,
+    // Go on with the rest
+    Continue,
+    // Stop and restore every item done before the failed one
+    Rollback,
+}
+
+#[derive(Debug)]
+pub enum Status<R, E> {
+    Done(R),
+    Failed(E),
+    Skipped,
+    RolledBack,
+    // Item was done, but its previous state couldn't be restored
+    RollbackFailed(E),
+}
+
+// Outcome of every item in input order
+#[derive(Debug)]
+pub struct Batch<K, R, E> {
+    pub items: Vec<(K, Status<R, E>)>,
+}
+
+impl<K, R, E> Batch<K, R, E> {
+    pub fn is_ok(self: &Self) -> bool {
+        self.items.iter().all(|(_, status)| matches!(status, Status::Done(_)))
+    }
+
+    pub fn done(self: &Self) -> impl Iterator<Item = (&K, &R)> {
+        self.items.iter().filter_map(|(key, status)| match status {
+            Status::Done(result) => Some((key, result)),
+            _ => None,
+        })
+    }
+
+    pub fn failed(self: &Self) -> impl Iterator<Item = (&K, &E)> {
+        self.items.iter().filter_map(|(key, status)| match status {
+            Status::Failed(err) | Status::RollbackFailed(err) => Some((key, err)),
+            _ => None,
+        })
+    }
+
+    // Results of all items or the first error, the way fail fast strategies
+    // used to report
+    pub fn into_result(self: Self) -> Result<Vec<R>, E> {
+        let mut out = Vec::with_capacity(self.items.len());
+
+        for (_, status) in self.items {
+            match status {
+                Status::Done(result) => out.push(result),
+                Status::Failed(err) | Status::RollbackFailed(err) => {
+                    return Err(err)
+                },
+                Status::Skipped | Status::RolledBack => {},
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+// Backup is taken right before the step only if rollback may be needed.
+// Undo is applied to done items in reverse order
+pub fn run<K, R, E, U>(
+    policy: Policy,
+    keys: impl Iterator<Item = K>,
+    managers: &mut Managers,
+    mut backup: impl FnMut(&mut Managers, &K) -> Result<U, E>,
+    mut step: impl FnMut(&mut Managers, &K) -> Result<R, E>,
+    mut undo: impl FnMut(&mut Managers, U) -> Result<(), E>
+) -> Batch<K, R, E> {
+    let mut items = Vec::new();
+    let mut backups = Vec::new();
+    let mut stopped = false;
+
+    for key in keys {
+        if stopped {
+            items.push((key, Status::Skipped));
+            continue;
+        }
+
+        let saved = match policy {
+            Policy::Rollback => match backup(managers, &key) {
+                Ok(saved) => Some(saved),
+                Err(err) => {
+                    items.push((key, Status::Failed(err)));
+                    stopped = true;
+                    continue;
+                },
+            },
+            _ => None,
+        };
+
+        match step(managers, &key) {
+            Ok(result) => {
+                backups.push((items.len(), saved));
+                items.push((key, Status::Done(result)));
+            },
+            Err(err) => {
+                items.push((key, Status::Failed(err)));
+                stopped = Policy::Continue != policy;
+            },
+        }
+    }
+
+    if stopped && Policy::Rollback == policy {
+        for (index, saved) in backups.into_iter().rev() {
+            if let Some(saved) = saved {
+                items[index].1 = match undo(managers, saved) {
+                    Ok(_) => Status::RolledBack,
+                    Err(err) => Status::RollbackFailed(err),
+                };
+            }
+        }
+    }
+
+    Batch { items }
+}
+
+// Both saved states of a light, absent ones are removed on restore
+#[derive(Debug)]
+pub struct Snapshot {
+    name: String,
+    dump: Option<Light>,
+    default: Option<Light>,
+}
+
+fn found(result: local::Result<Light>) -> local::Result<Option<Light>> {
+    match result {
+        Ok(light) => Ok(Some(light)),
+        Err(local::Error { etype: ErrorType::NotFound(_), .. }) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+impl Snapshot {
+    pub fn take(
+        local: &dyn LocalStateManager,
+        name: &str
+    ) -> local::Result<Self> {
+        Ok(Self {
+            name: name.to_string(),
+            dump: found(local.load(name))?,
+            default: found(local.get_default(name))?,
+        })
+    }
+
+    pub fn restore(
+        self: &Self,
+        local: &mut dyn LocalStateManager
+    ) -> local::Result<()> {
+        // Registry can only remove both sections at once
+        match local.remove(&self.name) {
+            Ok(_) | Err(local::Error { etype: ErrorType::NotFound(_), .. }) => {},
+            Err(err) => return Err(err),
+        }
+
+        if let Some(dump) = &self.dump {
+            local.save(dump)?;
+        }
+
+        if let Some(default) = &self.default {
+            local.set_default(default)?;
+        }
+
+        Ok(())
+    }
+}
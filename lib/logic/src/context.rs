@@ -1,12 +1,18 @@
 
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::sync::{Arc, PoisonError, RwLock};
+use std::ops::{Deref, DerefMut};
 use std::collections::HashMap;
 
 use domain::light::ProviderID;
 use local_registry::Registry;
 use provider::Provider;
 
+// Providers are shared, so managers take them out of the context and call
+// them without holding it
 pub struct Context {
-    pub providers: HashMap<String, Box<dyn Provider>>,
+    pub providers: HashMap<String, Arc<dyn Provider>>,
     pub registry: Box<dyn Registry>,
 }
 
@@ -15,7 +21,7 @@ impl Context {
         let mut map = HashMap::new();
 
         while let Some(provider) = providers.pop() {
-            map.insert(provider.name().to_string(), provider);
+            map.insert(provider.name().to_string(), Arc::from(provider));
         }
 
         Self {
@@ -24,12 +30,51 @@ impl Context {
         }
     }
 
-    pub fn get_provider_by_name(self: &Self, provider: &str) -> Option<&dyn Provider> {
-        self.providers.get(provider).map(Box::as_ref)
+    pub fn get_provider_by_name(self: &Self, provider: &str) -> Option<Arc<dyn Provider>> {
+        self.providers.get(provider).cloned()
     }
 
-    pub fn get_provider_by_id(self: &Self, id: &ProviderID) -> Option<&dyn Provider> {
-        self.providers.get(&id.name).map(Box::as_ref)
+    pub fn get_provider_by_id(self: &Self, id: &ProviderID) -> Option<Arc<dyn Provider>> {
+        self.providers.get(&id.name).cloned()
+    }
+
+    // Sorted by name, so anything going over all providers is deterministic
+    pub fn sorted_providers(self: &Self) -> Vec<Arc<dyn Provider>> {
+        let mut out: Vec<Arc<dyn Provider>> = self.providers.values()
+            .cloned()
+            .collect();
+        out.sort_by(|a, b| a.name().cmp(b.name()));
+
+        out
+    }
+}
+
+// Context shared by managers, either within a single thread or between
+// several of them
+pub trait SharedContext {
+    fn read(self: &Self) -> impl Deref<Target = Context> + '_;
+    fn write(self: &Self) -> impl DerefMut<Target = Context> + '_;
+}
+
+impl SharedContext for Rc<RefCell<Context>> {
+    fn read(self: &Self) -> impl Deref<Target = Context> + '_ {
+        RefCell::borrow(self)
+    }
+
+    fn write(self: &Self) -> impl DerefMut<Target = Context> + '_ {
+        RefCell::borrow_mut(self)
+    }
+}
+
+// Panic of another thread doesn't make context unusable, every change of
+// it is a single call
+impl SharedContext for Arc<RwLock<Context>> {
+    fn read(self: &Self) -> impl Deref<Target = Context> + '_ {
+        RwLock::read(self).unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(self: &Self) -> impl DerefMut<Target = Context> + '_ {
+        RwLock::write(self).unwrap_or_else(PoisonError::into_inner)
     }
 }
 
@@ -1,8 +1,15 @@
 
+#[cfg(feature = "async")]
+use provider::asynchronous::BoxFuture;
+
 use crate::managers::fetch::{FetchManager, SyncManager};
 use crate::managers::local::LocalStateManager;
+#[cfg(feature = "async")]
+use crate::managers::asynchronous::{AsyncFetchManager, AsyncSyncManager};
 
 pub mod default;
+#[cfg(feature = "async")]
+pub mod asynchronous;
 
 pub struct Managers<'a> {
     pub fetch: &'a dyn FetchManager,
@@ -24,3 +31,26 @@ pub trait Facade {
     fn accept(self: &mut Self, strategy: &mut dyn Strategy);
 }
 
+// Registry is local, so it stays blocking
+#[cfg(feature = "async")]
+pub struct AsyncManagers<'a> {
+    pub fetch: &'a dyn AsyncFetchManager,
+    pub sync:  &'a dyn AsyncSyncManager,
+    pub local: &'a mut (dyn LocalStateManager + Send),
+}
+
+#[cfg(feature = "async")]
+pub trait AsyncStrategy: Send {
+    fn execute<'a>(
+        self: &'a mut Self,
+        managers: AsyncManagers<'a>
+    ) -> BoxFuture<'a, ()>;
+}
+
+#[cfg(feature = "async")]
+pub trait AsyncFacade {
+    fn accept<'a>(
+        self: &'a mut Self,
+        strategy: &'a mut dyn AsyncStrategy
+    ) -> BoxFuture<'a, ()>;
+}
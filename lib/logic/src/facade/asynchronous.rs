@@ -0,0 +1,73 @@
+
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use local_registry::Registry;
+use provider::asynchronous::{AsyncProvider, BoxFuture, IntoAsync};
+
+use super::{AsyncFacade, AsyncManagers, AsyncStrategy};
+
+use crate::context::Context;
+use crate::managers::default::RegistryManager;
+use crate::managers::asynchronous::ProviderManager;
+
+// Strategies must be awaited within a tokio runtime. Clones share providers,
+// registry and the limit of workers
+#[derive(Clone)]
+pub struct TokioFacade {
+    provider_manager: ProviderManager,
+    registry_manager: RegistryManager<Arc<RwLock<Context>>>,
+}
+
+impl TokioFacade {
+    pub fn new(
+        providers: Vec<Arc<dyn AsyncProvider>>,
+        registry: Box<dyn Registry>
+    ) -> Self {
+        // Providers are kept by the async manager, context holds registry only
+        let context = Context::new(Vec::new(), registry);
+
+        Self {
+            provider_manager: ProviderManager::new(providers),
+            registry_manager: RegistryManager::new(Arc::new(RwLock::new(context))),
+        }
+    }
+
+    // Blocking providers of the context are run on the blocking pool
+    pub fn from_context(context: Context) -> Self {
+        let providers = context.providers.into_values()
+            .map(|provider| {
+                Arc::new(IntoAsync::new(provider)) as Arc<dyn AsyncProvider>
+            })
+            .collect();
+
+        Self::new(providers, context.registry)
+    }
+
+    pub fn with_workers(self: Self, workers: usize) -> Self {
+        Self {
+            provider_manager: self.provider_manager.with_workers(workers),
+            ..self
+        }
+    }
+
+    pub fn with_timeout(self: Self, timeout: Duration) -> Self {
+        Self {
+            provider_manager: self.provider_manager.with_timeout(timeout),
+            ..self
+        }
+    }
+}
+
+impl AsyncFacade for TokioFacade {
+    fn accept<'a>(
+        self: &'a mut Self,
+        strategy: &'a mut dyn AsyncStrategy
+    ) -> BoxFuture<'a, ()> {
+        strategy.execute(AsyncManagers {
+            fetch: &self.provider_manager,
+            sync: &self.provider_manager,
+            local: &mut self.registry_manager,
+        })
+    }
+}
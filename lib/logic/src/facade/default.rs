@@ -1,30 +1,53 @@
 
 use std::rc::Rc;
 use std::cell::RefCell;
+use std::sync::{Arc, RwLock};
 
 use super::{Facade, Managers, Strategy};
 
-use crate::context::Context;
+use crate::context::{Context, SharedContext};
 use crate::managers::default::{
     ProviderManager,
     RegistryManager,
 };
 
-pub struct DefaultFacade {
-    provider_manager: Box<ProviderManager>,
-    registry_manager: Box<RegistryManager>,
+// Workers used for provider calls by a concurrent facade unless set otherwise
+pub const DEFAULT_WORKERS: usize = 4;
+
+#[derive(Clone)]
+pub struct DefaultFacade<C = Rc<RefCell<Context>>> {
+    provider_manager: Box<ProviderManager<C>>,
+    registry_manager: Box<RegistryManager<C>>,
 }
 
-impl DefaultFacade {
-    pub fn new(context: Rc<RefCell<Context>>) -> Self {
+// Can be cloned and sent to other threads, all clones share the context
+pub type ConcurrentFacade = DefaultFacade<Arc<RwLock<Context>>>;
+
+impl<C: SharedContext + Clone> DefaultFacade<C> {
+    pub fn new(context: C) -> Self {
         Self {
             provider_manager: Box::new(ProviderManager::new(context.clone())),
             registry_manager: Box::new(RegistryManager::new(context)),
         }
     }
+
+    pub fn with_workers(self: Self, workers: usize) -> Self {
+        Self {
+            provider_manager: Box::new(self.provider_manager
+                                       .with_workers(workers)),
+            ..self
+        }
+    }
 }
 
-impl Facade for DefaultFacade {
+impl ConcurrentFacade {
+    pub fn shared(context: Context) -> Self {
+        Self::new(Arc::new(RwLock::new(context)))
+            .with_workers(DEFAULT_WORKERS)
+    }
+}
+
+impl<C: SharedContext> Facade for DefaultFacade<C> {
     fn accept(self: &mut Self, strategy: &mut dyn Strategy) {
         strategy.execute(Managers {
             fetch: self.provider_manager.as_ref(),
@@ -33,4 +56,3 @@ impl Facade for DefaultFacade {
         })
     }
 }
-
@@ -0,0 +1,169 @@
+
+use domain::light::{Light, ProviderID};
+use domain::group::{Group, Member};
+use crate::managers::{fetch, local};
+use crate::managers::local::LocalStateManager;
+
+// Flattens group into lights and saved names, nested groups are expanded in
+// place and every member appears once
+pub fn members(
+    local: &dyn LocalStateManager,
+    name: &str
+) -> Result<Vec<Member>, Error> {
+    let mut out = Vec::new();
+    expand(&|name| local.load_group(name), name, &mut Vec::new(), &mut out)?;
+
+    Ok(out)
+}
+
+// Checks that group can be saved as is, without forming a cycle with ones
+// already in the registry
+pub fn check(local: &dyn LocalStateManager, group: &Group) -> Result<(), Error> {
+    let lookup = |name: &str| if name == group.name {
+        Ok(group.clone())
+    } else {
+        local.load_group(name)
+    };
+
+    expand(&lookup, &group.name, &mut Vec::new(), &mut Vec::new())
+}
+
+fn expand(
+    lookup: &dyn Fn(&str) -> local::Result<Group>,
+    name: &str,
+    path: &mut Vec<String>,
+    out: &mut Vec<Member>
+) -> Result<(), Error> {
+    if let Some(pos) = path.iter().position(|visited| visited == name) {
+        let mut cycle = path[pos..].to_vec();
+        cycle.push(name.to_string());
+
+        return Err(Error::Cycle(cycle));
+    }
+
+    let group = lookup(name)?;
+    path.push(name.to_string());
+
+    for member in group.members {
+        match member {
+            Member::Group(inner) => expand(lookup, &inner, path, out)?,
+            member => if !out.contains(&member) {
+                out.push(member)
+            },
+        }
+    }
+
+    path.pop();
+    Ok(())
+}
+
+// Provider ids of every light in group, saved members are looked up in dumps
+pub fn provider_ids(
+    local: &dyn LocalStateManager,
+    name: &str
+) -> Result<Vec<ProviderID>, Error> {
+    let mut out: Vec<ProviderID> = Vec::new();
+
+    for member in members(local, name)? {
+        let id = match member {
+            Member::Light(id) => id,
+            Member::Saved(name) => local.load(&name)?.provider,
+            Member::Group(_) => continue,
+        };
+
+        if !out.contains(&id) {
+            out.push(id);
+        }
+    }
+
+    Ok(out)
+}
+
+// Names of saved lights in group, plain lights are matched against the given
+// saved ones by provider id
+pub fn saved_names(
+    local: &dyn LocalStateManager,
+    name: &str,
+    saved: impl Fn(&dyn LocalStateManager) -> local::Result<Vec<Light>>
+) -> Result<Vec<String>, Error> {
+    let members = members(local, name)?;
+    let mut out: Vec<String> = Vec::new();
+    let mut lights = None;
+
+    for member in members {
+        let name = match member {
+            Member::Saved(name) => name,
+            Member::Light(id) => {
+                if lights.is_none() {
+                    lights = Some(saved(local)?);
+                }
+
+                lights.iter().flatten()
+                    .find(|light| light.provider == id)
+                    .map(|light| light.name.clone())
+                    .ok_or(Error::Unsaved(id))?
+            },
+            Member::Group(_) => continue,
+        };
+
+        if !out.contains(&name) {
+            out.push(name);
+        }
+    }
+
+    Ok(out)
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Cycle(Vec<String>),
+    Unsaved(ProviderID),
+    Fetch(fetch::Error),
+    Local(local::Error),
+}
+
+impl From<fetch::Error> for Error {
+    fn from(err: fetch::Error) -> Self {
+        Error::Fetch(err)
+    }
+}
+
+impl From<local::Error> for Error {
+    fn from(err: local::Error) -> Self {
+        Error::Local(err)
+    }
+}
+
+impl From<crate::strategies::sync::Error> for Error {
+    fn from(err: crate::strategies::sync::Error) -> Self {
+        match err {
+            crate::strategies::sync::Error::Fetch(err) => Error::Fetch(err),
+            crate::strategies::sync::Error::Local(err) => Error::Local(err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Fetch(err) => Some(err),
+            Error::Local(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Cycle(path) => {
+                write!(f, "Groups form a cycle: {}", path.join(" -> "))
+            },
+            Error::Unsaved(id) => {
+                write!(f, "Light {} of the group has no saved state", id)
+            },
+            Error::Fetch(err) => err.fmt(f),
+            Error::Local(err) => err.fmt(f),
+        }
+    }
+}
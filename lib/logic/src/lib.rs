@@ -3,4 +3,8 @@ pub mod context;
 pub mod managers;
 pub mod facade;
 pub mod strategies;
+pub mod transition;
+pub mod group;
+pub mod batch;
+pub mod pool;
 
@@ -1,23 +1,71 @@
 
 pub mod default;
+#[cfg(feature = "async")]
+pub mod asynchronous;
 
 pub mod fetch {
+    use std::time::Duration;
+
     use provider;
     use domain::light::{
         Light,
         ProviderID
     };
+    use domain::mode::descriptor::ModeDescriptor;
+    use domain::patch::LightPatch;
 
     pub type Result<T> = std::result::Result<T, Error>;
 
+    // Failed providers (or lights) by name
+    pub type Errors = Vec<(String, Error)>;
+
+    // Lights of providers that answered along with errors of the rest
+    #[derive(Debug, Default)]
+    pub struct Partial {
+        pub lights: Vec<Light>,
+        pub errors: Errors,
+    }
+
+    impl Partial {
+        pub fn add(self: &mut Self, name: &str, result: Result<Vec<Light>>) {
+            match result {
+                Ok(mut lights) => self.lights.append(&mut lights),
+                Err(err) => self.errors.push((name.to_string(), err)),
+            }
+        }
+
+        pub fn is_complete(self: &Self) -> bool {
+            self.errors.is_empty()
+        }
+    }
+
     pub trait FetchManager {
         fn fetch_all(self: &Self) -> Result<Vec<Light>>;
+        // Doesn't fail, unreachable providers are reported separately
+        fn fetch_all_partial(self: &Self) -> Partial;
         fn fetch_provider(self: &Self, provider: &str) -> Result<Vec<Light>>;
         fn fetch(self: &Self, id: &ProviderID) -> Result<Light>;
+
+        fn fetch_modes(self: &Self, provider: &str) -> Result<Vec<ModeDescriptor>>;
+        // Catalogs of every provider in order of names
+        fn fetch_all_modes(self: &Self) -> Result<Vec<ModeDescriptor>>;
     }
 
     pub trait SyncManager {
         fn sync(self: &Self, light: &Light) -> Result<()>;
+        fn supports_transition(self: &Self, id: &ProviderID) -> Result<bool>;
+        fn sync_transition(self: &Self, light: &Light,
+                           duration: Duration) -> Result<()>;
+
+        // Only the patched parts of the light are changed, providers unable
+        // to take a patch get the fetched light with the patch merged in
+        fn apply(self: &Self, id: &ProviderID, patch: &LightPatch) -> Result<()>;
+
+        // Results follow the order of lights, different providers may be
+        // synced in parallel
+        fn sync_many(self: &Self, lights: &[Light]) -> Vec<Result<()>> {
+            lights.iter().map(|light| self.sync(light)).collect()
+        }
     }
 
     #[derive(Debug)]
@@ -52,7 +100,12 @@ pub mod fetch {
 pub mod local {
     pub use local_registry::Error;
     pub use local_registry::Result;
-    use domain::light::Light;
+    pub use local_registry::{Revision, Section};
+    use domain::light::{Light, ProviderID};
+    use domain::scene::Scene;
+    use domain::group::Group;
+    use domain::history::History;
+    use domain::patch::LightPatch;
 
     pub trait LocalStateManager {
         fn list_dumps(self: &Self) -> Result<Vec<Light>>;
@@ -66,6 +119,28 @@ pub mod local {
 
         fn remove(self: &mut Self, name: &str) -> Result<()>;
         fn rename(self: &mut Self, old: &str, new: &str) -> Result<()>;
+
+        fn store(self: &mut Self, section: Section, light: &Light,
+                 message: Option<&str>) -> Result<()>;
+        fn list_revisions(self: &Self, section: Section,
+                          name: &str) -> Result<Vec<Revision>>;
+        fn load_revision(self: &Self, section: Section, name: &str,
+                         number: u64) -> Result<Revision>;
+        fn diff_revisions(self: &Self, section: Section, name: &str,
+                          from: u64, to: u64) -> Result<LightPatch>;
+
+        fn list_scenes(self: &Self) -> Result<Vec<Scene>>;
+        fn load_scene(self: &Self, name: &str) -> Result<Scene>;
+        fn save_scene(self: &mut Self, scene: &Scene) -> Result<()>;
+        fn remove_scene(self: &mut Self, name: &str) -> Result<()>;
+
+        fn list_groups(self: &Self) -> Result<Vec<Group>>;
+        fn load_group(self: &Self, name: &str) -> Result<Group>;
+        fn save_group(self: &mut Self, group: &Group) -> Result<()>;
+        fn remove_group(self: &mut Self, name: &str) -> Result<()>;
+
+        fn load_history(self: &Self, id: &ProviderID) -> Result<History>;
+        fn save_history(self: &mut Self, history: &History) -> Result<()>;
     }
 }
 
@@ -0,0 +1,299 @@
+
+use std::sync::{Arc, Mutex, PoisonError};
+use std::future::Future;
+use std::time::Duration;
+use std::collections::HashMap;
+
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use domain::light::{Light, ProviderID};
+use domain::mode::descriptor::ModeDescriptor;
+use domain::patch::LightPatch;
+use provider::asynchronous::{AsyncProvider, BoxFuture};
+use crate::managers::fetch;
+use crate::managers::default::Catalogs;
+
+pub trait AsyncFetchManager: Sync {
+    fn fetch_all(self: &Self) -> BoxFuture<'_, fetch::Result<Vec<Light>>>;
+    fn fetch_all_partial(self: &Self) -> BoxFuture<'_, fetch::Partial>;
+    fn fetch_provider<'a>(
+        self: &'a Self,
+        provider: &'a str
+    ) -> BoxFuture<'a, fetch::Result<Vec<Light>>>;
+    fn fetch<'a>(
+        self: &'a Self,
+        id: &'a ProviderID
+    ) -> BoxFuture<'a, fetch::Result<Light>>;
+}
+
+pub trait AsyncSyncManager: Sync {
+    fn sync<'a>(self: &'a Self, light: &'a Light) -> BoxFuture<'a, fetch::Result<()>>;
+    // Results follow the order of lights
+    fn sync_many<'a>(
+        self: &'a Self,
+        lights: &'a [Light]
+    ) -> BoxFuture<'a, Vec<fetch::Result<()>>>;
+    // See SyncManager::apply
+    fn apply<'a>(
+        self: &'a Self,
+        id: &'a ProviderID,
+        patch: &'a LightPatch
+    ) -> BoxFuture<'a, fetch::Result<()>>;
+}
+
+// Calls to providers are made on tasks of the current runtime, at most
+// `workers` of them at once. Clones share catalogs, so each provider is
+// asked for its modes only once
+#[derive(Clone)]
+pub struct ProviderManager {
+    providers: Arc<HashMap<String, Arc<dyn AsyncProvider>>>,
+    limit: Arc<Semaphore>,
+    timeout: Option<Duration>,
+    catalogs: Arc<Catalogs>,
+}
+
+impl ProviderManager {
+    pub fn new(providers: Vec<Arc<dyn AsyncProvider>>) -> Self {
+        Self {
+            providers: Arc::new(providers.into_iter()
+                .map(|provider| (provider.name().to_string(), provider))
+                .collect()),
+            limit: Arc::new(Semaphore::new(Semaphore::MAX_PERMITS)),
+            timeout: None,
+            catalogs: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn with_workers(self: Self, workers: usize) -> Self {
+        Self {
+            limit: Arc::new(Semaphore::new(workers.max(1))),
+            ..self
+        }
+    }
+
+    // Applies to every single call of a provider
+    pub fn with_timeout(self: Self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    fn sorted(self: &Self) -> Vec<Arc<dyn AsyncProvider>> {
+        let mut out: Vec<_> = self.providers.values().cloned().collect();
+        out.sort_by(|a, b| a.name().cmp(b.name()));
+
+        out
+    }
+
+    // Call doesn't borrow anything, so it can be run as a separate task
+    fn call<T, F, Fut>(
+        self: &Self,
+        name: &str,
+        f: F
+    ) -> BoxFuture<'static, fetch::Result<T>>
+    where T: Send + 'static,
+          F: FnOnce(Arc<dyn AsyncProvider>) -> Fut + Send + 'static,
+          Fut: Future<Output = provider::Result<T>> + Send + 'static {
+        let name = name.to_string();
+        let provider = self.providers.get(&name).cloned();
+        let limit = self.limit.clone();
+        let timeout = self.timeout;
+
+        Box::pin(async move {
+            let provider = provider
+                .ok_or_else(|| fetch::Error::NotFound(name.clone()))?;
+            let _permit = limit.acquire_owned().await
+                .expect("Semaphore is never closed");
+
+            match timeout {
+                Some(timeout) => tokio::time::timeout(timeout, f(provider))
+                    .await
+                    .unwrap_or_else(|_| provider::Error::timeout(&name, timeout)),
+                None => f(provider).await,
+            }.map_err(fetch::Error::Provider)
+        })
+    }
+
+    fn sync_owned(self: &Self, light: &Light) -> BoxFuture<'static, fetch::Result<()>> {
+        let light = light.clone();
+        let catalogs = self.catalogs.clone();
+
+        self.call(&light.provider.name.clone(), |provider| async move {
+            if light.get_mode().is_ok() {
+                let catalog = catalog(&catalogs, &*provider).await?;
+                provider::check_mode(provider.name(), &catalog, &light)?;
+            }
+
+            provider.sync(&light).await
+        })
+    }
+
+    // Every provider in order of names along with its lights
+    async fn list_all(self: &Self) -> Vec<(String, fetch::Result<Vec<Light>>)> {
+        let names: Vec<String> = self.sorted().iter()
+            .map(|provider| provider.name().to_string())
+            .collect();
+        let calls = names.iter()
+            .map(|name| {
+                self.call(name, |provider| async move { provider.list().await })
+            })
+            .collect();
+
+        names.into_iter().zip(join(calls).await).collect()
+    }
+}
+
+// Failed requests aren't kept, so the catalog is asked for again next time
+async fn catalog(
+    catalogs: &Catalogs,
+    provider: &dyn AsyncProvider
+) -> provider::Result<Vec<ModeDescriptor>> {
+    let lock = || catalogs.lock().unwrap_or_else(PoisonError::into_inner);
+    let cached = lock().get(provider.name()).cloned();
+
+    if let Some(catalog) = cached {
+        return Ok(catalog);
+    }
+
+    let catalog = provider.modes().await?;
+    lock().insert(provider.name().to_string(), catalog.clone());
+
+    Ok(catalog)
+}
+
+// Runs every call as a separate task, results keep the order of calls.
+// Dropping the future aborts calls that aren't done yet
+async fn join<T: Send + 'static>(calls: Vec<BoxFuture<'static, T>>) -> Vec<T> {
+    let mut out: Vec<Option<T>> = calls.iter().map(|_| None).collect();
+    let mut set = JoinSet::new();
+
+    for (index, call) in calls.into_iter().enumerate() {
+        set.spawn(async move { (index, call.await) });
+    }
+
+    while let Some(joined) = set.join_next().await {
+        let (index, result) = joined
+            .unwrap_or_else(|err| std::panic::resume_unwind(err.into_panic()));
+        out[index] = Some(result);
+    }
+
+    out.into_iter().map(|result| result.expect("Every call is joined"))
+        .collect()
+}
+
+impl AsyncFetchManager for ProviderManager {
+    fn fetch_all(self: &Self) -> BoxFuture<'_, fetch::Result<Vec<Light>>> {
+        Box::pin(async move {
+            self.list_all().await.into_iter()
+                .try_fold(Vec::new(), |mut vec, (_, list)| {
+                    vec.append(&mut list?);
+                    Ok(vec)
+                })
+        })
+    }
+
+    fn fetch_all_partial(self: &Self) -> BoxFuture<'_, fetch::Partial> {
+        Box::pin(async move {
+            self.list_all().await.into_iter()
+                .fold(fetch::Partial::default(), |mut partial, (name, list)| {
+                    partial.add(&name, list);
+                    partial
+                })
+        })
+    }
+
+    fn fetch_provider<'a>(
+        self: &'a Self,
+        provider: &'a str
+    ) -> BoxFuture<'a, fetch::Result<Vec<Light>>> {
+        self.call(provider, |provider| async move { provider.list().await })
+    }
+
+    fn fetch<'a>(
+        self: &'a Self,
+        id: &'a ProviderID
+    ) -> BoxFuture<'a, fetch::Result<Light>> {
+        let light = id.id.clone();
+
+        self.call(&id.name, |provider| async move {
+            provider.get(&light).await
+        })
+    }
+}
+
+impl AsyncSyncManager for ProviderManager {
+    fn sync<'a>(self: &'a Self, light: &'a Light) -> BoxFuture<'a, fetch::Result<()>> {
+        self.sync_owned(light)
+    }
+
+    fn apply<'a>(
+        self: &'a Self,
+        id: &'a ProviderID,
+        patch: &'a LightPatch
+    ) -> BoxFuture<'a, fetch::Result<()>> {
+        let id = id.clone();
+        let patch = patch.clone();
+        let catalogs = self.catalogs.clone();
+
+        self.call(&id.name.clone(), |provider| async move {
+            if patch.mode.is_some() {
+                let catalog = catalog(&catalogs, &*provider).await?;
+                provider::check_patch(provider.name(), &catalog, &id.id,
+                                      &patch)?;
+            }
+
+            provider.apply(&id.id, &patch).await
+        })
+    }
+
+    // Lights of one provider are synced in order by the same task
+    fn sync_many<'a>(
+        self: &'a Self,
+        lights: &'a [Light]
+    ) -> BoxFuture<'a, Vec<fetch::Result<()>>> {
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+
+        for (index, light) in lights.iter().enumerate() {
+            let name = &light.provider.name;
+
+            match groups.iter_mut()
+                .find(|group| lights[group[0]].provider.name == *name) {
+                Some(group) => group.push(index),
+                None => groups.push(vec![index]),
+            }
+        }
+
+        let calls = groups.iter()
+            .map(|group| {
+                let syncs: Vec<_> = group.iter()
+                    .map(|&index| (index, self.sync_owned(&lights[index])))
+                    .collect();
+
+                Box::pin(async move {
+                    let mut out = Vec::with_capacity(syncs.len());
+
+                    for (index, sync) in syncs {
+                        out.push((index, sync.await));
+                    }
+
+                    out
+                }) as BoxFuture<'static, _>
+            })
+            .collect();
+
+        Box::pin(async move {
+            let mut out: Vec<Option<fetch::Result<()>>> = lights.iter()
+                .map(|_| None)
+                .collect();
+
+            for (index, result) in join(calls).await.into_iter().flatten() {
+                out[index] = Some(result);
+            }
+
+            out.into_iter().map(|result| result.expect("Every light is synced"))
+                .collect()
+        })
+    }
+}
@@ -1,119 +1,348 @@
 
 use std::rc::Rc;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::collections::HashMap;
+use std::time::Duration;
 use std::cell::RefCell;
 
 use domain::light::{
     Light,
     ProviderID,
 };
-use crate::context::Context;
+use domain::scene::Scene;
+use domain::group::Group;
+use domain::history::History;
+use domain::mode::descriptor::ModeDescriptor;
+use domain::patch::LightPatch;
+use provider::Provider;
+use crate::pool;
+use crate::context::{Context, SharedContext};
 use crate::managers::fetch::{
     self,
     FetchManager,
     SyncManager
 };
-use crate::managers::local::LocalStateManager;
+use crate::managers::local::{LocalStateManager, Revision, Section};
 
-pub struct ProviderManager {
-    context: Rc<RefCell<Context>>,
+// Catalogs of modes by provider name, kept once they were asked for
+pub(crate) type Catalogs = Mutex<HashMap<String, Vec<ModeDescriptor>>>;
+
+// Single worker means providers are called one by one on the calling thread.
+// Clones share catalogs, so each provider is asked for its modes only once
+#[derive(Clone)]
+pub struct ProviderManager<C = Rc<RefCell<Context>>> {
+    context: C,
+    workers: usize,
+    catalogs: Arc<Catalogs>,
 }
 
-pub struct RegistryManager {
-    context: Rc<RefCell<Context>>,
+#[derive(Clone)]
+pub struct RegistryManager<C = Rc<RefCell<Context>>> {
+    context: C,
 }
 
-impl ProviderManager {
-    pub fn new(context: Rc<RefCell<Context>>) -> Self {
+impl<C: SharedContext> ProviderManager<C> {
+    pub fn new(context: C) -> Self {
         Self {
-            context
+            context,
+            workers: 1,
+            catalogs: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn with_workers(self: Self, workers: usize) -> Self {
+        Self {
+            workers: workers.max(1),
+            ..self
         }
     }
+
+    // Provider is taken out of the context, so the context isn't locked
+    // while it's being called
+    fn provider(self: &Self, name: &str) -> fetch::Result<Arc<dyn Provider>> {
+        self.context.read().get_provider_by_name(name)
+            .ok_or_else(|| fetch::Error::NotFound(name.to_string()))
+    }
+
+    // Every provider in order of names along with its lights
+    fn list_all(self: &Self) -> Vec<(String, fetch::Result<Vec<Light>>)> {
+        let providers = self.context.read().sorted_providers();
+
+        pool::map(&providers, self.workers, |provider| {
+            (provider.name().to_string(),
+             provider.list().map_err(fetch::Error::Provider))
+        })
+    }
 }
 
-impl RegistryManager {
-    pub fn new(context: Rc<RefCell<Context>>) -> Self {
+impl<C: SharedContext> RegistryManager<C> {
+    pub fn new(context: C) -> Self {
         Self {
             context
         }
     }
 }
 
-impl FetchManager for ProviderManager {
+// Failed requests aren't kept, so the catalog is asked for again next time
+fn catalog(catalogs: &Catalogs,
+           provider: &dyn Provider) -> provider::Result<Vec<ModeDescriptor>> {
+    let lock = || catalogs.lock().unwrap_or_else(PoisonError::into_inner);
+
+    if let Some(catalog) = lock().get(provider.name()) {
+        return Ok(catalog.clone());
+    }
+
+    let catalog = provider.modes()?;
+    lock().insert(provider.name().to_string(), catalog.clone());
+
+    Ok(catalog)
+}
+
+// Catalog is only asked for when the light has a mode
+fn check_mode(catalogs: &Catalogs, provider: &dyn Provider,
+              light: &Light) -> provider::Result<()> {
+    match light.get_mode() {
+        Ok(_) => provider::check_mode(provider.name(),
+                                      &catalog(catalogs, provider)?, light),
+        Err(_) => Ok(()),
+    }
+}
+
+fn check_patch(catalogs: &Catalogs, provider: &dyn Provider, id: &str,
+               patch: &LightPatch) -> provider::Result<()> {
+    match patch.mode {
+        Some(_) => provider::check_patch(provider.name(),
+                                         &catalog(catalogs, provider)?,
+                                         id, patch),
+        None => Ok(()),
+    }
+}
+
+fn sync(catalogs: &Catalogs, provider: &dyn Provider,
+        light: &Light) -> fetch::Result<()> {
+    check_mode(catalogs, provider, light)
+        .and_then(|_| provider.sync(light))
+        .map_err(fetch::Error::Provider)
+}
+
+impl<C: SharedContext> FetchManager for ProviderManager<C> {
     fn fetch_all(self: &Self) -> fetch::Result<Vec<Light>> {
-        self.context.borrow().providers.values()
-            .map(|provider| provider.list())
-            .try_fold(Vec::new(), |mut vec, list| {
-                match list {
-                    Ok(mut list) => {
-                        vec.append(&mut list);
-                        Ok(vec)
-                    },
-                    Err(err) => Err(fetch::Error::Provider(err)),
-                }
+        self.list_all().into_iter()
+            .try_fold(Vec::new(), |mut vec, (_, list)| {
+                vec.append(&mut list?);
+                Ok(vec)
+            })
+    }
+
+    fn fetch_all_partial(self: &Self) -> fetch::Partial {
+        self.list_all().into_iter()
+            .fold(fetch::Partial::default(), |mut partial, (name, list)| {
+                partial.add(&name, list);
+                partial
             })
     }
 
     fn fetch_provider(self: &Self, provider: &str) -> fetch::Result<Vec<Light>> {
-        self.context.borrow().get_provider_by_name(provider)
-            .and_then(|provider| Some(provider.list()))
-            .map_or_else(
-                ||     Err(fetch::Error::NotFound(provider.to_string())),
-                |item| item.map_err(|err| fetch::Error::Provider(err))
-            )
+        self.provider(provider)?.list().map_err(fetch::Error::Provider)
     }
 
     fn fetch(self: &Self, id: &ProviderID) -> fetch::Result<Light> {
-        self.context.borrow().get_provider_by_id(id)
-            .and_then(|provider| Some(provider.get(&id.id)))
-            .map_or_else(
-                ||     Err(fetch::Error::NotFound(id.name.clone())),
-                |item| item.map_err(|err| fetch::Error::Provider(err))
-            )
+        self.provider(&id.name)?.get(&id.id).map_err(fetch::Error::Provider)
+    }
+
+    fn fetch_modes(self: &Self, provider: &str) -> fetch::Result<Vec<ModeDescriptor>> {
+        catalog(&self.catalogs, &*self.provider(provider)?)
+            .map_err(fetch::Error::Provider)
+    }
+
+    fn fetch_all_modes(self: &Self) -> fetch::Result<Vec<ModeDescriptor>> {
+        let providers = self.context.read().sorted_providers();
+        let catalogs = &*self.catalogs;
+
+        pool::map(&providers, self.workers,
+                  |provider| catalog(catalogs, &**provider))
+            .into_iter()
+            .try_fold(Vec::new(), |mut vec, modes| {
+                vec.append(&mut modes.map_err(fetch::Error::Provider)?);
+                Ok(vec)
+            })
     }
 }
 
-impl SyncManager for ProviderManager {
+impl<C: SharedContext> SyncManager for ProviderManager<C> {
     fn sync(self: &Self, light: &Light) -> fetch::Result<()> {
-        self.context.borrow().get_provider_by_id(&light.provider)
-            .and_then(|provider| Some(provider.sync(light)))
-            .map_or_else(
-                ||     Err(fetch::Error::NotFound(light.provider.name.clone())),
-                |item| item.map_err(|err| fetch::Error::Provider(err))
-            )
+        sync(&self.catalogs, &*self.provider(&light.provider.name)?, light)
+    }
+
+    fn supports_transition(self: &Self, id: &ProviderID) -> fetch::Result<bool> {
+        Ok(self.provider(&id.name)?.supports_transition())
+    }
+
+    fn sync_transition(self: &Self, light: &Light,
+                       duration: Duration) -> fetch::Result<()> {
+        let provider = self.provider(&light.provider.name)?;
+
+        check_mode(&self.catalogs, &*provider, light)
+            .and_then(|_| provider.sync_transition(light, duration))
+            .map_err(fetch::Error::Provider)
+    }
+
+    fn apply(self: &Self, id: &ProviderID,
+             patch: &LightPatch) -> fetch::Result<()> {
+        let provider = self.provider(&id.name)?;
+
+        check_patch(&self.catalogs, &*provider, &id.id, patch)
+            .and_then(|_| provider.apply(&id.id, patch))
+            .map_err(fetch::Error::Provider)
+    }
+
+    // Lights of one provider are synced in order by the same worker
+    fn sync_many(self: &Self, lights: &[Light]) -> Vec<fetch::Result<()>> {
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+
+        for (index, light) in lights.iter().enumerate() {
+            let name = &light.provider.name;
+
+            match groups.iter_mut()
+                .find(|group| lights[group[0]].provider.name == *name) {
+                Some(group) => group.push(index),
+                None => groups.push(vec![index]),
+            }
+        }
+
+        let groups: Vec<_> = {
+            let context = self.context.read();
+
+            groups.into_iter()
+                .map(|group| {
+                    let id = &lights[group[0]].provider;
+                    (context.get_provider_by_id(id), group)
+                })
+                .collect()
+        };
+
+        let catalogs: &Catalogs = &self.catalogs;
+        let synced = pool::map(&groups, self.workers, |(provider, group)| {
+            group.iter()
+                .map(|&index| {
+                    let light = &lights[index];
+
+                    (index, provider.as_ref()
+                        .ok_or_else(|| {
+                            fetch::Error::NotFound(light.provider.name.clone())
+                        })
+                        .and_then(|provider| {
+                            sync(catalogs, &**provider, light)
+                        }))
+                })
+                .collect::<Vec<_>>()
+        });
+
+        let mut out: Vec<Option<fetch::Result<()>>> = lights.iter()
+            .map(|_| None)
+            .collect();
+
+        for (index, result) in synced.into_iter().flatten() {
+            out[index] = Some(result);
+        }
+
+        out.into_iter().map(|result| result.expect("Every light is synced"))
+            .collect()
     }
 }
 
-impl LocalStateManager for RegistryManager {
+impl<C: SharedContext> LocalStateManager for RegistryManager<C> {
     fn list_dumps(self: &Self) -> local_registry::Result<Vec<Light>> {
-        self.context.borrow().registry.list_dumps()
+        self.context.read().registry.list_dumps()
     }
 
     fn list_defaults(self: &Self) -> local_registry::Result<Vec<Light>> {
-        self.context.borrow().registry.list_defaults()
+        self.context.read().registry.list_defaults()
     }
 
     fn save(self: &mut Self, light: &Light) -> local_registry::Result<()> {
-        self.context.borrow_mut().registry.dump(light)
+        self.context.write().registry.dump(light)
     }
 
     fn load(self: &Self, name: &str) -> local_registry::Result<Light> {
-        self.context.borrow().registry.load_dump(name)
+        self.context.read().registry.load_dump(name)
     }
 
     fn set_default(self: &mut Self, light: &Light) -> local_registry::Result<()> {
-        self.context.borrow_mut().registry.default(light)
+        self.context.write().registry.default(light)
     }
 
     fn get_default(self: &Self, name: &str) -> local_registry::Result<Light> {
-        self.context.borrow().registry.load_default(name)
+        self.context.read().registry.load_default(name)
     }
 
     fn remove(self: &mut Self, name: &str) -> local_registry::Result<()> {
-        self.context.borrow_mut().registry.remove(name)
+        self.context.write().registry.remove(name)
     }
 
     fn rename(self: &mut Self, old: &str, new: &str) -> local_registry::Result<()> {
-        self.context.borrow_mut().registry.rename(old, new)
+        self.context.write().registry.rename(old, new)
+    }
+
+    fn store(self: &mut Self, section: Section, light: &Light,
+             message: Option<&str>) -> local_registry::Result<()> {
+        self.context.write().registry.store(section, light, message)
+    }
+
+    fn list_revisions(self: &Self, section: Section,
+                      name: &str) -> local_registry::Result<Vec<Revision>> {
+        self.context.read().registry.list_revisions(section, name)
+    }
+
+    fn load_revision(self: &Self, section: Section, name: &str,
+                     number: u64) -> local_registry::Result<Revision> {
+        self.context.read().registry.load_revision(section, name, number)
+    }
+
+    fn diff_revisions(self: &Self, section: Section, name: &str, from: u64,
+                      to: u64) -> local_registry::Result<LightPatch> {
+        self.context.read().registry.diff_revisions(section, name, from, to)
+    }
+
+    fn list_scenes(self: &Self) -> local_registry::Result<Vec<Scene>> {
+        self.context.read().registry.list_scenes()
+    }
+
+    fn load_scene(self: &Self, name: &str) -> local_registry::Result<Scene> {
+        self.context.read().registry.load_scene(name)
+    }
+
+    fn save_scene(self: &mut Self, scene: &Scene) -> local_registry::Result<()> {
+        self.context.write().registry.save_scene(scene)
+    }
+
+    fn remove_scene(self: &mut Self, name: &str) -> local_registry::Result<()> {
+        self.context.write().registry.remove_scene(name)
+    }
+
+    fn list_groups(self: &Self) -> local_registry::Result<Vec<Group>> {
+        self.context.read().registry.list_groups()
     }
-}
 
+    fn load_group(self: &Self, name: &str) -> local_registry::Result<Group> {
+        self.context.read().registry.load_group(name)
+    }
+
+    fn save_group(self: &mut Self, group: &Group) -> local_registry::Result<()> {
+        self.context.write().registry.save_group(group)
+    }
+
+    fn remove_group(self: &mut Self, name: &str) -> local_registry::Result<()> {
+        self.context.write().registry.remove_group(name)
+    }
+
+    fn load_history(self: &Self,
+                    id: &ProviderID) -> local_registry::Result<History> {
+        self.context.read().registry.load_history(id)
+    }
+
+    fn save_history(self: &mut Self,
+                    history: &History) -> local_registry::Result<()> {
+        self.context.write().registry.save_history(history)
+    }
+}
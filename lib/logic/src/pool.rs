@@ -0,0 +1,44 @@
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Applies f to every item on at most `workers` threads, results keep the
+// order of items. Nothing is spawned for a single worker
+pub fn map<T, R, F>(items: &[T], workers: usize, f: F) -> Vec<R>
+where T: Sync,
+      R: Send,
+      F: Fn(&T) -> R + Sync {
+    let workers = workers.min(items.len());
+
+    if 1 >= workers {
+        return items.iter().map(f).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let results: Vec<Mutex<Option<R>>> = items.iter()
+        .map(|_| Mutex::new(None))
+        .collect();
+
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+
+                match items.get(index) {
+                    Some(item) => {
+                        let result = f(item);
+                        *results[index].lock().expect("Not poisoned") = Some(result);
+                    },
+                    None => break,
+                }
+            });
+        }
+    });
+
+    results.into_iter()
+        .map(|result| {
+            result.into_inner().expect("Not poisoned")
+                .expect("Every item is processed")
+        })
+        .collect()
+}
@@ -1,7 +1,15 @@
 
 pub use crate::facade::{Strategy, StrategyResult};
+#[cfg(feature = "async")]
+pub use crate::facade::AsyncStrategy;
 
 pub mod list;
 pub mod sync;
 pub mod save;
-
+pub mod power;
+pub mod transition;
+pub mod scene;
+pub mod group;
+pub mod history;
+#[cfg(feature = "async")]
+pub mod asynchronous;
@@ -0,0 +1,170 @@
+
+use domain::light::{Light, ProviderID};
+use provider::asynchronous::BoxFuture;
+use super::{AsyncStrategy, StrategyResult};
+use super::scene::Report;
+use crate::facade::AsyncManagers;
+use crate::managers::{fetch, local};
+
+pub struct All(Option<fetch::Result<Vec<Light>>>);
+
+impl Default for All {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl All {
+    pub fn new() -> Self {
+        Self(None)
+    }
+}
+
+impl AsyncStrategy for All {
+    fn execute<'a>(
+        self: &'a mut Self,
+        managers: AsyncManagers<'a>
+    ) -> BoxFuture<'a, ()> {
+        Box::pin(async move {
+            self.0 = Some(managers.fetch.fetch_all().await)
+        })
+    }
+}
+
+impl StrategyResult for All {
+    type Result = fetch::Result<Vec<Light>>;
+
+    fn result(self: Self) -> Option<Self::Result> {
+        self.0
+    }
+}
+
+// Lights of providers that answered in time, see fetch::Partial
+pub struct Partial(Option<fetch::Partial>);
+
+impl Default for Partial {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Partial {
+    pub fn new() -> Self {
+        Self(None)
+    }
+}
+
+impl AsyncStrategy for Partial {
+    fn execute<'a>(
+        self: &'a mut Self,
+        managers: AsyncManagers<'a>
+    ) -> BoxFuture<'a, ()> {
+        Box::pin(async move {
+            self.0 = Some(managers.fetch.fetch_all_partial().await)
+        })
+    }
+}
+
+impl StrategyResult for Partial {
+    type Result = fetch::Partial;
+
+    fn result(self: Self) -> Option<Self::Result> {
+        self.0
+    }
+}
+
+pub struct Get<'a>(&'a ProviderID, Option<fetch::Result<Light>>);
+
+impl<'a> Get<'a> {
+    pub fn new(id: &'a ProviderID) -> Self {
+        Self(id, None)
+    }
+}
+
+impl<'a> AsyncStrategy for Get<'a> {
+    fn execute<'b>(
+        self: &'b mut Self,
+        managers: AsyncManagers<'b>
+    ) -> BoxFuture<'b, ()> {
+        Box::pin(async move {
+            self.1 = Some(managers.fetch.fetch(self.0).await)
+        })
+    }
+}
+
+impl<'a> StrategyResult for Get<'a> {
+    type Result = fetch::Result<Light>;
+
+    fn result(self: Self) -> Option<Self::Result> {
+        self.1
+    }
+}
+
+// Syncs given lights as they are, failure of one light doesn't stop the rest
+pub struct SyncLights<'a>(&'a [Light], Option<Report>);
+
+impl<'a> SyncLights<'a> {
+    pub fn new(lights: &'a [Light]) -> Self {
+        Self(lights, None)
+    }
+}
+
+impl<'a> AsyncStrategy for SyncLights<'a> {
+    fn execute<'b>(
+        self: &'b mut Self,
+        managers: AsyncManagers<'b>
+    ) -> BoxFuture<'b, ()> {
+        Box::pin(async move {
+            self.1 = Some(report(self.0, managers.sync.sync_many(self.0).await))
+        })
+    }
+}
+
+impl<'a> StrategyResult for SyncLights<'a> {
+    type Result = Report;
+
+    fn result(self: Self) -> Option<Self::Result> {
+        self.1
+    }
+}
+
+// Async counterpart of scene::Apply
+pub struct Apply<'a>(&'a str, Option<local::Result<Report>>);
+
+impl<'a> Apply<'a> {
+    pub fn new(name: &'a str) -> Self {
+        Self(name, None)
+    }
+}
+
+impl<'a> AsyncStrategy for Apply<'a> {
+    fn execute<'b>(
+        self: &'b mut Self,
+        managers: AsyncManagers<'b>
+    ) -> BoxFuture<'b, ()> {
+        Box::pin(async move {
+            self.1 = Some(match managers.local.load_scene(self.0) {
+                Ok(scene) => {
+                    let synced = managers.sync.sync_many(&scene.lights).await;
+                    Ok(report(&scene.lights, synced))
+                },
+                Err(err) => Err(err),
+            })
+        })
+    }
+}
+
+impl<'a> StrategyResult for Apply<'a> {
+    type Result = local::Result<Report>;
+
+    fn result(self: Self) -> Option<Self::Result> {
+        self.1
+    }
+}
+
+fn report(lights: &[Light], synced: Vec<fetch::Result<()>>) -> Report {
+    lights.iter()
+        .map(|light| light.provider.clone())
+        .zip(synced)
+        .collect()
+}
@@ -0,0 +1,87 @@
+
+use domain::group::Group;
+use super::{Strategy, StrategyResult};
+use crate::facade::Managers;
+use crate::group::{self, Error};
+use crate::managers::local;
+
+// Saves group after making sure it doesn't include itself, directly or
+// through other groups
+pub struct Save<'a>(&'a Group, Option<Result<(), Error>>);
+
+impl<'a> Save<'a> {
+    pub fn new(group: &'a Group) -> Self {
+        Self(group, None)
+    }
+}
+
+impl<'a> Strategy for Save<'a> {
+    fn execute(self: &mut Self, managers: Managers) {
+        self.1 = Some(
+            group::check(&*managers.local, self.0)
+                .and_then(|_| {
+                    managers.local.save_group(self.0).map_err(Error::Local)
+                })
+        )
+    }
+}
+
+impl<'a> StrategyResult for Save<'a> {
+    type Result = Result<(), Error>;
+
+    fn result(self: Self) -> Option<Self::Result> {
+        self.1
+    }
+}
+
+pub struct All(Option<local::Result<Vec<Group>>>);
+
+impl Default for All {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl All {
+    pub fn new() -> Self {
+        Self(None)
+    }
+}
+
+impl Strategy for All {
+    fn execute(self: &mut Self, managers: Managers) {
+        self.0 = Some(managers.local.list_groups())
+    }
+}
+
+impl StrategyResult for All {
+    type Result = local::Result<Vec<Group>>;
+
+    fn result(self: Self) -> Option<Self::Result> {
+        self.0
+    }
+}
+
+// Groups including the removed one are left as is and fail to resolve until
+// updated
+pub struct Delete<'a>(&'a str, Option<local::Result<()>>);
+
+impl<'a> Delete<'a> {
+    pub fn new(name: &'a str) -> Self {
+        Self(name, None)
+    }
+}
+
+impl<'a> Strategy for Delete<'a> {
+    fn execute(self: &mut Self, managers: Managers) {
+        self.1 = Some(managers.local.remove_group(self.0))
+    }
+}
+
+impl<'a> StrategyResult for Delete<'a> {
+    type Result = local::Result<()>;
+
+    fn result(self: Self) -> Option<Self::Result> {
+        self.1
+    }
+}
@@ -0,0 +1,163 @@
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use domain::light::{Light, ProviderID};
+use domain::history::{Entry, History};
+use super::{Strategy, StrategyResult};
+use crate::facade::Managers;
+use crate::managers::{fetch, local};
+use crate::managers::local::LocalStateManager;
+
+fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() as i64)
+}
+
+// Light is synced already when its previous state is recorded, so failing
+// to save the history doesn't fail the sync, it's returned next to it
+pub type Recorded = local::Result<()>;
+
+pub fn record(local: &mut dyn LocalStateManager, previous: Light) -> Recorded {
+    let mut history = local.load_history(&previous.provider)?;
+    history.record(Entry::new(now(), previous));
+    local.save_history(&history)
+}
+
+// Lights synced at once with sync_many, only the ones that were synced are
+// recorded and the first failure is returned. Histories which couldn't be
+// saved are returned on success
+pub fn record_synced(
+    local: &mut dyn LocalStateManager,
+    previous: Vec<Light>,
+    results: Vec<fetch::Result<()>>
+) -> fetch::Result<Vec<local::Error>> {
+    let mut failure = None;
+    let mut unrecorded = Vec::new();
+
+    for (previous, result) in previous.into_iter().zip(results) {
+        match result {
+            Ok(_) => unrecorded.extend(record(local, previous).err()),
+            Err(err) => {
+                failure.get_or_insert(err);
+            },
+        }
+    }
+
+    failure.map_or(Ok(unrecorded), Err)
+}
+
+// Recorded states of a light, empty if it was never synced
+pub struct Get<'a>(&'a ProviderID, Option<local::Result<History>>);
+
+impl<'a> Get<'a> {
+    pub fn new(id: &'a ProviderID) -> Self {
+        Self(id, None)
+    }
+}
+
+impl<'a> Strategy for Get<'a> {
+    fn execute(self: &mut Self, managers: Managers) {
+        self.1 = Some(managers.local.load_history(self.0))
+    }
+}
+
+impl<'a> StrategyResult for Get<'a> {
+    type Result = local::Result<History>;
+
+    fn result(self: Self) -> Option<Self::Result> {
+        self.1
+    }
+}
+
+// Moves along the history one way, current state goes to the other one
+fn step(
+    managers: Managers,
+    id: &ProviderID,
+    next: fn(&mut History, Entry) -> Option<Entry>
+) -> Result<Light, Error> {
+    let mut history = managers.local.load_history(id).map_err(Error::Local)?;
+    let current = managers.fetch.fetch(id).map_err(Error::Fetch)?;
+    let entry = next(&mut history, Entry::new(now(), current))
+        .ok_or_else(|| Error::Empty(id.clone()))?;
+
+    managers.sync.sync(&entry.light).map_err(Error::Fetch)?;
+    managers.local.save_history(&history).map_err(Error::Local)?;
+
+    Ok(entry.light)
+}
+
+// Brings back the state the light had before its last sync, only the
+// provider is synced, saved lights are left as they are
+pub struct Undo<'a>(&'a ProviderID, Option<Result<Light, Error>>);
+
+impl<'a> Undo<'a> {
+    pub fn new(id: &'a ProviderID) -> Self {
+        Self(id, None)
+    }
+}
+
+impl<'a> Strategy for Undo<'a> {
+    fn execute(self: &mut Self, managers: Managers) {
+        self.1 = Some(step(managers, self.0, History::undo))
+    }
+}
+
+impl<'a> StrategyResult for Undo<'a> {
+    type Result = Result<Light, Error>;
+
+    fn result(self: Self) -> Option<Self::Result> {
+        self.1
+    }
+}
+
+pub struct Redo<'a>(&'a ProviderID, Option<Result<Light, Error>>);
+
+impl<'a> Redo<'a> {
+    pub fn new(id: &'a ProviderID) -> Self {
+        Self(id, None)
+    }
+}
+
+impl<'a> Strategy for Redo<'a> {
+    fn execute(self: &mut Self, managers: Managers) {
+        self.1 = Some(step(managers, self.0, History::redo))
+    }
+}
+
+impl<'a> StrategyResult for Redo<'a> {
+    type Result = Result<Light, Error>;
+
+    fn result(self: Self) -> Option<Self::Result> {
+        self.1
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Fetch(fetch::Error),
+    Local(local::Error),
+    // Nothing to undo or redo
+    Empty(ProviderID),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Fetch(err) => Some(err),
+            Error::Local(err) => Some(err),
+            Error::Empty(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Fetch(err) => err.fmt(f),
+            Error::Local(err) => err.fmt(f),
+            Error::Empty(id) => {
+                write!(f, "No recorded state of light \"{}\" is left", id)
+            },
+        }
+    }
+}
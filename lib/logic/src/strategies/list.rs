@@ -1,14 +1,24 @@
 
+use std::borrow::Borrow;
+
 use domain::light::{Light, ProviderID};
 use super::{Strategy, StrategyResult};
 use crate::facade::Managers;
 use crate::managers::{fetch, local};
+use crate::group;
+use crate::batch::{self, Batch, Policy};
 
 pub mod provider {
     use super::*;
 
     pub struct All(Option<fetch::Result<Vec<Light>>>);
 
+    impl Default for All {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
     impl All {
         pub fn new() -> Self {
             Self(None)
@@ -86,6 +96,129 @@ pub mod provider {
         }
     }
 
+    // Lights of healthy providers, others are reported instead of failing
+    // the whole listing
+    pub mod partial {
+        use super::*;
+
+        pub struct All(Option<fetch::Partial>);
+
+        impl Default for All {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl All {
+            pub fn new() -> Self {
+                Self(None)
+            }
+        }
+
+        impl Strategy for All {
+            fn execute(self: &mut Self, managers: Managers) {
+                self.0 = Some(managers.fetch.fetch_all_partial())
+            }
+        }
+
+        impl StrategyResult for All {
+            type Result = fetch::Partial;
+
+            fn result(self: Self) -> Option<Self::Result> {
+                self.0
+            }
+        }
+
+        pub struct Multiple<'a, I>(I, Option<fetch::Partial>)
+        where I: Iterator<Item = &'a str> + Clone;
+
+        impl<'a, I> Multiple<'a, I>
+        where I: Iterator<Item = &'a str> + Clone {
+            pub fn new(providers: I) -> Self {
+                Self(providers, None)
+            }
+        }
+
+        impl<'a, I> Strategy for Multiple<'a, I>
+        where I: Iterator<Item = &'a str> + Clone {
+            fn execute(self: &mut Self, managers: Managers) {
+                self.1 = Some(
+                    self.0.clone()
+                        .fold(fetch::Partial::default(), |mut partial, name| {
+                            let list = managers.fetch.fetch_provider(name);
+                            partial.add(name, list);
+                            partial
+                        })
+                )
+            }
+        }
+
+        impl<'a, I> StrategyResult for Multiple<'a, I>
+        where I: Iterator<Item = &'a str> + Clone {
+            type Result = fetch::Partial;
+
+            fn result(self: Self) -> Option<Self::Result> {
+                self.1
+            }
+        }
+    }
+
+    // Catalogs of modes advertised by providers
+    pub mod modes {
+        use super::*;
+        use domain::mode::descriptor::ModeDescriptor;
+
+        pub struct All(Option<fetch::Result<Vec<ModeDescriptor>>>);
+
+        impl Default for All {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl All {
+            pub fn new() -> Self {
+                Self(None)
+            }
+        }
+
+        impl Strategy for All {
+            fn execute(self: &mut Self, managers: Managers) {
+                self.0 = Some(managers.fetch.fetch_all_modes())
+            }
+        }
+
+        impl StrategyResult for All {
+            type Result = fetch::Result<Vec<ModeDescriptor>>;
+
+            fn result(self: Self) -> Option<Self::Result> {
+                self.0
+            }
+        }
+
+        pub struct Single<'a>(&'a str, Option<fetch::Result<Vec<ModeDescriptor>>>);
+
+        impl<'a> Single<'a> {
+            pub fn new(provider: &'a str) -> Self {
+                Self(provider, None)
+            }
+        }
+
+        impl<'a> Strategy for Single<'a> {
+            fn execute(self: &mut Self, managers: Managers) {
+                self.1 = Some(managers.fetch.fetch_modes(self.0))
+            }
+        }
+
+        impl<'a> StrategyResult for Single<'a> {
+            type Result = fetch::Result<Vec<ModeDescriptor>>;
+
+            fn result(self: Self) -> Option<Self::Result> {
+                self.1
+            }
+        }
+    }
+
     fn getter(
         managers: &Managers,
         id: &ProviderID
@@ -116,6 +249,26 @@ pub mod provider {
     > {
         misc::GetByIds::new(ids, getter)
     }
+
+    fn resolve(
+        managers: &Managers,
+        name: &str
+    ) -> Result<Vec<ProviderID>, group::Error> {
+        group::provider_ids(&*managers.local, name)
+    }
+
+    pub type GetByGroup<'a, G> = misc::GetByGroup<'a, ProviderID, G>;
+
+    pub fn get_by_group<'a>(
+        name: &'a str
+    ) -> GetByGroup<
+        'a,
+        impl FnMut(&Managers, &ProviderID) -> Result<Light, group::Error>,
+    > {
+        misc::GetByGroup::new(name, resolve, |managers, id| {
+            getter(managers, id).map_err(group::Error::from)
+        })
+    }
 }
 
 pub mod registry {
@@ -124,6 +277,12 @@ pub mod registry {
 
         pub struct All(Option<local::Result<Vec<Light>>>);
 
+        impl Default for All {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
         impl All {
             pub fn new() -> Self {
                 Self(None)
@@ -174,6 +333,26 @@ pub mod registry {
         > {
             misc::GetByIds::new(names, getter)
         }
+
+        fn resolve(
+            managers: &Managers,
+            name: &str
+        ) -> Result<Vec<String>, group::Error> {
+            group::saved_names(&*managers.local, name, |local| local.list_dumps())
+        }
+
+        pub type GetByGroup<'a, G> = misc::GetByGroup<'a, String, G>;
+
+        pub fn get_by_group<'a>(
+            name: &'a str
+        ) -> GetByGroup<
+            'a,
+            impl FnMut(&Managers, &String) -> Result<Light, group::Error>,
+        > {
+            misc::GetByGroup::new(name, resolve, |managers, id| {
+                getter(managers, id).map_err(group::Error::from)
+            })
+        }
     }
 
     pub mod defaults {
@@ -181,6 +360,12 @@ pub mod registry {
 
         pub struct All(Option<local::Result<Vec<Light>>>);
 
+        impl Default for All {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
         impl All {
             pub fn new() -> Self {
                 Self(None)
@@ -231,6 +416,27 @@ pub mod registry {
         > {
             misc::GetByIds::new(names, getter)
         }
+
+        fn resolve(
+            managers: &Managers,
+            name: &str
+        ) -> Result<Vec<String>, group::Error> {
+            group::saved_names(&*managers.local, name,
+                                    |local| local.list_defaults())
+        }
+
+        pub type GetByGroup<'a, G> = misc::GetByGroup<'a, String, G>;
+
+        pub fn get_by_group<'a>(
+            name: &'a str
+        ) -> GetByGroup<
+            'a,
+            impl FnMut(&Managers, &String) -> Result<Light, group::Error>,
+        > {
+            misc::GetByGroup::new(name, resolve, |managers, id| {
+                getter(managers, id).map_err(group::Error::from)
+            })
+        }
     }
 }
 
@@ -281,57 +487,130 @@ mod misc {
 
     pub struct GetByIds<'a, I, ID, G, E>
     where I: Iterator<Item = &'a ID> + Clone,
-          ID: ?Sized + 'a,
+          ID: ?Sized + ToOwned + 'a,
           G: FnMut(&Managers, &ID) -> Result<Light, E>,
           E: std::error::Error {
         ids: I,
         getter: G,
-        result: Option<Result<Vec<Light>, E>>
+        policy: Policy,
+        result: Option<Batch<ID::Owned, Light, E>>
     }
 
     impl<'a, I, ID, G, E> GetByIds<'a, I, ID, G, E>
     where I: Iterator<Item = &'a ID> + Clone,
-          ID: ?Sized + 'a,
+          ID: ?Sized + ToOwned + 'a,
           G: FnMut(&Managers, &ID) -> Result<Light, E>,
           E: std::error::Error {
         pub fn new(ids: I, getter: G) -> Self {
             Self {
                 ids,
                 getter,
+                policy: Policy::default(),
                 result: None,
             }
         }
+
+        // Nothing is changed by listing, so rollback is the same as fail fast
+        pub fn with_policy(mut self: Self, policy: Policy) -> Self {
+            self.policy = match policy {
+                Policy::Rollback => Policy::FailFast,
+                policy => policy,
+            };
+            self
+        }
     }
 
     impl<'a, I, ID, G, E> Strategy for GetByIds<'a, I, ID, G, E>
     where I: Iterator<Item = &'a ID> + Clone,
-          ID: ?Sized + 'a,
+          ID: ?Sized + ToOwned + 'a,
           G: FnMut(&Managers, &ID) -> Result<Light, E>,
           E: std::error::Error {
-        fn execute(self: &mut Self, managers: Managers) {
-            self.result = Some(
-                self.ids.clone()
-                    .map(|id| (self.getter)(&managers, id))
-                    .try_fold(Vec::new(), |mut acc, light| {
-                        light.map(|light| {
-                            acc.push(light);
-                            acc
-                        })
-                    })
-            )
+        fn execute(self: &mut Self, mut managers: Managers) {
+            self.result = Some(batch::run(
+                self.policy,
+                self.ids.clone().map(ToOwned::to_owned),
+                &mut managers,
+                |_, _| Ok(()),
+                |managers, id| (self.getter)(managers, id.borrow()),
+                |_, _| Ok(())
+            ))
         }
     }
 
     impl<'a, I, ID, G, E> StrategyResult for GetByIds<'a, I, ID, G, E>
     where I: Iterator<Item = &'a ID> + Clone,
-          ID: ?Sized + 'a,
+          ID: ?Sized + ToOwned + 'a,
           G: FnMut(&Managers, &ID) -> Result<Light, E>,
           E: std::error::Error {
-        type Result = Result<Vec<Light>, E>;
+        type Result = Batch<ID::Owned, Light, E>;
 
         fn result(self: Self) -> Option<Self::Result> {
             self.result
         }
     }
-}
 
+    // Members of the group are resolved on execution, so changes made to it
+    // after creation are seen. Failing to resolve the group is reported apart
+    // from the members
+    pub struct GetByGroup<'a, O, G>
+    where G: FnMut(&Managers, &O) -> Result<Light, group::Error> {
+        name: &'a str,
+        resolver: fn(&Managers, &str) -> Result<Vec<O>, group::Error>,
+        getter: G,
+        policy: Policy,
+        result: Option<Result<Batch<O, Light, group::Error>, group::Error>>
+    }
+
+    impl<'a, O, G> GetByGroup<'a, O, G>
+    where G: FnMut(&Managers, &O) -> Result<Light, group::Error> {
+        pub fn new(
+            name: &'a str,
+            resolver: fn(&Managers, &str) -> Result<Vec<O>, group::Error>,
+            getter: G
+        ) -> Self {
+            Self {
+                name,
+                resolver,
+                getter,
+                policy: Policy::default(),
+                result: None,
+            }
+        }
+
+        // Nothing is changed by listing, so rollback is the same as fail fast
+        pub fn with_policy(mut self: Self, policy: Policy) -> Self {
+            self.policy = match policy {
+                Policy::Rollback => Policy::FailFast,
+                policy => policy,
+            };
+            self
+        }
+    }
+
+    impl<'a, O, G> Strategy for GetByGroup<'a, O, G>
+    where G: FnMut(&Managers, &O) -> Result<Light, group::Error> {
+        fn execute(self: &mut Self, mut managers: Managers) {
+            self.result = Some(
+                (self.resolver)(&managers, self.name).map(|members| {
+                    batch::run(
+                        self.policy,
+                        members.into_iter(),
+                        &mut managers,
+                        |_, _| Ok(()),
+                        |managers, member| (self.getter)(managers, member),
+                        |_, _| Ok(())
+                    )
+                })
+            )
+        }
+    }
+
+    impl<'a, O, G> StrategyResult for GetByGroup<'a, O, G>
+    where G: FnMut(&Managers, &O) -> Result<Light, group::Error> {
+        type Result = Result<Batch<O, Light, group::Error>, group::Error>;
+
+        fn result(self: Self) -> Option<Self::Result> {
+            self.result
+        }
+    }
+}
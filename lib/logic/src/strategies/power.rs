@@ -0,0 +1,199 @@
+
+use domain::light::{self, Light, ProviderID};
+use domain::capabilities::Capability;
+use super::{Strategy, StrategyResult, history};
+use crate::facade::Managers;
+use crate::managers::{fetch, local};
+use crate::managers::local::LocalStateManager;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    On,
+    Off,
+    Toggle,
+}
+
+impl Action {
+    pub fn apply(self: &Self, light: &mut Light) -> light::Result<()> {
+        let power = match self {
+            Action::On => true,
+            Action::Off => false,
+            Action::Toggle => !light.get_power()?,
+        };
+
+        light.set_power(power)
+    }
+}
+
+enum Target<'a> {
+    Ids(Vec<&'a ProviderID>),
+    Names(Vec<&'a str>),
+    Provider(&'a str),
+}
+
+// Changed lights, with the histories which couldn't be saved for them
+#[derive(Debug)]
+pub struct Powered {
+    pub lights: Vec<Light>,
+    pub unrecorded: Vec<local::Error>,
+}
+
+// Power is always changed on the current state of a light, so saved names
+// only point to the light and the rest of the dump isn't synced
+pub struct Power<'a> {
+    target: Target<'a>,
+    action: Action,
+    save: bool,
+    result: Option<Result<Powered, Error>>,
+}
+
+impl<'a> Power<'a> {
+    fn new(target: Target<'a>, action: Action) -> Self {
+        Self {
+            target,
+            action,
+            save: false,
+            result: None,
+        }
+    }
+
+    pub fn id(id: &'a ProviderID, action: Action) -> Self {
+        Self::new(Target::Ids(vec![id]), action)
+    }
+
+    pub fn ids(
+        ids: impl Iterator<Item = &'a ProviderID>,
+        action: Action
+    ) -> Self {
+        Self::new(Target::Ids(ids.collect()), action)
+    }
+
+    pub fn name(name: &'a str, action: Action) -> Self {
+        Self::new(Target::Names(vec![name]), action)
+    }
+
+    pub fn names(names: impl Iterator<Item = &'a str>, action: Action) -> Self {
+        Self::new(Target::Names(names.collect()), action)
+    }
+
+    // Lights of the provider without power capability are left as is
+    pub fn provider(provider: &'a str, action: Action) -> Self {
+        Self::new(Target::Provider(provider), action)
+    }
+
+    // New power is written to every dump of the changed lights
+    pub fn saved(mut self: Self) -> Self {
+        self.save = true;
+        self
+    }
+
+    fn lights(self: &Self, managers: &Managers) -> Result<Vec<Light>, Error> {
+        match &self.target {
+            Target::Ids(ids) => ids.iter()
+                .map(|id| managers.fetch.fetch(id).map_err(Error::Fetch))
+                .collect(),
+            Target::Names(names) => names.iter()
+                .map(|name| {
+                    let dump = managers.local.load(name)
+                        .map_err(Error::Local)?;
+                    let mut light = managers.fetch.fetch(&dump.provider)
+                        .map_err(Error::Fetch)?;
+                    light.name = name.to_string();
+
+                    Ok(light)
+                })
+                .collect(),
+            Target::Provider(provider) => managers.fetch.fetch_provider(provider)
+                .map_err(Error::Fetch)
+                .map(|lights| {
+                    lights.into_iter()
+                        .filter(|light| light.is_capable(&[Capability::Power]))
+                        .collect()
+                }),
+        }
+    }
+
+    fn run(self: &Self, managers: Managers) -> Result<Powered, Error> {
+        // Everything is fetched first, so nothing is synced if any of the
+        // lights is unavailable
+        let mut lights = self.lights(&managers)?;
+        let previous = lights.clone();
+
+        for light in lights.iter_mut() {
+            self.action.apply(light).map_err(Error::Light)?;
+        }
+
+        let results = managers.sync.sync_many(&lights);
+        let unrecorded = history::record_synced(managers.local, previous,
+                                                results)
+            .map_err(Error::Fetch)?;
+
+        if self.save {
+            save(managers.local, &lights)?;
+        }
+
+        Ok(Powered { lights, unrecorded })
+    }
+}
+
+fn save(
+    local: &mut dyn LocalStateManager,
+    lights: &[Light]
+) -> Result<(), Error> {
+    for mut dump in local.list_dumps().map_err(Error::Local)? {
+        let power = lights.iter()
+            .find(|light| {
+                light.provider.name == dump.provider.name
+                    && light.provider.id == dump.provider.id
+            })
+            .and_then(|light| light.get_power().ok());
+
+        // Dumps without power capability have nothing to update
+        if let Some(Ok(_)) = power.map(|power| dump.set_power(power)) {
+            local.save(&dump).map_err(Error::Local)?;
+        }
+    }
+
+    Ok(())
+}
+
+impl<'a> Strategy for Power<'a> {
+    fn execute(self: &mut Self, managers: Managers) {
+        self.result = Some(self.run(managers))
+    }
+}
+
+impl<'a> StrategyResult for Power<'a> {
+    type Result = Result<Powered, Error>;
+
+    fn result(self: Self) -> Option<Self::Result> {
+        self.result
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Fetch(fetch::Error),
+    Local(local::Error),
+    Light(light::Error),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Fetch(err) => Some(err),
+            Error::Local(err) => Some(err),
+            Error::Light(err) => Some(err),
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Fetch(err) => err.fmt(f),
+            Error::Local(err) => err.fmt(f),
+            Error::Light(err) => err.fmt(f),
+        }
+    }
+}
@@ -5,6 +5,7 @@ use crate::managers::{fetch, local};
 pub mod dump;
 pub mod load_and_save;
 pub mod manage;
+pub mod revisions;
 
 #[derive(Debug)]
 pub enum Error {
@@ -3,6 +3,7 @@ use domain::light::Light;
 use super::{Strategy, StrategyResult};
 use crate::facade::Managers;
 use crate::managers::local;
+use crate::batch::{self, Batch, Policy, Snapshot};
 
 pub type Dump<'a, S> = misc::saver::Single<'a, S, local::Error>;
 pub type Dumps<'a, I, S> = misc::saver::Multiple<'a, I, S, local::Error>;
@@ -126,16 +127,17 @@ mod misc {
         pub struct Multiple<'a, I, S, E>
         where I: Iterator<Item = &'a Light> + Clone,
               S: FnMut(&mut Managers, &Light) -> Result<(), E>,
-              E: std::error::Error {
+              E: std::error::Error + From<local::Error> {
             lights: I,
             saver: S,
-            result: Option<Result<(), E>>,
+            policy: Policy,
+            result: Option<Batch<String, (), E>>,
         }
 
         impl<'a, I, S, E> Multiple<'a, I, S, E>
         where I: Iterator<Item = &'a Light> + Clone,
               S: FnMut(&mut Managers, &Light) -> Result<(), E>,
-              E: std::error::Error {
+              E: std::error::Error + From<local::Error> {
             pub fn new(lights: I, saver: S) -> Option<Self> {
                 if lights.clone().any(|light| light.name.is_empty()) {
                     None
@@ -143,31 +145,47 @@ mod misc {
                     Some(Self {
                         lights,
                         saver,
+                        policy: Policy::default(),
                         result: None,
                     })
                 }
             }
+
+            pub fn with_policy(mut self: Self, policy: Policy) -> Self {
+                self.policy = policy;
+                self
+            }
         }
 
         impl<'a, I, S, E> Strategy for Multiple<'a, I, S, E>
         where I: Iterator<Item = &'a Light> + Clone,
               S: FnMut(&mut Managers, &Light) -> Result<(), E>,
-              E: std::error::Error {
+              E: std::error::Error + From<local::Error> {
             fn execute(self: &mut Self, mut managers: Managers) {
-                self.result = Some(
-                    self.lights.clone()
-                        .try_for_each(|light| {
-                            (self.saver)(&mut managers, light)
-                        })
-                )
+                let batch = batch::run(
+                    self.policy,
+                    self.lights.clone(),
+                    &mut managers,
+                    |managers, light| {
+                        Ok(Snapshot::take(&*managers.local, &light.name)?)
+                    },
+                    |managers, light| (self.saver)(managers, light),
+                    |managers, snapshot| Ok(snapshot.restore(managers.local)?)
+                );
+
+                self.result = Some(Batch {
+                    items: batch.items.into_iter()
+                        .map(|(light, status)| (light.name.clone(), status))
+                        .collect(),
+                })
             }
         }
 
         impl<'a, I, S, E> StrategyResult for Multiple<'a, I, S, E>
         where I: Iterator<Item = &'a Light> + Clone,
               S: FnMut(&mut Managers, &Light) -> Result<(), E>,
-              E: std::error::Error {
-            type Result = Result<(), E>;
+              E: std::error::Error + From<local::Error> {
+            type Result = Batch<String, (), E>;
 
             fn result(self: Self) -> Option<Self::Result> {
                 self.result
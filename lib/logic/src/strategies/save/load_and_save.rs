@@ -15,7 +15,7 @@ fn apply<NF: FnMut(&Light) -> String>(
     light.name = name_function(light);
     managers.local.save(light)
         .and_then(|_| managers.local.set_default(light))
-        .map_err(|err| Error::Local(err))
+        .map_err(Error::Local)
 }
 
 pub struct All<NF>(NF, Option<Result<(), Error>>)
@@ -37,7 +37,7 @@ where NF: FnMut(&Light) -> String {
     fn execute(self: &mut Self, mut managers: Managers) {
         self.1 = Some(
             managers.fetch.fetch_all()
-                .map_err(|err| Error::Fetch(err))
+                .map_err(Error::Fetch)
                 .and_then(|mut lights| {
                     lights.iter_mut()
                         .try_for_each(|light| {
@@ -78,7 +78,7 @@ where NF: FnMut(&Light) -> String {
     fn execute(self: &mut Self, mut managers: Managers) {
         self.2 = Some(
             managers.fetch.fetch_provider(self.0)
-                .map_err(|err| Error::Fetch(err))
+                .map_err(Error::Fetch)
                 .and_then(|mut lights| {
                     lights.iter_mut()
                         .try_for_each(|light| {
@@ -124,7 +124,7 @@ where NF: FnMut(&Light) -> String,
             self.0.clone()
                 .try_for_each(|provider| {
                     managers.fetch.fetch_provider(provider)
-                        .map_err(|err| Error::Fetch(err))
+                        .map_err(Error::Fetch)
                         .and_then(|mut lights| {
                             lights.iter_mut()
                                 .try_for_each(|light| {
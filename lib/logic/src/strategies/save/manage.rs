@@ -2,6 +2,7 @@
 use super::{Strategy, StrategyResult};
 use crate::facade::Managers;
 use crate::managers::local;
+use crate::batch::{self, Batch, Policy, Snapshot};
 
 pub struct Rename<'a>{
     from: &'a str,
@@ -23,10 +24,48 @@ impl<'a> Rename<'a> {
     }
 }
 
+impl<'a> Rename<'a> {
+    // Groups refer to saved lights by name, so they follow the rename.
+    // Groups saved before a failure are restored
+    fn rename_members(
+        self: &Self,
+        managers: &mut Managers
+    ) -> Result<(), local::Error> {
+        let mut renamed = Vec::new();
+
+        for group in managers.local.list_groups()? {
+            let mut changed = group.clone();
+
+            if !changed.rename_saved(self.from, self.to) {
+                continue;
+            }
+
+            if let Err(err) = managers.local.save_group(&changed) {
+                for group in renamed.iter() {
+                    let _ = managers.local.save_group(group);
+                }
+
+                return Err(err);
+            }
+
+            renamed.push(group);
+        }
+
+        Ok(())
+    }
+}
+
 impl<'a> Strategy for Rename<'a> {
-    fn execute(self: &mut Self, managers: Managers) {
+    // Light is renamed back if its groups can't follow, so no member is
+    // left pointing to a name that isn't saved
+    fn execute(self: &mut Self, mut managers: Managers) {
         self.result = Some(
             managers.local.rename(self.from, self.to)
+                .and_then(|_| {
+                    self.rename_members(&mut managers).inspect_err(|_| {
+                        let _ = managers.local.rename(self.to, self.from);
+                    })
+                })
         );
     }
 }
@@ -79,7 +118,8 @@ pub mod delete {
     pub struct Multiple<'a, I>
     where I: Iterator<Item = &'a str> + Clone {
         names: I,
-        result: Option<Result<(), local::Error>>,
+        policy: Policy,
+        result: Option<Batch<String, (), local::Error>>,
     }
 
     impl<'a, I> Multiple<'a, I>
@@ -90,25 +130,35 @@ pub mod delete {
             } else {
                 Some(Self {
                     names,
+                    policy: Policy::default(),
                     result: None,
                 })
             }
         }
+
+        pub fn with_policy(mut self: Self, policy: Policy) -> Self {
+            self.policy = policy;
+            self
+        }
     }
 
     impl<'a, I> Strategy for Multiple<'a, I>
     where I: Iterator<Item = &'a str> + Clone {
-        fn execute(self: &mut Self, managers: Managers) {
-            self.result = Some(
-                self.names.clone()
-                    .try_for_each(|name| managers.local.remove(name))
-            );
+        fn execute(self: &mut Self, mut managers: Managers) {
+            self.result = Some(batch::run(
+                self.policy,
+                self.names.clone().map(str::to_string),
+                &mut managers,
+                |managers, name| Snapshot::take(&*managers.local, name),
+                |managers, name| managers.local.remove(name),
+                |managers, snapshot| snapshot.restore(managers.local)
+            ));
         }
     }
 
     impl<'a, I> StrategyResult for Multiple<'a, I>
     where I: Iterator<Item = &'a str> + Clone {
-        type Result = Result<(), local::Error>;
+        type Result = Batch<String, (), local::Error>;
 
         fn result(self: Self) -> Option<Self::Result> {
             self.result
@@ -0,0 +1,146 @@
+
+use domain::light::Light;
+use domain::patch::LightPatch;
+use super::{Strategy, StrategyResult};
+use crate::facade::Managers;
+use crate::managers::local::{self, Revision, Section};
+
+// Saves the light as a new revision, optionally saying why
+pub struct Store<'a> {
+    section: Section,
+    light: &'a Light,
+    message: Option<&'a str>,
+    result: Option<local::Result<()>>,
+}
+
+impl<'a> Store<'a> {
+    pub fn new(section: Section, light: &'a Light) -> Option<Self> {
+        if light.name.is_empty() {
+            None
+        } else {
+            Some(Self {
+                section,
+                light,
+                message: None,
+                result: None,
+            })
+        }
+    }
+
+    pub fn with_message(self: Self, message: &'a str) -> Self {
+        Self {
+            message: Some(message),
+            ..self
+        }
+    }
+}
+
+impl<'a> Strategy for Store<'a> {
+    fn execute(self: &mut Self, managers: Managers) {
+        self.result = Some(
+            managers.local.store(self.section, self.light, self.message)
+        )
+    }
+}
+
+impl<'a> StrategyResult for Store<'a> {
+    type Result = local::Result<()>;
+
+    fn result(self: Self) -> Option<Self::Result> {
+        self.result
+    }
+}
+
+// Every kept revision of a saved light, oldest first
+pub struct All<'a>(Section, &'a str, Option<local::Result<Vec<Revision>>>);
+
+impl<'a> All<'a> {
+    pub fn new(section: Section, name: &'a str) -> Self {
+        Self(section, name, None)
+    }
+}
+
+impl<'a> Strategy for All<'a> {
+    fn execute(self: &mut Self, managers: Managers) {
+        self.2 = Some(managers.local.list_revisions(self.0, self.1))
+    }
+}
+
+impl<'a> StrategyResult for All<'a> {
+    type Result = local::Result<Vec<Revision>>;
+
+    fn result(self: Self) -> Option<Self::Result> {
+        self.2
+    }
+}
+
+pub struct Get<'a> {
+    section: Section,
+    name: &'a str,
+    number: u64,
+    result: Option<local::Result<Revision>>,
+}
+
+impl<'a> Get<'a> {
+    pub fn new(section: Section, name: &'a str, number: u64) -> Self {
+        Self {
+            section,
+            name,
+            number,
+            result: None,
+        }
+    }
+}
+
+impl<'a> Strategy for Get<'a> {
+    fn execute(self: &mut Self, managers: Managers) {
+        self.result = Some(
+            managers.local.load_revision(self.section, self.name, self.number)
+        )
+    }
+}
+
+impl<'a> StrategyResult for Get<'a> {
+    type Result = local::Result<Revision>;
+
+    fn result(self: Self) -> Option<Self::Result> {
+        self.result
+    }
+}
+
+// Changes turning one revision into the other
+pub struct Diff<'a> {
+    section: Section,
+    name: &'a str,
+    from: u64,
+    to: u64,
+    result: Option<local::Result<LightPatch>>,
+}
+
+impl<'a> Diff<'a> {
+    pub fn new(section: Section, name: &'a str, from: u64, to: u64) -> Self {
+        Self {
+            section,
+            name,
+            from,
+            to,
+            result: None,
+        }
+    }
+}
+
+impl<'a> Strategy for Diff<'a> {
+    fn execute(self: &mut Self, managers: Managers) {
+        self.result = Some(managers.local.diff_revisions(
+            self.section, self.name, self.from, self.to
+        ))
+    }
+}
+
+impl<'a> StrategyResult for Diff<'a> {
+    type Result = local::Result<LightPatch>;
+
+    fn result(self: Self) -> Option<Self::Result> {
+        self.result
+    }
+}
@@ -0,0 +1,231 @@
+
+use domain::light::{Light, ProviderID};
+use domain::scene::Scene;
+use super::{Strategy, StrategyResult};
+use crate::facade::Managers;
+use crate::managers::{fetch, local};
+
+// Outcome of syncing every member of a scene, in scene order
+pub type Report = Vec<(ProviderID, fetch::Result<()>)>;
+
+// Saves current state of lights as a scene, every known light is taken
+// unless limited to given ids
+pub struct Capture<'a> {
+    name: &'a str,
+    ids: Option<Vec<&'a ProviderID>>,
+    result: Option<Result<Scene, Error>>,
+}
+
+impl<'a> Capture<'a> {
+    pub fn new(name: &'a str) -> Self {
+        Self {
+            name,
+            ids: None,
+            result: None,
+        }
+    }
+
+    pub fn with_ids(mut self: Self,
+                    ids: impl Iterator<Item = &'a ProviderID>) -> Self {
+        self.ids = Some(ids.collect());
+        self
+    }
+
+    // Captures whatever can be fetched instead of failing
+    pub fn partial(self: Self) -> PartialCapture<'a> {
+        PartialCapture(self, None)
+    }
+
+    fn run(self: &Self, managers: Managers) -> Result<Scene, Error> {
+        let lights = match &self.ids {
+            Some(ids) => ids.iter()
+                .map(|id| managers.fetch.fetch(id))
+                .collect::<fetch::Result<Vec<Light>>>(),
+            None => managers.fetch.fetch_all(),
+        }.map_err(Error::Fetch)?;
+
+        self.save(managers, lights)
+    }
+
+    fn run_partial(
+        self: &Self,
+        managers: Managers
+    ) -> Result<(Scene, fetch::Errors), Error> {
+        let partial = match &self.ids {
+            Some(ids) => ids.iter()
+                .fold(fetch::Partial::default(), |mut partial, id| {
+                    let light = managers.fetch.fetch(id).map(|light| vec![light]);
+                    partial.add(&id.to_string(), light);
+                    partial
+                }),
+            None => managers.fetch.fetch_all_partial(),
+        };
+        let fetch::Partial { lights, mut errors } = partial;
+
+        // Nothing to save
+        if lights.is_empty() && !errors.is_empty() {
+            return Err(Error::Fetch(errors.remove(0).1));
+        }
+
+        self.save(managers, lights).map(|scene| (scene, errors))
+    }
+
+    fn save(
+        self: &Self,
+        managers: Managers,
+        lights: Vec<Light>
+    ) -> Result<Scene, Error> {
+        let scene = Scene::new(
+            self.name.to_string(),
+            lights.into_iter()
+                .map(|mut light| { light.name.clear(); light })
+                .collect()
+        );
+
+        managers.local.save_scene(&scene).map_err(Error::Local)?;
+
+        Ok(scene)
+    }
+}
+
+impl<'a> Strategy for Capture<'a> {
+    fn execute(self: &mut Self, managers: Managers) {
+        self.result = Some(self.run(managers))
+    }
+}
+
+impl<'a> StrategyResult for Capture<'a> {
+    type Result = Result<Scene, Error>;
+
+    fn result(self: Self) -> Option<Self::Result> {
+        self.result
+    }
+}
+
+// Result holds the scene along with lights or providers that were left out
+pub struct PartialCapture<'a>(
+    Capture<'a>,
+    Option<Result<(Scene, fetch::Errors), Error>>
+);
+
+impl<'a> Strategy for PartialCapture<'a> {
+    fn execute(self: &mut Self, managers: Managers) {
+        self.1 = Some(self.0.run_partial(managers))
+    }
+}
+
+impl<'a> StrategyResult for PartialCapture<'a> {
+    type Result = Result<(Scene, fetch::Errors), Error>;
+
+    fn result(self: Self) -> Option<Self::Result> {
+        self.1
+    }
+}
+
+// Syncs every member of a saved scene, failure of one light doesn't stop
+// the rest
+pub struct Apply<'a>(&'a str, Option<local::Result<Report>>);
+
+impl<'a> Apply<'a> {
+    pub fn new(name: &'a str) -> Self {
+        Self(name, None)
+    }
+}
+
+impl<'a> Strategy for Apply<'a> {
+    fn execute(self: &mut Self, managers: Managers) {
+        self.1 = Some(
+            managers.local.load_scene(self.0)
+                .map(|scene| {
+                    let synced = managers.sync.sync_many(&scene.lights);
+
+                    scene.lights.iter()
+                        .map(|light| light.provider.clone())
+                        .zip(synced)
+                        .collect()
+                })
+        )
+    }
+}
+
+impl<'a> StrategyResult for Apply<'a> {
+    type Result = local::Result<Report>;
+
+    fn result(self: Self) -> Option<Self::Result> {
+        self.1
+    }
+}
+
+pub struct All(Option<local::Result<Vec<Scene>>>);
+
+impl Default for All {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl All {
+    pub fn new() -> Self {
+        Self(None)
+    }
+}
+
+impl Strategy for All {
+    fn execute(self: &mut Self, managers: Managers) {
+        self.0 = Some(managers.local.list_scenes())
+    }
+}
+
+impl StrategyResult for All {
+    type Result = local::Result<Vec<Scene>>;
+
+    fn result(self: Self) -> Option<Self::Result> {
+        self.0
+    }
+}
+
+pub struct Delete<'a>(&'a str, Option<local::Result<()>>);
+
+impl<'a> Delete<'a> {
+    pub fn new(name: &'a str) -> Self {
+        Self(name, None)
+    }
+}
+
+impl<'a> Strategy for Delete<'a> {
+    fn execute(self: &mut Self, managers: Managers) {
+        self.1 = Some(managers.local.remove_scene(self.0))
+    }
+}
+
+impl<'a> StrategyResult for Delete<'a> {
+    type Result = local::Result<()>;
+
+    fn result(self: Self) -> Option<Self::Result> {
+        self.1
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Fetch(fetch::Error),
+    Local(local::Error),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Fetch(err) => Some(err),
+            Error::Local(err) => Some(err),
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Fetch(err) => err.fmt(f),
+            Error::Local(err) => err.fmt(f),
+        }
+    }
+}
@@ -1,10 +1,16 @@
 
+use std::borrow::Borrow;
+
 use domain::light::{Light, ProviderID};
+use domain::patch::LightPatch;
 use super::{Strategy, StrategyResult};
 use crate::facade::Managers;
 use crate::managers::{fetch, local};
+use crate::group;
+use crate::batch::{self, Batch, Policy, Snapshot};
+use super::history;
 
-pub struct General<'a>(&'a Light, Option<Result<(), Error>>);
+pub struct General<'a>(&'a Light, Option<Result<history::Recorded, Error>>);
 
 impl<'a> General<'a> {
     pub fn new(light: &'a Light) -> Self {
@@ -15,14 +21,15 @@ impl<'a> General<'a> {
 impl<'a> Strategy for General<'a> {
     fn execute(self: &mut Self, managers: Managers) {
         self.1 = Some(
-            managers.sync.sync(self.0)
-                .map_err(|err| Error::Fetch(err))
-                .and_then(|_| {
+            synced(&managers, self.0)
+                .map(|previous| history::record(managers.local, previous))
+                .and_then(|recorded| {
                     if self.0.name.is_empty() {
-                        Ok(())
+                        Ok(recorded)
                     } else {
-                        managers.local.save(&self.0)
-                            .map_err(|err| Error::Local(err))
+                        managers.local.save(self.0)
+                            .map(|_| recorded)
+                            .map_err(Error::Local)
                     }
                 })
         )
@@ -30,30 +37,98 @@ impl<'a> Strategy for General<'a> {
 }
 
 impl<'a> StrategyResult for General<'a> {
-    type Result = Result<(), Error>;
+    type Result = Result<history::Recorded, Error>;
 
     fn result(self: Self) -> Option<Self::Result> {
         self.1
     }
 }
 
+// Sends the patch as is, current state of the light isn't fetched
+pub struct Patch<'a> {
+    id: &'a ProviderID,
+    patch: &'a LightPatch,
+    result: Option<Result<(), fetch::Error>>,
+}
+
+impl<'a> Patch<'a> {
+    pub fn new(id: &'a ProviderID, patch: &'a LightPatch) -> Self {
+        Self {
+            id,
+            patch,
+            result: None,
+        }
+    }
+}
+
+impl<'a> Strategy for Patch<'a> {
+    fn execute(self: &mut Self, managers: Managers) {
+        self.result = Some(managers.sync.apply(self.id, self.patch))
+    }
+}
+
+impl<'a> StrategyResult for Patch<'a> {
+    type Result = Result<(), fetch::Error>;
+
+    fn result(self: Self) -> Option<Self::Result> {
+        self.result
+    }
+}
+
+// State the light had is fetched first, so it can be recorded once the
+// light is synced
+fn synced(managers: &Managers, light: &Light) -> Result<Light, Error> {
+    managers.fetch.fetch(&light.provider)
+        .and_then(|previous| managers.sync.sync(light).map(|_| previous))
+        .map_err(Error::Fetch)
+}
+
+// Saved lights are restored both on provider and in registry
+fn saved_backup(
+    managers: &Managers,
+    name: &str,
+    saved: Result<Light, local::Error>
+) -> Result<(Light, Snapshot), Error> {
+    let light = saved.map_err(Error::Local)?;
+    let current = managers.fetch.fetch(&light.provider).map_err(Error::Fetch)?;
+    let snapshot = Snapshot::take(&*managers.local, name).map_err(Error::Local)?;
+
+    Ok((current, snapshot))
+}
+
+fn saved_undo(
+    managers: &mut Managers,
+    (light, snapshot): (Light, Snapshot)
+) -> Result<(), Error> {
+    managers.sync.sync(&light).map_err(Error::Fetch)?;
+    snapshot.restore(managers.local).map_err(Error::Local)
+}
+
 pub mod fetch_and_sync {
     use super::*;
 
+    // Only what the map changed is sent, so the provider doesn't get the
+    // rest of the state back
     fn transform(
         id: &ProviderID,
         map: &mut dyn FnMut(&mut Light),
         managers: &mut Managers
-    ) -> Result<(), fetch::Error> {
+    ) -> Result<history::Recorded, fetch::Error> {
         managers.fetch.fetch(id)
-            .and_then(|mut light| {
-                map(&mut light);
-                managers.sync.sync(&light)
+            .and_then(|light| {
+                let mut changed = light.clone();
+                map(&mut changed);
+
+                match light.diff(&changed) {
+                    patch if patch.is_empty() => Ok(Ok(())),
+                    patch => managers.sync.apply(id, &patch)
+                        .map(|_| history::record(managers.local, light)),
+                }
             })
     }
 
     pub type Single<'a, F, T> =
-        misc::Single<'a, F, T, ProviderID, (), fetch::Error>;
+        misc::Single<'a, F, T, ProviderID, history::Recorded, fetch::Error>;
 
     pub fn single<'a>(
         id: &'a ProviderID,
@@ -65,7 +140,7 @@ pub mod fetch_and_sync {
             &ProviderID,
             &mut dyn FnMut(&mut Light),
             &mut Managers
-        ) -> Result<(), fetch::Error>
+        ) -> Result<history::Recorded, fetch::Error>
     > {
         misc::Single::new(
             transform,
@@ -75,7 +150,19 @@ pub mod fetch_and_sync {
     }
 
     pub type Multiple<'a, F, T, I> =
-        misc::Multiple<'a, F, T, ProviderID, I, (), fetch::Error>;
+        misc::Multiple<'a, F, T, ProviderID, I, history::Recorded, fetch::Error,
+                       Light>;
+
+    fn backup(
+        managers: &mut Managers,
+        id: &ProviderID
+    ) -> Result<Light, fetch::Error> {
+        managers.fetch.fetch(id)
+    }
+
+    fn undo(managers: &mut Managers, light: Light) -> Result<(), fetch::Error> {
+        managers.sync.sync(&light)
+    }
 
     pub fn multiple<'a>(
         ids: impl Iterator<Item = &'a ProviderID> + Clone,
@@ -87,13 +174,48 @@ pub mod fetch_and_sync {
             &ProviderID,
             &mut dyn FnMut(&mut Light),
             &mut Managers
-        ) -> Result<(), fetch::Error>,
+        ) -> Result<history::Recorded, fetch::Error>,
         impl Iterator<Item = &'a ProviderID> + Clone
     > {
         misc::Multiple::new(
             transform,
             map,
-            ids
+            ids,
+            backup,
+            undo
+        )
+    }
+
+    pub type Group<'a, F, T> = misc::Group<'a, F, T, ProviderID, Light>;
+
+    fn resolve(
+        managers: &Managers,
+        name: &str
+    ) -> Result<Vec<ProviderID>, group::Error> {
+        group::provider_ids(&*managers.local, name)
+    }
+
+    pub fn group<'a>(
+        name: &'a str,
+        map: impl FnMut(&mut Light)
+    ) -> Group<
+        'a,
+        impl FnMut(&mut Light),
+        impl FnMut(
+            &ProviderID,
+            &mut dyn FnMut(&mut Light),
+            &mut Managers
+        ) -> Result<history::Recorded, group::Error>
+    > {
+        misc::Group::new(
+            resolve,
+            |id, map, managers| {
+                transform(id, map, managers).map_err(group::Error::from)
+            },
+            map,
+            name,
+            |managers, id| backup(managers, id).map_err(group::Error::from),
+            |managers, light| undo(managers, light).map_err(group::Error::from)
         )
     }
 }
@@ -105,25 +227,29 @@ pub mod load_and_sync {
         name: &str,
         map: &mut dyn FnMut(&mut Light),
         managers: &mut Managers
-    ) -> Result<(), Error> {
+    ) -> Result<history::Recorded, Error> {
         managers.local.load(name)
-            .map_err(|err| Error::Local(err))
+            .map_err(Error::Local)
             .map(|mut light| { map(&mut light); light })
             .and_then(|mut light| {
-                managers.sync.sync(&light)
-                    .map_err(|err| Error::Fetch(err))
-                    .and_then(|_| {
+                synced(managers, &light)
+                    .and_then(|previous| {
+                        let recorded = history::record(managers.local,
+                                                       previous);
+
                         if light.name != name {
                             light.name = name.to_string();
                         }
 
                         managers.local.save(&light)
-                            .map_err(|err| Error::Local(err))
+                            .map(|_| recorded)
+                            .map_err(Error::Local)
                     })
             })
     }
 
-    pub type Single<'a, F, T> = misc::Single<'a, F, T, str, (), Error>;
+    pub type Single<'a, F, T> =
+        misc::Single<'a, F, T, str, history::Recorded, Error>;
 
     pub fn single<'a>(
         name: &'a str,
@@ -135,7 +261,7 @@ pub mod load_and_sync {
             &str,
             &mut dyn FnMut(&mut Light),
             &mut Managers
-        ) -> Result<(), Error>
+        ) -> Result<history::Recorded, Error>
     > {
         misc::Single::new(
             transform,
@@ -145,7 +271,15 @@ pub mod load_and_sync {
     }
 
     pub type Multiple<'a, F, T, I> =
-        misc::Multiple<'a, F, T, str, I, (), Error>;
+        misc::Multiple<'a, F, T, str, I, history::Recorded, Error,
+                       (Light, Snapshot)>;
+
+    fn backup(
+        managers: &mut Managers,
+        name: &str
+    ) -> Result<(Light, Snapshot), Error> {
+        saved_backup(managers, name, managers.local.load(name))
+    }
 
     pub fn multiple<'a>(
         names: impl Iterator<Item = &'a str> + Clone,
@@ -157,13 +291,51 @@ pub mod load_and_sync {
             &str,
             &mut dyn FnMut(&mut Light),
             &mut Managers
-        ) -> Result<(), Error>,
+        ) -> Result<history::Recorded, Error>,
         impl Iterator<Item = &'a str> + Clone
     > {
         misc::Multiple::new(
             transform,
             map,
-            names
+            names,
+            backup,
+            saved_undo
+        )
+    }
+
+    pub type Group<'a, F, T> =
+        misc::Group<'a, F, T, String, (Light, Snapshot)>;
+
+    fn resolve(
+        managers: &Managers,
+        name: &str
+    ) -> Result<Vec<String>, group::Error> {
+        group::saved_names(&*managers.local, name, |local| local.list_dumps())
+    }
+
+    pub fn group<'a>(
+        name: &'a str,
+        map: impl FnMut(&mut Light)
+    ) -> Group<
+        'a,
+        impl FnMut(&mut Light),
+        impl FnMut(
+            &String,
+            &mut dyn FnMut(&mut Light),
+            &mut Managers
+        ) -> Result<history::Recorded, group::Error>
+    > {
+        misc::Group::new(
+            resolve,
+            |id, map, managers| {
+                transform(id, map, managers).map_err(group::Error::from)
+            },
+            map,
+            name,
+            |managers, name| backup(managers, name).map_err(group::Error::from),
+            |managers, saved| {
+                saved_undo(managers, saved).map_err(group::Error::from)
+            }
         )
     }
 }
@@ -175,25 +347,29 @@ pub mod default_and_sync {
         name: &str,
         map: &mut dyn FnMut(&mut Light),
         managers: &mut Managers
-    ) -> Result<(), Error> {
+    ) -> Result<history::Recorded, Error> {
         managers.local.get_default(name)
-            .map_err(|err| Error::Local(err))
+            .map_err(Error::Local)
             .map(|mut light| { map(&mut light); light })
             .and_then(|mut light| {
-                managers.sync.sync(&light)
-                    .map_err(|err| Error::Fetch(err))
-                    .and_then(|_| {
+                synced(managers, &light)
+                    .and_then(|previous| {
+                        let recorded = history::record(managers.local,
+                                                       previous);
+
                         if light.name != name {
                             light.name = name.to_string();
                         }
 
                         managers.local.save(&light)
-                            .map_err(|err| Error::Local(err))
+                            .map(|_| recorded)
+                            .map_err(Error::Local)
                     })
             })
     }
 
-    pub type Single<'a, F, T> = misc::Single<'a, F, T, str, (), Error>;
+    pub type Single<'a, F, T> =
+        misc::Single<'a, F, T, str, history::Recorded, Error>;
 
     pub fn single<'a>(
         name: &'a str,
@@ -205,7 +381,7 @@ pub mod default_and_sync {
             &str,
             &mut dyn FnMut(&mut Light),
             &mut Managers
-        ) -> Result<(), Error>
+        ) -> Result<history::Recorded, Error>
     > {
         misc::Single::new(
             transform,
@@ -215,7 +391,15 @@ pub mod default_and_sync {
     }
 
     pub type Multiple<'a, F, T, I> =
-        misc::Multiple<'a, F, T, str, I, (), Error>;
+        misc::Multiple<'a, F, T, str, I, history::Recorded, Error,
+                       (Light, Snapshot)>;
+
+    fn backup(
+        managers: &mut Managers,
+        name: &str
+    ) -> Result<(Light, Snapshot), Error> {
+        saved_backup(managers, name, managers.local.get_default(name))
+    }
 
     pub fn multiple<'a>(
         names: impl Iterator<Item = &'a str> + Clone,
@@ -227,13 +411,52 @@ pub mod default_and_sync {
             &str,
             &mut dyn FnMut(&mut Light),
             &mut Managers
-        ) -> Result<(), Error>,
+        ) -> Result<history::Recorded, Error>,
         impl Iterator<Item = &'a str> + Clone
     > {
         misc::Multiple::new(
             transform,
             map,
-            names
+            names,
+            backup,
+            saved_undo
+        )
+    }
+
+    pub type Group<'a, F, T> =
+        misc::Group<'a, F, T, String, (Light, Snapshot)>;
+
+    fn resolve(
+        managers: &Managers,
+        name: &str
+    ) -> Result<Vec<String>, group::Error> {
+        group::saved_names(&*managers.local, name,
+                            |local| local.list_defaults())
+    }
+
+    pub fn group<'a>(
+        name: &'a str,
+        map: impl FnMut(&mut Light)
+    ) -> Group<
+        'a,
+        impl FnMut(&mut Light),
+        impl FnMut(
+            &String,
+            &mut dyn FnMut(&mut Light),
+            &mut Managers
+        ) -> Result<history::Recorded, group::Error>
+    > {
+        misc::Group::new(
+            resolve,
+            |id, map, managers| {
+                transform(id, map, managers).map_err(group::Error::from)
+            },
+            map,
+            name,
+            |managers, name| backup(managers, name).map_err(group::Error::from),
+            |managers, saved| {
+                saved_undo(managers, saved).map_err(group::Error::from)
+            }
         )
     }
 }
@@ -252,17 +475,21 @@ mod misc {
         result: Option<Result<R, E>>,
     }
 
-    pub struct Multiple<'a, F, T, ID, I, R, E>
+    // Previous state of an item is taken with backup and brought back with
+    // undo, when rollback is requested
+    pub struct Multiple<'a, F, T, ID, I, R, E, U>
     where F: FnMut(&mut Light),
           T: FnMut(&ID, &mut dyn FnMut(&mut Light), &mut Managers) -> Result<R, E>,
           I: Iterator<Item = &'a ID> + Clone,
-          ID: ?Sized + 'a,
-          R: Default,
+          ID: ?Sized + ToOwned + 'a,
           E: std::error::Error {
         transformer: T,
         map: F,
         ids: I,
-        result: Option<Result<R, E>>,
+        policy: Policy,
+        backup: fn(&mut Managers, &ID) -> Result<U, E>,
+        undo: fn(&mut Managers, U) -> Result<(), E>,
+        result: Option<Batch<ID::Owned, R, E>>,
     }
 
     impl<'a, F, T, ID, R, E> Single<'a, F, T, ID, R, E>
@@ -280,21 +507,34 @@ mod misc {
         }
     }
 
-    impl<'a, F, T, ID, I, R, E> Multiple<'a, F, T, ID, I, R, E>
+    impl<'a, F, T, ID, I, R, E, U> Multiple<'a, F, T, ID, I, R, E, U>
     where F: FnMut(&mut Light),
           T: FnMut(&ID, &mut dyn FnMut(&mut Light), &mut Managers) -> Result<R, E>,
           I: Iterator<Item = &'a ID> + Clone,
-          ID: ?Sized + 'a,
-          R: Default,
+          ID: ?Sized + ToOwned + 'a,
           E: std::error::Error {
-        pub fn new(transformer: T, map: F, ids: I) -> Self {
+        pub fn new(
+            transformer: T,
+            map: F,
+            ids: I,
+            backup: fn(&mut Managers, &ID) -> Result<U, E>,
+            undo: fn(&mut Managers, U) -> Result<(), E>
+        ) -> Self {
             Self {
                 transformer,
                 map,
                 ids,
+                policy: Policy::default(),
+                backup,
+                undo,
                 result: None,
             }
         }
+
+        pub fn with_policy(mut self: Self, policy: Policy) -> Self {
+            self.policy = policy;
+            self
+        }
     }
 
     impl<'a, F, T, ID, R, E> Strategy for Single<'a, F, T, ID, R, E>
@@ -321,35 +561,123 @@ mod misc {
         }
     }
 
-    impl<'a, F, T, ID, I, R, E> Strategy for Multiple<'a, F, T, ID, I, R, E>
+    impl<'a, F, T, ID, I, R, E, U> Strategy for Multiple<'a, F, T, ID, I, R, E, U>
     where F: FnMut(&mut Light),
           T: FnMut(&ID, &mut dyn FnMut(&mut Light), &mut Managers) -> Result<R, E>,
           I: Iterator<Item = &'a ID> + Clone,
-          ID: ?Sized + 'a,
-          R: Default,
+          ID: ?Sized + ToOwned + 'a,
           E: std::error::Error {
         fn execute(self: &mut Self, mut managers: Managers) {
-            self.result = Some({
-                let mut clone = self.ids.clone();
-                let mut res = Ok(R::default());
-
-                while let (Ok(_), Some(id)) = (&res, clone.next()) {
-                    res = (self.transformer)(id, &mut self.map, &mut managers);
-                }
-
-                res
-            })
+            let backup = self.backup;
+            let undo = self.undo;
+
+            self.result = Some(batch::run(
+                self.policy,
+                self.ids.clone().map(ToOwned::to_owned),
+                &mut managers,
+                |managers, id| backup(managers, id.borrow()),
+                |managers, id| {
+                    (self.transformer)(id.borrow(), &mut self.map, managers)
+                },
+                undo
+            ))
         }
     }
 
-    impl<'a, F, T, ID, I, R, E> StrategyResult for Multiple<'a, F, T, ID, I, R, E>
+    impl<'a, F, T, ID, I, R, E, U> StrategyResult
+    for Multiple<'a, F, T, ID, I, R, E, U>
     where F: FnMut(&mut Light),
           T: FnMut(&ID, &mut dyn FnMut(&mut Light), &mut Managers) -> Result<R, E>,
           I: Iterator<Item = &'a ID> + Clone,
-          ID: ?Sized + 'a,
-          R: Default,
+          ID: ?Sized + ToOwned + 'a,
           E: std::error::Error {
-        type Result = Result<R, E>;
+        type Result = Batch<ID::Owned, R, E>;
+
+        fn result(self: Self) -> Option<Self::Result> {
+            self.result
+        }
+    }
+
+    // Resolves members of the group first, then goes over them as Multiple.
+    // Failing to resolve the group is reported apart from the members
+    pub struct Group<'a, F, T, O, U>
+    where F: FnMut(&mut Light),
+          T: FnMut(&O, &mut dyn FnMut(&mut Light), &mut Managers)
+              -> Result<history::Recorded, group::Error> {
+        resolver: fn(&Managers, &str) -> Result<Vec<O>, group::Error>,
+        transformer: T,
+        map: F,
+        name: &'a str,
+        policy: Policy,
+        backup: fn(&mut Managers, &O) -> Result<U, group::Error>,
+        undo: fn(&mut Managers, U) -> Result<(), group::Error>,
+        result: Option<Result<
+            Batch<O, history::Recorded, group::Error>,
+            group::Error
+        >>,
+    }
+
+    impl<'a, F, T, O, U> Group<'a, F, T, O, U>
+    where F: FnMut(&mut Light),
+          T: FnMut(&O, &mut dyn FnMut(&mut Light), &mut Managers)
+              -> Result<history::Recorded, group::Error> {
+        pub fn new(
+            resolver: fn(&Managers, &str) -> Result<Vec<O>, group::Error>,
+            transformer: T,
+            map: F,
+            name: &'a str,
+            backup: fn(&mut Managers, &O) -> Result<U, group::Error>,
+            undo: fn(&mut Managers, U) -> Result<(), group::Error>
+        ) -> Self {
+            Self {
+                resolver,
+                transformer,
+                map,
+                name,
+                policy: Policy::default(),
+                backup,
+                undo,
+                result: None,
+            }
+        }
+
+        pub fn with_policy(mut self: Self, policy: Policy) -> Self {
+            self.policy = policy;
+            self
+        }
+    }
+
+    impl<'a, F, T, O, U> Strategy for Group<'a, F, T, O, U>
+    where F: FnMut(&mut Light),
+          T: FnMut(&O, &mut dyn FnMut(&mut Light), &mut Managers)
+              -> Result<history::Recorded, group::Error> {
+        fn execute(self: &mut Self, mut managers: Managers) {
+            let backup = self.backup;
+            let undo = self.undo;
+
+            self.result = Some(
+                (self.resolver)(&managers, self.name).map(|members| {
+                    batch::run(
+                        self.policy,
+                        members.into_iter(),
+                        &mut managers,
+                        backup,
+                        |managers, member| {
+                            (self.transformer)(member, &mut self.map, managers)
+                        },
+                        undo
+                    )
+                })
+            )
+        }
+    }
+
+    impl<'a, F, T, O, U> StrategyResult for Group<'a, F, T, O, U>
+    where F: FnMut(&mut Light),
+          T: FnMut(&O, &mut dyn FnMut(&mut Light), &mut Managers)
+              -> Result<history::Recorded, group::Error> {
+        type Result = Result<Batch<O, history::Recorded, group::Error>,
+                           group::Error>;
 
         fn result(self: Self) -> Option<Self::Result> {
             self.result
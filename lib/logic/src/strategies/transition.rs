@@ -0,0 +1,106 @@
+
+use std::time::Duration;
+
+use domain::light::Light;
+use super::{Strategy, StrategyResult};
+use crate::facade::Managers;
+use crate::managers::fetch;
+use crate::transition::{self, Clock, Easing, SystemClock};
+
+const DEFAULT_RATE: u32 = 20; // Frames per second
+
+static SYSTEM_CLOCK: SystemClock = SystemClock;
+
+// Brings a light to the target state over the duration. Providers with
+// native transitions get the target once, others get frames at the rate
+pub struct Transition<'a> {
+    to: &'a Light,
+    from: Option<&'a Light>,
+    duration: Duration,
+    easing: Easing,
+    rate: u32,
+    clock: &'a dyn Clock,
+    result: Option<fetch::Result<()>>,
+}
+
+impl<'a> Transition<'a> {
+    pub fn new(to: &'a Light, duration: Duration) -> Self {
+        Self {
+            to,
+            from: None,
+            duration,
+            easing: Easing::default(),
+            rate: DEFAULT_RATE,
+            clock: &SYSTEM_CLOCK,
+            result: None,
+        }
+    }
+
+    // Current state of the light is fetched if start isn't given
+    pub fn with_start(mut self: Self, from: &'a Light) -> Self {
+        self.from = Some(from);
+        self
+    }
+
+    pub fn with_easing(mut self: Self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn with_rate(mut self: Self, rate: u32) -> Self {
+        self.rate = rate.max(1);
+        self
+    }
+
+    pub fn with_clock(mut self: Self, clock: &'a dyn Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    fn run(self: &Self, managers: &Managers) -> fetch::Result<()> {
+        if self.duration.is_zero() {
+            return managers.sync.sync(self.to);
+        }
+
+        if managers.sync.supports_transition(&self.to.provider)? {
+            return managers.sync.sync_transition(self.to, self.duration);
+        }
+
+        let from = match self.from {
+            Some(from) => from.clone(),
+            None => managers.fetch.fetch(&self.to.provider)?,
+        };
+        let interval = Duration::from_secs(1) / self.rate;
+        let start = self.clock.now();
+
+        loop {
+            let elapsed = self.clock.now().saturating_duration_since(start);
+
+            if elapsed >= self.duration {
+                break;
+            }
+
+            let t = elapsed.as_secs_f64() / self.duration.as_secs_f64();
+            managers.sync.sync(&transition::frame(&from, self.to,
+                                                  self.easing.apply(t)))?;
+            // Last frame is not to overshoot the end
+            self.clock.sleep(interval.min(self.duration - elapsed));
+        }
+
+        managers.sync.sync(self.to)
+    }
+}
+
+impl<'a> Strategy for Transition<'a> {
+    fn execute(self: &mut Self, managers: Managers) {
+        self.result = Some(self.run(&managers))
+    }
+}
+
+impl<'a> StrategyResult for Transition<'a> {
+    type Result = fetch::Result<()>;
+
+    fn result(self: Self) -> Option<Self::Result> {
+        self.result
+    }
+}
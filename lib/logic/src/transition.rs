@@ -0,0 +1,90 @@
+
+use std::time::{Duration, Instant};
+
+use domain::light::Light;
+use domain::color::Color;
+use domain::color::oklab::OKLab;
+use domain::brightness::Brightness;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Easing {
+    #[default]
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    // Progress of the change at t, both are in [0; 1]
+    pub fn apply(self: &Self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => if 0.5 > t {
+                4.0 * t * t * t
+            } else {
+                1.0 - (2.0 - 2.0 * t).powi(3) / 2.0
+            },
+        }
+    }
+}
+
+pub trait Clock {
+    fn now(self: &Self) -> Instant;
+    fn sleep(self: &Self, duration: Duration);
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(self: &Self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(self: &Self, duration: Duration) {
+        std::thread::sleep(duration)
+    }
+}
+
+// State between from and to at progress t. Color goes through OKLab, so
+// intermediate colors look evenly spaced. Light being switched on starts
+// from zero brightness and light being switched off fades to it, while
+// switching off and mode change happen only at the end
+pub fn frame(from: &Light, to: &Light, t: f64) -> Light {
+    if 1.0 <= t {
+        return to.clone();
+    }
+
+    let mut out = to.clone();
+
+    if let Ok(power) = from.get_power() {
+        let _ = out.set_power(power || to.is_on());
+    }
+
+    if let Ok(mode) = from.get_mode() {
+        let _ = out.set_mode(mode.clone());
+    }
+
+    if let (Ok(start), Ok(end)) = (from.get_color(), to.get_color()) {
+        let start = OKLab::from(start.clone());
+        let end = OKLab::from(end.clone());
+        let _ = out.set_color(Color::from(start.mix(&end, t)));
+    }
+
+    let brightness = |light: &Light, other: &Light| -> Option<f64> {
+        match (light.get_power(), other.get_power()) {
+            (Ok(false), Ok(true)) => Some(0.0),
+            _ => light.get_brightness().ok().map(|brightness| **brightness),
+        }
+    };
+
+    if let (Some(start), Some(end)) = (brightness(from, to), brightness(to, from)) {
+        let _ = out.set_brightness(Brightness::new(start + (end - start) * t));
+    }
+
+    out
+}
@@ -0,0 +1,246 @@
+
+mod common;
+
+use common::*;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use domain::light::Light;
+use domain::scene::Scene;
+use mock_provider::MockProvider;
+use provider::{ErrorType, Provider};
+use provider::asynchronous::{AsyncProvider, BoxFuture, IntoAsync, IntoBlocking};
+use logic::facade::{AsyncFacade, AsyncStrategy, StrategyResult};
+use logic::facade::asynchronous::TokioFacade;
+use logic::managers::fetch;
+use logic::strategies::asynchronous::{All, Apply, Partial, SyncLights};
+
+const LATENCY: Duration = Duration::from_millis(100);
+
+// Native async provider, counts calls that weren't cancelled
+struct Slow {
+    mock: MockProvider,
+    delay: Duration,
+    done: Arc<AtomicUsize>,
+}
+
+impl Slow {
+    fn new(name: &str, delay: Duration) -> Self {
+        Self {
+            mock: common::provider(name),
+            delay,
+            done: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    async fn wait(self: &Self) {
+        tokio::time::sleep(self.delay).await;
+        self.done.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl AsyncProvider for Slow {
+    fn name(self: &Self) -> &str {
+        self.mock.name()
+    }
+
+    fn list(self: &Self) -> BoxFuture<'_, provider::Result<Vec<Light>>> {
+        Box::pin(async move {
+            self.wait().await;
+            self.mock.list()
+        })
+    }
+
+    fn get<'a>(self: &'a Self, id: &'a str) -> BoxFuture<'a, provider::Result<Light>> {
+        Box::pin(async move {
+            self.wait().await;
+            self.mock.get(id)
+        })
+    }
+
+    fn sync<'a>(self: &'a Self, light: &'a Light) -> BoxFuture<'a, provider::Result<()>> {
+        Box::pin(async move {
+            self.wait().await;
+            self.mock.sync(light)
+        })
+    }
+}
+
+fn blocking(providers: Vec<MockProvider>) -> (Vec<Arc<MockProvider>>, TokioFacade) {
+    let providers: Vec<_> = providers.into_iter().map(Arc::new).collect();
+    let facade = TokioFacade::from_context(context(&providers));
+
+    (providers, facade)
+}
+
+async fn run<S: AsyncStrategy + StrategyResult>(
+    facade: &mut TokioFacade,
+    mut strategy: S
+) -> S::Result {
+    facade.accept(&mut strategy).await;
+    strategy.result().expect("Strategy must be executed")
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn fetch_all_in_parallel() {
+    let (_, mut facade) = blocking(["c", "a", "b"].into_iter()
+        .map(|name| common::provider(name).latency(LATENCY))
+        .collect());
+
+    let start = Instant::now();
+    let lights = run(&mut facade, All::new()).await.expect("Fetched");
+
+    assert!(start.elapsed() < LATENCY * 2);
+    let ids: Vec<_> = lights.into_iter()
+        .map(|light| light.provider.to_string())
+        .collect();
+    assert_eq!(ids, vec!["1@a", "2@a", "1@b", "2@b", "1@c", "2@c"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn workers_limit() {
+    let (_, facade) = blocking(["a", "b"].into_iter()
+        .map(|name| common::provider(name).latency(LATENCY))
+        .collect());
+    let mut facade = facade.with_workers(1);
+
+    let start = Instant::now();
+    run(&mut facade, All::new()).await.expect("Fetched");
+
+    assert!(start.elapsed() >= LATENCY * 2);
+}
+
+#[tokio::test]
+async fn timeout() {
+    let providers: Vec<Arc<dyn AsyncProvider>> = vec![
+        Arc::new(Slow::new("a", Duration::ZERO)),
+        Arc::new(Slow::new("b", Duration::from_secs(10))),
+    ];
+    let mut facade = TokioFacade::new(providers, context(&[]).registry)
+        .with_timeout(LATENCY);
+
+    let partial = run(&mut facade, Partial::new()).await;
+
+    assert_eq!(partial.lights.len(), 2);
+    match &partial.errors[..] {
+        [(name, fetch::Error::Provider(err))] => {
+            assert_eq!(name, "b");
+            assert!(matches!(err.etype, ErrorType::Timeout(_)));
+        },
+        _ => panic!("Only \"b\" must time out"),
+    }
+}
+
+#[tokio::test]
+async fn cancellation() {
+    let slow = Slow::new("a", LATENCY);
+    let done = slow.done.clone();
+    let mut facade = TokioFacade::new(vec![Arc::new(slow)],
+                                      context(&[]).registry);
+
+    let mut strategy = All::new();
+    let cut = tokio::time::timeout(LATENCY / 4, facade.accept(&mut strategy));
+    assert!(cut.await.is_err());
+
+    tokio::time::sleep(LATENCY * 2).await;
+    assert_eq!(done.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sync_lights() {
+    let (providers, mut facade) = blocking(vec![common::provider("a"),
+                                                common::provider("b")
+                                                .fail_on(1)]);
+    let lights = [named(&providers[1], "1", ""), named(&providers[0], "2", ""),
+                  named(&providers[0], "1", "")];
+
+    let report = run(&mut facade, SyncLights::new(&lights)).await;
+
+    let ok: Vec<_> = report.iter()
+        .map(|(id, result)| (id.to_string(), result.is_ok()))
+        .collect();
+    assert_eq!(ok, vec![("1@b".to_string(), false), ("2@a".to_string(), true),
+                        ("1@a".to_string(), true)]);
+    let ids: Vec<_> = providers[0].syncs().into_iter()
+        .map(|light| light.provider.id)
+        .collect();
+    assert_eq!(ids, vec!["2", "1"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn apply_scene() {
+    let providers = vec![Arc::new(common::provider("a"))];
+    let mut context = context(&providers);
+    let lights = vec![named(&providers[0], "1", ""),
+                      named(&providers[0], "2", "")];
+    context.registry.save_scene(&Scene::new("evening".to_string(), lights))
+        .expect("Saved");
+    let mut facade = TokioFacade::from_context(context);
+
+    let report = run(&mut facade, Apply::new("evening")).await.expect("Loaded");
+
+    assert!(report.iter().all(|(_, result)| result.is_ok()));
+    assert_eq!(providers[0].syncs().len(), 2);
+    assert!(run(&mut facade, Apply::new("night")).await.is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn catalog_cached() {
+    use domain::mode::Mode;
+    use domain::mode::descriptor::ModeDescriptor;
+
+    let catalog = vec![ModeDescriptor::new("a", "colorloop", "Cycles")];
+    let (providers, mut facade) = blocking(vec![common::provider("a")
+                                                .with_catalog(catalog)]);
+    let mut light = named(&providers[0], "1", "");
+    light.set_mode(Mode::new_empty("a".to_string(), "colorloop".to_string()))
+        .expect("Capable");
+    let lights = [light.clone(), light.clone(), light];
+
+    let report = run(&mut facade, SyncLights::new(&lights)).await;
+
+    assert!(report.iter().all(|(_, result)| result.is_ok()));
+    assert_eq!(providers[0].syncs().len(), 3);
+    assert_eq!(providers[0].catalog_requests(), 1);
+}
+
+#[test]
+fn into_blocking() {
+    let mock: Arc<dyn Provider> = Arc::new(common::provider("a"));
+    let provider = IntoBlocking::new(Arc::new(IntoAsync::new(mock)))
+        .expect("Runtime");
+
+    assert_eq!(provider.name(), "a");
+    assert_eq!(provider.list().expect("Listed").len(), 2);
+
+    let slow = IntoBlocking::new(Arc::new(Slow::new("b", LATENCY * 10)))
+        .expect("Runtime")
+        .with_timeout(LATENCY);
+    assert!(matches!(slow.get("1"),
+                     Err(provider::Error { etype: ErrorType::Timeout(_), .. })));
+}
+
+#[test]
+fn transitions_forwarded() {
+    let mock = Arc::new(common::provider("a").with_transitions());
+    let provider = IntoBlocking::new(Arc::new(IntoAsync::new(mock.clone())))
+        .expect("Runtime");
+    let light = named(&mock, "1", "");
+
+    assert!(provider.supports_transition());
+    provider.sync_transition(&light, LATENCY).expect("Synced");
+
+    assert_eq!(mock.transitions().len(), 1);
+    assert_eq!(mock.transitions()[0].1, LATENCY);
+}
+
+#[tokio::test]
+async fn into_blocking_within_runtime() {
+    let provider = IntoBlocking::new(Arc::new(Slow::new("a", Duration::ZERO)))
+        .expect("Runtime");
+
+    assert_eq!(provider.list().expect("Listed").len(), 2);
+    assert!(!provider.supports_transition());
+}
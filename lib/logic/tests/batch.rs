@@ -0,0 +1,135 @@
+
+mod common;
+
+use common::*;
+
+use domain::brightness::Brightness;
+use logic::batch::{Policy, Status};
+use logic::managers::fetch;
+use logic::strategies::list;
+use logic::strategies::save::{dump, manage::delete};
+use logic::strategies::sync::{self, fetch_and_sync, load_and_sync};
+
+fn saved() -> Setup {
+    let mut setup = Setup::single();
+    let lights = [named(setup.provider(), "1", "desk"),
+                  named(setup.provider(), "2", "lamp")];
+
+    setup.run(dump::saves(lights.iter()).expect("Named"))
+        .into_result().expect("Saved");
+
+    setup
+}
+
+fn dim(light: &mut domain::light::Light) {
+    light.set_brightness(Brightness::new(0.5)).expect("Capable");
+}
+
+#[test]
+fn get_by_ids_continue() {
+    let mut setup = Setup::single();
+    let ids = [id(PROVIDER, "1"), id(PROVIDER, "3"), id(PROVIDER, "2")];
+
+    let batch = setup.run(list::provider::get_by_ids(ids.iter())
+                          .with_policy(Policy::Continue));
+
+    assert!(!batch.is_ok());
+    assert_eq!(batch.done().count(), 2);
+    let failed: Vec<_> = batch.failed().map(|(id, _)| id.clone()).collect();
+    assert_eq!(failed, vec![id(PROVIDER, "3")]);
+}
+
+#[test]
+fn get_by_ids_fail_fast() {
+    let mut setup = Setup::single();
+    let ids = [id(PROVIDER, "3"), id(PROVIDER, "1")];
+
+    let batch = setup.run(list::provider::get_by_ids(ids.iter())
+                          .with_policy(Policy::Rollback));
+
+    assert!(matches!(batch.items[0].1,
+                     Status::Failed(fetch::Error::Provider(_))));
+    assert!(matches!(batch.items[1].1, Status::Skipped));
+}
+
+#[test]
+fn sync_continue() {
+    let mut setup = Setup::single();
+    let ids = [id(PROVIDER, "3"), id(PROVIDER, "1"), id(PROVIDER, "2")];
+
+    let batch = setup.run(fetch_and_sync::multiple(ids.iter(), dim)
+                          .with_policy(Policy::Continue));
+
+    assert_eq!(batch.done().count(), 2);
+    assert_eq!(setup.provider().syncs().len(), 2);
+    assert_eq!(brightness(&setup.provider().state("2").expect("Exists")), 0.5);
+}
+
+#[test]
+fn sync_rollback() {
+    let mut setup = Setup::single();
+    let ids = [id(PROVIDER, "1"), id(PROVIDER, "2"), id(PROVIDER, "3")];
+
+    let batch = setup.run(fetch_and_sync::multiple(ids.iter(), dim)
+                          .with_policy(Policy::Rollback));
+
+    assert!(matches!(batch.items[0].1, Status::RolledBack));
+    assert!(matches!(batch.items[1].1, Status::RolledBack));
+    assert!(matches!(batch.items[2].1, Status::Failed(_)));
+
+    // Changed ones and then restored ones, latest first
+    let syncs: Vec<f64> = setup.provider().syncs().iter().map(brightness)
+        .collect();
+    assert_eq!(syncs, vec![0.5, 0.5, 0.8, 0.2]);
+    assert_eq!(brightness(&setup.provider().state("1").expect("Exists")), 0.2);
+}
+
+#[test]
+fn load_rollback() {
+    let mut setup = saved();
+
+    let batch = setup.run(load_and_sync::multiple(
+        ["desk", "bed"].into_iter(),
+        dim
+    ).with_policy(Policy::Rollback));
+
+    assert!(matches!(batch.items[0].1, Status::RolledBack));
+    assert!(matches!(batch.items[1].1, Status::Failed(sync::Error::Local(_))));
+
+    // Mapped state was saved back and then restored
+    let desk = setup.registry(|registry| registry.load_dump("desk"))
+        .expect("Saved");
+    assert_eq!(brightness(&desk), 0.2);
+    assert_eq!(setup.defaults(), vec!["desk", "lamp"]);
+}
+
+#[test]
+fn delete_continue() {
+    let mut setup = saved();
+
+    let batch = setup.run(delete::Multiple::new(["desk", "bed", "lamp"]
+                                                .into_iter())
+                          .expect("Named").with_policy(Policy::Continue));
+
+    let failed: Vec<_> = batch.failed().map(|(name, _)| name.clone()).collect();
+    assert_eq!(failed, vec!["bed"]);
+    assert!(setup.dumps().is_empty());
+}
+
+#[test]
+fn delete_rollback() {
+    let mut setup = saved();
+    let desk = named(setup.provider(), "1", "desk");
+    setup.registry(|registry| registry.remove("desk")).expect("Removed");
+    setup.run(dump::dump(&desk).expect("Named")).expect("Saved");
+
+    let batch = setup.run(delete::Multiple::new(["desk", "bed", "lamp"]
+                                                .into_iter())
+                          .expect("Named").with_policy(Policy::Rollback));
+
+    assert!(matches!(batch.items[0].1, Status::RolledBack));
+    assert!(matches!(batch.items[2].1, Status::Skipped));
+    // Desk comes back without a default, the way it was
+    assert_eq!(setup.dumps(), vec!["desk", "lamp"]);
+    assert_eq!(setup.defaults(), vec!["lamp"]);
+}
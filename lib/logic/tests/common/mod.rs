@@ -0,0 +1,187 @@
+
+#![allow(dead_code)]
+
+use std::rc::Rc;
+use std::sync::Arc;
+use std::cell::{Cell, RefCell};
+use std::time::{Duration, Instant};
+
+use domain::light::{Light, ProviderID};
+use domain::capabilities::Capability;
+use domain::brightness::Brightness;
+use domain::mode::descriptor::ModeDescriptor;
+use domain::patch::LightPatch;
+use local_registry::Registry;
+use provider::Provider;
+use mock_provider::{MockProvider, VirtualLight};
+use memory_registry::MemoryRegistry;
+use logic::context::Context;
+use logic::facade::{Facade, Strategy, StrategyResult};
+use logic::facade::default::DefaultFacade;
+use logic::transition::Clock;
+
+pub const PROVIDER: &str = "mock";
+
+// Context owns its providers, so mock is shared to be inspected by tests
+pub struct Shared(pub Arc<MockProvider>);
+
+impl Provider for Shared {
+    fn name(self: &Self) -> &str {
+        self.0.name()
+    }
+
+    fn list(self: &Self) -> provider::Result<Vec<Light>> {
+        self.0.list()
+    }
+
+    fn get(self: &Self, id: &str) -> provider::Result<Light> {
+        self.0.get(id)
+    }
+
+    fn sync(self: &Self, light: &Light) -> provider::Result<()> {
+        self.0.sync(light)
+    }
+
+    fn supports_transition(self: &Self) -> bool {
+        self.0.supports_transition()
+    }
+
+    fn sync_transition(self: &Self, light: &Light,
+                       duration: Duration) -> provider::Result<()> {
+        self.0.sync_transition(light, duration)
+    }
+
+    fn modes(self: &Self) -> provider::Result<Vec<ModeDescriptor>> {
+        self.0.modes()
+    }
+
+    fn apply(self: &Self, id: &str,
+             patch: &LightPatch) -> provider::Result<()> {
+        self.0.apply(id, patch)
+    }
+}
+
+pub struct Setup {
+    pub providers: Vec<Arc<MockProvider>>,
+    pub context: Rc<RefCell<Context>>,
+    pub facade: DefaultFacade,
+}
+
+impl Setup {
+    pub fn new(providers: Vec<MockProvider>) -> Self {
+        let providers: Vec<Arc<MockProvider>> = providers.into_iter()
+            .map(Arc::new)
+            .collect();
+        let context = Rc::new(RefCell::new(context(&providers)));
+
+        Self {
+            providers,
+            facade: DefaultFacade::new(context.clone()),
+            context,
+        }
+    }
+
+    pub fn single() -> Self {
+        Self::new(vec![provider(PROVIDER)])
+    }
+
+    pub fn provider(self: &Self) -> &MockProvider {
+        &self.providers[0]
+    }
+
+    pub fn run<S: Strategy + StrategyResult>(self: &mut Self, mut strategy: S) -> S::Result {
+        self.facade.accept(&mut strategy);
+        strategy.result().expect("Strategy must be executed")
+    }
+
+    pub fn registry<R, F: FnOnce(&mut dyn Registry) -> R>(self: &Self, f: F) -> R {
+        f(self.context.borrow_mut().registry.as_mut())
+    }
+
+    pub fn dumps(self: &Self) -> Vec<String> {
+        names(self.registry(|registry| registry.list_dumps()).expect("Listed"))
+    }
+
+    pub fn defaults(self: &Self) -> Vec<String> {
+        names(self.registry(|registry| registry.list_defaults()).expect("Listed"))
+    }
+}
+
+pub fn context(providers: &[Arc<MockProvider>]) -> Context {
+    Context::new(
+        providers.iter()
+            .map(|provider| {
+                Box::new(Shared(provider.clone())) as Box<dyn Provider>
+            })
+            .collect(),
+        Box::new(MemoryRegistry::new())
+    )
+}
+
+pub fn provider(name: &str) -> MockProvider {
+    MockProvider::new(name)
+        .with_light(
+            VirtualLight::new(name, "1", vec![Capability::Power,
+                                              Capability::Color,
+                                              Capability::Brightness,
+                                              Capability::Mode])
+                .with_modes(&["colorloop"])
+                .with_state(|light| {
+                    light.set_power(false)?;
+                    light.set_brightness(Brightness::new(0.2))
+                })
+        )
+        .with_light(
+            VirtualLight::new(name, "2", vec![Capability::Brightness])
+                .with_state(|light| light.set_brightness(Brightness::new(0.8)))
+        )
+}
+
+pub fn id(provider: &str, id: &str) -> ProviderID {
+    ProviderID::new(provider.to_string(), id.to_string())
+}
+
+pub fn named(provider: &MockProvider, id: &str, name: &str) -> Light {
+    let mut light = provider.state(id).expect("Light exists");
+    light.name = name.to_string();
+
+    light
+}
+
+pub fn names(lights: Vec<Light>) -> Vec<String> {
+    lights.into_iter().map(|light| light.name).collect()
+}
+
+pub fn brightness(light: &Light) -> f64 {
+    **light.get_brightness().expect("Brightness is set")
+}
+
+// Time only moves when someone sleeps
+pub struct FakeClock {
+    now: Cell<Instant>,
+    sleeps: RefCell<Vec<Duration>>,
+}
+
+impl FakeClock {
+    pub fn new() -> Self {
+        Self {
+            now: Cell::new(Instant::now()),
+            sleeps: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn sleeps(self: &Self) -> Vec<Duration> {
+        self.sleeps.borrow().clone()
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(self: &Self) -> Instant {
+        self.now.get()
+    }
+
+    fn sleep(self: &Self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+        self.sleeps.borrow_mut().push(duration);
+    }
+}
@@ -0,0 +1,152 @@
+
+mod common;
+
+use common::*;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use domain::light::Light;
+use mock_provider::MockProvider;
+use logic::pool;
+use logic::facade::{Facade, Managers, Strategy, StrategyResult};
+use logic::facade::default::ConcurrentFacade;
+use logic::managers::fetch;
+use logic::strategies::list;
+use logic::strategies::save::dump;
+
+const LATENCY: Duration = Duration::from_millis(100);
+
+fn shared(providers: &[Arc<MockProvider>]) -> ConcurrentFacade {
+    ConcurrentFacade::shared(context(providers))
+}
+
+fn run<S: Strategy + StrategyResult>(
+    facade: &mut ConcurrentFacade,
+    mut strategy: S
+) -> S::Result {
+    facade.accept(&mut strategy);
+    strategy.result().expect("Strategy must be executed")
+}
+
+struct SyncMany(Vec<Light>, Option<Vec<fetch::Result<()>>>);
+
+impl Strategy for SyncMany {
+    fn execute(self: &mut Self, managers: Managers) {
+        self.1 = Some(managers.sync.sync_many(&self.0))
+    }
+}
+
+impl StrategyResult for SyncMany {
+    type Result = Vec<fetch::Result<()>>;
+
+    fn result(self: Self) -> Option<Self::Result> {
+        self.1
+    }
+}
+
+#[test]
+fn send_and_sync() {
+    fn check<T: Send + Sync + Clone>() {}
+
+    check::<ConcurrentFacade>();
+}
+
+#[test]
+fn fetch_all_in_parallel() {
+    let providers: Vec<_> = ["d", "c", "b", "a"].into_iter()
+        .map(|name| Arc::new(common::provider(name).latency(LATENCY)))
+        .collect();
+    let mut facade = shared(&providers);
+
+    let start = Instant::now();
+    let lights = run(&mut facade, list::provider::All::new())
+        .expect("Fetched");
+
+    assert!(start.elapsed() < LATENCY * 3);
+    // Order of providers doesn't depend on which one answered first
+    let names: Vec<_> = lights.into_iter()
+        .map(|light| light.provider.to_string())
+        .collect();
+    assert_eq!(names, vec!["1@a", "2@a", "1@b", "2@b",
+                           "1@c", "2@c", "1@d", "2@d"]);
+}
+
+#[test]
+fn sync_many_order() {
+    let providers = vec![Arc::new(common::provider("a")),
+                         Arc::new(common::provider("b").fail_on(1))];
+    let mut facade = shared(&providers);
+    let lights = vec![named(&providers[0], "2", ""),
+                      named(&providers[1], "1", ""),
+                      named(&providers[0], "1", ""),
+                      named(&providers[1], "2", "")];
+
+    let results = run(&mut facade, SyncMany(lights, None));
+
+    let ok: Vec<bool> = results.iter().map(Result::is_ok).collect();
+    assert_eq!(ok, vec![true, false, true, true]);
+    // Lights of one provider are synced in the given order
+    let ids: Vec<_> = providers[0].syncs().into_iter()
+        .map(|light| light.provider.id)
+        .collect();
+    assert_eq!(ids, vec!["2", "1"]);
+}
+
+#[test]
+fn shared_between_threads() {
+    let providers = vec![Arc::new(common::provider("a"))];
+    let facade = shared(&providers);
+    let desk = named(&providers[0], "1", "desk");
+
+    std::thread::scope(|scope| {
+        let mut saver = facade.clone();
+        scope.spawn(move || {
+            run(&mut saver, dump::dump(&desk).expect("Named")).expect("Saved")
+        }).join().expect("Not panicked");
+
+        for _ in 0..4 {
+            let mut reader = facade.clone();
+            scope.spawn(move || {
+                let dumps = run(&mut reader, list::registry::dumps::All::new())
+                    .expect("Listed");
+                assert_eq!(names(dumps), vec!["desk"]);
+            });
+        }
+    });
+}
+
+#[test]
+fn registry_not_blocked_by_providers() {
+    let providers = vec![Arc::new(common::provider("a").latency(LATENCY * 3))];
+    let facade = shared(&providers);
+    let desk = named(&providers[0], "1", "desk");
+
+    std::thread::scope(|scope| {
+        let mut fetcher = facade.clone();
+        scope.spawn(move || {
+            run(&mut fetcher, list::provider::All::new()).expect("Fetched")
+        });
+
+        std::thread::sleep(LATENCY);
+        let mut saver = facade.clone();
+        let start = Instant::now();
+        run(&mut saver, dump::dump(&desk).expect("Named")).expect("Saved");
+
+        // Saving doesn't wait for the provider to answer
+        assert!(start.elapsed() < LATENCY);
+    });
+}
+
+#[test]
+fn pool_keeps_order() {
+    let items: Vec<u64> = (0..10).rev().collect();
+
+    let out = pool::map(&items, 3, |item| {
+        std::thread::sleep(Duration::from_millis(item * 2));
+        item * 10
+    });
+
+    assert_eq!(out, (0..10).rev().map(|item| item * 10).collect::<Vec<_>>());
+    assert!(pool::map(&[] as &[u64], 3, |item| *item).is_empty());
+}
@@ -0,0 +1,268 @@
+
+mod common;
+
+use common::*;
+
+use domain::brightness::Brightness;
+use domain::group::{Group, Member};
+use domain::light::Light;
+use local_registry::{ErrorType, Registry};
+use memory_registry::MemoryRegistry;
+use logic::batch::{Policy, Status};
+use logic::group::Error;
+use logic::strategies::group::{self, Delete, Save};
+use logic::strategies::list;
+use logic::strategies::save::{dump, manage};
+use logic::strategies::sync::{fetch_and_sync, load_and_sync};
+
+fn light(provider: &str, id: &str) -> Member {
+    Member::Light(common::id(provider, id))
+}
+
+fn saved(name: &str) -> Member {
+    Member::Saved(name.to_string())
+}
+
+fn nested(name: &str) -> Member {
+    Member::Group(name.to_string())
+}
+
+fn save(setup: &mut Setup, name: &str, members: Vec<Member>) {
+    setup.run(Save::new(&Group::new(name.to_string(), members)))
+        .expect("Saved");
+}
+
+fn dim(light: &mut Light) {
+    light.set_brightness(Brightness::new(0.5)).expect("Capable");
+}
+
+// Kitchen spans both providers and includes the "hall" group
+fn kitchen() -> Setup {
+    let mut setup = Setup::new(vec![common::provider("a"),
+                                    common::provider("b")]);
+    let lamp = named(&setup.providers[1], "2", "lamp");
+    setup.run(dump::dumps([lamp].iter()).expect("Named"))
+        .into_result().expect("Saved");
+
+    save(&mut setup, "hall", vec![saved("lamp"), light("a", "2")]);
+    save(&mut setup, "kitchen", vec![light("a", "1"), nested("hall"),
+                                     light("a", "2")]);
+
+    setup
+}
+
+#[test]
+fn fetch_and_sync_group() {
+    let mut setup = kitchen();
+
+    setup.run(fetch_and_sync::group("kitchen", dim)).expect("Resolved")
+        .into_result().expect("Synced");
+
+    // Shared member is synced once
+    assert_eq!(setup.providers[0].syncs().len(), 2);
+    assert_eq!(setup.providers[1].syncs().len(), 1);
+    assert_eq!(brightness(&setup.providers[1].state("2").expect("Exists")),
+               0.5);
+    assert_eq!(brightness(&setup.providers[1].state("1").expect("Exists")),
+               0.2);
+}
+
+// Porch has a light the provider doesn't know in the middle
+fn porch() -> Setup {
+    let mut setup = kitchen();
+    save(&mut setup, "porch", vec![light("a", "1"), light("a", "3"),
+                                   light("b", "2")]);
+
+    setup
+}
+
+#[test]
+fn group_continue() {
+    let mut setup = porch();
+
+    let batch = setup.run(fetch_and_sync::group("porch", dim)
+                          .with_policy(Policy::Continue))
+        .expect("Resolved");
+
+    let failed: Vec<_> = batch.failed().map(|(id, _)| id.clone()).collect();
+    assert_eq!(failed, vec![id("a", "3")]);
+    assert_eq!(batch.done().count(), 2);
+    assert_eq!(brightness(&setup.providers[1].state("2").expect("Exists")),
+               0.5);
+}
+
+#[test]
+fn group_rollback() {
+    let mut setup = porch();
+
+    let batch = setup.run(fetch_and_sync::group("porch", dim)
+                          .with_policy(Policy::Rollback))
+        .expect("Resolved");
+
+    assert!(matches!(batch.items[0].1, Status::RolledBack));
+    assert!(matches!(batch.items[1].1, Status::Failed(Error::Fetch(_))));
+    assert!(matches!(batch.items[2].1, Status::Skipped));
+    assert_eq!(brightness(&setup.providers[0].state("1").expect("Exists")),
+               0.2);
+    assert!(setup.providers[1].syncs().is_empty());
+}
+
+#[test]
+fn get_by_group_continue() {
+    let mut setup = porch();
+
+    let batch = setup.run(list::provider::get_by_group("porch")
+                          .with_policy(Policy::Continue))
+        .expect("Resolved");
+
+    let ids: Vec<_> = batch.done().map(|(_, light)| light.provider.clone())
+        .collect();
+    assert_eq!(ids, vec![id("a", "1"), id("b", "2")]);
+    assert_eq!(batch.failed().count(), 1);
+}
+
+#[test]
+fn get_by_group() {
+    let mut setup = kitchen();
+
+    let lights = setup.run(list::provider::get_by_group("kitchen"))
+        .expect("Resolved").into_result().expect("Fetched");
+
+    let ids: Vec<_> = lights.into_iter().map(|light| light.provider).collect();
+    assert_eq!(ids, vec![id("a", "1"), id("b", "2"), id("a", "2")]);
+}
+
+#[test]
+fn load_group() {
+    let mut setup = kitchen();
+    let mut desk = named(&setup.providers[0], "2", "desk");
+    desk.set_brightness(Brightness::new(0.1)).expect("Capable");
+    setup.run(dump::dumps([desk].iter()).expect("Named"))
+        .into_result().expect("Saved");
+
+    // Plain light is matched with its dump
+    setup.run(load_and_sync::group("hall", |_| {})).expect("Resolved")
+        .into_result().expect("Synced");
+    assert_eq!(brightness(&setup.providers[0].state("2").expect("Exists")),
+               0.1);
+
+    let names = names(setup.run(list::registry::dumps::get_by_group("hall"))
+                      .expect("Resolved").into_result().expect("Loaded"));
+    assert_eq!(names, vec!["lamp", "desk"]);
+}
+
+#[test]
+fn load_unsaved() {
+    let mut setup = kitchen();
+
+    assert!(matches!(setup.run(load_and_sync::group("kitchen", |_| {})),
+                     Err(Error::Unsaved(_))));
+}
+
+#[test]
+fn missing() {
+    let mut setup = kitchen();
+
+    assert!(matches!(setup.run(fetch_and_sync::group("bedroom", |_| {})),
+                     Err(Error::Local(local_registry::Error {
+                         etype: ErrorType::NotFound(_), ..
+                     }))));
+}
+
+#[test]
+fn cycle() {
+    let mut setup = kitchen();
+    let hall = Group::new("hall".to_string(), vec![nested("kitchen")]);
+
+    match setup.run(Save::new(&hall)) {
+        Err(Error::Cycle(path)) => {
+            assert_eq!(path, vec!["hall", "kitchen", "hall"]);
+        },
+        _ => panic!("Cycle isn't detected"),
+    }
+
+    // Nothing is overwritten
+    let stored = setup.registry(|registry| registry.load_group("hall"))
+        .expect("Loaded");
+    assert_eq!(stored.members.len(), 2);
+
+    let itself = Group::new("attic".to_string(), vec![nested("attic")]);
+    assert!(matches!(setup.run(Save::new(&itself)), Err(Error::Cycle(_))));
+}
+
+#[test]
+fn cycle_in_registry() {
+    let mut setup = kitchen();
+    // Registry doesn't check groups itself
+    setup.registry(|registry| {
+        registry.save_group(&Group::new("hall".to_string(),
+                                        vec![nested("kitchen")]))
+    }).expect("Saved");
+
+    assert!(matches!(setup.run(list::provider::get_by_group("kitchen")),
+                     Err(Error::Cycle(_))));
+    assert!(setup.providers[0].syncs().is_empty());
+}
+
+#[test]
+fn rename() {
+    let mut setup = kitchen();
+    let desk = named(&setup.providers[0], "2", "desk");
+    setup.run(dump::dumps([desk].iter()).expect("Named"))
+        .into_result().expect("Saved");
+
+    setup.run(manage::Rename::new("lamp", "bulb").expect("Named"))
+        .expect("Renamed");
+
+    let hall = setup.registry(|registry| registry.load_group("hall"))
+        .expect("Loaded");
+    assert_eq!(hall.members[0], saved("bulb"));
+
+    let names = names(setup.run(list::registry::dumps::get_by_group("hall"))
+                      .expect("Resolved").into_result().expect("Loaded"));
+    assert_eq!(names, vec!["bulb", "desk"]);
+}
+
+#[test]
+fn rename_rolled_back() {
+    let mut setup = kitchen();
+    save(&mut setup, "porch", vec![saved("lamp")]);
+    // Copying takes three saves, the second group to follow the rename fails
+    let registry = setup.registry(|registry| {
+        let mut failing = MemoryRegistry::new().fail_on_group(5);
+
+        for light in registry.list_dumps().expect("Listed") {
+            failing.dump(&light).expect("Saved");
+        }
+
+        for group in registry.list_groups().expect("Listed") {
+            failing.save_group(&group).expect("Saved");
+        }
+
+        failing
+    });
+    setup.context.borrow_mut().registry = Box::new(registry);
+
+    assert!(setup.run(manage::Rename::new("lamp", "bulb").expect("Named"))
+            .is_err());
+
+    assert_eq!(setup.dumps(), vec!["lamp"]);
+    for name in ["hall", "porch"] {
+        let group = setup.registry(|registry| registry.load_group(name))
+            .expect("Loaded");
+        assert_eq!(group.members[0], saved("lamp"));
+    }
+}
+
+#[test]
+fn list_and_delete() {
+    let mut setup = kitchen();
+
+    let names: Vec<String> = setup.run(group::All::new()).expect("Listed")
+        .into_iter().map(|group| group.name).collect();
+    assert_eq!(names, vec!["hall", "kitchen"]);
+
+    setup.run(Delete::new("hall")).expect("Deleted");
+    assert!(matches!(setup.run(list::provider::get_by_group("kitchen")),
+                     Err(Error::Local(_))));
+}
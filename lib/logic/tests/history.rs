@@ -0,0 +1,138 @@
+mod common;
+
+use common::*;
+
+use domain::brightness::Brightness;
+use domain::history::LIMIT;
+use memory_registry::MemoryRegistry;
+use logic::strategies::history::{self, Undo, Redo};
+use logic::strategies::power;
+use logic::strategies::save::dump;
+use logic::strategies::sync::{self, fetch_and_sync, load_and_sync};
+
+fn dim(setup: &mut Setup, level: f64) {
+    setup.run(fetch_and_sync::single(&id(PROVIDER, "1"), |light| {
+        light.set_brightness(Brightness::new(level)).expect("Capable");
+    })).expect("Synced").expect("Recorded");
+}
+
+fn current(setup: &Setup) -> f64 {
+    brightness(&setup.provider().state("1").expect("Exists"))
+}
+
+#[test]
+fn recorded() {
+    let mut setup = Setup::single();
+    dim(&mut setup, 0.5);
+    dim(&mut setup, 0.7);
+
+    let history = setup.run(history::Get::new(&id(PROVIDER, "1")))
+        .expect("Loaded");
+    let levels: Vec<f64> = history.undo.iter()
+        .map(|entry| brightness(&entry.light))
+        .collect();
+
+    assert_eq!(levels, vec![0.2, 0.5]);
+    assert!(history.undo[0].at > 0);
+    assert!(history.redo.is_empty());
+
+    // Nothing changed, nothing recorded
+    setup.run(fetch_and_sync::single(&id(PROVIDER, "1"), |_| {}))
+        .expect("Synced").expect("Recorded");
+    assert_eq!(setup.run(history::Get::new(&id(PROVIDER, "1")))
+               .expect("Loaded").undo.len(), 2);
+}
+
+#[test]
+fn undo_and_redo() {
+    let mut setup = Setup::single();
+    dim(&mut setup, 0.5);
+    dim(&mut setup, 0.7);
+
+    let light = setup.run(Undo::new(&id(PROVIDER, "1"))).expect("Undone");
+    assert_eq!(brightness(&light), 0.5);
+    assert_eq!(current(&setup), 0.5);
+
+    setup.run(Undo::new(&id(PROVIDER, "1"))).expect("Undone");
+    assert_eq!(current(&setup), 0.2);
+    assert!(matches!(setup.run(Undo::new(&id(PROVIDER, "1"))),
+                     Err(history::Error::Empty(_))));
+
+    setup.run(Redo::new(&id(PROVIDER, "1"))).expect("Redone");
+    assert_eq!(current(&setup), 0.5);
+
+    // New change drops what is left to redo
+    dim(&mut setup, 0.9);
+    assert!(matches!(setup.run(Redo::new(&id(PROVIDER, "1"))),
+                     Err(history::Error::Empty(_))));
+    setup.run(Undo::new(&id(PROVIDER, "1"))).expect("Undone");
+    assert_eq!(current(&setup), 0.5);
+}
+
+#[test]
+fn saved_syncs() {
+    let mut setup = Setup::single();
+    let mut desk = named(setup.provider(), "1", "desk");
+    desk.set_brightness(Brightness::new(0.6)).expect("Capable");
+    setup.run(dump::dump(&desk).expect("Named")).expect("Saved");
+
+    setup.run(load_and_sync::single("desk", |_| {}))
+        .expect("Synced").expect("Recorded");
+    desk.set_brightness(Brightness::new(0.4)).expect("Capable");
+    setup.run(sync::General::new(&desk)).expect("Synced").expect("Recorded");
+
+    setup.run(Undo::new(&id(PROVIDER, "1"))).expect("Undone");
+    assert_eq!(current(&setup), 0.6);
+    setup.run(Undo::new(&id(PROVIDER, "1"))).expect("Undone");
+    assert_eq!(current(&setup), 0.2);
+}
+
+#[test]
+fn bounded() {
+    let mut setup = Setup::single();
+
+    for step in 0..(LIMIT + 3) {
+        dim(&mut setup, step as f64 / 100.0);
+    }
+
+    let history = setup.run(history::Get::new(&id(PROVIDER, "1")))
+        .expect("Loaded");
+    assert_eq!(history.undo.len(), LIMIT);
+    assert_eq!(brightness(&history.undo[0].light), 0.02);
+}
+
+#[test]
+fn power_undone() {
+    let mut setup = Setup::single();
+    let before = setup.provider().state("1").expect("Exists").is_on();
+
+    // Light without power capability is skipped and has nothing recorded
+    setup.run(power::Power::provider(PROVIDER, power::Action::Toggle))
+        .expect("Toggled");
+    assert!(setup.run(Undo::new(&id(PROVIDER, "2"))).is_err());
+
+    let light = setup.run(Undo::new(&id(PROVIDER, "1"))).expect("Undone");
+    assert_eq!(light.is_on(), before);
+    assert_eq!(setup.provider().state("1").expect("Exists").is_on(), before);
+}
+
+#[test]
+fn unrecorded_reported() {
+    let mut setup = Setup::single();
+    setup.context.borrow_mut().registry =
+        Box::new(MemoryRegistry::new().fail_on_history());
+
+    // Light is synced anyway, failing history comes next to it
+    let id = id(PROVIDER, "1");
+    let recorded = setup.run(fetch_and_sync::single(&id, |light| {
+        light.set_brightness(Brightness::new(0.7)).expect("Capable");
+    })).expect("Synced");
+    assert!(recorded.is_err());
+    assert_eq!(current(&setup), 0.7);
+
+    let powered = setup.run(power::Power::provider(PROVIDER,
+                                                   power::Action::Toggle))
+        .expect("Toggled");
+    assert_eq!(powered.lights.len(), 1);
+    assert_eq!(powered.unrecorded.len(), 1);
+}
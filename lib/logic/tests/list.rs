@@ -0,0 +1,59 @@
+
+mod common;
+
+use common::*;
+
+use logic::managers::fetch;
+use logic::strategies::list::provider::{self, partial};
+
+fn providers() -> Setup {
+    Setup::new(vec![common::provider("a"),
+                    common::provider("b").fail_on(1),
+                    common::provider("c")])
+}
+
+#[test]
+fn all_fails() {
+    let mut setup = providers();
+
+    assert!(setup.run(provider::All::new()).is_err());
+}
+
+#[test]
+fn partial_all() {
+    let mut setup = providers();
+
+    let partial = setup.run(partial::All::new());
+
+    assert!(!partial.is_complete());
+    assert_eq!(partial.lights.len(), 4);
+    assert!(partial.lights.iter().all(|light| "b" != light.provider.name));
+    assert_eq!(partial.errors.len(), 1);
+    assert_eq!(partial.errors[0].0, "b");
+    assert!(matches!(partial.errors[0].1, fetch::Error::Provider(_)));
+}
+
+#[test]
+fn partial_multiple() {
+    let mut setup = providers();
+
+    let partial = setup.run(partial::Multiple::new(["c", "b", "d"].into_iter()));
+
+    let names: Vec<&str> = partial.errors.iter()
+        .map(|(name, _)| name.as_str())
+        .collect();
+    assert_eq!(names, vec!["b", "d"]);
+    assert!(matches!(partial.errors[1].1, fetch::Error::NotFound(_)));
+    assert_eq!(partial.lights.len(), 2);
+}
+
+#[test]
+fn partial_complete() {
+    let mut setup = Setup::new(vec![common::provider("a"),
+                                    common::provider("b")]);
+
+    let partial = setup.run(partial::All::new());
+
+    assert!(partial.is_complete());
+    assert_eq!(partial.lights.len(), 4);
+}
@@ -0,0 +1,109 @@
+
+mod common;
+
+use common::*;
+
+use domain::mode::Mode;
+use domain::mode::parameter::{Parameter, Value};
+use domain::mode::descriptor::ModeDescriptor;
+use domain::mode::schema::{Field, ParameterSchema};
+use provider::ErrorType;
+use logic::managers::fetch;
+use logic::strategies::list::provider::modes;
+use logic::strategies::sync::{self, fetch_and_sync};
+
+fn catalog(provider: &str) -> Vec<ModeDescriptor> {
+    vec![
+        ModeDescriptor::new(provider, "colorloop", "Cycles through colors"),
+        ModeDescriptor::new(provider, "pulse", "Pulses brightness")
+            .with_parameter(Field::new(
+                "period", ParameterSchema::uint(Some(100), Some(10000)),
+                "Period in ms"
+            )),
+    ]
+}
+
+fn described() -> Setup {
+    Setup::new(vec![common::provider("b").with_catalog(catalog("b")),
+                    common::provider("a")])
+}
+
+fn pulse(period: Value) -> Mode {
+    Mode::new("b".to_string(), "pulse".to_string(),
+              vec![Parameter::new("period".to_string(), period)])
+}
+
+#[test]
+fn all() {
+    let mut setup = described();
+
+    let modes: Vec<_> = setup.run(modes::All::new()).expect("Listed")
+        .into_iter()
+        .map(|mode| format!("{}@{}", mode.name, mode.provider))
+        .collect();
+
+    // Provider without a catalog advertises modes of its lights
+    assert_eq!(modes, vec!["colorloop@a", "colorloop@b", "pulse@b"]);
+}
+
+#[test]
+fn single() {
+    let mut setup = described();
+
+    assert_eq!(setup.run(modes::Single::new("b")).expect("Listed"),
+               catalog("b"));
+    assert!(matches!(setup.run(modes::Single::new("c")),
+                     Err(fetch::Error::NotFound(_))));
+}
+
+#[test]
+fn checked_before_sync() {
+    let mut setup = described();
+
+    let result = setup.run(fetch_and_sync::single(&id("b", "1"), |light| {
+        light.set_mode(pulse(Value::UInt(10))).expect("Capable");
+    }));
+
+    match result {
+        Err(fetch::Error::Provider(err)) => {
+            assert!(matches!(err.etype, ErrorType::IncorrectState(..)));
+        },
+        _ => panic!("Mode must be rejected"),
+    }
+    assert!(setup.providers[0].syncs().is_empty());
+}
+
+#[test]
+fn valid_mode() {
+    let mut setup = described();
+
+    setup.run(fetch_and_sync::single(&id("b", "1"), |light| {
+        light.set_mode(Mode::new_empty("b".to_string(),
+                                       "colorloop".to_string()))
+            .expect("Capable");
+    })).expect("Synced").expect("Recorded");
+
+    assert_eq!(setup.providers[0].syncs().len(), 1);
+}
+
+#[test]
+fn catalog_cached() {
+    let mut setup = described();
+    let mut light = named(&setup.providers[0], "1", "");
+    light.set_mode(Mode::new_empty("b".to_string(), "colorloop".to_string()))
+        .expect("Capable");
+
+    for _ in 0..3 {
+        setup.run(sync::General::new(&light))
+            .expect("Synced").expect("Recorded");
+    }
+
+    assert_eq!(setup.providers[0].syncs().len(), 3);
+    assert_eq!(setup.providers[0].catalog_requests(), 1);
+
+    // Listings are served from the same catalogs
+    setup.run(modes::Single::new("b")).expect("Listed");
+    setup.run(modes::All::new()).expect("Listed");
+    assert_eq!(setup.providers[0].catalog_requests(), 1);
+    assert_eq!(setup.providers[1].catalog_requests(), 1);
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use common::*;
+
+use domain::brightness::Brightness;
+use domain::mode::Mode;
+use domain::patch::LightPatch;
+use mock_provider::Call;
+use logic::managers::fetch;
+use logic::strategies::sync::{self, fetch_and_sync};
+
+fn patching() -> Setup {
+    Setup::new(vec![provider(PROVIDER).with_patches()])
+}
+
+#[test]
+fn fetch_and_sync_sends_changes() {
+    let mut setup = patching();
+
+    setup.run(fetch_and_sync::single(&id(PROVIDER, "1"), |light| {
+        light.set_power(true).expect("Capable");
+        light.set_brightness(Brightness::new(0.2)).expect("Capable");
+    })).expect("Synced").expect("Recorded");
+
+    // Brightness is the same, so only power is sent
+    assert_eq!(setup.provider().patches(),
+               vec![("1".to_string(), LightPatch::new().with_power(true))]);
+    assert!(setup.provider().syncs().is_empty());
+    assert!(setup.provider().state("1").expect("Exists").is_on());
+}
+
+#[test]
+fn fetch_and_sync_unchanged() {
+    let mut setup = patching();
+
+    setup.run(fetch_and_sync::single(&id(PROVIDER, "2"), |_| {}))
+        .expect("Synced").expect("Recorded");
+
+    assert!(setup.provider().patches().is_empty());
+}
+
+#[test]
+fn fallback_merges() {
+    let mut setup = Setup::single();
+    let patch = LightPatch::new().with_brightness(Brightness::new(0.9));
+
+    setup.run(sync::Patch::new(&id(PROVIDER, "1"), &patch)).expect("Synced");
+
+    // Light is fetched by the provider and synced whole
+    let calls = setup.provider().calls();
+    assert!(matches!(calls.as_slice(), [Call::Get(_), Call::Sync(_)]));
+
+    let state = setup.provider().state("1").expect("Exists");
+    assert_eq!(brightness(&state), 0.9);
+    assert!(!state.is_on());
+}
+
+#[test]
+fn mode_checked() {
+    let mut setup = patching();
+    let patch = LightPatch::new()
+        .with_mode(Mode::new_empty(PROVIDER.to_string(), "strobe".to_string()));
+
+    let result = setup.run(sync::Patch::new(&id(PROVIDER, "1"), &patch));
+
+    assert!(matches!(result, Err(fetch::Error::Provider(_))));
+    assert!(setup.provider().patches().is_empty());
+}
@@ -0,0 +1,162 @@
+
+mod common;
+
+use common::*;
+
+use domain::light;
+use domain::capabilities::Capability;
+use domain::brightness::Brightness;
+use mock_provider::{MockProvider, VirtualLight};
+use logic::strategies::save::dump;
+use logic::strategies::sync;
+use logic::strategies::power::{self, Action, Power};
+
+fn power(setup: &Setup, id: &str) -> bool {
+    setup.provider().state(id).expect("Exists").get_power().expect("Set")
+}
+
+fn saved() -> Setup {
+    let mut setup = Setup::single();
+    let desk = named(setup.provider(), "1", "desk");
+    let lamp = named(setup.provider(), "2", "lamp");
+
+    setup.run(dump::dumps([desk, lamp].iter()).expect("Named"))
+        .into_result().expect("Saved");
+
+    setup
+}
+
+#[test]
+fn on_by_id() {
+    let mut setup = Setup::single();
+    let id = id(PROVIDER, "1");
+
+    let lights = setup.run(Power::id(&id, Action::On)).expect("Synced").lights;
+
+    assert_eq!(lights.len(), 1);
+    assert!(lights[0].is_on());
+    assert!(power(&setup, "1"));
+    // Nothing is saved unless asked
+    assert!(setup.dumps().is_empty());
+}
+
+#[test]
+fn toggle() {
+    let mut setup = Setup::single();
+    let id = id(PROVIDER, "1");
+
+    setup.run(Power::id(&id, Action::Toggle)).expect("Synced");
+    assert!(power(&setup, "1"));
+
+    setup.run(Power::id(&id, Action::Toggle)).expect("Synced");
+    assert!(!power(&setup, "1"));
+}
+
+#[test]
+fn toggle_unset() {
+    let mut setup = Setup::new(vec![
+        MockProvider::new(PROVIDER).with_light(
+            VirtualLight::new(PROVIDER, "1", vec![Capability::Power])
+        )
+    ]);
+    let id = id(PROVIDER, "1");
+    let result = setup.run(Power::id(&id, Action::Toggle));
+
+    assert!(matches!(result, Err(power::Error::Light(light::Error::Unset(..)))));
+    assert!(setup.provider().syncs().is_empty());
+}
+
+#[test]
+fn incapable() {
+    let mut setup = Setup::single();
+    let id = id(PROVIDER, "2");
+    let result = setup.run(Power::id(&id, Action::On));
+
+    assert!(matches!(result,
+                     Err(power::Error::Light(light::Error::Incapable(..)))));
+    assert!(setup.provider().syncs().is_empty());
+}
+
+#[test]
+fn missing_id_syncs_nothing() {
+    let mut setup = Setup::single();
+    let ids = [id(PROVIDER, "1"), id(PROVIDER, "3")];
+    let result = setup.run(Power::ids(ids.iter(), Action::On));
+
+    assert!(matches!(result, Err(power::Error::Fetch(_))));
+    assert!(setup.provider().syncs().is_empty());
+}
+
+#[test]
+fn by_name() {
+    let mut setup = saved();
+    // Only power is changed, the rest of the dump isn't synced
+    let mut changed = setup.provider().state("1").expect("Exists");
+    changed.set_brightness(Brightness::new(0.9)).expect("Capable");
+    setup.run(sync::General::new(&changed)).expect("Synced").expect("Recorded");
+
+    let lights = setup.run(Power::name("desk", Action::On))
+        .expect("Synced").lights;
+
+    assert_eq!(lights[0].name, "desk");
+    assert!(power(&setup, "1"));
+    assert_eq!(brightness(&setup.provider().state("1").expect("Exists")), 0.9);
+}
+
+#[test]
+fn by_name_missing() {
+    let mut setup = saved();
+    let result = setup.run(Power::names(["desk", "bed"].into_iter(),
+                                        Action::On));
+
+    assert!(matches!(result, Err(power::Error::Local(_))));
+    assert!(setup.provider().syncs().is_empty());
+}
+
+#[test]
+fn by_name_saved() {
+    let mut setup = saved();
+
+    setup.run(Power::name("desk", Action::On).saved()).expect("Synced");
+
+    let dump = setup.registry(|registry| registry.load_dump("desk"))
+        .expect("Saved");
+    assert!(dump.get_power().expect("Set"));
+    // Saved state besides power is kept
+    assert_eq!(brightness(&dump), 0.2);
+}
+
+#[test]
+fn by_id_saved() {
+    let mut setup = saved();
+    let id = id(PROVIDER, "1");
+
+    setup.run(Power::id(&id, Action::On).saved()).expect("Synced");
+
+    let dump = setup.registry(|registry| registry.load_dump("desk"))
+        .expect("Saved");
+    assert!(dump.get_power().expect("Set"));
+}
+
+#[test]
+fn provider() {
+    let mut setup = Setup::new(vec![common::provider("a"),
+                                    common::provider("b")]);
+
+    let lights = setup.run(Power::provider("b", Action::On))
+        .expect("Synced").lights;
+
+    // Light without power capability is skipped
+    assert_eq!(names(lights.clone()), vec![""]);
+    assert_eq!(lights[0].provider.id, "1");
+    assert!(setup.providers[1].state("1").expect("Exists").is_on());
+    assert!(!setup.providers[0].state("1").expect("Exists").is_on());
+}
+
+#[test]
+fn unknown_provider() {
+    let mut setup = Setup::single();
+
+    assert!(matches!(setup.run(Power::provider("none", Action::Off)),
+                     Err(power::Error::Fetch(_))));
+}
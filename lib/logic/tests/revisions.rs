@@ -0,0 +1,43 @@
+mod common;
+
+use common::*;
+
+use domain::brightness::Brightness;
+use logic::managers::local::Section;
+use logic::strategies::save::{dump, manage::Rename};
+use logic::strategies::save::revisions::{All, Diff, Get, Store};
+
+#[test]
+fn kept_per_name() {
+    let mut setup = Setup::single();
+    let mut desk = named(setup.provider(), "1", "desk");
+    setup.run(dump::dump(&desk).expect("Named")).expect("Saved");
+    desk.set_brightness(Brightness::new(0.9)).expect("Capable");
+    setup.run(Store::new(Section::Dumps, &desk).expect("Named")
+              .with_message("brighter")).expect("Saved");
+
+    let revisions = setup.run(All::new(Section::Dumps, "desk"))
+        .expect("Listed");
+    assert_eq!(revisions.len(), 2);
+    assert_eq!(revisions[1].message.as_deref(), Some("brighter"));
+    assert_eq!(brightness(&setup.run(Get::new(Section::Dumps, "desk", 1))
+                          .expect("Loaded").light), 0.2);
+
+    let patch = setup.run(Diff::new(Section::Dumps, "desk", 1, 2))
+        .expect("Compared");
+    assert_eq!(patch.brightness, Some(Brightness::new(0.9)));
+    assert!(setup.run(All::new(Section::Defaults, "desk")).is_err());
+
+    // Revisions follow renames
+    setup.run(Rename::new("desk", "table").expect("Named")).expect("Renamed");
+    assert_eq!(setup.run(All::new(Section::Dumps, "table"))
+               .expect("Listed").len(), 2);
+}
+
+#[test]
+fn unnamed() {
+    let setup = Setup::single();
+    let light = setup.provider().state("1").expect("Exists");
+
+    assert!(Store::new(Section::Dumps, &light).is_none());
+}
@@ -0,0 +1,226 @@
+
+mod common;
+
+use common::*;
+
+use local_registry::ErrorType;
+use logic::strategies::save::{self, dump, load_and_save};
+use logic::strategies::save::manage::{self as managing, delete};
+
+fn registry_error(result: Result<(), local_registry::Error>) -> ErrorType {
+    result.expect_err("Must fail").etype
+}
+
+mod dumps {
+    use super::*;
+
+    #[test]
+    fn dump() {
+        let mut setup = Setup::single();
+        let light = named(setup.provider(), "1", "desk");
+
+        setup.run(dump::dump(&light).expect("Named")).expect("Saved");
+
+        assert_eq!(setup.dumps(), vec!["desk"]);
+        assert!(setup.defaults().is_empty());
+    }
+
+    #[test]
+    fn default() {
+        let mut setup = Setup::single();
+        let light = named(setup.provider(), "1", "desk");
+
+        setup.run(dump::default(&light).expect("Named")).expect("Saved");
+
+        assert!(setup.dumps().is_empty());
+        assert_eq!(setup.defaults(), vec!["desk"]);
+    }
+
+    #[test]
+    fn save() {
+        let mut setup = Setup::single();
+        let light = named(setup.provider(), "1", "desk");
+
+        setup.run(dump::save(&light).expect("Named")).expect("Saved");
+
+        assert_eq!(setup.dumps(), vec!["desk"]);
+        assert_eq!(setup.defaults(), vec!["desk"]);
+    }
+
+    #[test]
+    fn unnamed() {
+        let setup = Setup::single();
+        let light = named(setup.provider(), "1", "");
+
+        assert!(dump::dump(&light).is_none());
+        assert!(dump::dumps([light.clone(), light].iter()).is_none());
+    }
+
+    #[test]
+    fn multiple() {
+        let mut setup = Setup::single();
+        let lights = [
+            named(setup.provider(), "1", "desk"),
+            named(setup.provider(), "2", "lamp"),
+        ];
+
+        setup.run(dump::dumps(lights.iter()).expect("Named"))
+            .into_result().expect("Saved");
+
+        assert_eq!(setup.dumps(), vec!["desk", "lamp"]);
+    }
+}
+
+mod load {
+    use super::*;
+
+    #[test]
+    fn all_default() {
+        let mut setup = Setup::new(vec![common::provider("a"),
+                                        common::provider("b")]);
+
+        setup.run(load_and_save::all_default()).expect("Saved");
+
+        assert_eq!(setup.dumps(), vec!["1@a", "1@b", "2@a", "2@b"]);
+        assert_eq!(setup.defaults(), setup.dumps());
+    }
+
+    #[test]
+    fn custom_names() {
+        let mut setup = Setup::single();
+
+        setup.run(load_and_save::All::new(|light| {
+            format!("light_{}", light.provider.id)
+        })).expect("Saved");
+
+        assert_eq!(setup.dumps(), vec!["light_1", "light_2"]);
+    }
+
+    #[test]
+    fn provider() {
+        let mut setup = Setup::new(vec![common::provider("a"),
+                                        common::provider("b")]);
+
+        setup.run(load_and_save::provider_default("b")).expect("Saved");
+
+        assert_eq!(setup.dumps(), vec!["1@b", "2@b"]);
+    }
+
+    #[test]
+    fn providers() {
+        let mut setup = Setup::new(vec![common::provider("a"),
+                                        common::provider("b"),
+                                        common::provider("c")]);
+
+        setup.run(load_and_save::providers_default(["a", "c"].into_iter()))
+            .expect("Saved");
+
+        assert_eq!(setup.dumps(), vec!["1@a", "1@c", "2@a", "2@c"]);
+    }
+
+    #[test]
+    fn unknown_provider() {
+        let mut setup = Setup::single();
+
+        assert!(matches!(setup.run(load_and_save::provider_default("none")),
+                         Err(save::Error::Fetch(_))));
+        assert!(setup.dumps().is_empty());
+    }
+
+    #[test]
+    fn provider_failure() {
+        let mut setup = Setup::new(vec![common::provider("a").fail_on(1)]);
+
+        assert!(matches!(setup.run(load_and_save::all_default()),
+                         Err(save::Error::Fetch(_))));
+        assert!(setup.dumps().is_empty());
+    }
+}
+
+mod manage {
+    use super::*;
+
+    fn saved() -> Setup {
+        let mut setup = Setup::single();
+        let lights = [
+            named(setup.provider(), "1", "desk"),
+            named(setup.provider(), "2", "lamp"),
+        ];
+        setup.run(dump::saves(lights.iter()).expect("Named"))
+            .into_result().expect("Saved");
+
+        setup
+    }
+
+    #[test]
+    fn rename() {
+        let mut setup = saved();
+
+        setup.run(managing::Rename::new("desk", "table").expect("Named"))
+            .expect("Renamed");
+
+        assert_eq!(setup.dumps(), vec!["lamp", "table"]);
+        assert_eq!(setup.defaults(), vec!["lamp", "table"]);
+    }
+
+    #[test]
+    fn rename_unnamed() {
+        assert!(managing::Rename::new("", "table").is_none());
+        assert!(managing::Rename::new("desk", "").is_none());
+    }
+
+    #[test]
+    fn rename_missing() {
+        let mut setup = saved();
+        let result = setup.run(managing::Rename::new("bed", "table")
+                               .expect("Named"));
+
+        assert!(matches!(registry_error(result), ErrorType::NotFound(_)));
+    }
+
+    #[test]
+    fn rename_collision() {
+        let mut setup = saved();
+        let result = setup.run(managing::Rename::new("desk", "lamp")
+                               .expect("Named"));
+
+        assert!(matches!(registry_error(result), ErrorType::Exists(_)));
+        assert_eq!(setup.dumps(), vec!["desk", "lamp"]);
+    }
+
+    #[test]
+    fn delete() {
+        let mut setup = saved();
+
+        setup.run(delete::Single::new("desk").expect("Named")).expect("Deleted");
+
+        assert_eq!(setup.dumps(), vec!["lamp"]);
+        assert_eq!(setup.defaults(), vec!["lamp"]);
+    }
+
+    #[test]
+    fn delete_missing() {
+        let mut setup = saved();
+        let result = setup.run(delete::Single::new("bed").expect("Named"));
+
+        assert!(matches!(registry_error(result), ErrorType::NotFound(_)));
+    }
+
+    #[test]
+    fn delete_multiple() {
+        let mut setup = saved();
+
+        setup.run(delete::Multiple::new(["desk", "lamp"].into_iter())
+                  .expect("Named"))
+            .into_result().expect("Deleted");
+
+        assert!(setup.dumps().is_empty());
+        assert!(setup.defaults().is_empty());
+    }
+
+    #[test]
+    fn delete_unnamed() {
+        assert!(delete::Single::new("").is_none());
+        assert!(delete::Multiple::new(["desk", ""].into_iter()).is_none());
+    }
+}
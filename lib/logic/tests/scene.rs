@@ -0,0 +1,152 @@
+
+mod common;
+
+use common::*;
+
+use domain::brightness::Brightness;
+use domain::scene::Scene;
+use local_registry::ErrorType;
+use provider::Provider;
+use logic::managers::fetch;
+use logic::strategies::scene::{self, Apply, Capture, Delete};
+
+fn change(setup: &Setup, id: &str, value: f64) {
+    let mut light = setup.provider().state(id).expect("Exists");
+    light.set_brightness(Brightness::new(value)).expect("Capable");
+    setup.provider().sync(&light).expect("Synced");
+}
+
+#[test]
+fn capture_all() {
+    let mut setup = Setup::new(vec![common::provider("a"),
+                                    common::provider("b")]);
+
+    let scene = setup.run(Capture::new("evening")).expect("Captured");
+
+    assert_eq!(scene.name, "evening");
+    assert_eq!(scene.lights.len(), 4);
+
+    let saved = setup.registry(|registry| registry.load_scene("evening"))
+        .expect("Saved");
+    assert_eq!(saved.lights.len(), 4);
+    // Scene isn't a saved light
+    assert!(setup.dumps().is_empty());
+}
+
+#[test]
+fn capture_ids() {
+    let mut setup = Setup::single();
+    let ids = [id(PROVIDER, "2")];
+
+    let scene = setup.run(Capture::new("hall").with_ids(ids.iter()))
+        .expect("Captured");
+
+    assert_eq!(scene.lights.len(), 1);
+    assert_eq!(brightness(scene.get(&ids[0]).expect("Member")), 0.8);
+}
+
+#[test]
+fn capture_failure() {
+    let mut setup = Setup::new(vec![provider(PROVIDER).fail_on(1)]);
+
+    assert!(matches!(setup.run(Capture::new("evening")),
+                     Err(scene::Error::Fetch(_))));
+    assert!(setup.registry(|registry| registry.list_scenes())
+            .expect("Listed").is_empty());
+}
+
+#[test]
+fn capture_unnamed() {
+    let mut setup = Setup::single();
+
+    assert!(matches!(setup.run(Capture::new("")),
+                     Err(scene::Error::Local(local_registry::Error {
+                         etype: ErrorType::Unnamed, ..
+                     }))));
+}
+
+#[test]
+fn capture_partial() {
+    let mut setup = Setup::new(vec![provider("a").fail_on(1), provider("b")]);
+
+    let (scene, errors) = setup.run(Capture::new("evening").partial())
+        .expect("Captured");
+
+    assert_eq!(scene.lights.len(), 2);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, "a");
+    assert_eq!(setup.registry(|registry| registry.load_scene("evening"))
+               .expect("Saved").lights.len(), 2);
+}
+
+#[test]
+fn capture_partial_nothing() {
+    let mut setup = Setup::new(vec![provider(PROVIDER).fail_on(1)]);
+
+    assert!(matches!(setup.run(Capture::new("evening").partial()),
+                     Err(scene::Error::Fetch(_))));
+}
+
+#[test]
+fn apply() {
+    let mut setup = Setup::single();
+    setup.run(Capture::new("evening")).expect("Captured");
+    change(&setup, "1", 0.9);
+    change(&setup, "2", 0.1);
+
+    let report = setup.run(Apply::new("evening")).expect("Loaded");
+
+    assert_eq!(report.len(), 2);
+    assert!(report.iter().all(|(_, result)| result.is_ok()));
+    assert_eq!(brightness(&setup.provider().state("1").expect("Exists")), 0.2);
+    assert_eq!(brightness(&setup.provider().state("2").expect("Exists")), 0.8);
+}
+
+#[test]
+fn apply_continues_after_failure() {
+    let mut setup = Setup::single();
+    let mut first = setup.provider().state("1").expect("Exists");
+    first.provider.id = "3".to_string();
+    let second = setup.provider().state("2").expect("Exists");
+    setup.registry(|registry| {
+        registry.save_scene(&Scene::new("broken".to_string(),
+                                        vec![first, second]))
+    }).expect("Saved");
+    change(&setup, "2", 0.1);
+
+    let report = setup.run(Apply::new("broken")).expect("Loaded");
+
+    assert_eq!(report[0].0.id, "3");
+    assert!(matches!(report[0].1, Err(fetch::Error::Provider(_))));
+    assert!(report[1].1.is_ok());
+    assert_eq!(brightness(&setup.provider().state("2").expect("Exists")), 0.8);
+}
+
+#[test]
+fn apply_missing() {
+    let mut setup = Setup::single();
+
+    assert!(matches!(setup.run(Apply::new("evening")),
+                     Err(local_registry::Error {
+                         etype: ErrorType::NotFound(_), ..
+                     })));
+    assert!(setup.provider().syncs().is_empty());
+}
+
+#[test]
+fn list_and_delete() {
+    let mut setup = Setup::single();
+    setup.run(Capture::new("morning")).expect("Captured");
+    setup.run(Capture::new("evening")).expect("Captured");
+
+    let names: Vec<String> = setup.run(scene::All::new()).expect("Listed")
+        .into_iter().map(|scene| scene.name).collect();
+    assert_eq!(names, vec!["evening", "morning"]);
+
+    setup.run(Delete::new("morning")).expect("Deleted");
+    assert_eq!(setup.run(scene::All::new()).expect("Listed").len(), 1);
+    assert!(matches!(setup.run(Delete::new("morning")),
+                     Err(local_registry::Error {
+                         etype: ErrorType::NotFound(_), ..
+                     })));
+}
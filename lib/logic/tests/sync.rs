@@ -0,0 +1,99 @@
+
+mod common;
+
+use common::*;
+
+use domain::brightness::Brightness;
+use local_registry::ErrorType;
+use logic::batch::Status;
+use logic::strategies::save::dump;
+use logic::strategies::sync::{self, load_and_sync};
+
+fn saved() -> Setup {
+    let mut setup = Setup::single();
+    let mut desk = named(setup.provider(), "1", "desk");
+    desk.set_power(true).expect("Capable");
+    desk.set_brightness(Brightness::new(0.6)).expect("Capable");
+    let lamp = named(setup.provider(), "2", "lamp");
+
+    setup.run(dump::dumps([desk, lamp].iter()).expect("Named"))
+        .into_result().expect("Saved");
+
+    setup
+}
+
+#[test]
+fn single() {
+    let mut setup = saved();
+
+    setup.run(load_and_sync::single("desk", |_| {}))
+        .expect("Synced").expect("Recorded");
+
+    let state = setup.provider().state("1").expect("Exists");
+    assert!(state.is_on());
+    assert_eq!(brightness(&state), 0.6);
+    assert_eq!(setup.provider().syncs().len(), 1);
+}
+
+#[test]
+fn single_mapped() {
+    let mut setup = saved();
+
+    setup.run(load_and_sync::single("desk", |light| {
+        light.set_brightness(Brightness::new(0.1)).expect("Capable");
+    })).expect("Synced").expect("Recorded");
+
+    assert_eq!(brightness(&setup.provider().state("1").expect("Exists")), 0.1);
+
+    // Mapped state is saved back
+    let saved = setup.registry(|registry| registry.load_dump("desk"))
+        .expect("Saved");
+    assert_eq!(brightness(&saved), 0.1);
+}
+
+#[test]
+fn single_missing() {
+    let mut setup = saved();
+    let result = setup.run(load_and_sync::single("bed", |_| {}));
+
+    assert!(matches!(result, Err(sync::Error::Local(local_registry::Error {
+        etype: ErrorType::NotFound(_), ..
+    }))));
+    assert!(setup.provider().syncs().is_empty());
+}
+
+#[test]
+fn single_provider_failure() {
+    let mut setup = Setup::new(vec![provider(PROVIDER).fail_on(1)]);
+    let light = named(setup.provider(), "1", "desk");
+    setup.run(dump::dump(&light).expect("Named")).expect("Saved");
+
+    assert!(matches!(setup.run(load_and_sync::single("desk", |_| {})),
+                     Err(sync::Error::Fetch(_))));
+}
+
+#[test]
+fn multiple() {
+    let mut setup = saved();
+
+    setup.run(load_and_sync::multiple(["desk", "lamp"].into_iter(), |light| {
+        light.set_brightness(Brightness::new(1.0)).expect("Capable");
+    })).into_result().expect("Synced");
+
+    let syncs = setup.provider().syncs();
+    assert_eq!(syncs.len(), 2);
+    assert!(syncs.iter().all(|light| 1.0 == brightness(light)));
+}
+
+#[test]
+fn multiple_stops_on_error() {
+    let mut setup = saved();
+    let result = setup.run(load_and_sync::multiple(
+        ["desk", "bed", "lamp"].into_iter(),
+        |_| {}
+    ));
+
+    assert!(matches!(result.items[2].1, Status::Skipped));
+    assert!(matches!(result.into_result(), Err(sync::Error::Local(_))));
+    assert_eq!(setup.provider().syncs().len(), 1);
+}
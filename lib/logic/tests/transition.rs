@@ -0,0 +1,262 @@
+
+mod common;
+
+use common::*;
+
+use std::time::Duration;
+
+use domain::light::Light;
+use domain::capabilities::Capability;
+use domain::color::rgb::RGB;
+use domain::brightness::Brightness;
+use mock_provider::{MockProvider, VirtualLight};
+use logic::managers::fetch;
+use logic::strategies::transition::Transition;
+use logic::transition::{frame, Easing};
+
+fn state(power: bool, rgb: RGB, brightness: f64) -> Light {
+    let mut light = Light::new(PROVIDER.to_string(), "1".to_string(),
+                               vec![Capability::Power, Capability::Color,
+                                    Capability::Brightness]);
+    light.set_power(power).expect("Capable");
+    light.set_color(rgb.into()).expect("Capable");
+    light.set_brightness(Brightness::new(brightness)).expect("Capable");
+
+    light
+}
+
+fn target(setup: &Setup, value: f64) -> Light {
+    let mut light = setup.provider().state("1").expect("Exists");
+    light.set_power(true).expect("Capable");
+    light.set_brightness(Brightness::new(value)).expect("Capable");
+
+    light
+}
+
+mod frames {
+    use super::*;
+
+    #[test]
+    fn easing_bounds() {
+        for easing in [Easing::Linear, Easing::EaseIn, Easing::EaseOut,
+                       Easing::EaseInOut] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+            assert_eq!(easing.apply(2.0), 1.0);
+        }
+    }
+
+    #[test]
+    fn easing_shape() {
+        assert_eq!(Easing::Linear.apply(0.25), 0.25);
+        assert!(0.25 > Easing::EaseIn.apply(0.25));
+        assert!(0.25 < Easing::EaseOut.apply(0.25));
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+    }
+
+    #[test]
+    fn brightness_midway() {
+        let from = state(true, RGB::new(255, 0, 0), 0.2);
+        let to = state(true, RGB::new(255, 0, 0), 0.6);
+
+        assert!((brightness(&frame(&from, &to, 0.5)) - 0.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn color_midway() {
+        let from = state(true, RGB::new(255, 0, 0), 1.0);
+        let to = state(true, RGB::new(0, 0, 255), 1.0);
+        let rgb = RGB::from(frame(&from, &to, 0.5).get_color()
+                            .expect("Set").clone());
+
+        // Purple, not the dark one of sRGB interpolation
+        assert!(rgb.red > 100 && rgb.blue > 100);
+        assert!(rgb.green < rgb.red);
+    }
+
+    #[test]
+    fn ends() {
+        let from = state(true, RGB::new(255, 0, 0), 0.2);
+        let to = state(true, RGB::new(0, 0, 255), 0.6);
+
+        assert_eq!(brightness(&frame(&from, &to, 0.0)), 0.2);
+        assert_eq!(brightness(&frame(&from, &to, 1.0)), 0.6);
+    }
+
+    #[test]
+    fn switching_on() {
+        let from = state(false, RGB::new(255, 0, 0), 0.8);
+        let to = state(true, RGB::new(255, 0, 0), 0.6);
+        let first = frame(&from, &to, 0.0);
+
+        assert!(first.is_on());
+        assert_eq!(brightness(&first), 0.0);
+    }
+
+    #[test]
+    fn switching_off() {
+        let from = state(true, RGB::new(255, 0, 0), 0.8);
+        let to = state(false, RGB::new(255, 0, 0), 0.6);
+        let last = frame(&from, &to, 0.99);
+
+        assert!(last.is_on());
+        assert!(0.01 > brightness(&last));
+        assert!(!frame(&from, &to, 1.0).is_on());
+    }
+}
+
+mod strategy {
+    use super::*;
+
+    #[test]
+    fn frames() {
+        let mut setup = Setup::single();
+        let clock = FakeClock::new();
+        let to = target(&setup, 0.7);
+
+        setup.run(Transition::new(&to, Duration::from_secs(1))
+                  .with_rate(10)
+                  .with_clock(&clock))
+            .expect("Synced");
+
+        // Frames at every 100ms and the target itself at the end
+        let syncs = setup.provider().syncs();
+        assert_eq!(syncs.len(), 11);
+        assert_eq!(clock.sleeps(), vec![Duration::from_millis(100); 10]);
+        // Light is switched off, so it fades in from zero
+        assert!(syncs[0].is_on());
+        assert_eq!(brightness(&syncs[0]), 0.0);
+        assert!((brightness(&syncs[5]) - 0.35).abs() < 1e-9);
+        assert_eq!(brightness(&setup.provider().state("1").expect("Exists")),
+                   0.7);
+
+        let values: Vec<f64> = syncs.iter().map(brightness).collect();
+        assert!(values.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn last_frame_not_overshot() {
+        let mut setup = Setup::single();
+        let clock = FakeClock::new();
+        let to = target(&setup, 0.7);
+
+        setup.run(Transition::new(&to, Duration::from_millis(250))
+                  .with_rate(10)
+                  .with_clock(&clock))
+            .expect("Synced");
+
+        assert_eq!(clock.sleeps(), vec![Duration::from_millis(100),
+                                        Duration::from_millis(100),
+                                        Duration::from_millis(50)]);
+        assert_eq!(setup.provider().syncs().len(), 4);
+    }
+
+    #[test]
+    fn eased() {
+        let mut setup = Setup::single();
+        let clock = FakeClock::new();
+        let to = target(&setup, 1.0);
+
+        setup.run(Transition::new(&to, Duration::from_secs(1))
+                  .with_rate(4)
+                  .with_easing(Easing::EaseIn)
+                  .with_clock(&clock))
+            .expect("Synced");
+
+        // Quarter of time is far below quarter of the change
+        let second = brightness(&setup.provider().syncs()[1]);
+        assert!(0.25 > second);
+    }
+
+    #[test]
+    fn given_start() {
+        let mut setup = Setup::single();
+        let clock = FakeClock::new();
+        let to = target(&setup, 1.0);
+        let from = target(&setup, 0.0);
+
+        setup.run(Transition::new(&to, Duration::from_secs(1))
+                  .with_start(&from)
+                  .with_clock(&clock))
+            .expect("Synced");
+
+        assert_eq!(brightness(&setup.provider().syncs()[0]), 0.0);
+        // Start isn't fetched
+        assert!(!setup.provider().calls().iter()
+                .any(|call| matches!(call, mock_provider::Call::Get(_))));
+    }
+
+    #[test]
+    fn native() {
+        let mut setup = Setup::new(vec![provider(PROVIDER).with_transitions()]);
+        let clock = FakeClock::new();
+        let to = target(&setup, 0.7);
+
+        setup.run(Transition::new(&to, Duration::from_secs(2))
+                  .with_clock(&clock))
+            .expect("Synced");
+
+        assert!(setup.provider().syncs().is_empty());
+        assert!(clock.sleeps().is_empty());
+
+        let transitions = setup.provider().transitions();
+        assert_eq!(transitions.len(), 1);
+        assert_eq!(transitions[0].1, Duration::from_secs(2));
+        assert_eq!(brightness(&transitions[0].0), 0.7);
+    }
+
+    #[test]
+    fn instant() {
+        let mut setup = Setup::single();
+        let clock = FakeClock::new();
+        let to = target(&setup, 0.7);
+
+        setup.run(Transition::new(&to, Duration::ZERO).with_clock(&clock))
+            .expect("Synced");
+
+        assert_eq!(setup.provider().syncs().len(), 1);
+        assert!(clock.sleeps().is_empty());
+    }
+
+    #[test]
+    fn color() {
+        let mut setup = Setup::new(vec![
+            MockProvider::new(PROVIDER).with_light(
+                VirtualLight::new(PROVIDER, "1", vec![Capability::Power,
+                                                      Capability::Color,
+                                                      Capability::Brightness])
+                    .with_state(|light| {
+                        light.set_power(true)?;
+                        light.set_color(RGB::new(255, 0, 0).into())?;
+                        light.set_brightness(Brightness::new(1.0))
+                    })
+            )
+        ]);
+        let clock = FakeClock::new();
+        let to = state(true, RGB::new(0, 0, 255), 1.0);
+
+        setup.run(Transition::new(&to, Duration::from_secs(1))
+                  .with_rate(2)
+                  .with_clock(&clock))
+            .expect("Synced");
+
+        let syncs = setup.provider().syncs();
+        let midway = RGB::from(syncs[1].get_color().expect("Set").clone());
+        assert!(midway.red > 100 && midway.blue > 100);
+    }
+
+    #[test]
+    fn failure_stops() {
+        // Fetch of the start is the first call
+        let mut setup = Setup::new(vec![provider(PROVIDER).fail_on(3)]);
+        let clock = FakeClock::new();
+        let to = target(&setup, 0.7);
+
+        let result = setup.run(Transition::new(&to, Duration::from_secs(1))
+                               .with_clock(&clock));
+
+        assert!(matches!(result, Err(fetch::Error::Provider(_))));
+        assert_eq!(setup.provider().syncs().len(), 2);
+        assert_eq!(clock.sleeps().len(), 1);
+    }
+}
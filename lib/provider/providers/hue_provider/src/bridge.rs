@@ -0,0 +1,234 @@
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+// Error types of the bridge API (v1)
+pub const UNAUTHORIZED: u64 = 1;
+pub const UNAVAILABLE: u64 = 3;
+pub const PARAMETER_UNAVAILABLE: u64 = 6;
+pub const INVALID_VALUE: u64 = 7;
+pub const LINK_BUTTON: u64 = 101;
+pub const DEVICE_OFF: u64 = 201;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LightInfo {
+    pub name: String,
+    pub state: StateInfo,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StateInfo {
+    pub on: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bri: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub xy: Option<[f64; 2]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effect: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct StateUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bri: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub xy: Option<[f64; 2]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effect: Option<String>,
+    // In multiples of 100ms
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transitiontime: Option<u16>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BridgeError {
+    #[serde(rename = "type")]
+    pub kind: u64,
+    pub address: String,
+    pub description: String,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Http(Box<ureq::Error>),
+    Io(std::io::Error),
+    Bridge(BridgeError),
+    Response(serde_json::Error),
+    Scheme(String),
+}
+
+// Only plain HTTP is built in, bridges don't have trusted certificates
+// anyway
+pub fn check_address(address: &str) -> Result<()> {
+    match address.split_once("://") {
+        Some((scheme, _)) if scheme != "http" => {
+            Err(Error::Scheme(scheme.to_string()))
+        },
+        _ => Ok(()),
+    }
+}
+
+// Bridge replies with a list of {"success": ...} or {"error": ...} items
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Reply {
+    Success(Value),
+    Error(BridgeError),
+}
+
+pub struct Bridge {
+    base: String,
+    agent: ureq::Agent,
+}
+
+impl Bridge {
+    pub fn new(address: &str) -> Self {
+        let address = address.trim_end_matches('/');
+        let base = if address.contains("://") {
+            address.to_string()
+        } else {
+            format!("http://{}", address)
+        };
+
+        Self {
+            base,
+            agent: ureq::AgentBuilder::new().timeout(TIMEOUT).build(),
+        }
+    }
+
+    pub fn address(self: &Self) -> &str {
+        &self.base
+    }
+
+    // Bridge answers only while its link button is pressed
+    pub fn pair(self: &Self, devicetype: &str) -> Result<String> {
+        let body = serde_json::json!({ "devicetype": devicetype });
+        let replies = self.replies(self.agent.post(&self.url(&[]))
+                                       .send_json(body))?;
+
+        replies.into_iter()
+            .find_map(|reply| match reply {
+                Reply::Success(value) => value.get("username")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                Reply::Error(_) => None,
+            })
+            .ok_or_else(|| {
+                Error::Response(serde::de::Error::missing_field("username"))
+            })
+    }
+
+    pub fn lights(self: &Self, username: &str) -> Result<HashMap<String, LightInfo>> {
+        self.get(&self.url(&[username, "lights"]))
+    }
+
+    pub fn light(self: &Self, username: &str, id: &str) -> Result<LightInfo> {
+        self.get(&self.url(&[username, "lights", id]))
+    }
+
+    pub fn set_state(self: &Self, username: &str, id: &str,
+                     update: &StateUpdate) -> Result<()> {
+        let url = self.url(&[username, "lights", id, "state"]);
+        self.replies(self.agent.put(&url).send_json(update)).map(|_| ())
+    }
+
+    fn url(self: &Self, path: &[&str]) -> String {
+        let mut out = format!("{}/api", self.base);
+
+        for item in path {
+            out.push('/');
+            out.push_str(item);
+        }
+
+        out
+    }
+
+    fn body(response: std::result::Result<ureq::Response, ureq::Error>) -> Result<Value> {
+        response.map_err(|err| Error::Http(Box::new(err)))?
+            .into_json()
+            .map_err(Error::Io)
+    }
+
+    // Failures are reported with status 200 and an error list as the body
+    fn check(body: Value) -> Result<Value> {
+        if let Some(Reply::Error(err)) = body.as_array()
+            .and_then(|items| items.first())
+            .and_then(|item| Reply::deserialize(item).ok())
+        {
+            Err(Error::Bridge(err))
+        } else {
+            Ok(body)
+        }
+    }
+
+    fn get<T: DeserializeOwned>(self: &Self, url: &str) -> Result<T> {
+        let body = Self::check(Self::body(self.agent.get(url).call())?)?;
+        serde_json::from_value(body).map_err(Error::Response)
+    }
+
+    fn replies(self: &Self,
+               response: std::result::Result<ureq::Response, ureq::Error>)
+        -> Result<Vec<Reply>>
+    {
+        let replies: Vec<Reply> = serde_json::from_value(Self::body(response)?)
+            .map_err(Error::Response)?;
+
+        for reply in replies.iter() {
+            if let Reply::Error(err) = reply {
+                return Err(Error::Bridge(err.clone()));
+            }
+        }
+
+        Ok(replies)
+    }
+}
+
+impl Error {
+    pub fn kind(self: &Self) -> Option<u64> {
+        match self {
+            Self::Bridge(err) => Some(err.kind),
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Http(err) => Some(err.as_ref()),
+            Self::Io(err) => Some(err),
+            Self::Response(err) => Some(err),
+            Self::Bridge(_) | Self::Scheme(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Http(err) => write!(f, "Bridge is unreachable: {}", err),
+            Self::Io(err) => write!(f, "Can't read bridge response: {}", err),
+            Self::Bridge(err) => {
+                write!(f, "Bridge error {} at \"{}\": {}",
+                       err.kind, err.address, err.description)
+            },
+            Self::Response(err) => {
+                write!(f, "Unexpected bridge response: {}", err)
+            },
+            Self::Scheme(scheme) => {
+                write!(f, "Bridge can't be reached over \"{}\", only \
+                           \"http\" is supported", scheme)
+            },
+        }
+    }
+}
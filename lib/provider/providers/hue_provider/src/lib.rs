@@ -0,0 +1,339 @@
+
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use domain::light::Light;
+use domain::capabilities::Capability;
+use domain::color::Color;
+use domain::color::xy::XY;
+use domain::brightness::Brightness;
+use domain::mode::Mode;
+use domain::mode::descriptor::ModeDescriptor;
+use domain::patch::LightPatch;
+use provider::{Provider, Error, Result};
+
+pub mod bridge;
+
+use bridge::{Bridge, LightInfo, StateUpdate};
+
+pub const EFFECTS: [&str; 2] = ["none", "colorloop"];
+const EFFECT_DOCS: [&str; 2] = ["No effect", "Cycles through all hues"];
+
+// Hue brightness is within [1, 254]
+const BRI_MIN: f64 = 1.0;
+const BRI_MAX: f64 = 254.0;
+
+pub struct HueProvider {
+    name: String,
+    username: String,
+    bridge: Bridge,
+}
+
+impl HueProvider {
+    pub fn new(name: &str, address: &str, username: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            username: username.to_string(),
+            bridge: Bridge::new(address),
+        }
+    }
+
+    // Link button on the bridge must be pressed right before pairing
+    pub fn pair(address: &str, devicetype: &str) -> bridge::Result<String> {
+        bridge::check_address(address)?;
+        Bridge::new(address).pair(devicetype)
+    }
+
+    pub fn bridge(self: &Self) -> &Bridge {
+        &self.bridge
+    }
+
+    fn error<T>(self: &Self, id: &str, light: Option<&Light>,
+                err: bridge::Error) -> Result<T> {
+        match (err.kind(), light) {
+            (Some(bridge::UNAVAILABLE), _) => Error::not_found(&self.name, id),
+            (Some(bridge::PARAMETER_UNAVAILABLE | bridge::INVALID_VALUE
+                  | bridge::DEVICE_OFF), Some(light)) => {
+                Error::incorrect_state(&self.name, light, err.to_string())
+            },
+            _ => Error::internal(&self.name, Box::new(err)),
+        }
+    }
+
+    fn convert(self: &Self, id: &str, info: LightInfo) -> Result<Light> {
+        let state = info.state;
+        let mut capabilities = vec![Capability::Power];
+
+        if state.xy.is_some() {
+            capabilities.push(Capability::Color);
+        }
+
+        if state.bri.is_some() {
+            capabilities.push(Capability::Brightness);
+        }
+
+        if state.effect.is_some() {
+            capabilities.push(Capability::Mode);
+        }
+
+        let mut light = Light::new(self.name.clone(), id.to_string(),
+                                   capabilities);
+        let result = (|| {
+            light.set_power(state.on)?;
+
+            if let Some([x, y]) = state.xy {
+                light.set_color(Color::from(XY::new(x, y)))?;
+            }
+
+            if let Some(bri) = state.bri {
+                light.set_brightness(from_bri(bri))?;
+            }
+
+            if let Some(effect) = state.effect {
+                light.set_mode(Mode::new_empty(self.name.clone(), effect))?;
+            }
+
+            Ok::<(), domain::light::Error>(())
+        })();
+
+        match result {
+            Ok(_) => Ok(light),
+            Err(err) => Error::internal(&self.name, Box::new(err)),
+        }
+    }
+
+    fn send(self: &Self, light: &Light, update: StateUpdate) -> Result<()> {
+        if light.provider.name != self.name {
+            return Error::foreign_light(&self.name, light);
+        }
+
+        let id = &light.provider.id;
+
+        match self.bridge.set_state(&self.username, id, &update) {
+            Ok(_) => Ok(()),
+            Err(err) => self.error(id, Some(light), err),
+        }
+    }
+
+    fn update(self: &Self, light: &Light) -> Result<StateUpdate> {
+        let mut out = StateUpdate {
+            on: light.get_power().ok(),
+            ..Default::default()
+        };
+
+        // Bridge refuses to change anything else for a switched off light
+        if Some(false) == out.on {
+            return Ok(out);
+        }
+
+        if let Ok(color) = light.get_color() {
+            let xy = XY::from(color.clone());
+            out.xy = Some([xy.x, xy.y]);
+        }
+
+        if let Ok(brightness) = light.get_brightness() {
+            out.bri = Some(to_bri(brightness));
+        }
+
+        if let Ok(mode) = light.get_mode() {
+            if !EFFECTS.contains(&mode.name.as_str()) {
+                return Error::incorrect_state(&self.name, light,
+                    format!("Unsupported effect \"{}\"", mode.name));
+            }
+
+            out.effect = Some(mode.name.clone());
+        }
+
+        Ok(out)
+    }
+}
+
+fn to_transitiontime(duration: Duration) -> u16 {
+    (duration.as_millis() / 100).min(u16::MAX as u128) as u16
+}
+
+fn from_bri(bri: u8) -> Brightness {
+    let bri = (bri as f64).clamp(BRI_MIN, BRI_MAX);
+    Brightness::new((bri - BRI_MIN) / (BRI_MAX - BRI_MIN))
+}
+
+fn to_bri(brightness: &Brightness) -> u8 {
+    (BRI_MIN + **brightness * (BRI_MAX - BRI_MIN)).round() as u8
+}
+
+// Numeric ids go first in numeric order
+fn id_key(id: &str) -> (Option<u64>, &str) {
+    (id.parse().ok(), id)
+}
+
+impl Provider for HueProvider {
+    fn name(self: &Self) -> &str {
+        &self.name
+    }
+
+    fn list(self: &Self) -> Result<Vec<Light>> {
+        let mut lights: Vec<(String, LightInfo)> = match self.bridge.lights(&self.username) {
+            Ok(lights) => lights.into_iter().collect(),
+            Err(err) => return Error::internal(&self.name, Box::new(err)),
+        };
+        lights.sort_by(|(a, _), (b, _)| id_key(a).cmp(&id_key(b)));
+
+        lights.into_iter()
+            .map(|(id, info)| self.convert(&id, info))
+            .collect()
+    }
+
+    fn get(self: &Self, id: &str) -> Result<Light> {
+        match self.bridge.light(&self.username, id) {
+            Ok(info) => self.convert(id, info),
+            Err(err) => self.error(id, None, err),
+        }
+    }
+
+    fn sync(self: &Self, light: &Light) -> Result<()> {
+        self.send(light, self.update(light)?)
+    }
+
+    fn supports_transition(self: &Self) -> bool {
+        true
+    }
+
+    fn sync_transition(self: &Self, light: &Light,
+                       duration: Duration) -> Result<()> {
+        let mut update = self.update(light)?;
+        update.transitiontime = Some(to_transitiontime(duration));
+
+        self.send(light, update)
+    }
+
+    fn apply(self: &Self, id: &str, patch: &LightPatch) -> Result<()> {
+        provider::apply_partial(self, id, patch)
+    }
+
+    fn modes(self: &Self) -> Result<Vec<ModeDescriptor>> {
+        Ok(EFFECTS.iter().zip(EFFECT_DOCS)
+           .map(|(name, doc)| ModeDescriptor::new(&self.name, name, doc))
+           .collect())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Settings {
+    #[serde(deserialize_with = "address")]
+    pub address: String,
+    pub username: String,
+}
+
+fn address<'de, D>(deserializer: D) -> std::result::Result<String, D::Error>
+where D: serde::Deserializer<'de> {
+    let address = String::deserialize(deserializer)?;
+
+    bridge::check_address(&address)
+        .map(|_| address)
+        .map_err(serde::de::Error::custom)
+}
+
+impl Settings {
+    pub fn build(self: Self, name: &str) -> HueProvider {
+        HueProvider::new(name, &self.address, &self.username)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider() -> HueProvider {
+        HueProvider::new("hue", "127.0.0.1:9", "user")
+    }
+
+    #[test]
+    fn transitiontime() {
+        assert_eq!(to_transitiontime(Duration::from_millis(250)), 2);
+        assert_eq!(to_transitiontime(Duration::from_secs(2)), 20);
+        assert_eq!(to_transitiontime(Duration::from_secs(86400)), u16::MAX);
+    }
+
+    #[test]
+    fn brightness_bounds() {
+        assert_eq!(*from_bri(1), 0.0);
+        assert_eq!(*from_bri(254), 1.0);
+        assert_eq!(*from_bri(0), 0.0);
+        assert_eq!(to_bri(&Brightness::new(0.0)), 1);
+        assert_eq!(to_bri(&Brightness::new(1.0)), 254);
+    }
+
+    #[test]
+    fn brightness_round_trip() {
+        for bri in 1..=254 {
+            assert_eq!(to_bri(&from_bri(bri)), bri);
+        }
+    }
+
+    #[test]
+    fn id_order() {
+        let mut ids = vec!["10", "2", "b", "1", "a"];
+        ids.sort_by_key(|id| id_key(id));
+
+        assert_eq!(ids, vec!["a", "b", "1", "2", "10"]);
+    }
+
+    #[test]
+    fn address() {
+        assert_eq!(Bridge::new("10.0.0.2").address(), "http://10.0.0.2");
+        assert_eq!(Bridge::new("http://hue.local/").address(),
+                   "http://hue.local");
+    }
+
+    #[test]
+    fn https_refused() {
+        let settings = |address: &str| {
+            serde_json::from_value::<Settings>(serde_json::json!({
+                "address": address,
+                "username": "user",
+            }))
+        };
+
+        assert!(settings("10.0.0.2").is_ok());
+        assert!(settings("http://hue.local").is_ok());
+        assert!(settings("https://hue.local").is_err());
+        assert!(matches!(HueProvider::pair("https://hue.local", "lighting"),
+                         Err(bridge::Error::Scheme(_))));
+    }
+
+    #[test]
+    fn update_switched_off() {
+        let mut light = Light::new("hue".to_string(), "1".to_string(),
+                                   vec![Capability::Power,
+                                        Capability::Brightness]);
+        light.set_power(false).expect("Capable");
+        light.set_brightness(Brightness::new(0.5)).expect("Capable");
+
+        assert_eq!(provider().update(&light).expect("Correct"),
+                   StateUpdate { on: Some(false), ..Default::default() });
+    }
+
+    #[test]
+    fn update_power_unknown() {
+        let mut light = Light::new("hue".to_string(), "1".to_string(),
+                                   vec![Capability::Brightness]);
+        light.set_brightness(Brightness::new(1.0)).expect("Capable");
+
+        assert_eq!(provider().update(&light).expect("Correct"),
+                   StateUpdate { bri: Some(254), ..Default::default() });
+    }
+
+    #[test]
+    fn update_unsupported_effect() {
+        let mut light = Light::new("hue".to_string(), "1".to_string(),
+                                   vec![Capability::Power, Capability::Mode]);
+        light.set_power(true).expect("Capable");
+        light.set_mode(Mode::new_empty("hue".to_string(), "strobe".to_string()))
+            .expect("Capable");
+
+        assert!(matches!(provider().update(&light),
+                         Err(Error { etype: provider::ErrorType::IncorrectState(..), .. })));
+    }
+}
@@ -0,0 +1,236 @@
+
+mod common;
+
+use common::*;
+
+use std::time::Duration;
+
+use serde_json::json;
+
+use domain::capabilities::Capability;
+use domain::color::Color;
+use domain::color::xy::XY;
+use domain::brightness::Brightness;
+use domain::mode::Mode;
+use provider::{Provider, ErrorType};
+use hue_provider::{HueProvider, bridge};
+
+fn setup() -> (StandIn, HueProvider) {
+    let stand_in = StandIn::start()
+        .with_light("1", color_light("Desk"))
+        .with_light("2", dimmable_light("Hall"))
+        .with_light("10", color_light("Porch"));
+    let provider = HueProvider::new("hue", stand_in.address(), USERNAME);
+
+    (stand_in, provider)
+}
+
+mod pairing {
+    use super::*;
+
+    #[test]
+    fn link_button_pressed() {
+        let stand_in = StandIn::start();
+        stand_in.press_link_button();
+
+        let username = HueProvider::pair(stand_in.address(), "lighting#test")
+            .expect("Paired");
+
+        assert_eq!(username, USERNAME);
+        assert_eq!(stand_in.requests()[0].body,
+                   json!({ "devicetype": "lighting#test" }));
+    }
+
+    #[test]
+    fn link_button_not_pressed() {
+        let stand_in = StandIn::start();
+        let result = HueProvider::pair(stand_in.address(), "lighting#test");
+
+        assert_eq!(result.expect_err("Must fail").kind(),
+                   Some(bridge::LINK_BUTTON));
+    }
+
+    #[test]
+    fn unreachable() {
+        let result = HueProvider::pair("127.0.0.1:1", "lighting#test");
+
+        assert!(matches!(result, Err(bridge::Error::Http(_))));
+    }
+}
+
+mod fetch {
+    use super::*;
+
+    #[test]
+    fn list() {
+        let (_stand_in, provider) = setup();
+        let lights = provider.list().expect("Listed");
+        let ids: Vec<&str> = lights.iter()
+            .map(|light| light.provider.id.as_str())
+            .collect();
+
+        assert_eq!(ids, vec!["1", "2", "10"]);
+        assert!(lights.iter().all(|light| light.provider.name == "hue"));
+    }
+
+    #[test]
+    fn color_light() {
+        let (_stand_in, provider) = setup();
+        let light = provider.get("1").expect("Exists");
+
+        assert!(light.get_power().expect("Set"));
+        assert_eq!(light.capabilities(), vec![Capability::Power,
+                                              Capability::Color,
+                                              Capability::Brightness,
+                                              Capability::Mode]);
+        assert_eq!(**light.get_brightness().expect("Set"), 1.0);
+        assert_eq!(light.get_mode().expect("Set").name, "none");
+
+        let xy = XY::from(light.get_color().expect("Set").clone());
+        assert!((xy.x - 0.4573).abs() < 1e-9);
+        assert!((xy.y - 0.41).abs() < 1e-9);
+    }
+
+    #[test]
+    fn dimmable_light() {
+        let (_stand_in, provider) = setup();
+        let light = provider.get("2").expect("Exists");
+
+        assert!(!light.get_power().expect("Set"));
+        assert_eq!(light.capabilities(), vec![Capability::Power,
+                                              Capability::Brightness]);
+        assert!((**light.get_brightness().expect("Set") - 127.0 / 253.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn not_found() {
+        let (_stand_in, provider) = setup();
+
+        assert!(matches!(provider.get("7").expect_err("Must fail").etype,
+                         ErrorType::NotFound(id) if id == "7"));
+    }
+
+    #[test]
+    fn unauthorized() {
+        let (stand_in, _) = setup();
+        let provider = HueProvider::new("hue", stand_in.address(), "stranger");
+
+        assert!(matches!(provider.list().expect_err("Must fail").etype,
+                         ErrorType::Internal(_)));
+    }
+}
+
+mod sync {
+    use super::*;
+
+    #[test]
+    fn state() {
+        let (stand_in, provider) = setup();
+        let mut light = provider.get("1").expect("Exists");
+        light.set_color(Color::from(XY::new(0.15, 0.06))).expect("Capable");
+        light.set_brightness(Brightness::new(0.5)).expect("Capable");
+        light.set_mode(Mode::new_empty("hue".to_string(),
+                                       "colorloop".to_string()))
+            .expect("Capable");
+
+        provider.sync(&light).expect("Synced");
+
+        let state = &stand_in.light("1")["state"];
+        assert_eq!(state["bri"], 128);
+        assert_eq!(state["effect"], "colorloop");
+        assert!((state["xy"][0].as_f64().unwrap() - 0.15).abs() < 1e-9);
+        assert!((state["xy"][1].as_f64().unwrap() - 0.06).abs() < 1e-9);
+
+        let request = stand_in.requests().pop().expect("Requested");
+        assert_eq!(request.method, "PUT");
+        assert_eq!(request.path, format!("/api/{}/lights/1/state", USERNAME));
+    }
+
+    #[test]
+    fn transition() {
+        let (stand_in, provider) = setup();
+        let mut light = provider.get("1").expect("Exists");
+        light.set_brightness(Brightness::new(0.5)).expect("Capable");
+
+        assert!(provider.supports_transition());
+        provider.sync_transition(&light, Duration::from_millis(1500))
+            .expect("Synced");
+
+        let request = stand_in.requests().pop().expect("Requested");
+        assert_eq!(request.body["transitiontime"], 15);
+        assert_eq!(request.body["bri"], 128);
+        assert_eq!(stand_in.light("1")["state"]["bri"], 128);
+    }
+
+    #[test]
+    fn round_trip() {
+        let (_stand_in, provider) = setup();
+        let mut light = provider.get("1").expect("Exists");
+        light.set_brightness(Brightness::new(0.25)).expect("Capable");
+
+        provider.sync(&light).expect("Synced");
+        let synced = provider.get("1").expect("Exists");
+
+        assert_eq!(**synced.get_brightness().expect("Set"), 63.0 / 253.0);
+    }
+
+    #[test]
+    fn switch_off() {
+        let (stand_in, provider) = setup();
+        let mut light = provider.get("1").expect("Exists");
+        light.set_power(false).expect("Capable");
+        light.set_brightness(Brightness::new(0.1)).expect("Capable");
+
+        provider.sync(&light).expect("Synced");
+
+        let request = stand_in.requests().pop().expect("Requested");
+        assert_eq!(request.body, json!({ "on": false }));
+        assert_eq!(stand_in.light("1")["state"]["bri"], 254);
+    }
+
+    #[test]
+    fn switch_on() {
+        let (stand_in, provider) = setup();
+        let mut light = provider.get("2").expect("Exists");
+        light.set_power(true).expect("Capable");
+
+        provider.sync(&light).expect("Synced");
+
+        assert_eq!(stand_in.light("2")["state"]["on"], true);
+    }
+
+    #[test]
+    fn unsupported_parameter() {
+        let (stand_in, provider) = setup();
+        let mut light = provider.get("1").expect("Exists");
+        light.provider.id = "2".to_string();
+
+        assert!(matches!(provider.sync(&light).expect_err("Must fail").etype,
+                         ErrorType::IncorrectState(..)));
+        // Bridge still applies supported parameters
+        assert_eq!(stand_in.light("2")["state"]["on"], true);
+        assert!(stand_in.light("2")["state"].get("xy").is_none());
+    }
+
+    #[test]
+    fn not_found() {
+        let (_stand_in, provider) = setup();
+        let mut light = provider.get("1").expect("Exists");
+        light.provider.id = "7".to_string();
+
+        assert!(matches!(provider.sync(&light).expect_err("Must fail").etype,
+                         ErrorType::NotFound(_)));
+    }
+
+    #[test]
+    fn foreign() {
+        let (stand_in, provider) = setup();
+        let mut light = provider.get("1").expect("Exists");
+        light.provider.name = "other".to_string();
+        let before = stand_in.requests().len();
+
+        assert!(matches!(provider.sync(&light).expect_err("Must fail").etype,
+                         ErrorType::ForeignLight(_)));
+        assert_eq!(stand_in.requests().len(), before);
+    }
+}
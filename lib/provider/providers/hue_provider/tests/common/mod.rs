@@ -0,0 +1,220 @@
+
+#![allow(dead_code)]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use serde_json::{json, Map, Value};
+
+pub const USERNAME: &str = "stand-in-user";
+
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub body: Value,
+}
+
+#[derive(Default)]
+struct State {
+    link_pressed: bool,
+    lights: Map<String, Value>,
+    requests: Vec<Request>,
+}
+
+// Mimics local REST API (v1) of the Hue bridge
+pub struct StandIn {
+    address: String,
+    state: Arc<Mutex<State>>,
+}
+
+impl StandIn {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Bound");
+        let address = listener.local_addr().expect("Has address").to_string();
+        let state = Arc::new(Mutex::new(State::default()));
+        let shared = state.clone();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => handle(stream, &shared),
+                    Err(_) => break,
+                }
+            }
+        });
+
+        Self {
+            address,
+            state,
+        }
+    }
+
+    pub fn address(self: &Self) -> &str {
+        &self.address
+    }
+
+    pub fn press_link_button(self: &Self) {
+        self.state.lock().unwrap().link_pressed = true;
+    }
+
+    pub fn with_light(self: Self, id: &str, light: Value) -> Self {
+        self.state.lock().unwrap().lights.insert(id.to_string(), light);
+        self
+    }
+
+    pub fn light(self: &Self, id: &str) -> Value {
+        self.state.lock().unwrap().lights[id].clone()
+    }
+
+    pub fn requests(self: &Self) -> Vec<Request> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+pub fn color_light(name: &str) -> Value {
+    json!({
+        "name": name,
+        "type": "Extended color light",
+        "modelid": "LCT015",
+        "state": {
+            "on": true,
+            "bri": 254,
+            "hue": 8418,
+            "sat": 140,
+            "effect": "none",
+            "xy": [0.4573, 0.41],
+            "ct": 366,
+            "alert": "select",
+            "colormode": "xy",
+            "reachable": true
+        }
+    })
+}
+
+pub fn dimmable_light(name: &str) -> Value {
+    json!({
+        "name": name,
+        "type": "Dimmable light",
+        "modelid": "LWB010",
+        "state": {
+            "on": false,
+            "bri": 128,
+            "alert": "select",
+            "reachable": true
+        }
+    })
+}
+
+fn error(kind: u64, address: &str, description: &str) -> Value {
+    json!([{
+        "error": {
+            "type": kind,
+            "address": address,
+            "description": description
+        }
+    }])
+}
+
+fn route(state: &mut State, method: &str, path: &str, body: &Value) -> Value {
+    let parts: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    match (method, parts.as_slice()) {
+        ("POST", ["api"]) => {
+            if state.link_pressed {
+                json!([{ "success": { "username": USERNAME } }])
+            } else {
+                error(101, "", "link button not pressed")
+            }
+        },
+        (_, ["api", user, ..]) if *user != USERNAME => {
+            error(1, "/", "unauthorized user")
+        },
+        ("GET", ["api", _, "lights"]) => Value::Object(state.lights.clone()),
+        ("GET", ["api", _, "lights", id]) => match state.lights.get(*id) {
+            Some(light) => light.clone(),
+            None => error(3, &format!("/lights/{}", id),
+                          &format!("resource, /lights/{}, not available", id)),
+        },
+        ("PUT", ["api", _, "lights", id, "state"]) => {
+            let Some(light) = state.lights.get_mut(*id) else {
+                return error(3, &format!("/lights/{}/state", id),
+                             &format!("resource, /lights/{}/state, not available", id));
+            };
+            let current = light["state"].as_object_mut().expect("Has state");
+            let on = body.get("on")
+                .and_then(Value::as_bool)
+                .unwrap_or(current["on"].as_bool().unwrap_or(false));
+            let mut out = Vec::new();
+
+            for (key, value) in body.as_object().expect("Object body") {
+                let address = format!("/lights/{}/state/{}", id, key);
+
+                if "transitiontime" == key {
+                    // Applies to the request itself, not kept in state
+                    out.push(json!({ "success": { address: value } }));
+                } else if !current.contains_key(key) {
+                    out.push(error(6, &address,
+                                   &format!("parameter, {}, not available", key))[0].clone());
+                } else if !on && "on" != key {
+                    out.push(error(201, &address,
+                                   &format!("parameter, {}, is not modifiable. \
+                                             Device is set to off.", key))[0].clone());
+                } else {
+                    current.insert(key.clone(), value.clone());
+                    out.push(json!({ "success": { address: value } }));
+                }
+            }
+
+            Value::Array(out)
+        },
+        _ => error(4, path, "method not available"),
+    }
+}
+
+fn handle(stream: TcpStream, state: &Arc<Mutex<State>>) {
+    let mut reader = BufReader::new(stream.try_clone().expect("Cloned"));
+    let mut line = String::new();
+    reader.read_line(&mut line).expect("Request line");
+
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+    let mut length = 0;
+
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header).expect("Header");
+        let header = header.trim_end();
+
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().expect("Correct length");
+            }
+        }
+    }
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).expect("Body");
+    let body = serde_json::from_slice(&body).unwrap_or(Value::Null);
+
+    let reply = {
+        let mut state = state.lock().unwrap();
+        let reply = route(&mut state, &method, &path, &body);
+        state.requests.push(Request { method, path, body });
+        reply
+    }.to_string();
+
+    let mut stream = stream;
+    write!(stream, "HTTP/1.1 200 OK\r\n\
+                    Content-Type: application/json\r\n\
+                    Content-Length: {}\r\n\
+                    Connection: close\r\n\r\n{}",
+           reply.len(), reply).expect("Replied");
+}
@@ -0,0 +1,625 @@
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use domain::light::{self, Light};
+use domain::capabilities::Capability;
+use domain::color::Color;
+use domain::brightness::Brightness;
+use domain::mode::Mode;
+use domain::mode::descriptor::ModeDescriptor;
+use domain::patch::LightPatch;
+use provider::{Provider, Error, Result};
+
+#[derive(Debug, Clone)]
+pub struct VirtualLight {
+    light: Light,
+    modes: Vec<String>, // Names of supported modes
+}
+
+#[derive(Debug, Clone)]
+pub enum Call {
+    List,
+    Get(String),
+    Sync(Light),
+    Transition(Light, Duration),
+    Apply(String, LightPatch),
+}
+
+pub struct MockProvider {
+    name: String,
+    lights: Mutex<Vec<VirtualLight>>,
+    calls: Mutex<Vec<Call>>,
+    counter: AtomicUsize,
+    fail_on: Option<usize>,
+    latency: Duration,
+    transitions: bool,
+    patches: bool,
+    catalog: Option<Vec<ModeDescriptor>>,
+    catalog_requests: AtomicUsize,
+}
+
+#[derive(Debug)]
+pub struct InjectedFault(pub usize);
+
+impl std::error::Error for InjectedFault {}
+
+impl std::fmt::Display for InjectedFault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Injected fault on call #{}", self.0)
+    }
+}
+
+impl VirtualLight {
+    pub fn new(provider: &str, id: &str, capabilities: Vec<Capability>) -> Self {
+        Self {
+            light: Light::new(provider.to_string(), id.to_string(), capabilities),
+            modes: Vec::new(),
+        }
+    }
+
+    pub fn with_modes(mut self: Self, modes: &[&str]) -> Self {
+        self.modes = modes.iter().map(|mode| mode.to_string()).collect();
+        self
+    }
+
+    pub fn with_state<F>(mut self: Self, f: F) -> Self
+    where F: FnOnce(&mut Light) -> light::Result<()> {
+        f(&mut self.light).expect("Virtual light state must match capabilities");
+        self
+    }
+
+    pub fn light(self: &Self) -> &Light {
+        &self.light
+    }
+
+    pub fn modes(self: &Self) -> &[String] {
+        &self.modes
+    }
+}
+
+impl MockProvider {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            lights: Mutex::new(Vec::new()),
+            calls: Mutex::new(Vec::new()),
+            counter: AtomicUsize::new(0),
+            fail_on: None,
+            latency: Duration::ZERO,
+            transitions: false,
+            patches: false,
+            catalog: None,
+            catalog_requests: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn with_light(self: Self, light: VirtualLight) -> Self {
+        assert_eq!(light.light.provider.name, self.name,
+                   "Virtual light must belong to the provider");
+
+        self.lights.lock().expect("Not poisoned").push(light);
+        self
+    }
+
+    // Calls are counted from 1, every kind of call is counted
+    pub fn fail_on(mut self: Self, call: usize) -> Self {
+        self.fail_on = Some(call);
+        self
+    }
+
+    pub fn latency(mut self: Self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    // Transitions are then taken as a whole instead of frame by frame
+    pub fn with_transitions(mut self: Self) -> Self {
+        self.transitions = true;
+        self
+    }
+
+    // Patches are then taken as they are instead of being merged into the
+    // fetched light and synced
+    pub fn with_patches(mut self: Self) -> Self {
+        self.patches = true;
+        self
+    }
+
+    // Advertised instead of bare names of modes supported by lights
+    pub fn with_catalog(mut self: Self, catalog: Vec<ModeDescriptor>) -> Self {
+        self.catalog = Some(catalog);
+        self
+    }
+
+    // Modes aren't counted as calls, so faults are injected the same way
+    // whether they are asked for or not
+    pub fn catalog_requests(self: &Self) -> usize {
+        self.catalog_requests.load(Ordering::SeqCst)
+    }
+
+    pub fn calls(self: &Self) -> Vec<Call> {
+        self.calls.lock().expect("Not poisoned").clone()
+    }
+
+    pub fn syncs(self: &Self) -> Vec<Light> {
+        self.calls.lock().expect("Not poisoned").iter()
+            .filter_map(|call| match call {
+                Call::Sync(light) => Some(light.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn transitions(self: &Self) -> Vec<(Light, Duration)> {
+        self.calls.lock().expect("Not poisoned").iter()
+            .filter_map(|call| match call {
+                Call::Transition(light, duration) => {
+                    Some((light.clone(), *duration))
+                },
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn patches(self: &Self) -> Vec<(String, LightPatch)> {
+        self.calls.lock().expect("Not poisoned").iter()
+            .filter_map(|call| match call {
+                Call::Apply(id, patch) => Some((id.clone(), patch.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn state(self: &Self, id: &str) -> Option<Light> {
+        self.lights.lock().expect("Not poisoned").iter()
+            .find(|item| item.light.provider.id == id)
+            .map(|item| item.light.clone())
+    }
+
+    fn call(self: &Self, call: Call) -> Result<()> {
+        let number = self.counter.fetch_add(1, Ordering::SeqCst) + 1;
+        self.calls.lock().expect("Not poisoned").push(call);
+
+        if !self.latency.is_zero() {
+            std::thread::sleep(self.latency);
+        }
+
+        if self.fail_on == Some(number) {
+            Error::internal(&self.name, Box::new(InjectedFault(number)))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn validate(self: &Self, stored: &VirtualLight, light: &Light) -> Result<()> {
+        let supported = stored.light.capabilities();
+
+        if let Some(capability) = light.capabilities().into_iter()
+            .find(|capability| !supported.contains(capability)) {
+            return Error::incorrect_state(
+                &self.name, light,
+                format!("Capability \"{}\" isn't supported", capability)
+            );
+        }
+
+        match light.get_mode() {
+            Ok(mode) if !stored.modes.contains(&mode.name) => {
+                Error::incorrect_state(
+                    &self.name, light,
+                    format!("Mode \"{}\" isn't supported", mode.name)
+                )
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn store(self: &Self, light: &Light) -> Result<()> {
+        if light.provider.name != self.name {
+            return Error::foreign_light(&self.name, light);
+        }
+
+        let mut lights = self.lights.lock().expect("Not poisoned");
+
+        match lights.iter_mut().find(|item| item.light.provider.id == light.provider.id) {
+            None => Error::not_found(&self.name, &light.provider.id),
+            Some(stored) => {
+                self.validate(stored, light)?;
+                apply(&mut stored.light, light);
+                Ok(())
+            },
+        }
+    }
+}
+
+fn apply(target: &mut Light, source: &Light) {
+    if let Ok(power) = source.get_power() {
+        let _ = target.set_power(power);
+    }
+
+    if let Ok(color) = source.get_color() {
+        let _ = target.set_color(color.clone());
+    }
+
+    if let Ok(brightness) = source.get_brightness() {
+        let _ = target.set_brightness(brightness.clone());
+    }
+
+    if let Ok(mode) = source.get_mode() {
+        let _ = target.set_mode(mode.clone());
+    }
+}
+
+impl Provider for MockProvider {
+    fn name(self: &Self) -> &str {
+        &self.name
+    }
+
+    fn list(self: &Self) -> Result<Vec<Light>> {
+        self.call(Call::List)?;
+
+        Ok(self.lights.lock().expect("Not poisoned").iter()
+           .map(|item| item.light.clone())
+           .collect())
+    }
+
+    fn get(self: &Self, id: &str) -> Result<Light> {
+        self.call(Call::Get(id.to_string()))?;
+
+        match self.state(id) {
+            Some(light) => Ok(light),
+            None => Error::not_found(&self.name, id),
+        }
+    }
+
+    fn sync(self: &Self, light: &Light) -> Result<()> {
+        self.call(Call::Sync(light.clone()))?;
+        self.store(light)
+    }
+
+    fn supports_transition(self: &Self) -> bool {
+        self.transitions
+    }
+
+    fn sync_transition(self: &Self, light: &Light,
+                       duration: Duration) -> Result<()> {
+        if !self.transitions {
+            return self.sync(light);
+        }
+
+        self.call(Call::Transition(light.clone(), duration))?;
+        self.store(light)
+    }
+
+    fn apply(self: &Self, id: &str, patch: &LightPatch) -> Result<()> {
+        if !self.patches {
+            return provider::merge_and_sync(self, id, patch);
+        }
+
+        self.call(Call::Apply(id.to_string(), patch.clone()))?;
+
+        let mut light = match self.state(id) {
+            Some(light) => light,
+            None => return Error::not_found(&self.name, id),
+        };
+
+        patch.apply(&mut light).or_else(|err| {
+            Error::incorrect_state(&self.name, &light, err.to_string())
+        })?;
+
+        self.store(&light)
+    }
+
+    // Not counted as a call, catalog is static
+    fn modes(self: &Self) -> Result<Vec<ModeDescriptor>> {
+        self.catalog_requests.fetch_add(1, Ordering::SeqCst);
+
+        if let Some(catalog) = &self.catalog {
+            return Ok(catalog.clone());
+        }
+
+        let mut names: Vec<String> = self.lights.lock().expect("Not poisoned")
+            .iter()
+            .flat_map(|item| item.modes.iter().cloned())
+            .collect();
+        names.sort();
+        names.dedup();
+
+        Ok(names.into_iter()
+           .map(|name| ModeDescriptor::new(&self.name, &name, ""))
+           .collect())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Settings {
+    #[serde(default)]
+    pub lights: Vec<LightSettings>,
+    pub fail_on: Option<usize>,
+    #[serde(default)]
+    pub latency_ms: u64,
+    #[serde(default)]
+    pub transitions: bool,
+    #[serde(default)]
+    pub patches: bool,
+    #[serde(default)]
+    pub catalog: Option<Vec<ModeDescriptor>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LightSettings {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    pub capabilities: Vec<Capability>,
+    pub power: Option<bool>,
+    pub color: Option<Color>,
+    pub brightness: Option<Brightness>,
+    pub mode: Option<String>,
+    #[serde(default)]
+    pub modes: Vec<String>,
+}
+
+impl Settings {
+    pub fn build(self: Self, name: &str) -> light::Result<MockProvider> {
+        let mut provider = MockProvider::new(name)
+            .latency(Duration::from_millis(self.latency_ms));
+        provider.fail_on = self.fail_on;
+        provider.transitions = self.transitions;
+        provider.patches = self.patches;
+        provider.catalog = self.catalog;
+
+        for settings in self.lights {
+            let mut light = Light::named(name.to_string(), settings.id,
+                                         settings.capabilities, settings.name);
+            if let Some(power) = settings.power {
+                light.set_power(power)?;
+            }
+
+            if let Some(color) = settings.color {
+                light.set_color(color)?;
+            }
+
+            if let Some(brightness) = settings.brightness {
+                light.set_brightness(brightness)?;
+            }
+
+            if let Some(mode) = settings.mode {
+                light.set_mode(Mode::new_empty(name.to_string(), mode))?;
+            }
+
+            provider = provider.with_light(VirtualLight {
+                light,
+                modes: settings.modes,
+            });
+        }
+
+        Ok(provider)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::color::rgb::RGB;
+    use provider::ErrorType;
+
+    fn provider() -> MockProvider {
+        MockProvider::new("mock")
+            .with_light(
+                VirtualLight::new("mock", "1", vec![Capability::Power,
+                                                    Capability::Color,
+                                                    Capability::Brightness,
+                                                    Capability::Mode])
+                    .with_modes(&["colorloop"])
+                    .with_state(|light| {
+                        light.set_brightness(Brightness::new(0.3))
+                    })
+            )
+            .with_light(VirtualLight::new("mock", "2",
+                                          vec![Capability::Brightness]))
+    }
+
+    #[test]
+    fn list() {
+        let lights = provider().list().expect("Listed");
+        let ids: Vec<&str> = lights.iter()
+            .map(|light| light.provider.id.as_str())
+            .collect();
+
+        assert_eq!(ids, vec!["1", "2"]);
+        assert!(lights.iter().all(|light| light.provider.name == "mock"));
+    }
+
+    #[test]
+    fn get() {
+        let light = provider().get("1").expect("Exists");
+
+        assert_eq!(**light.get_brightness().expect("Set"), 0.3);
+    }
+
+    #[test]
+    fn get_missing() {
+        assert!(matches!(provider().get("3"),
+                         Err(Error { etype: ErrorType::NotFound(_), .. })));
+    }
+
+    #[test]
+    fn sync() {
+        let provider = provider();
+        let mut light = provider.get("1").expect("Exists");
+        light.set_power(true).expect("Capable");
+        light.set_color(RGB::new(255, 0, 0).into()).expect("Capable");
+        light.set_mode(Mode::new_empty("mock".to_string(),
+                                       "colorloop".to_string()))
+            .expect("Capable");
+
+        provider.sync(&light).expect("Synced");
+
+        let state = provider.state("1").expect("Exists");
+        assert!(state.get_power().expect("Set"));
+        assert_eq!(RGB::from(state.get_color().expect("Set").clone()).red, 255);
+        assert_eq!(state.get_mode().expect("Set").name, "colorloop");
+        assert_eq!(provider.syncs().len(), 1);
+    }
+
+    #[test]
+    fn apply() {
+        let merged = provider();
+        let patched = provider().with_patches();
+        let patch = LightPatch::new().with_power(true);
+
+        merged.apply("1", &patch).expect("Applied");
+        patched.apply("1", &patch).expect("Applied");
+
+        for provider in [&merged, &patched] {
+            let state = provider.state("1").expect("Exists");
+            assert!(state.get_power().expect("Set"));
+            assert_eq!(**state.get_brightness().expect("Set"), 0.3);
+        }
+
+        assert_eq!(merged.syncs().len(), 1);
+        assert!(merged.patches().is_empty());
+        assert!(patched.syncs().is_empty());
+        assert_eq!(patched.patches(), vec![("1".to_string(), patch.clone())]);
+        assert!(patched.apply("2", &patch).is_err());
+    }
+
+    #[test]
+    fn transition() {
+        let duration = Duration::from_secs(1);
+        let mut light = provider().get("1").expect("Exists");
+        light.set_brightness(Brightness::new(0.8)).expect("Capable");
+
+        // Without support transition is a plain sync
+        let plain = provider();
+        plain.sync_transition(&light, duration).expect("Synced");
+        assert!(!plain.supports_transition());
+        assert_eq!(plain.syncs().len(), 1);
+        assert!(plain.transitions().is_empty());
+
+        let native = provider().with_transitions();
+        native.sync_transition(&light, duration).expect("Synced");
+        assert!(native.supports_transition());
+        assert!(native.syncs().is_empty());
+        assert_eq!(native.transitions()[0].1, duration);
+        assert_eq!(**native.state("1").expect("Exists").get_brightness()
+                   .expect("Set"), 0.8);
+    }
+
+    #[test]
+    fn sync_foreign() {
+        let provider = provider();
+        let light = Light::new("other".to_string(), "1".to_string(),
+                               vec![Capability::Brightness]);
+
+        assert!(matches!(provider.sync(&light),
+                         Err(Error { etype: ErrorType::ForeignLight(_), .. })));
+    }
+
+    #[test]
+    fn sync_unsupported_capability() {
+        let provider = provider();
+        let light = Light::new("mock".to_string(), "2".to_string(),
+                               vec![Capability::Brightness, Capability::Color]);
+
+        assert!(matches!(provider.sync(&light),
+                         Err(Error { etype: ErrorType::IncorrectState(..), .. })));
+    }
+
+    #[test]
+    fn sync_unsupported_mode() {
+        let provider = provider();
+        let mut light = provider.get("1").expect("Exists");
+        light.set_mode(Mode::new_empty("mock".to_string(), "strobe".to_string()))
+            .expect("Capable");
+
+        assert!(matches!(provider.sync(&light),
+                         Err(Error { etype: ErrorType::IncorrectState(..), .. })));
+        assert!(provider.state("1").expect("Exists").get_mode().is_err());
+    }
+
+    #[test]
+    fn records_calls() {
+        let provider = provider();
+        let _ = provider.list();
+        let _ = provider.get("2");
+
+        assert!(matches!(provider.calls().as_slice(),
+                         [Call::List, Call::Get(id)] if id == "2"));
+    }
+
+    #[test]
+    fn fail_on() {
+        let provider = provider().fail_on(2);
+
+        assert!(provider.list().is_ok());
+        assert!(matches!(provider.list(),
+                         Err(Error { etype: ErrorType::Internal(_), .. })));
+        assert!(provider.list().is_ok());
+    }
+
+    #[test]
+    fn modes() {
+        let names: Vec<String> = provider().modes().expect("Listed")
+            .into_iter()
+            .map(|mode| mode.name)
+            .collect();
+        assert_eq!(names, vec!["colorloop"]);
+
+        let catalog = vec![ModeDescriptor::new("mock", "pulse", "Pulses")];
+        assert_eq!(provider().with_catalog(catalog.clone()).modes()
+                   .expect("Listed"), catalog);
+    }
+
+    #[test]
+    fn latency() {
+        let provider = provider().latency(Duration::from_millis(20));
+        let start = std::time::Instant::now();
+        let _ = provider.list();
+
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn settings() {
+        let settings: Settings = serde_json::from_str(r#"{
+            "fail_on": 3,
+            "lights": [{
+                "id": "1",
+                "name": "desk",
+                "capabilities": ["Power", "Brightness", "Mode"],
+                "power": true,
+                "brightness": 0.5,
+                "mode": "colorloop",
+                "modes": ["colorloop"]
+            }]
+        }"#).expect("Correct settings");
+
+        let provider = settings.build("virtual").expect("Built");
+        let light = provider.get("1").expect("Exists");
+
+        assert_eq!(provider.name(), "virtual");
+        assert_eq!(light.name, "desk");
+        assert!(light.get_power().expect("Set"));
+        assert_eq!(light.get_mode().expect("Set").provider, "virtual");
+    }
+
+    #[test]
+    fn settings_incapable() {
+        let settings: Settings = serde_json::from_str(r#"{
+            "lights": [{
+                "id": "1",
+                "capabilities": ["Color"],
+                "brightness": 0.5
+            }]
+        }"#).expect("Correct settings");
+
+        assert!(settings.build("mock").is_err());
+    }
+}
@@ -0,0 +1,355 @@
+
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use domain::light::{self, Light};
+use domain::capabilities::Capability;
+use domain::color::Color;
+use domain::color::xy;
+use domain::brightness::Brightness;
+use domain::mode::Mode;
+use domain::mode::descriptor::ModeDescriptor;
+use domain::patch::LightPatch;
+use provider::{Provider, Error, Result};
+
+pub mod transport;
+pub mod zigbee2mqtt;
+
+use transport::{Transport, MqttTransport};
+use zigbee2mqtt::{Device, LightInfo, State, Set};
+
+const BRIGHTNESS_MAX: f64 = 254.0;
+
+pub struct MqttProvider {
+    name: String,
+    base: String,
+    timeout: Duration,
+    transport: Box<dyn Transport>,
+    // Device list as of the last fetch, refreshed by listing or when a
+    // light isn't in it
+    devices: Mutex<Option<Vec<LightInfo>>>,
+}
+
+impl MqttProvider {
+    pub fn new(name: &str, base: &str, timeout: Duration,
+               transport: Box<dyn Transport>) -> Self {
+        Self {
+            name: name.to_string(),
+            base: base.trim_end_matches('/').to_string(),
+            timeout,
+            transport,
+            devices: Mutex::new(None),
+        }
+    }
+
+    fn topic(self: &Self, path: &str) -> String {
+        format!("{}/{}", self.base, path)
+    }
+
+    fn internal<T>(self: &Self, err: Box<dyn std::error::Error + Send + Sync>) -> Result<T> {
+        Error::internal(&self.name, err)
+    }
+
+    fn fetch<T: serde::de::DeserializeOwned>(self: &Self, topic: &str) -> Result<Option<T>> {
+        let payload = match self.transport.fetch(topic, self.timeout) {
+            Ok(Some(payload)) => payload,
+            Ok(None) => return Ok(None),
+            Err(err) => return self.internal(err),
+        };
+
+        match serde_json::from_slice(&payload) {
+            Ok(value) => Ok(Some(value)),
+            Err(err) => self.internal(Box::new(err)),
+        }
+    }
+
+    fn publish(self: &Self, topic: &str, payload: &serde_json::Value) -> Result<()> {
+        match self.transport.publish(topic, payload.to_string().as_bytes()) {
+            Ok(_) => Ok(()),
+            Err(err) => self.internal(err),
+        }
+    }
+
+    // Zigbee2MQTT retains device list
+    fn devices(self: &Self) -> Result<Vec<LightInfo>> {
+        let topic = self.topic("bridge/devices");
+
+        let devices: Vec<LightInfo> = match self.fetch::<Vec<Device>>(&topic)? {
+            Some(devices) => devices.iter().filter_map(Device::light).collect(),
+            None => {
+                return self.internal(Box::new(transport::Error::NoMessage(topic)));
+            },
+        };
+
+        *self.devices.lock().unwrap_or_else(PoisonError::into_inner) =
+            Some(devices.clone());
+
+        Ok(devices)
+    }
+
+    fn cached_devices(self: &Self) -> Result<Vec<LightInfo>> {
+        let cached = self.devices.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+
+        match cached {
+            Some(devices) => Ok(devices),
+            None => self.devices(),
+        }
+    }
+
+    // Newly paired devices are picked up by refetching the list
+    fn device(self: &Self, id: &str) -> Result<LightInfo> {
+        let find = |devices: Vec<LightInfo>| {
+            devices.into_iter().find(|info| info.id == id)
+        };
+
+        if let Some(info) = find(self.cached_devices()?) {
+            return Ok(info);
+        }
+
+        match find(self.devices()?) {
+            Some(info) => Ok(info),
+            None => Error::not_found(&self.name, id),
+        }
+    }
+
+    // Retained state is used if there is one, otherwise device is asked
+    // to report it
+    fn state(self: &Self, id: &str) -> Result<State> {
+        let topic = self.topic(id);
+        let request = serde_json::json!({ "state": "" }).to_string();
+
+        let payload = match self.transport.request(
+            &topic, &self.topic(&format!("{}/get", id)), request.as_bytes(),
+            self.timeout
+        ) {
+            Ok(Some(payload)) => payload,
+            Ok(None) => {
+                return self.internal(Box::new(transport::Error::NoMessage(topic)));
+            },
+            Err(err) => return self.internal(err),
+        };
+
+        match serde_json::from_slice(&payload) {
+            Ok(state) => Ok(state),
+            Err(err) => self.internal(Box::new(err)),
+        }
+    }
+
+    fn convert(self: &Self, info: &LightInfo, state: State) -> light::Result<Light> {
+        let mut capabilities = Vec::new();
+
+        if info.power {
+            capabilities.push(Capability::Power);
+        }
+
+        if info.color {
+            capabilities.push(Capability::Color);
+        }
+
+        if info.brightness {
+            capabilities.push(Capability::Brightness);
+        }
+
+        if !info.effects.is_empty() {
+            capabilities.push(Capability::Mode);
+        }
+
+        let mut light = Light::new(self.name.clone(), info.id.clone(),
+                                   capabilities);
+
+        if let (true, Some(power)) = (info.power, state.power()) {
+            light.set_power(power)?;
+        }
+
+        if let (true, Some(color)) = (info.color, state.color) {
+            light.set_color(Color::from(xy::XY::new(color.x, color.y)))?;
+        }
+
+        if let (true, Some(brightness)) = (info.brightness, state.brightness) {
+            light.set_brightness(Brightness::new(brightness as f64 / BRIGHTNESS_MAX))?;
+        }
+
+        // Effects are momentary, so they are reported only by some devices
+        if let Some(effect) = state.effect.filter(|effect| info.effects.contains(effect)) {
+            light.set_mode(Mode::new_empty(self.name.clone(), effect))?;
+        }
+
+        Ok(light)
+    }
+
+    fn get_light(self: &Self, info: &LightInfo) -> Result<Light> {
+        let state = self.state(&info.id)?;
+
+        match self.convert(info, state) {
+            Ok(light) => Ok(light),
+            Err(err) => self.internal(Box::new(err)),
+        }
+    }
+
+    pub fn payload(self: &Self, info: &LightInfo, light: &Light) -> Result<Set> {
+        let supported = light.capabilities().into_iter().all(|capability| {
+            match capability {
+                Capability::Power => info.power,
+                Capability::Color => info.color,
+                Capability::Brightness => info.brightness,
+                Capability::Mode => !info.effects.is_empty(),
+            }
+        });
+
+        if !supported {
+            return Error::incorrect_state(&self.name, light,
+                "Light has capabilities the device doesn't expose".to_string());
+        }
+
+        let power = light.get_power().ok();
+
+        // Changing anything else would switch the light on
+        if Some(false) == power {
+            return Ok(Set {
+                state: Some(zigbee2mqtt::OFF),
+                brightness: None,
+                color: None,
+                effect: None,
+                transition: None,
+            });
+        }
+
+        let effect = match light.get_mode() {
+            Ok(mode) if info.effects.contains(&mode.name) => Some(mode.name.clone()),
+            Ok(mode) => {
+                return Error::incorrect_state(&self.name, light,
+                    format!("Unsupported effect \"{}\"", mode.name));
+            },
+            Err(_) => None,
+        };
+
+        Ok(Set {
+            state: power.map(|_| zigbee2mqtt::ON),
+            brightness: light.get_brightness().ok().map(|brightness| {
+                (**brightness * BRIGHTNESS_MAX).round() as u8
+            }),
+            color: light.get_color().ok().map(|color| {
+                let xy = xy::XY::from(color.clone());
+                zigbee2mqtt::XY { x: xy.x, y: xy.y }
+            }),
+            effect,
+            transition: None,
+        })
+    }
+
+    fn send(self: &Self, light: &Light,
+            transition: Option<Duration>) -> Result<()> {
+        if light.provider.name != self.name {
+            return Error::foreign_light(&self.name, light);
+        }
+
+        let info = self.device(&light.provider.id)?;
+        let mut set = self.payload(&info, light)?;
+        set.transition = transition.map(|duration| duration.as_secs_f64());
+
+        let payload = match serde_json::to_value(set) {
+            Ok(payload) => payload,
+            Err(err) => return self.internal(Box::new(err)),
+        };
+
+        self.publish(&self.topic(&format!("{}/set", info.id)), &payload)
+    }
+}
+
+impl Provider for MqttProvider {
+    fn name(self: &Self) -> &str {
+        &self.name
+    }
+
+    fn list(self: &Self) -> Result<Vec<Light>> {
+        self.devices()?.iter()
+            .map(|info| self.get_light(info))
+            .collect()
+    }
+
+    fn get(self: &Self, id: &str) -> Result<Light> {
+        self.get_light(&self.device(id)?)
+    }
+
+    fn sync(self: &Self, light: &Light) -> Result<()> {
+        self.send(light, None)
+    }
+
+    fn supports_transition(self: &Self) -> bool {
+        true
+    }
+
+    fn sync_transition(self: &Self, light: &Light,
+                       duration: Duration) -> Result<()> {
+        self.send(light, Some(duration))
+    }
+
+    fn apply(self: &Self, id: &str, patch: &LightPatch) -> Result<()> {
+        provider::apply_partial(self, id, patch)
+    }
+
+    // Effects are exposed per device, so the catalog joins all of them
+    fn modes(self: &Self) -> Result<Vec<ModeDescriptor>> {
+        let mut effects: Vec<String> = self.cached_devices()?.into_iter()
+            .flat_map(|info| info.effects)
+            .collect();
+        effects.sort();
+        effects.dedup();
+
+        Ok(effects.iter()
+           .map(|effect| ModeDescriptor::new(&self.name, effect,
+                                             "Zigbee2MQTT effect"))
+           .collect())
+    }
+}
+
+fn default_port() -> u16 {
+    1883
+}
+
+fn default_base() -> String {
+    "zigbee2mqtt".to_string()
+}
+
+fn default_timeout() -> u64 {
+    1000
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Settings {
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default = "default_base")]
+    pub base_topic: String,
+    #[serde(default)]
+    pub client_id: Option<String>, // "lighting-<provider name>" by default
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default = "default_timeout")]
+    pub timeout_ms: u64,
+}
+
+impl Settings {
+    pub fn build(self: Self, name: &str) -> MqttProvider {
+        let client_id = self.client_id.unwrap_or_else(|| format!("lighting-{}", name));
+        let mut options = rumqttc::MqttOptions::new(client_id, self.host, self.port);
+        options.set_keep_alive(Duration::from_secs(30));
+
+        if let Some(username) = self.username {
+            options.set_credentials(username, self.password.unwrap_or_default());
+        }
+
+        let timeout = Duration::from_millis(self.timeout_ms);
+
+        MqttProvider::new(name, &self.base_topic, timeout,
+                          Box::new(MqttTransport::new(options, timeout)))
+    }
+}
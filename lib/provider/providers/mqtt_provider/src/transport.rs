@@ -0,0 +1,153 @@
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use rumqttc::{Client, Connection, Event, MqttOptions, Packet, QoS};
+
+pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+// Minimal broker access needed by the provider, so tests can replace
+// the broker with a stand-in
+pub trait Transport: Send + Sync {
+    fn publish(self: &Self, topic: &str, payload: &[u8]) -> Result<()>;
+    // First message on the topic (retained one if there is), None if
+    // nothing arrived in time
+    fn fetch(self: &Self, topic: &str, timeout: Duration) -> Result<Option<Vec<u8>>>;
+    // Retained message on the topic, otherwise the request is published and
+    // the reply is awaited on the same subscription, so it can't be missed
+    fn request(self: &Self, topic: &str, request: &str, payload: &[u8],
+               timeout: Duration) -> Result<Option<Vec<u8>>>;
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Client(rumqttc::ClientError),
+    Connection(rumqttc::ConnectionError),
+    Timeout,
+    NoMessage(String),
+    Disconnected,
+}
+
+pub struct MqttTransport {
+    client: Client,
+    connection: Mutex<Connection>,
+    timeout: Duration,
+}
+
+impl MqttTransport {
+    pub fn new(options: MqttOptions, timeout: Duration) -> Self {
+        let (client, connection) = Client::new(options, 16);
+
+        Self {
+            client,
+            connection: Mutex::new(connection),
+            timeout,
+        }
+    }
+
+    // Drives event loop until predicate picks an event or time runs out
+    fn poll<T, F>(self: &Self, timeout: Duration, mut f: F) -> Result<Option<T>>
+    where F: FnMut(Event) -> Option<T> {
+        let deadline = Instant::now() + timeout;
+        let mut connection = self.connection.lock()
+            .map_err(|_| Box::new(Error::Disconnected))?;
+
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+
+            if left.is_zero() {
+                return Ok(None);
+            }
+
+            match connection.recv_timeout(left) {
+                Ok(Ok(event)) => if let Some(out) = f(event) {
+                    return Ok(Some(out));
+                },
+                Ok(Err(err)) => return Err(Box::new(Error::Connection(err))),
+                Err(rumqttc::RecvTimeoutError::Timeout) => return Ok(None),
+                Err(rumqttc::RecvTimeoutError::Disconnected) => {
+                    return Err(Box::new(Error::Disconnected));
+                },
+            }
+        }
+    }
+}
+
+impl MqttTransport {
+    fn receive(self: &Self, topic: &str, timeout: Duration) -> Result<Option<Vec<u8>>> {
+        self.poll(timeout, |event| match event {
+            Event::Incoming(Packet::Publish(publish)) if publish.topic == topic => {
+                Some(publish.payload.to_vec())
+            },
+            _ => None,
+        })
+    }
+}
+
+impl Transport for MqttTransport {
+    fn publish(self: &Self, topic: &str, payload: &[u8]) -> Result<()> {
+        // Acknowledged, so broker has the message once sync returns
+        self.client.publish(topic, QoS::AtLeastOnce, false, payload)
+            .map_err(Error::Client)?;
+
+        let sent = self.poll(self.timeout, |event| match event {
+            Event::Incoming(Packet::PubAck(_)) => Some(()),
+            _ => None,
+        })?;
+
+        sent.ok_or_else(|| Box::new(Error::Timeout) as Box<dyn std::error::Error + Send + Sync>)
+    }
+
+    fn fetch(self: &Self, topic: &str, timeout: Duration) -> Result<Option<Vec<u8>>> {
+        self.client.subscribe(topic, QoS::AtMostOnce).map_err(Error::Client)?;
+
+        let out = self.receive(topic, timeout);
+
+        self.client.unsubscribe(topic).map_err(Error::Client)?;
+
+        out
+    }
+
+    fn request(self: &Self, topic: &str, request: &str, payload: &[u8],
+               timeout: Duration) -> Result<Option<Vec<u8>>> {
+        self.client.subscribe(topic, QoS::AtMostOnce).map_err(Error::Client)?;
+
+        // Reply stands for the acknowledgement, so it isn't awaited
+        let out = self.receive(topic, timeout).and_then(|retained| match retained {
+            Some(payload) => Ok(Some(payload)),
+            None => {
+                self.client.publish(request, QoS::AtLeastOnce, false, payload)
+                    .map_err(Error::Client)?;
+                self.receive(topic, timeout)
+            },
+        });
+
+        self.client.unsubscribe(topic).map_err(Error::Client)?;
+
+        out
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Client(err) => Some(err),
+            Self::Connection(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Client(err) => write!(f, "Can't queue request: {}", err),
+            Self::Connection(err) => write!(f, "Broker connection failed: {}", err),
+            Self::Timeout => write!(f, "Broker didn't respond in time"),
+            Self::NoMessage(topic) => {
+                write!(f, "Nothing was received on \"{}\"", topic)
+            },
+            Self::Disconnected => write!(f, "Broker connection is closed"),
+        }
+    }
+}
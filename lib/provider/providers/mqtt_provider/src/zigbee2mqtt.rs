@@ -0,0 +1,167 @@
+
+use serde::{Serialize, Deserialize};
+
+// Payload of "<base>/bridge/devices"
+#[derive(Debug, Clone, Deserialize)]
+pub struct Device {
+    pub friendly_name: String,
+    #[serde(default)]
+    pub definition: Option<Definition>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Definition {
+    #[serde(default)]
+    pub exposes: Vec<Expose>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Expose {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub property: Option<String>,
+    #[serde(default)]
+    pub features: Vec<Expose>,
+    #[serde(default)]
+    pub values: Vec<String>,
+}
+
+// Light as it is described by its exposes
+#[derive(Debug, Clone, PartialEq)]
+pub struct LightInfo {
+    pub id: String,
+    pub power: bool,
+    pub color: bool,
+    pub brightness: bool,
+    pub effects: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct XY {
+    pub x: f64,
+    pub y: f64,
+}
+
+// Payload of "<base>/<device>"
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct State {
+    #[serde(default)]
+    pub state: Option<String>,
+    #[serde(default)]
+    pub brightness: Option<u8>,
+    #[serde(default)]
+    pub color: Option<XY>,
+    #[serde(default)]
+    pub effect: Option<String>,
+}
+
+// Payload of "<base>/<device>/set"
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Set {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brightness: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<XY>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effect: Option<String>,
+    // In seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transition: Option<f64>,
+}
+
+pub const ON: &str = "ON";
+pub const OFF: &str = "OFF";
+
+impl Expose {
+    fn is(self: &Self, name: &str) -> bool {
+        self.name.as_deref() == Some(name) || self.property.as_deref() == Some(name)
+    }
+}
+
+impl Device {
+    // None for anything but lights
+    pub fn light(self: &Self) -> Option<LightInfo> {
+        let exposes = &self.definition.as_ref()?.exposes;
+        let features = &exposes.iter().find(|item| "light" == item.kind)?.features;
+
+        Some(LightInfo {
+            id: self.friendly_name.clone(),
+            power: features.iter().any(|item| item.is("state")),
+            color: features.iter().any(|item| item.is("color_xy")),
+            brightness: features.iter().any(|item| item.is("brightness")),
+            effects: exposes.iter()
+                .find(|item| "enum" == item.kind && item.is("effect"))
+                .map(|item| item.values.clone())
+                .unwrap_or_default(),
+        })
+    }
+}
+
+impl State {
+    pub fn power(self: &Self) -> Option<bool> {
+        self.state.as_deref().map(|state| ON == state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lights() {
+        let devices: Vec<Device> = serde_json::from_str(r#"[
+            { "friendly_name": "Coordinator", "type": "Coordinator" },
+            {
+                "friendly_name": "hall/sensor",
+                "definition": { "exposes": [
+                    { "type": "numeric", "name": "temperature", "property": "temperature" }
+                ]}
+            },
+            {
+                "friendly_name": "desk",
+                "definition": { "exposes": [
+                    { "type": "light", "features": [
+                        { "type": "binary", "name": "state", "property": "state" },
+                        { "type": "numeric", "name": "brightness", "property": "brightness" },
+                        { "type": "composite", "name": "color_xy", "property": "color" }
+                    ]},
+                    { "type": "enum", "name": "effect", "property": "effect",
+                      "values": ["blink", "breathe"] }
+                ]}
+            }
+        ]"#).expect("Correct devices");
+
+        let lights: Vec<LightInfo> = devices.iter().filter_map(Device::light).collect();
+
+        assert_eq!(lights, vec![LightInfo {
+            id: "desk".to_string(),
+            power: true,
+            color: true,
+            brightness: true,
+            effects: vec!["blink".to_string(), "breathe".to_string()],
+        }]);
+    }
+
+    #[test]
+    fn set_payload() {
+        let set = Set {
+            state: Some(ON),
+            brightness: Some(254),
+            color: Some(XY { x: 0.3, y: 0.3 }),
+            effect: None,
+            transition: None,
+        };
+
+        assert_eq!(serde_json::to_value(&set).expect("Serialized"),
+                   serde_json::json!({
+                       "state": "ON",
+                       "brightness": 254,
+                       "color": { "x": 0.3, "y": 0.3 },
+                   }));
+    }
+}
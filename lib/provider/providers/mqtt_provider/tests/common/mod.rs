@@ -0,0 +1,389 @@
+
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use mqtt_provider::transport::{self, Transport};
+
+pub const BASE: &str = "zigbee2mqtt";
+
+#[derive(Debug)]
+pub struct Down;
+
+impl std::error::Error for Down {}
+
+impl std::fmt::Display for Down {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Broker is down")
+    }
+}
+
+#[derive(Default)]
+struct State {
+    down: bool,
+    retained: HashMap<String, Vec<u8>>,
+    // Replies of devices which don't retain their state
+    pending: HashMap<String, Vec<u8>>,
+    silent: HashMap<String, Value>,
+    published: Vec<(String, Value)>,
+    fetched: Vec<String>,
+}
+
+// Broker with Zigbee2MQTT behind it
+#[derive(Clone, Default)]
+pub struct Broker {
+    state: Arc<Mutex<State>>,
+}
+
+impl State {
+    fn publish(self: &mut Self, topic: &str, payload: &[u8]) {
+        let payload: Value = serde_json::from_slice(payload).unwrap_or(Value::Null);
+        self.published.push((topic.to_string(), payload.clone()));
+
+        let Some(path) = topic.strip_prefix(&format!("{}/", BASE)) else {
+            return;
+        };
+
+        if let Some(device) = path.strip_suffix("/set") {
+            let state_topic = format!("{}/{}", BASE, device);
+
+            if let Some(state) = self.silent.get_mut(device) {
+                merge(state, &payload);
+            } else if let Some(retained) = self.retained.get(&state_topic) {
+                let mut state: Value = serde_json::from_slice(retained)
+                    .expect("JSON state");
+                merge(&mut state, &payload);
+                self.retained.insert(state_topic, state.to_string().into_bytes());
+            }
+        } else if let Some(device) = path.strip_suffix("/get") {
+            if let Some(state) = self.silent.get(device) {
+                self.pending.insert(format!("{}/{}", BASE, device),
+                                    state.to_string().into_bytes());
+            }
+        }
+    }
+
+    fn take(self: &mut Self, topic: &str) -> Option<Vec<u8>> {
+        self.retained.get(topic).cloned().or_else(|| self.pending.remove(topic))
+    }
+}
+
+fn merge(state: &mut Value, update: &Value) {
+    if let (Some(state), Some(update)) = (state.as_object_mut(), update.as_object()) {
+        for (key, value) in update {
+            state.insert(key.clone(), value.clone());
+        }
+    }
+}
+
+impl Broker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_devices(self: Self, devices: Value) -> Self {
+        self.retain("bridge/devices", devices);
+        self
+    }
+
+    pub fn retain(self: &Self, path: &str, payload: Value) {
+        self.state.lock().unwrap().retained
+            .insert(format!("{}/{}", BASE, path), payload.to_string().into_bytes());
+    }
+
+    // Device answers only to "<device>/get"
+    pub fn silent(self: &Self, device: &str, state: Value) {
+        let mut inner = self.state.lock().unwrap();
+        inner.retained.remove(&format!("{}/{}", BASE, device));
+        inner.silent.insert(device.to_string(), state);
+    }
+
+    pub fn state(self: &Self, device: &str) -> Value {
+        let state = self.state.lock().unwrap();
+
+        match state.silent.get(device) {
+            Some(value) => value.clone(),
+            None => serde_json::from_slice(
+                &state.retained[&format!("{}/{}", BASE, device)]
+            ).expect("JSON state"),
+        }
+    }
+
+    pub fn published(self: &Self) -> Vec<(String, Value)> {
+        self.state.lock().unwrap().published.clone()
+    }
+
+    pub fn fetched(self: &Self) -> Vec<String> {
+        self.state.lock().unwrap().fetched.clone()
+    }
+
+    pub fn shutdown(self: &Self) {
+        self.state.lock().unwrap().down = true;
+    }
+
+    // Same broker behind MQTT 3.1.1 on a loopback socket
+    pub fn listen(self: &Self) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Bound");
+        let port = listener.local_addr().expect("Has address").port();
+        let broker = self.clone();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    break;
+                };
+                let broker = broker.clone();
+                thread::spawn(move || serve(stream, &broker));
+            }
+        });
+
+        port
+    }
+}
+
+impl Transport for Broker {
+    fn publish(self: &Self, topic: &str, payload: &[u8]) -> transport::Result<()> {
+        let mut state = self.state.lock().unwrap();
+
+        if state.down {
+            return Err(Box::new(Down));
+        }
+
+        state.publish(topic, payload);
+        Ok(())
+    }
+
+    fn fetch(self: &Self, topic: &str,
+             _timeout: Duration) -> transport::Result<Option<Vec<u8>>> {
+        let mut state = self.state.lock().unwrap();
+
+        if state.down {
+            return Err(Box::new(Down));
+        }
+
+        state.fetched.push(topic.to_string());
+        Ok(state.take(topic))
+    }
+
+    fn request(self: &Self, topic: &str, request: &str, payload: &[u8],
+               _timeout: Duration) -> transport::Result<Option<Vec<u8>>> {
+        let mut state = self.state.lock().unwrap();
+
+        if state.down {
+            return Err(Box::new(Down));
+        }
+
+        state.fetched.push(topic.to_string());
+
+        if let Some(retained) = state.retained.get(topic) {
+            return Ok(Some(retained.clone()));
+        }
+
+        state.publish(request, payload);
+        Ok(state.pending.remove(topic))
+    }
+}
+
+fn deliver(stream: &mut TcpStream, topic: &str, payload: &[u8]) -> Option<()> {
+    let mut publish = (topic.len() as u16).to_be_bytes().to_vec();
+    publish.extend_from_slice(topic.as_bytes());
+    publish.extend_from_slice(payload);
+
+    write_packet(stream, 0x30, &publish)
+}
+
+fn topics(body: &[u8], options: bool) -> Vec<String> {
+    let mut at = 2;
+    let mut topics = Vec::new();
+
+    while at < body.len() {
+        let (topic, next) = string(body, at);
+        topics.push(topic);
+        at = if options { next + 1 } else { next };
+    }
+
+    topics
+}
+
+fn read_packet(stream: &mut TcpStream) -> Option<(u8, Vec<u8>)> {
+    let mut header = [0u8; 1];
+    stream.read_exact(&mut header).ok()?;
+
+    let (mut length, mut shift) = (0usize, 0);
+
+    loop {
+        let mut byte = [0u8; 1];
+        stream.read_exact(&mut byte).ok()?;
+        length += ((byte[0] & 0x7F) as usize) << shift;
+        shift += 7;
+
+        if 0 == byte[0] & 0x80 {
+            break;
+        }
+    }
+
+    let mut body = vec![0u8; length];
+    stream.read_exact(&mut body).ok()?;
+
+    Some((header[0], body))
+}
+
+fn write_packet(stream: &mut TcpStream, header: u8, body: &[u8]) -> Option<()> {
+    let mut out = vec![header];
+    let mut length = body.len();
+
+    loop {
+        let mut byte = (length % 128) as u8;
+        length /= 128;
+
+        if 0 < length {
+            byte |= 0x80;
+        }
+
+        out.push(byte);
+
+        if 0 == length {
+            break;
+        }
+    }
+
+    out.extend_from_slice(body);
+    stream.write_all(&out).ok()
+}
+
+fn string(body: &[u8], at: usize) -> (String, usize) {
+    let length = u16::from_be_bytes([body[at], body[at + 1]]) as usize;
+    let value = String::from_utf8_lossy(&body[at + 2..at + 2 + length]).to_string();
+
+    (value, at + 2 + length)
+}
+
+// Replies of devices which don't retain their state reach only the topics
+// subscribed at the moment, like any other message that isn't retained
+fn serve(mut stream: TcpStream, broker: &Broker) {
+    let mut subscribed: Vec<String> = Vec::new();
+
+    while let Some((header, body)) = read_packet(&mut stream) {
+        let done = match header >> 4 {
+            // CONNECT
+            1 => write_packet(&mut stream, 0x20, &[0, 0]),
+            // PUBLISH
+            3 => {
+                let (topic, mut at) = string(&body, 0);
+                let qos = (header >> 1) & 0x03;
+                let id = [body.get(at).copied().unwrap_or(0),
+                          body.get(at + 1).copied().unwrap_or(0)];
+
+                if 0 < qos {
+                    at += 2;
+                }
+
+                let replies: Vec<(String, Vec<u8>)> = {
+                    let mut state = broker.state.lock().unwrap();
+                    state.publish(&topic, &body[at..]);
+
+                    state.pending.drain()
+                        .filter(|(topic, _)| subscribed.contains(topic))
+                        .collect()
+                };
+
+                let mut done = if 1 == qos {
+                    write_packet(&mut stream, 0x40, &id)
+                } else {
+                    Some(())
+                };
+
+                for (topic, payload) in replies {
+                    done = done.and(deliver(&mut stream, &topic, &payload));
+                }
+
+                done
+            },
+            // SUBSCRIBE
+            8 => {
+                let topics = topics(&body, true);
+                let mut ack = body[..2].to_vec();
+                ack.extend(topics.iter().map(|_| 0u8));
+                let mut done = write_packet(&mut stream, 0x90, &ack);
+
+                for topic in topics {
+                    let message = broker.state.lock().unwrap()
+                        .retained.get(&topic).cloned();
+
+                    if let Some(payload) = message {
+                        let mut publish = (topic.len() as u16).to_be_bytes().to_vec();
+                        publish.extend_from_slice(topic.as_bytes());
+                        publish.extend_from_slice(&payload);
+                        done = done.and(write_packet(&mut stream, 0x31, &publish));
+                    }
+
+                    subscribed.push(topic);
+                }
+
+                done
+            },
+            // UNSUBSCRIBE
+            10 => {
+                let topics = topics(&body, false);
+                subscribed.retain(|topic| !topics.contains(topic));
+
+                write_packet(&mut stream, 0xB0, &body[..2])
+            },
+            // PINGREQ
+            12 => write_packet(&mut stream, 0xD0, &[]),
+            // DISCONNECT
+            _ => None,
+        };
+
+        if done.is_none() {
+            break;
+        }
+    }
+}
+
+pub fn color_device(name: &str) -> Value {
+    json!({
+        "friendly_name": name,
+        "type": "Router",
+        "definition": { "exposes": [
+            { "type": "light", "features": [
+                { "type": "binary", "name": "state", "property": "state" },
+                { "type": "numeric", "name": "brightness", "property": "brightness",
+                  "value_min": 0, "value_max": 254 },
+                { "type": "composite", "name": "color_xy", "property": "color" }
+            ]},
+            { "type": "enum", "name": "effect", "property": "effect",
+              "values": ["blink", "breathe", "okay", "stop_effect"] }
+        ]}
+    })
+}
+
+pub fn dimmable_device(name: &str) -> Value {
+    json!({
+        "friendly_name": name,
+        "type": "Router",
+        "definition": { "exposes": [
+            { "type": "light", "features": [
+                { "type": "binary", "name": "state", "property": "state" },
+                { "type": "numeric", "name": "brightness", "property": "brightness" }
+            ]}
+        ]}
+    })
+}
+
+pub fn sensor_device(name: &str) -> Value {
+    json!({
+        "friendly_name": name,
+        "type": "EndDevice",
+        "definition": { "exposes": [
+            { "type": "numeric", "name": "temperature", "property": "temperature" }
+        ]}
+    })
+}
@@ -0,0 +1,288 @@
+
+mod common;
+
+use common::*;
+
+use std::time::Duration;
+
+use serde_json::json;
+
+use domain::capabilities::Capability;
+use domain::color::Color;
+use domain::color::xy::XY;
+use domain::brightness::Brightness;
+use domain::mode::Mode;
+use provider::{Provider, ErrorType};
+use mqtt_provider::MqttProvider;
+
+const TIMEOUT: Duration = Duration::from_millis(200);
+
+fn setup() -> (Broker, MqttProvider) {
+    let broker = Broker::new().with_devices(json!([
+        { "friendly_name": "Coordinator", "type": "Coordinator" },
+        color_device("living/desk"),
+        dimmable_device("hall"),
+        sensor_device("bathroom"),
+    ]));
+    broker.retain("living/desk", json!({
+        "state": "ON",
+        "brightness": 127,
+        "color": { "x": 0.3, "y": 0.32 },
+        "color_mode": "xy",
+        "linkquality": 120,
+    }));
+    broker.retain("hall", json!({ "state": "OFF", "brightness": 254 }));
+
+    let provider = MqttProvider::new("zigbee", BASE, TIMEOUT,
+                                     Box::new(broker.clone()));
+
+    (broker, provider)
+}
+
+mod fetch {
+    use super::*;
+
+    #[test]
+    fn list() {
+        let (_broker, provider) = setup();
+        let lights = provider.list().expect("Listed");
+        let ids: Vec<&str> = lights.iter()
+            .map(|light| light.provider.id.as_str())
+            .collect();
+
+        assert_eq!(ids, vec!["living/desk", "hall"]);
+    }
+
+    #[test]
+    fn modes() {
+        let (_broker, provider) = setup();
+        let names: Vec<String> = provider.modes().expect("Listed").into_iter()
+            .map(|mode| mode.name)
+            .collect();
+
+        assert_eq!(names, vec!["blink", "breathe", "okay", "stop_effect"]);
+    }
+
+    #[test]
+    fn color_light() {
+        let (_broker, provider) = setup();
+        let light = provider.get("living/desk").expect("Exists");
+
+        assert!(light.get_power().expect("Set"));
+        assert_eq!(light.capabilities(), vec![Capability::Power,
+                                              Capability::Color,
+                                              Capability::Brightness,
+                                              Capability::Mode]);
+        assert_eq!(**light.get_brightness().expect("Set"), 0.5);
+        assert!(light.get_mode().is_err());
+
+        let xy = XY::from(light.get_color().expect("Set").clone());
+        assert!((xy.x - 0.3).abs() < 1e-9);
+        assert!((xy.y - 0.32).abs() < 1e-9);
+    }
+
+    #[test]
+    fn dimmable_light() {
+        let (_broker, provider) = setup();
+        let light = provider.get("hall").expect("Exists");
+
+        assert!(!light.get_power().expect("Set"));
+        assert_eq!(light.capabilities(), vec![Capability::Power,
+                                              Capability::Brightness]);
+        assert_eq!(**light.get_brightness().expect("Set"), 1.0);
+    }
+
+    #[test]
+    fn not_retained() {
+        let (broker, provider) = setup();
+        broker.silent("hall", json!({ "state": "ON", "brightness": 0 }));
+
+        let light = provider.get("hall").expect("Exists");
+
+        assert!(light.get_power().expect("Set"));
+        assert_eq!(**light.get_brightness().expect("Set"), 0.0);
+        assert_eq!(broker.published(),
+                   vec![(format!("{}/hall/get", BASE), json!({ "state": "" }))]);
+    }
+
+    #[test]
+    fn devices_cached() {
+        let (broker, provider) = setup();
+        let mut light = provider.get("hall").expect("Exists");
+        provider.get("living/desk").expect("Exists");
+        light.set_power(true).expect("Capable");
+        provider.sync(&light).expect("Synced");
+
+        let devices = format!("{}/bridge/devices", BASE);
+        assert_eq!(broker.fetched().iter().filter(|topic| **topic == devices)
+                   .count(), 1);
+
+        // Device paired later is still found
+        broker.retain("bridge/devices", json!([dimmable_device("porch")]));
+        broker.retain("porch", json!({ "state": "ON", "brightness": 1 }));
+        assert!(provider.get("porch").is_ok());
+    }
+
+    #[test]
+    fn not_light() {
+        let (_broker, provider) = setup();
+
+        assert!(matches!(provider.get("bathroom").expect_err("Must fail").etype,
+                         ErrorType::NotFound(_)));
+    }
+
+    #[test]
+    fn no_devices() {
+        let provider = MqttProvider::new("zigbee", BASE, TIMEOUT,
+                                         Box::new(Broker::new()));
+
+        assert!(matches!(provider.list().expect_err("Must fail").etype,
+                         ErrorType::Internal(_)));
+    }
+
+    #[test]
+    fn broker_down() {
+        let (broker, provider) = setup();
+        broker.shutdown();
+
+        assert!(matches!(provider.get("hall").expect_err("Must fail").etype,
+                         ErrorType::Internal(_)));
+    }
+}
+
+mod sync {
+    use super::*;
+
+    #[test]
+    fn state() {
+        let (broker, provider) = setup();
+        let mut light = provider.get("living/desk").expect("Exists");
+        light.set_color(Color::from(XY::new(0.64, 0.33))).expect("Capable");
+        light.set_brightness(Brightness::new(1.0)).expect("Capable");
+        light.set_mode(Mode::new_empty("zigbee".to_string(), "breathe".to_string()))
+            .expect("Capable");
+
+        provider.sync(&light).expect("Synced");
+
+        let (topic, payload) = broker.published().pop().expect("Published");
+        assert_eq!(topic, format!("{}/living/desk/set", BASE));
+        assert_eq!(payload["state"], "ON");
+        assert_eq!(payload["brightness"], 254);
+        assert_eq!(payload["effect"], "breathe");
+        assert!((payload["color"]["x"].as_f64().unwrap() - 0.64).abs() < 1e-9);
+        assert!((payload["color"]["y"].as_f64().unwrap() - 0.33).abs() < 1e-9);
+
+        let synced = provider.get("living/desk").expect("Exists");
+        assert_eq!(**synced.get_brightness().expect("Set"), 1.0);
+    }
+
+    #[test]
+    fn transition() {
+        let (broker, provider) = setup();
+        let mut light = provider.get("living/desk").expect("Exists");
+        light.set_brightness(Brightness::new(1.0)).expect("Capable");
+
+        assert!(provider.supports_transition());
+        provider.sync_transition(&light, Duration::from_millis(1500))
+            .expect("Synced");
+
+        let payload = broker.published().pop().expect("Published").1;
+        assert_eq!(payload["transition"], 1.5);
+        assert_eq!(payload["brightness"], 254);
+
+        // Plain sync has no transition
+        provider.sync(&light).expect("Synced");
+        let payload = broker.published().pop().expect("Published").1;
+        assert!(payload.get("transition").is_none());
+    }
+
+    #[test]
+    fn power_off() {
+        let (broker, provider) = setup();
+        let mut light = provider.get("living/desk").expect("Exists");
+        light.set_power(false).expect("Capable");
+
+        provider.sync(&light).expect("Synced");
+
+        assert_eq!(broker.published().pop().expect("Published").1,
+                   json!({ "state": "OFF" }));
+        assert_eq!(broker.state("living/desk")["brightness"], 127);
+    }
+
+    #[test]
+    fn unsupported_effect() {
+        let (broker, provider) = setup();
+        let mut light = provider.get("living/desk").expect("Exists");
+        light.set_mode(Mode::new_empty("zigbee".to_string(), "disco".to_string()))
+            .expect("Capable");
+
+        assert!(matches!(provider.sync(&light).expect_err("Must fail").etype,
+                         ErrorType::IncorrectState(..)));
+        assert!(broker.published().is_empty());
+    }
+
+    #[test]
+    fn unsupported_capability() {
+        let (broker, provider) = setup();
+        let mut light = provider.get("living/desk").expect("Exists");
+        light.provider.id = "hall".to_string();
+
+        assert!(matches!(provider.sync(&light).expect_err("Must fail").etype,
+                         ErrorType::IncorrectState(..)));
+        assert!(broker.published().is_empty());
+    }
+
+    #[test]
+    fn foreign() {
+        let (broker, provider) = setup();
+        let mut light = provider.get("hall").expect("Exists");
+        light.provider.name = "hue".to_string();
+
+        assert!(matches!(provider.sync(&light).expect_err("Must fail").etype,
+                         ErrorType::ForeignLight(_)));
+        assert!(broker.published().is_empty());
+    }
+}
+
+mod network {
+    use super::*;
+
+    fn settings(port: u16) -> mqtt_provider::Settings {
+        serde_json::from_value(json!({
+            "host": "127.0.0.1",
+            "port": port,
+            "timeout_ms": 500,
+        })).expect("Correct settings")
+    }
+
+    #[test]
+    fn round_trip() {
+        let (broker, _) = setup();
+        let provider = settings(broker.listen()).build("zigbee");
+
+        let mut light = provider.get("hall").expect("Exists");
+        light.set_power(true).expect("Capable");
+        light.set_brightness(Brightness::new(0.5)).expect("Capable");
+        provider.sync(&light).expect("Synced");
+
+        assert_eq!(broker.state("hall"), json!({ "state": "ON", "brightness": 127 }));
+        assert!(provider.get("hall").expect("Exists").is_on());
+    }
+
+    #[test]
+    fn not_retained() {
+        let (broker, _) = setup();
+        broker.silent("hall", json!({ "state": "ON", "brightness": 10 }));
+        let provider = settings(broker.listen()).build("zigbee");
+
+        assert!(provider.get("hall").expect("Exists").is_on());
+    }
+
+    #[test]
+    fn unreachable() {
+        let provider = settings(1).build("zigbee");
+
+        assert!(matches!(provider.list().expect_err("Must fail").etype,
+                         ErrorType::Internal(_)));
+    }
+}
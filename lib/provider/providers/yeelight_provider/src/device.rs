@@ -0,0 +1,164 @@
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream};
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+pub const PORT: u16 = 55443;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceError {
+    pub code: i64,
+    pub message: String,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    Response(serde_json::Error),
+    Device(DeviceError),
+}
+
+#[derive(Debug, Deserialize)]
+struct Reply {
+    id: Option<u64>,
+    result: Option<Vec<Value>>,
+    error: Option<DeviceError>,
+}
+
+// Yeelight LAN control: JSON-RPC, one command per line
+pub struct Device {
+    address: String,
+}
+
+// Single connection to a device, commands of one change are sent over it,
+// since the device limits both connections and commands per minute
+pub struct Session {
+    stream: BufReader<TcpStream>,
+    id: u64,
+}
+
+impl Device {
+    pub fn new(address: &str) -> Self {
+        // Bare IPv6 address has colons too, so it's told from "host:port"
+        // by parsing it first
+        let address = if address.parse::<SocketAddr>().is_ok() {
+            address.to_string()
+        } else if let Ok(ip) = address.parse::<IpAddr>() {
+            SocketAddr::new(ip, PORT).to_string()
+        } else if address.contains(':') {
+            address.to_string()
+        } else {
+            format!("{}:{}", address, PORT)
+        };
+
+        Self {
+            address,
+        }
+    }
+
+    pub fn address(self: &Self) -> &str {
+        &self.address
+    }
+
+    pub fn connect(self: &Self) -> Result<Session> {
+        let stream = TcpStream::connect(&self.address).map_err(Error::Io)?;
+        stream.set_read_timeout(Some(TIMEOUT)).map_err(Error::Io)?;
+        stream.set_write_timeout(Some(TIMEOUT)).map_err(Error::Io)?;
+
+        Ok(Session {
+            stream: BufReader::new(stream),
+            id: 0,
+        })
+    }
+
+    pub fn call(self: &Self, method: &str, params: Vec<Value>) -> Result<Vec<Value>> {
+        self.connect()?.call(method, params)
+    }
+
+    pub fn properties(self: &Self, names: &[&str]) -> Result<HashMap<String, String>> {
+        self.connect()?.properties(names)
+    }
+}
+
+impl Session {
+    pub fn call(self: &mut Self, method: &str, params: Vec<Value>) -> Result<Vec<Value>> {
+        self.id += 1;
+
+        let request = json!({ "id": self.id, "method": method, "params": params });
+        self.stream.get_mut().write_all(format!("{}\r\n", request).as_bytes())
+            .map_err(Error::Io)?;
+
+        loop {
+            let mut line = String::new();
+
+            if 0 == self.stream.read_line(&mut line).map_err(Error::Io)? {
+                return Err(Error::Io(std::io::ErrorKind::UnexpectedEof.into()));
+            }
+
+            // Device also broadcasts "props" notifications without id
+            let reply: Reply = serde_json::from_str(&line)
+                .map_err(Error::Response)?;
+
+            if Some(self.id) != reply.id {
+                continue;
+            }
+
+            return match (reply.result, reply.error) {
+                (_, Some(err)) => Err(Error::Device(err)),
+                (Some(result), None) => Ok(result),
+                (None, None) => Err(Error::Response(
+                    serde::de::Error::missing_field("result")
+                )),
+            };
+        }
+    }
+
+    // Unsupported properties are reported as empty strings
+    pub fn properties(self: &mut Self, names: &[&str]) -> Result<HashMap<String, String>> {
+        let params = names.iter().map(|name| json!(name)).collect();
+        let values = self.call("get_prop", params)?;
+
+        Ok(names.iter()
+            .zip(values)
+            .filter_map(|(name, value)| match value {
+                Value::String(value) if !value.is_empty() => {
+                    Some((name.to_string(), value))
+                },
+                Value::Number(value) => Some((name.to_string(), value.to_string())),
+                _ => None,
+            })
+            .collect())
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Response(err) => Some(err),
+            Self::Device(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "Device is unreachable: {}", err),
+            Self::Response(err) => {
+                write!(f, "Unexpected device response: {}", err)
+            },
+            Self::Device(err) => {
+                write!(f, "Device error {}: {}", err.code, err.message)
+            },
+        }
+    }
+}
@@ -0,0 +1,426 @@
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use domain::light::{self, Light};
+use domain::capabilities::Capability;
+use domain::color::Color;
+use domain::color::rgb::RGB;
+use domain::color::hsv::HSV;
+use domain::color::xy::XY;
+use domain::color::temperature::{self, Temperature};
+use domain::brightness::Brightness;
+use domain::mode::descriptor::ModeDescriptor;
+use domain::patch::LightPatch;
+use provider::{Provider, Error, Result};
+
+pub mod device;
+pub mod scene;
+
+use device::Device;
+use scene::{Scene, Flow};
+
+const PROPERTIES: [&str; 10] = ["power", "bright", "color_mode", "rgb", "ct",
+                                "hue", "sat", "flowing", "flow_params",
+                                "delayoff"];
+
+// Smooth transition applied to every change
+const EFFECT: &str = "smooth";
+const DURATION: Duration = Duration::from_millis(300);
+// Shortest smooth transition the device accepts
+const MIN_DURATION: u64 = 30;
+
+// Range of set_ct_abx
+const MIN_TEMPERATURE: f64 = 1700.0;
+const MAX_TEMPERATURE: f64 = 6500.0;
+
+// Max distance in xy from the Planckian locus for a color to be sent as
+// a color temperature (D65 is about 0.006 away)
+const WHITE_DISTANCE: f64 = 0.01;
+
+pub type Command = (&'static str, Vec<Value>);
+
+pub struct YeelightProvider {
+    name: String,
+    bulbs: Vec<Bulb>,
+}
+
+struct Bulb {
+    id: String,
+    device: Device,
+    // Whether rgb and hsv are taken besides color temperature, learnt from
+    // properties of the bulb
+    rgb: OnceLock<bool>,
+}
+
+impl YeelightProvider {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            bulbs: Vec::new(),
+        }
+    }
+
+    pub fn with_device(mut self: Self, id: &str, address: &str) -> Self {
+        self.bulbs.push(Bulb {
+            id: id.to_string(),
+            device: Device::new(address),
+            rgb: OnceLock::new(),
+        });
+        self
+    }
+
+    fn bulb(self: &Self, id: &str) -> Result<&Bulb> {
+        match self.bulbs.iter().find(|bulb| bulb.id == id) {
+            Some(bulb) => Ok(bulb),
+            None => Error::not_found(&self.name, id),
+        }
+    }
+
+    fn unreachable<T>(self: &Self, err: device::Error) -> Result<T> {
+        Error::internal(&self.name, Box::new(err))
+    }
+
+    fn convert(self: &Self, id: &str,
+               props: HashMap<String, String>) -> light::Result<Light> {
+        let number = |name: &str| -> Option<u64> {
+            props.get(name).and_then(|value| value.parse().ok())
+        };
+        let mut capabilities = Vec::new();
+
+        if props.contains_key("power") {
+            capabilities.push(Capability::Power);
+        }
+
+        // White bulbs have color temperature only
+        if props.contains_key("rgb") || props.contains_key("ct") {
+            capabilities.push(Capability::Color);
+        }
+
+        if props.contains_key("bright") {
+            capabilities.push(Capability::Brightness);
+        }
+
+        capabilities.push(Capability::Mode);
+
+        let mut light = Light::new(self.name.clone(), id.to_string(),
+                                   capabilities);
+
+        if let Some(power) = props.get("power") {
+            light.set_power("on" == power)?;
+        }
+
+        let color = match number("color_mode") {
+            Some(1) => number("rgb").map(|rgb| Color::from(RGB::new(
+                (rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8
+            ))),
+            Some(2) => number("ct").map(|ct| {
+                Color::from(Temperature::new(ct as f64))
+            }),
+            Some(3) => number("hue").zip(number("sat")).map(|(hue, sat)| {
+                Color::from(HSV::new(hue as f64 / 360.0, sat as f64 / 100.0,
+                                     1.0))
+            }),
+            _ => None,
+        };
+
+        if let (Some(color), true) = (color, light.is_capable(&[Capability::Color])) {
+            light.set_color(color)?;
+        }
+
+        if let Some(bright) = number("bright") {
+            light.set_brightness(from_bright(bright))?;
+        }
+
+        let scene = match (number("flowing"), number("delayoff")) {
+            (Some(1), _) => props.get("flow_params")
+                .and_then(|params| Flow::parse(params))
+                .map(Scene::Flow)
+                .unwrap_or(Scene::Static),
+            (_, Some(minutes)) if 0 < minutes => Scene::SleepTimer(minutes),
+            _ => Scene::Static,
+        };
+        light.set_mode(scene.into_mode(&self.name))?;
+
+        Ok(light)
+    }
+
+    // Commands to bring the device into state of the light over duration,
+    // colors are sent as temperature only unless the bulb takes rgb
+    pub fn commands(self: &Self, light: &Light, duration: Duration,
+                    rgb: bool) -> Result<Vec<Command>> {
+        let duration = to_duration(duration);
+        let power = |state: &str| -> Command {
+            ("set_power", vec![json!(state), json!(EFFECT), json!(duration)])
+        };
+        let mut out = Vec::new();
+
+        match light.get_power() {
+            Ok(false) => return Ok(vec![power("off")]),
+            Ok(true) => out.push(power("on")),
+            // Unknown power is left as is
+            Err(_) => {},
+        }
+
+        let scene = match light.get_mode() {
+            Ok(mode) => match Scene::from_mode(mode) {
+                Ok(scene) => Some(scene),
+                Err(msg) => {
+                    return Error::incorrect_state(&self.name, light, msg);
+                },
+            },
+            Err(_) => None,
+        };
+
+        // Flow drives both color and brightness itself
+        if let Some(Scene::Flow(flow)) = &scene {
+            out.push(("start_cf", vec![json!(flow.count),
+                                       json!(flow.action as u64),
+                                       json!(flow.expression())]));
+            return Ok(out);
+        }
+
+        if let Some(Scene::Static) = scene {
+            out.push(("stop_cf", Vec::new()));
+        }
+
+        if let Ok(brightness) = light.get_brightness() {
+            out.push(("set_bright", vec![json!(to_bright(brightness)),
+                                         json!(EFFECT), json!(duration)]));
+        }
+
+        if let Ok(color) = light.get_color() {
+            out.push(color_command(color, duration, rgb));
+        }
+
+        match scene {
+            Some(Scene::SleepTimer(minutes)) => {
+                out.push(("cron_add", vec![json!(0), json!(minutes)]));
+            },
+            Some(Scene::Static) => out.push(("cron_del", vec![json!(0)])),
+            _ => {},
+        }
+
+        Ok(out)
+    }
+}
+
+fn to_duration(duration: Duration) -> u64 {
+    (duration.as_millis() as u64).max(MIN_DURATION)
+}
+
+fn from_bright(bright: u64) -> Brightness {
+    Brightness::new((bright.clamp(1, 100) - 1) as f64 / 99.0)
+}
+
+fn to_bright(brightness: &Brightness) -> u64 {
+    (1.0 + **brightness * 99.0).round() as u64
+}
+
+// Whites go as native color temperature, everything else as hue and
+// saturation, since brightness is set separately
+fn color_command(color: &Color, duration: u64, rgb: bool) -> Command {
+    let xy = XY::from(color.clone());
+    // Whites just outside of supported range are still sent as the nearest
+    // supported temperature
+    let cct = Temperature::from(color.clone())
+        .clamp(MIN_TEMPERATURE, MAX_TEMPERATURE);
+    let locus = temperature::planckian(cct);
+    let distance = (xy.x - locus.x).hypot(xy.y - locus.y);
+
+    if !rgb || WHITE_DISTANCE >= distance {
+        ("set_ct_abx", vec![json!(cct.round() as u64), json!(EFFECT),
+                            json!(duration)])
+    } else {
+        let hsv = HSV::from(color.clone());
+        ("set_hsv", vec![json!((*hsv.hue * 360.0).round() as u64 % 360),
+                         json!((*hsv.saturation * 100.0).round() as u64),
+                         json!(EFFECT), json!(duration)])
+    }
+}
+
+impl Provider for YeelightProvider {
+    fn name(self: &Self) -> &str {
+        &self.name
+    }
+
+    fn list(self: &Self) -> Result<Vec<Light>> {
+        self.bulbs.iter()
+            .map(|bulb| self.get(&bulb.id))
+            .collect()
+    }
+
+    fn get(self: &Self, id: &str) -> Result<Light> {
+        let bulb = self.bulb(id)?;
+        let props = match bulb.device.properties(&PROPERTIES) {
+            Ok(props) => props,
+            Err(err) => return self.unreachable(err),
+        };
+        let _ = bulb.rgb.set(props.contains_key("rgb"));
+
+        match self.convert(id, props) {
+            Ok(light) => Ok(light),
+            Err(err) => Error::internal(&self.name, Box::new(err)),
+        }
+    }
+
+    fn sync(self: &Self, light: &Light) -> Result<()> {
+        self.sync_transition(light, DURATION)
+    }
+
+    fn supports_transition(self: &Self) -> bool {
+        true
+    }
+
+    fn sync_transition(self: &Self, light: &Light,
+                       duration: Duration) -> Result<()> {
+        if light.provider.name != self.name {
+            return Error::foreign_light(&self.name, light);
+        }
+
+        let bulb = self.bulb(&light.provider.id)?;
+        let mut session = match bulb.device.connect() {
+            Ok(session) => session,
+            Err(err) => return self.unreachable(err),
+        };
+        let rgb = match bulb.rgb.get() {
+            Some(rgb) => *rgb,
+            None => match session.properties(&["rgb"]) {
+                Ok(props) => *bulb.rgb.get_or_init(|| props.contains_key("rgb")),
+                Err(err) => return self.unreachable(err),
+            },
+        };
+
+        for (method, params) in self.commands(light, duration, rgb)? {
+            match session.call(method, params) {
+                Ok(_) => {},
+                Err(device::Error::Device(err)) => {
+                    return Error::incorrect_state(&self.name, light,
+                        format!("\"{}\" rejected: {}", method, err.message));
+                },
+                Err(err) => return self.unreachable(err),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn apply(self: &Self, id: &str, patch: &LightPatch) -> Result<()> {
+        provider::apply_partial(self, id, patch)
+    }
+
+    fn modes(self: &Self) -> Result<Vec<ModeDescriptor>> {
+        Ok(scene::descriptors(&self.name))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Settings {
+    #[serde(default)]
+    pub lights: Vec<DeviceSettings>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeviceSettings {
+    pub id: String,
+    pub address: String, // Port defaults to 55443
+}
+
+impl Settings {
+    pub fn build(self: Self, name: &str) -> YeelightProvider {
+        self.lights.into_iter()
+            .fold(YeelightProvider::new(name), |provider, device| {
+                provider.with_device(&device.id, &device.address)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn brightness_bounds() {
+        assert_eq!(*from_bright(1), 0.0);
+        assert_eq!(*from_bright(100), 1.0);
+        assert_eq!(to_bright(&Brightness::new(0.0)), 1);
+        assert_eq!(to_bright(&Brightness::new(1.0)), 100);
+
+        for bright in 1..=100 {
+            assert_eq!(to_bright(&from_bright(bright)), bright);
+        }
+    }
+
+    #[test]
+    fn white_as_temperature() {
+        let (method, params) = color_command(&Color::from(Temperature::new(2700.0)),
+                                           300, true);
+
+        assert_eq!(method, "set_ct_abx");
+        assert_eq!(params[0], 2700);
+    }
+
+    #[test]
+    fn color_as_hsv() {
+        let (method, params) = color_command(&Color::from(RGB::new(255, 0, 0)), 300,
+                                           true);
+
+        assert_eq!(method, "set_hsv");
+        assert_eq!(params[0], 0);
+        assert_eq!(params[1], 100);
+    }
+
+    #[test]
+    fn white_bulb() {
+        let (method, _) = color_command(&Color::from(RGB::new(255, 0, 0)), 300,
+                                        false);
+        assert_eq!(method, "set_ct_abx");
+
+        let props = HashMap::from([("ct".to_string(), "4000".to_string()),
+                                   ("color_mode".to_string(), "2".to_string())]);
+        let light = YeelightProvider::new("yeelight").convert("desk", props)
+            .expect("Converted");
+        assert!(light.is_capable(&[Capability::Color]));
+        assert!(light.get_color().is_ok());
+    }
+
+    #[test]
+    fn duration() {
+        let mut light = Light::new("yeelight".to_string(), "desk".to_string(),
+                                   vec![Capability::Brightness]);
+        light.set_brightness(Brightness::new(1.0)).expect("Capable");
+        let provider = YeelightProvider::new("yeelight");
+        let commands = provider.commands(&light, Duration::from_secs(2), true)
+            .expect("Correct");
+
+        assert_eq!(commands[0].1[2], 2000);
+        // Too short transition is clamped
+        assert_eq!(to_duration(Duration::ZERO), MIN_DURATION);
+    }
+
+    #[test]
+    fn default_port() {
+        assert_eq!(Device::new("10.0.0.3").address(), "10.0.0.3:55443");
+        assert_eq!(Device::new("10.0.0.3:1000").address(), "10.0.0.3:1000");
+        assert_eq!(Device::new("fe80::1").address(), "[fe80::1]:55443");
+        assert_eq!(Device::new("[fe80::1]:1000").address(), "[fe80::1]:1000");
+        assert_eq!(Device::new("desk.lan").address(), "desk.lan:55443");
+    }
+
+    #[test]
+    fn settings() {
+        let settings: Settings = serde_json::from_str(r#"{
+            "lights": [{ "id": "desk", "address": "10.0.0.3" }]
+        }"#).expect("Correct settings");
+        let provider = settings.build("yeelight");
+
+        assert!(provider.bulb("desk").is_ok());
+        assert!(provider.bulb("hall").is_err());
+    }
+}
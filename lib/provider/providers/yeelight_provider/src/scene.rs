@@ -0,0 +1,476 @@
+
+use std::collections::HashMap;
+
+use domain::mode::Mode;
+use domain::mode::parameter::{Parameter, Value};
+use domain::mode::descriptor::ModeDescriptor;
+use domain::mode::schema::{Field, ParameterSchema};
+
+pub const STATIC: &str = "static";
+pub const FLOW: &str = "flow";
+pub const SLEEP_TIMER: &str = "sleep_timer";
+
+pub const MODES: [&str; 3] = [STATIC, FLOW, SLEEP_TIMER];
+
+// Device limits for flow transitions
+const MIN_DURATION: u64 = 50;
+const MIN_TEMPERATURE: u64 = 1700;
+const MAX_TEMPERATURE: u64 = 6500;
+const MAX_RGB: u64 = 0xFFFFFF;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Scene {
+    Static,
+    Flow(Flow),
+    SleepTimer(u64), // Minutes before the light goes off
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Flow {
+    pub count: u64, // 0 repeats infinitely
+    pub action: Action,
+    pub transitions: Vec<Transition>,
+}
+
+// What happens when flow stops
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Recover = 0,
+    Stay = 1,
+    Off = 2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Color = 1,
+    Temperature = 2,
+    Sleep = 7,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transition {
+    pub duration: u64, // ms
+    pub kind: Kind,
+    pub value: u64,      // RGB as integer or temperature in K
+    pub brightness: i64, // -1 keeps current brightness
+}
+
+impl Action {
+    const NAMES: [(&'static str, Action); 3] = [
+        ("recover", Action::Recover),
+        ("stay", Action::Stay),
+        ("off", Action::Off),
+    ];
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES.iter().find(|(n, _)| *n == name).map(|(_, a)| *a)
+    }
+
+    fn from_code(code: u64) -> Option<Self> {
+        Self::NAMES.iter().find(|(_, a)| *a as u64 == code).map(|(_, a)| *a)
+    }
+
+    fn name(self: &Self) -> &'static str {
+        Self::NAMES.iter().find(|(_, a)| a == self).map(|(n, _)| *n)
+            .unwrap_or_default()
+    }
+}
+
+impl Kind {
+    const NAMES: [(&'static str, Kind); 3] = [
+        ("color", Kind::Color),
+        ("temperature", Kind::Temperature),
+        ("sleep", Kind::Sleep),
+    ];
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES.iter().find(|(n, _)| *n == name).map(|(_, k)| *k)
+    }
+
+    fn from_code(code: u64) -> Option<Self> {
+        Self::NAMES.iter().find(|(_, k)| *k as u64 == code).map(|(_, k)| *k)
+    }
+
+    fn name(self: &Self) -> &'static str {
+        Self::NAMES.iter().find(|(_, k)| k == self).map(|(n, _)| *n)
+            .unwrap_or_default()
+    }
+}
+
+fn uint(path: &str, value: Option<&Value>) -> Result<u64, String> {
+    match value {
+        Some(Value::UInt(value)) => Ok(*value),
+        Some(Value::Int(value)) if 0 <= *value => Ok(*value as u64),
+        Some(_) => Err(format!("\"{}\" must be an unsigned integer", path)),
+        None => Err(format!("\"{}\" is missing", path)),
+    }
+}
+
+fn int(path: &str, value: Option<&Value>) -> Result<i64, String> {
+    match value {
+        Some(Value::Int(value)) => Ok(*value),
+        Some(Value::UInt(value)) => i64::try_from(*value)
+            .map_err(|_| format!("\"{}\" is too large", path)),
+        Some(_) => Err(format!("\"{}\" must be an integer", path)),
+        None => Err(format!("\"{}\" is missing", path)),
+    }
+}
+
+fn string<'a>(path: &str, value: Option<&'a Value>) -> Result<&'a str, String> {
+    match value {
+        Some(Value::String(value)) => Ok(value),
+        Some(_) => Err(format!("\"{}\" must be a string", path)),
+        None => Err(format!("\"{}\" is missing", path)),
+    }
+}
+
+fn group_value<'a>(group: &'a HashMap<String, Parameter>,
+                   name: &str) -> Option<&'a Value> {
+    group.get(name).map(|param| &param.value)
+}
+
+impl Transition {
+    fn from_value(path: &str, value: &Value) -> Result<Self, String> {
+        let Value::Group(group) = value else {
+            return Err(format!("\"{}\" must be a group", path));
+        };
+
+        let field = |name: &str| format!("{}.{}", path, name);
+        let duration = uint(&field("duration"), group_value(group, "duration"))?;
+        let kind = string(&field("mode"), group_value(group, "mode"))?;
+        let kind = Kind::from_name(kind).ok_or_else(|| {
+            format!("\"{}\" must be one of color, temperature, sleep",
+                    field("mode"))
+        })?;
+        let value = match kind {
+            Kind::Sleep => 0,
+            _ => uint(&field("value"), group_value(group, "value"))?,
+        };
+        let brightness = match group_value(group, "brightness") {
+            None => -1,
+            brightness => int(&field("brightness"), brightness)?,
+        };
+
+        if MIN_DURATION > duration {
+            return Err(format!("\"{}\" must be at least {} ms",
+                               field("duration"), MIN_DURATION));
+        }
+
+        match kind {
+            Kind::Color if MAX_RGB < value => {
+                return Err(format!("\"{}\" must be a 24 bit RGB value",
+                                   field("value")));
+            },
+            Kind::Temperature if !(MIN_TEMPERATURE..=MAX_TEMPERATURE).contains(&value) => {
+                return Err(format!("\"{}\" must be within [{}, {}] K",
+                                   field("value"), MIN_TEMPERATURE,
+                                   MAX_TEMPERATURE));
+            },
+            _ => {},
+        }
+
+        if -1 != brightness && !(1..=100).contains(&brightness) {
+            return Err(format!("\"{}\" must be -1 or within [1, 100]",
+                               field("brightness")));
+        }
+
+        Ok(Self {
+            duration,
+            kind,
+            value,
+            brightness,
+        })
+    }
+
+    fn into_value(self: &Self) -> Value {
+        let params = [
+            ("duration", Value::UInt(self.duration)),
+            ("mode", Value::String(self.kind.name().to_string())),
+            ("value", Value::UInt(self.value)),
+            ("brightness", Value::Int(self.brightness)),
+        ];
+
+        Value::Group(params.into_iter()
+            .map(|(name, value)| {
+                (name.to_string(), Parameter::new(name.to_string(), value))
+            })
+            .collect())
+    }
+}
+
+impl Flow {
+    // Flow expression is a flat list of "duration,mode,value,brightness"
+    pub fn expression(self: &Self) -> String {
+        self.transitions.iter()
+            .map(|t| format!("{},{},{},{}", t.duration, t.kind as u64,
+                             t.value, t.brightness))
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+
+    // Reported "flow_params" property is "count,action" followed by
+    // the expression
+    pub fn parse(params: &str) -> Option<Self> {
+        let numbers: Vec<i64> = params.split(',')
+            .map(|item| item.trim().parse().ok())
+            .collect::<Option<Vec<i64>>>()?;
+        let (head, tail) = numbers.split_at_checked(2)?;
+
+        if tail.is_empty() || 0 != tail.len() % 4 {
+            return None;
+        }
+
+        let transitions = tail.chunks(4)
+            .map(|chunk| Some(Transition {
+                duration: u64::try_from(chunk[0]).ok()?,
+                kind: Kind::from_code(u64::try_from(chunk[1]).ok()?)?,
+                value: u64::try_from(chunk[2]).ok()?,
+                brightness: chunk[3],
+            }))
+            .collect::<Option<Vec<Transition>>>()?;
+
+        Some(Self {
+            count: u64::try_from(head[0]).ok()?,
+            action: Action::from_code(u64::try_from(head[1]).ok()?)?,
+            transitions,
+        })
+    }
+}
+
+// Limits that depend on the kind of transition are left to Transition itself
+fn transition_schema() -> ParameterSchema {
+    ParameterSchema::group(vec![
+        Field::new("duration", ParameterSchema::uint(Some(MIN_DURATION), None),
+                   "Duration in ms"),
+        Field::new("mode", ParameterSchema::one_of(&Kind::NAMES.map(|(n, _)| n)),
+                   "What changes"),
+        Field::new("value", ParameterSchema::uint(None, Some(MAX_RGB)),
+                   "RGB or temperature in K, unused for sleep").optional(),
+        Field::new("brightness", ParameterSchema::int(Some(-1), Some(100)),
+                   "Percent, -1 keeps current one").optional(),
+    ])
+}
+
+pub fn descriptors(provider: &str) -> Vec<ModeDescriptor> {
+    vec![
+        ModeDescriptor::new(provider, STATIC, "Plain state, stops flow"),
+        ModeDescriptor::new(provider, FLOW, "Sequence of color changes")
+            .with_parameter(Field::new(
+                "count", ParameterSchema::uint(None, None),
+                "Transitions to run, 0 repeats infinitely"
+            ).with_default(Value::UInt(0)))
+            .with_parameter(Field::new(
+                "action", ParameterSchema::one_of(&["recover", "stay", "off"]),
+                "What happens after the flow"
+            ).with_default(Value::String(Action::Recover.name().to_string())))
+            .with_parameter(Field::new(
+                "transitions", ParameterSchema::array(transition_schema())
+                    .with_length(Some(1), None),
+                "Steps of the flow"
+            )),
+        ModeDescriptor::new(provider, SLEEP_TIMER, "Turns light off later")
+            .with_parameter(Field::new(
+                "minutes", ParameterSchema::uint(Some(1), None),
+                "Minutes before power off"
+            )),
+    ]
+}
+
+impl Scene {
+    pub fn from_mode(mode: &Mode) -> Result<Self, String> {
+        match mode.name.as_str() {
+            STATIC => Ok(Self::Static),
+            SLEEP_TIMER => {
+                let minutes = uint("minutes", mode.parameter("minutes"))?;
+
+                if 0 == minutes {
+                    Err("\"minutes\" must be positive".to_string())
+                } else {
+                    Ok(Self::SleepTimer(minutes))
+                }
+            },
+            FLOW => {
+                let count = match mode.parameter("count") {
+                    None => 0,
+                    count => uint("count", count)?,
+                };
+                let action = match mode.parameter("action") {
+                    None => Action::Recover,
+                    action => {
+                        let name = string("action", action)?;
+                        Action::from_name(name).ok_or_else(|| {
+                            "\"action\" must be one of recover, stay, off"
+                                .to_string()
+                        })?
+                    },
+                };
+                let transitions = match mode.parameter("transitions") {
+                    Some(Value::Array(items)) if !items.is_empty() => items,
+                    Some(Value::Array(_)) => {
+                        return Err("\"transitions\" can't be empty".to_string());
+                    },
+                    Some(_) => {
+                        return Err("\"transitions\" must be an array".to_string());
+                    },
+                    None => return Err("\"transitions\" is missing".to_string()),
+                };
+                let transitions = transitions.iter()
+                    .enumerate()
+                    .map(|(i, item)| {
+                        Transition::from_value(&format!("transitions[{}]", i),
+                                               item)
+                    })
+                    .collect::<Result<Vec<Transition>, String>>()?;
+
+                Ok(Self::Flow(Flow {
+                    count,
+                    action,
+                    transitions,
+                }))
+            },
+            name => Err(format!("Unsupported mode \"{}\"", name)),
+        }
+    }
+
+    pub fn into_mode(self: &Self, provider: &str) -> Mode {
+        let (name, parameters) = match self {
+            Self::Static => (STATIC, Vec::new()),
+            Self::SleepTimer(minutes) => (SLEEP_TIMER, vec![
+                Parameter::new("minutes".to_string(), Value::UInt(*minutes)),
+            ]),
+            Self::Flow(flow) => (FLOW, vec![
+                Parameter::new("count".to_string(), Value::UInt(flow.count)),
+                Parameter::new("action".to_string(),
+                               Value::String(flow.action.name().to_string())),
+                Parameter::new("transitions".to_string(), Value::Array(
+                    flow.transitions.iter().map(Transition::into_value).collect()
+                )),
+            ]),
+        };
+
+        Mode::new(provider.to_string(), name.to_string(), parameters)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transition(duration: u64, mode: &str, value: u64, brightness: i64) -> Value {
+        Transition::from_value("", &Value::Group(HashMap::from([
+            ("duration".to_string(),
+             Parameter::new("duration".to_string(), Value::UInt(duration))),
+            ("mode".to_string(),
+             Parameter::new("mode".to_string(), Value::String(mode.to_string()))),
+            ("value".to_string(),
+             Parameter::new("value".to_string(), Value::UInt(value))),
+            ("brightness".to_string(),
+             Parameter::new("brightness".to_string(), Value::Int(brightness))),
+        ]))).expect("Correct transition").into_value()
+    }
+
+    fn flow(transitions: Vec<Value>) -> Mode {
+        Mode::new("yeelight".to_string(), FLOW.to_string(), vec![
+            Parameter::new("count".to_string(), Value::UInt(4)),
+            Parameter::new("action".to_string(), Value::String("stay".to_string())),
+            Parameter::new("transitions".to_string(), Value::Array(transitions)),
+        ])
+    }
+
+    #[test]
+    fn flow_expression() {
+        let mode = flow(vec![transition(1000, "color", 0xFF0000, 100),
+                             transition(500, "sleep", 0, -1),
+                             transition(1000, "temperature", 2700, 50)]);
+
+        let Scene::Flow(flow) = Scene::from_mode(&mode).expect("Correct") else {
+            panic!("Flow expected");
+        };
+
+        assert_eq!(flow.count, 4);
+        assert_eq!(flow.action, Action::Stay);
+        assert_eq!(flow.expression(),
+                   "1000,1,16711680,100,500,7,0,-1,1000,2,2700,50");
+    }
+
+    #[test]
+    fn flow_round_trip() {
+        let scene = Scene::from_mode(&flow(vec![
+            transition(1000, "color", 0x00FF00, 10)
+        ])).expect("Correct");
+        let back = Scene::from_mode(&scene.into_mode("yeelight"))
+            .expect("Correct");
+
+        assert_eq!(back, scene);
+    }
+
+    #[test]
+    fn descriptors_match() {
+        let catalog = descriptors("yeelight");
+        let scenes = [
+            Scene::Static,
+            Scene::SleepTimer(30),
+            Scene::from_mode(&flow(vec![transition(1000, "color", 0xFF, 10)]))
+                .expect("Correct"),
+        ];
+
+        for scene in scenes {
+            domain::mode::descriptor::validate(&catalog,
+                                               &scene.into_mode("yeelight"))
+                .expect("Described");
+        }
+    }
+
+    #[test]
+    fn descriptors_reject() {
+        let mode = flow(vec![transition(1000, "color", 0xFF, 10),
+                             Value::Group(HashMap::from([
+                                 ("mode".to_string(),
+                                  Parameter::new("mode".to_string(),
+                                                 Value::String("blink".to_string()))),
+                             ]))]);
+
+        let err = domain::mode::descriptor::validate(&descriptors("yeelight"),
+                                                     &mode)
+            .expect_err("Must fail");
+        assert!(err.to_string().contains("\"transitions[1].mode\""));
+        assert!(err.to_string().contains("\"transitions[1].duration\""));
+    }
+
+    #[test]
+    fn flow_parse() {
+        let flow = Flow::parse("0,2,1000,1,255,100,60000,2,4000,-1")
+            .expect("Correct");
+
+        assert_eq!(flow.count, 0);
+        assert_eq!(flow.action, Action::Off);
+        assert_eq!(flow.transitions.len(), 2);
+        assert_eq!(flow.transitions[1].kind, Kind::Temperature);
+        assert_eq!(flow.expression(), "1000,1,255,100,60000,2,4000,-1");
+
+        assert!(Flow::parse("0,2").is_none());
+        assert!(Flow::parse("0,2,1000,1,255").is_none());
+        assert!(Flow::parse("0,9,1000,1,255,100").is_none());
+    }
+
+    #[test]
+    fn incorrect_parameters() {
+        let wrong_type = Mode::new("yeelight".to_string(), SLEEP_TIMER.to_string(),
+                                   vec![Parameter::new("minutes".to_string(),
+                                                       Value::Float(1.5))]);
+        let empty = flow(Vec::new());
+        let wrong_transition = flow(vec![Value::Group(HashMap::from([
+            ("duration".to_string(),
+             Parameter::new("duration".to_string(), Value::UInt(10))),
+            ("mode".to_string(),
+             Parameter::new("mode".to_string(), Value::String("sleep".to_string()))),
+        ]))]);
+        let unknown = Mode::new_empty("yeelight".to_string(), "disco".to_string());
+
+        assert!(Scene::from_mode(&wrong_type).is_err());
+        assert!(Scene::from_mode(&empty).is_err());
+        assert_eq!(Scene::from_mode(&wrong_transition).expect_err("Must fail"),
+                   "\"transitions[0].duration\" must be at least 50 ms");
+        assert!(Scene::from_mode(&unknown).is_err());
+    }
+}
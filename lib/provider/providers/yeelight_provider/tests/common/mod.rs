@@ -0,0 +1,217 @@
+
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use serde_json::{json, Value};
+
+#[derive(Default)]
+struct State {
+    color: bool,
+    white: bool,
+    connections: usize,
+    props: HashMap<String, String>,
+    calls: Vec<(String, Vec<Value>)>,
+}
+
+// Emulates Yeelight LAN control on a loopback socket
+pub struct Emulator {
+    address: String,
+    state: Arc<Mutex<State>>,
+}
+
+impl Emulator {
+    pub fn start(color: bool) -> Self {
+        Self::with_kind(color, false)
+    }
+
+    // Color temperature only
+    pub fn white() -> Self {
+        let emulator = Self::with_kind(false, true);
+        emulator.set("color_mode", "2");
+        emulator.set("ct", "4000");
+
+        emulator
+    }
+
+    fn with_kind(color: bool, white: bool) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Bound");
+        let address = listener.local_addr().expect("Has address").to_string();
+        let mut props = HashMap::from([
+            ("power", "on"),
+            ("bright", "100"),
+            ("flowing", "0"),
+            ("flow_params", ""),
+            ("delayoff", "0"),
+        ].map(|(k, v)| (k.to_string(), v.to_string())));
+
+        if color {
+            props.extend([
+                ("color_mode", "1"),
+                ("rgb", "16711680"),
+                ("ct", "4000"),
+                ("hue", "0"),
+                ("sat", "100"),
+            ].map(|(k, v)| (k.to_string(), v.to_string())));
+        }
+
+        let state = Arc::new(Mutex::new(State {
+            color,
+            white,
+            props,
+            ..Default::default()
+        }));
+        let shared = state.clone();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        shared.lock().unwrap().connections += 1;
+                        handle(stream, &shared)
+                    },
+                    Err(_) => break,
+                }
+            }
+        });
+
+        Self {
+            address,
+            state,
+        }
+    }
+
+    pub fn address(self: &Self) -> &str {
+        &self.address
+    }
+
+    pub fn set(self: &Self, name: &str, value: &str) {
+        self.state.lock().unwrap().props.insert(name.to_string(),
+                                                value.to_string());
+    }
+
+    pub fn prop(self: &Self, name: &str) -> String {
+        self.state.lock().unwrap().props.get(name).cloned().unwrap_or_default()
+    }
+
+    pub fn connections(self: &Self) -> usize {
+        self.state.lock().unwrap().connections
+    }
+
+    pub fn methods(self: &Self) -> Vec<String> {
+        self.state.lock().unwrap().calls.iter()
+            .map(|(method, _)| method.clone())
+            .collect()
+    }
+
+    pub fn call(self: &Self, method: &str) -> Vec<Value> {
+        self.state.lock().unwrap().calls.iter()
+            .rev()
+            .find(|(item, _)| item == method)
+            .map(|(_, params)| params.clone())
+            .expect("Method was called")
+    }
+}
+
+fn error(code: i64, message: &str) -> Result<Vec<Value>, Value> {
+    Err(json!({ "code": code, "message": message }))
+}
+
+fn execute(state: &mut State, method: &str,
+           params: &[Value]) -> Result<Vec<Value>, Value> {
+    let text = |i: usize| params.get(i).map(|value| match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }).unwrap_or_default();
+    let color = state.color;
+    let white = state.white;
+    let props = &mut state.props;
+
+    match method {
+        "get_prop" => return Ok(params.iter()
+            .map(|name| json!(props.get(name.as_str().unwrap_or_default())
+                              .cloned()
+                              .unwrap_or_default()))
+            .collect()),
+        "set_power" => {
+            props.insert("power".to_string(), text(0));
+        },
+        "set_bright" => {
+            props.insert("bright".to_string(), text(0));
+        },
+        "set_ct_abx" if !color && !white => {
+            return error(-1, "method not supported");
+        },
+        "set_hsv" | "set_rgb" if !color => {
+            return error(-1, "method not supported");
+        },
+        "set_hsv" => {
+            props.insert("color_mode".to_string(), "3".to_string());
+            props.insert("hue".to_string(), text(0));
+            props.insert("sat".to_string(), text(1));
+        },
+        "set_ct_abx" => {
+            let ct: u64 = text(0).parse().unwrap_or_default();
+
+            if !(1700..=6500).contains(&ct) {
+                return error(-5000, "general error");
+            }
+
+            props.insert("color_mode".to_string(), "2".to_string());
+            props.insert("ct".to_string(), text(0));
+        },
+        "start_cf" => {
+            props.insert("flowing".to_string(), "1".to_string());
+            props.insert("flow_params".to_string(),
+                         format!("{},{},{}", text(0), text(1), text(2)));
+        },
+        "stop_cf" => {
+            props.insert("flowing".to_string(), "0".to_string());
+        },
+        "cron_add" => {
+            props.insert("delayoff".to_string(), text(1));
+        },
+        "cron_del" => {
+            props.insert("delayoff".to_string(), "0".to_string());
+        },
+        _ => return error(-1, "method not supported"),
+    }
+
+    Ok(vec![json!("ok")])
+}
+
+fn handle(stream: TcpStream, state: &Arc<Mutex<State>>) {
+    let reader = BufReader::new(stream.try_clone().expect("Cloned"));
+    let mut stream = stream;
+
+    for line in reader.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let request: Value = serde_json::from_str(&line).expect("JSON request");
+        let method = request["method"].as_str().unwrap_or_default().to_string();
+        let params = request["params"].as_array().cloned().unwrap_or_default();
+
+        let reply = {
+            let mut state = state.lock().unwrap();
+            let result = execute(&mut state, &method, &params);
+            state.calls.push((method, params));
+
+            match result {
+                Ok(result) => json!({ "id": request["id"], "result": result }),
+                Err(error) => json!({ "id": request["id"], "error": error }),
+            }
+        };
+
+        // Real devices interleave notifications with replies
+        let notification = json!({ "method": "props", "params": {} });
+
+        if write!(stream, "{}\r\n{}\r\n", notification, reply).is_err() {
+            break;
+        }
+    }
+}
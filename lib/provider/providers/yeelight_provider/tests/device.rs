@@ -0,0 +1,313 @@
+
+mod common;
+
+use common::*;
+
+use std::collections::HashMap;
+
+use domain::capabilities::Capability;
+use domain::color::Color;
+use domain::color::rgb::RGB;
+use domain::color::hsv::HSV;
+use domain::brightness::Brightness;
+use domain::mode::Mode;
+use domain::mode::parameter::{Parameter, Value};
+use provider::{Provider, ErrorType};
+use yeelight_provider::YeelightProvider;
+use yeelight_provider::scene::{FLOW, SLEEP_TIMER, STATIC};
+
+fn setup(color: bool) -> (Emulator, YeelightProvider) {
+    let emulator = Emulator::start(color);
+    let provider = YeelightProvider::new("yeelight")
+        .with_device("desk", emulator.address());
+
+    (emulator, provider)
+}
+
+fn mode(name: &str, parameters: Vec<Parameter>) -> Mode {
+    Mode::new("yeelight".to_string(), name.to_string(), parameters)
+}
+
+fn param(name: &str, value: Value) -> Parameter {
+    Parameter::new(name.to_string(), value)
+}
+
+fn transition(duration: u64, kind: &str, value: u64) -> Value {
+    Value::Group(HashMap::from([
+        ("duration", Value::UInt(duration)),
+        ("mode", Value::String(kind.to_string())),
+        ("value", Value::UInt(value)),
+    ].map(|(name, value)| (name.to_string(), param(name, value)))))
+}
+
+mod fetch {
+    use super::*;
+
+    #[test]
+    fn color_bulb() {
+        let (_emulator, provider) = setup(true);
+        let light = provider.get("desk").expect("Exists");
+
+        assert!(light.get_power().expect("Set"));
+        assert_eq!(light.capabilities(), vec![Capability::Power,
+                                              Capability::Color,
+                                              Capability::Brightness,
+                                              Capability::Mode]);
+        assert_eq!(**light.get_brightness().expect("Set"), 1.0);
+        assert_eq!(light.get_mode().expect("Set").name, STATIC);
+
+        let rgb = RGB::from(light.get_color().expect("Set").clone());
+        assert_eq!((rgb.red, rgb.green, rgb.blue), (255, 0, 0));
+    }
+
+    #[test]
+    fn hsv_mode() {
+        let (emulator, provider) = setup(true);
+        emulator.set("color_mode", "3");
+        emulator.set("hue", "120");
+        emulator.set("sat", "50");
+
+        let light = provider.get("desk").expect("Exists");
+        let hsv = HSV::from(light.get_color().expect("Set").clone());
+
+        assert!((*hsv.hue - 1.0 / 3.0).abs() < 0.01);
+        assert!((*hsv.saturation - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn mono_bulb() {
+        let (_emulator, provider) = setup(false);
+        let light = provider.get("desk").expect("Exists");
+
+        assert_eq!(light.capabilities(), vec![Capability::Power,
+                                              Capability::Brightness,
+                                              Capability::Mode]);
+    }
+
+    #[test]
+    fn white_bulb() {
+        let emulator = Emulator::white();
+        let provider = YeelightProvider::new("yeelight")
+            .with_device("desk", emulator.address());
+        let light = provider.get("desk").expect("Exists");
+
+        assert_eq!(light.capabilities(), vec![Capability::Power,
+                                              Capability::Color,
+                                              Capability::Brightness,
+                                              Capability::Mode]);
+        assert!(light.get_color().is_ok());
+    }
+
+    #[test]
+    fn flowing() {
+        let (emulator, provider) = setup(true);
+        emulator.set("flowing", "1");
+        emulator.set("flow_params", "3,1,1000,1,255,100,500,7,0,-1");
+
+        let light = provider.get("desk").expect("Exists");
+        let mode = light.get_mode().expect("Set");
+
+        assert_eq!(mode.name, FLOW);
+        assert_eq!(mode.parameter("count"), Some(&Value::UInt(3)));
+        assert_eq!(mode.parameter("action"),
+                   Some(&Value::String("stay".to_string())));
+        assert!(matches!(mode.parameter("transitions"),
+                         Some(Value::Array(items)) if 2 == items.len()));
+    }
+
+    #[test]
+    fn sleep_timer() {
+        let (emulator, provider) = setup(true);
+        emulator.set("delayoff", "15");
+
+        let light = provider.get("desk").expect("Exists");
+        let mode = light.get_mode().expect("Set");
+
+        assert_eq!(mode.name, SLEEP_TIMER);
+        assert_eq!(mode.parameter("minutes"), Some(&Value::UInt(15)));
+    }
+
+    #[test]
+    fn not_configured() {
+        let (_emulator, provider) = setup(true);
+
+        assert!(matches!(provider.get("hall").expect_err("Must fail").etype,
+                         ErrorType::NotFound(_)));
+    }
+
+    #[test]
+    fn unreachable() {
+        let provider = YeelightProvider::new("yeelight")
+            .with_device("desk", "127.0.0.1:1");
+
+        assert!(matches!(provider.list().expect_err("Must fail").etype,
+                         ErrorType::Internal(_)));
+    }
+}
+
+mod sync {
+    use super::*;
+
+    #[test]
+    fn hsv() {
+        let (emulator, provider) = setup(true);
+        let mut light = provider.get("desk").expect("Exists");
+        light.set_color(Color::from(RGB::new(0, 0, 255))).expect("Capable");
+        light.set_brightness(Brightness::new(0.5)).expect("Capable");
+
+        provider.sync(&light).expect("Synced");
+
+        assert_eq!(emulator.methods(),
+                   vec!["get_prop", "set_power", "stop_cf", "set_bright",
+                        "set_hsv", "cron_del"]);
+        assert_eq!(emulator.prop("bright"), "51");
+        assert_eq!(emulator.prop("color_mode"), "3");
+        assert_eq!(emulator.prop("hue"), "240");
+        assert_eq!(emulator.prop("sat"), "100");
+    }
+
+    #[test]
+    fn temperature() {
+        let (emulator, provider) = setup(true);
+        let mut light = provider.get("desk").expect("Exists");
+        // sRGB white is D65, close to 6500K
+        light.set_color(Color::from(RGB::new(255, 255, 255))).expect("Capable");
+
+        provider.sync(&light).expect("Synced");
+
+        let ct: i64 = emulator.prop("ct").parse().expect("Number");
+        assert_eq!(emulator.prop("color_mode"), "2");
+        assert!((ct - 6500).abs() < 100);
+    }
+
+    #[test]
+    fn temperature_round_trip() {
+        let (emulator, provider) = setup(true);
+        emulator.set("color_mode", "2");
+        emulator.set("ct", "2700");
+
+        let light = provider.get("desk").expect("Exists");
+        provider.sync(&light).expect("Synced");
+
+        assert_eq!(emulator.call("set_ct_abx")[0], 2700);
+    }
+
+    #[test]
+    fn power_off() {
+        let (emulator, provider) = setup(true);
+        let mut light = provider.get("desk").expect("Exists");
+        light.set_power(false).expect("Capable");
+
+        provider.sync(&light).expect("Synced");
+
+        assert_eq!(emulator.methods(), vec!["get_prop", "set_power"]);
+        assert_eq!(emulator.prop("power"), "off");
+    }
+
+    #[test]
+    fn flow() {
+        let (emulator, provider) = setup(true);
+        let mut light = provider.get("desk").expect("Exists");
+        light.set_mode(mode(FLOW, vec![
+            param("count", Value::UInt(2)),
+            param("action", Value::String("off".to_string())),
+            param("transitions", Value::Array(vec![
+                transition(1000, "color", 0xFF00FF),
+                transition(2000, "temperature", 2700),
+            ])),
+        ])).expect("Capable");
+
+        provider.sync(&light).expect("Synced");
+
+        assert_eq!(emulator.methods(), vec!["get_prop", "set_power", "start_cf"]);
+        assert_eq!(emulator.call("start_cf"), vec![
+            serde_json::json!(2), serde_json::json!(2),
+            serde_json::json!("1000,1,16711935,-1,2000,2,2700,-1"),
+        ]);
+
+        let synced = provider.get("desk").expect("Exists");
+        assert_eq!(synced.get_mode().expect("Set").name, FLOW);
+    }
+
+    #[test]
+    fn sleep_timer() {
+        let (emulator, provider) = setup(false);
+        let mut light = provider.get("desk").expect("Exists");
+        light.set_mode(mode(SLEEP_TIMER, vec![param("minutes", Value::UInt(30))]))
+            .expect("Capable");
+
+        provider.sync(&light).expect("Synced");
+
+        assert_eq!(emulator.call("cron_add"),
+                   vec![serde_json::json!(0), serde_json::json!(30)]);
+        assert_eq!(emulator.prop("delayoff"), "30");
+    }
+
+    #[test]
+    fn incorrect_parameters() {
+        let (emulator, provider) = setup(true);
+        let mut light = provider.get("desk").expect("Exists");
+        light.set_mode(mode(FLOW, vec![
+            param("transitions", Value::Array(vec![
+                transition(1000, "temperature", 10000),
+            ])),
+        ])).expect("Capable");
+
+        assert!(matches!(provider.sync(&light).expect_err("Must fail").etype,
+                         ErrorType::IncorrectState(..)));
+        assert_eq!(emulator.methods(), vec!["get_prop"]);
+    }
+
+    #[test]
+    fn rejected() {
+        let (_color, color_provider) = setup(true);
+        let (emulator, provider) = setup(false);
+        let mut light = color_provider.get("desk").expect("Exists");
+        light.set_color(Color::from(RGB::new(0, 255, 0))).expect("Capable");
+
+        assert!(matches!(provider.sync(&light).expect_err("Must fail").etype,
+                         ErrorType::IncorrectState(..)));
+        // Bulb without rgb is sent color temperature only
+        assert_eq!(emulator.methods().last().map(String::as_str),
+                   Some("set_ct_abx"));
+    }
+
+    #[test]
+    fn white_bulb() {
+        let emulator = Emulator::white();
+        let provider = YeelightProvider::new("yeelight")
+            .with_device("desk", emulator.address());
+        let mut light = provider.get("desk").expect("Exists");
+        light.set_color(Color::from(RGB::new(255, 0, 0))).expect("Capable");
+
+        provider.sync(&light).expect("Synced");
+
+        assert!(!emulator.methods().iter().any(|method| method == "set_hsv"));
+        assert_eq!(emulator.prop("color_mode"), "2");
+        assert_eq!(emulator.call("set_ct_abx")[0], 1700);
+    }
+
+    #[test]
+    fn single_connection() {
+        let (emulator, provider) = setup(true);
+        let mut light = provider.get("desk").expect("Exists");
+        light.set_brightness(Brightness::new(0.5)).expect("Capable");
+
+        provider.sync(&light).expect("Synced");
+
+        // One for the fetch, one for every command of the sync
+        assert_eq!(emulator.connections(), 2);
+        assert!(emulator.methods().len() > 3);
+    }
+
+    #[test]
+    fn foreign() {
+        let (emulator, provider) = setup(true);
+        let mut light = provider.get("desk").expect("Exists");
+        light.provider.name = "hue".to_string();
+
+        assert!(matches!(provider.sync(&light).expect_err("Must fail").etype,
+                         ErrorType::ForeignLight(_)));
+        assert_eq!(emulator.methods(), vec!["get_prop"]);
+    }
+}
@@ -0,0 +1,222 @@
+
+use std::sync::Arc;
+use std::pin::Pin;
+use std::future::Future;
+use std::time::Duration;
+
+use tokio::runtime::{Builder, Handle, Runtime};
+
+use domain::light::Light;
+use domain::patch::LightPatch;
+use domain::mode::descriptor::ModeDescriptor;
+use crate::{Error, Provider, Result};
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+// Counterpart of Provider for network backends, calls may be awaited in
+// parallel and dropped at any point to cancel them
+pub trait AsyncProvider: Send + Sync {
+    fn name(self: &Self) -> &str;
+    fn list(self: &Self) -> BoxFuture<'_, Result<Vec<Light>>>;
+    fn get<'a>(self: &'a Self, id: &'a str) -> BoxFuture<'a, Result<Light>>;
+    fn sync<'a>(self: &'a Self, light: &'a Light) -> BoxFuture<'a, Result<()>>;
+
+    // See Provider::supports_transition
+    fn supports_transition(self: &Self) -> bool {
+        false
+    }
+
+    fn sync_transition<'a>(
+        self: &'a Self,
+        light: &'a Light,
+        _duration: Duration
+    ) -> BoxFuture<'a, Result<()>> {
+        self.sync(light)
+    }
+
+    // See Provider::modes
+    fn modes(self: &Self) -> BoxFuture<'_, Result<Vec<ModeDescriptor>>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+
+    // See Provider::apply
+    fn apply<'a>(
+        self: &'a Self,
+        id: &'a str,
+        patch: &'a LightPatch
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let mut light = self.get(id).await?;
+
+            patch.apply(&mut light).or_else(|err| {
+                Error::incorrect_state(self.name(), &light, err.to_string())
+            })?;
+
+            self.sync(&light).await
+        })
+    }
+}
+
+// Runs blocking provider on the blocking pool of the current runtime
+pub struct IntoAsync {
+    provider: Arc<dyn Provider>,
+    name: String,
+}
+
+impl IntoAsync {
+    pub fn new(provider: Arc<dyn Provider>) -> Self {
+        Self {
+            name: provider.name().to_string(),
+            provider,
+        }
+    }
+
+    fn offload<T, F>(self: &Self, f: F) -> BoxFuture<'_, Result<T>>
+    where T: Send + 'static,
+          F: FnOnce(&dyn Provider) -> Result<T> + Send + 'static {
+        let provider = self.provider.clone();
+
+        Box::pin(async move {
+            tokio::task::spawn_blocking(move || f(provider.as_ref())).await
+                .unwrap_or_else(|err| Error::internal(&self.name, Box::new(err)))
+        })
+    }
+}
+
+impl AsyncProvider for IntoAsync {
+    fn name(self: &Self) -> &str {
+        &self.name
+    }
+
+    fn list(self: &Self) -> BoxFuture<'_, Result<Vec<Light>>> {
+        self.offload(|provider| provider.list())
+    }
+
+    fn get<'a>(self: &'a Self, id: &'a str) -> BoxFuture<'a, Result<Light>> {
+        let id = id.to_string();
+        self.offload(move |provider| provider.get(&id))
+    }
+
+    fn sync<'a>(self: &'a Self, light: &'a Light) -> BoxFuture<'a, Result<()>> {
+        let light = light.clone();
+        self.offload(move |provider| provider.sync(&light))
+    }
+
+    fn supports_transition(self: &Self) -> bool {
+        self.provider.supports_transition()
+    }
+
+    fn sync_transition<'a>(
+        self: &'a Self,
+        light: &'a Light,
+        duration: Duration
+    ) -> BoxFuture<'a, Result<()>> {
+        let light = light.clone();
+        self.offload(move |provider| provider.sync_transition(&light, duration))
+    }
+
+    fn modes(self: &Self) -> BoxFuture<'_, Result<Vec<ModeDescriptor>>> {
+        self.offload(|provider| provider.modes())
+    }
+
+    fn apply<'a>(
+        self: &'a Self,
+        id: &'a str,
+        patch: &'a LightPatch
+    ) -> BoxFuture<'a, Result<()>> {
+        let id = id.to_string();
+        let patch = patch.clone();
+        self.offload(move |provider| provider.apply(&id, &patch))
+    }
+}
+
+// Blocks caller until async provider is done. Calls are driven by a runtime
+// of its own, within another runtime they are driven from a separate thread,
+// since that one can't be blocked
+pub struct IntoBlocking {
+    provider: Arc<dyn AsyncProvider>,
+    // Only taken on drop
+    runtime: Option<Runtime>,
+    timeout: Option<Duration>,
+}
+
+impl IntoBlocking {
+    pub fn new(provider: Arc<dyn AsyncProvider>) -> std::io::Result<Self> {
+        Ok(Self {
+            provider,
+            runtime: Some(Builder::new_multi_thread()
+                .worker_threads(1)
+                .enable_time()
+                .build()?),
+            timeout: None,
+        })
+    }
+
+    pub fn with_timeout(mut self: Self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    fn wait<T: Send>(self: &Self, future: BoxFuture<'_, Result<T>>) -> Result<T> {
+        let runtime = self.runtime.as_ref().expect("Runtime is kept until drop");
+        let future = async {
+            match self.timeout {
+                Some(timeout) => tokio::time::timeout(timeout, future).await
+                    .unwrap_or_else(|_| Error::timeout(self.name(), timeout)),
+                None => future.await,
+            }
+        };
+
+        match Handle::try_current() {
+            Ok(_) => std::thread::scope(|scope| {
+                scope.spawn(|| runtime.block_on(future)).join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            }),
+            Err(_) => runtime.block_on(future),
+        }
+    }
+}
+
+// Dropping a runtime blocks, which isn't allowed within another runtime
+impl Drop for IntoBlocking {
+    fn drop(self: &mut Self) {
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+impl Provider for IntoBlocking {
+    fn name(self: &Self) -> &str {
+        self.provider.name()
+    }
+
+    fn list(self: &Self) -> Result<Vec<Light>> {
+        self.wait(self.provider.list())
+    }
+
+    fn get(self: &Self, id: &str) -> Result<Light> {
+        self.wait(self.provider.get(id))
+    }
+
+    fn sync(self: &Self, light: &Light) -> Result<()> {
+        self.wait(self.provider.sync(light))
+    }
+
+    fn supports_transition(self: &Self) -> bool {
+        self.provider.supports_transition()
+    }
+
+    fn sync_transition(self: &Self, light: &Light,
+                       duration: Duration) -> Result<()> {
+        self.wait(self.provider.sync_transition(light, duration))
+    }
+
+    fn modes(self: &Self) -> Result<Vec<ModeDescriptor>> {
+        self.wait(self.provider.modes())
+    }
+
+    fn apply(self: &Self, id: &str, patch: &LightPatch) -> Result<()> {
+        self.wait(self.provider.apply(id, patch))
+    }
+}
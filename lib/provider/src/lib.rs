@@ -1,13 +1,100 @@
 
+use std::time::Duration;
+
 use domain::light::Light;
+use domain::patch::LightPatch;
+use domain::mode::descriptor::{self, ModeDescriptor};
+
+#[cfg(feature = "async")]
+pub mod asynchronous;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-pub trait Provider {
+// Providers are shared between threads, so calls may come in parallel
+pub trait Provider: Send + Sync {
     fn name(self: &Self) -> &str;
     fn list(self: &Self) -> Result<Vec<Light>>;
     fn get(self: &Self, id: &str) -> Result<Light>;
     fn sync(self: &Self, light: &Light) -> Result<()>;
+
+    // Providers able to fade to a state by themselves, the rest are fed
+    // with intermediate states
+    fn supports_transition(self: &Self) -> bool {
+        false
+    }
+
+    fn sync_transition(self: &Self, light: &Light,
+                       _duration: Duration) -> Result<()> {
+        self.sync(light)
+    }
+
+    // Empty catalog means modes aren't advertised, so only sync can tell
+    // whether one is supported
+    fn modes(self: &Self) -> Result<Vec<ModeDescriptor>> {
+        Ok(Vec::new())
+    }
+
+    // Providers able to change a part of the state send only the patch,
+    // the rest get the whole merged light
+    fn apply(self: &Self, id: &str, patch: &LightPatch) -> Result<()> {
+        merge_and_sync(self, id, patch)
+    }
+}
+
+// Fallback for providers without partial updates
+pub fn merge_and_sync<P: Provider + ?Sized>(provider: &P, id: &str,
+                                            patch: &LightPatch) -> Result<()> {
+    let mut light = provider.get(id)?;
+
+    patch.apply(&mut light).or_else(|err| {
+        Error::incorrect_state(provider.name(), &light, err.to_string())
+    })?;
+
+    provider.sync(&light)
+}
+
+// For providers sending only the set parts of the state, nothing has to be
+// fetched first
+pub fn apply_partial<P: Provider + ?Sized>(provider: &P, id: &str,
+                                           patch: &LightPatch) -> Result<()> {
+    provider.sync(&partial_light(provider.name(), id, patch)?)
+}
+
+// Checks mode of the light against the catalog before it's synced, lights
+// without a mode pass
+pub fn check_mode(provider: &str, catalog: &[ModeDescriptor],
+                  light: &Light) -> Result<()> {
+    match light.get_mode() {
+        Ok(mode) if !catalog.is_empty() => {
+            descriptor::validate(catalog, mode).or_else(|err| {
+                Error::incorrect_state(provider, light, err.to_string())
+            })
+        },
+        _ => Ok(()),
+    }
+}
+
+// Light holding nothing but the patch, suits providers sending only the
+// parts of the state which are set
+pub fn partial_light(provider: &str, id: &str,
+                     patch: &LightPatch) -> Result<Light> {
+    let mut light = Light::new(provider.to_string(), id.to_string(),
+                               patch.capabilities());
+
+    patch.apply(&mut light).or_else(|err| {
+        Error::incorrect_state(provider, &light, err.to_string())
+    })?;
+
+    Ok(light)
+}
+
+pub fn check_patch(provider: &str, catalog: &[ModeDescriptor], id: &str,
+                   patch: &LightPatch) -> Result<()> {
+    match patch.mode {
+        Some(_) => check_mode(provider, catalog,
+                              &partial_light(provider, id, patch)?),
+        None => Ok(()),
+    }
 }
 
 #[derive(Debug)]
@@ -22,7 +109,8 @@ pub enum ErrorType {
     IncorrectLight(Light),
     IncorrectState(Light, String),
     ForeignLight(Light),
-    Internal(Box<dyn std::error::Error>),
+    Internal(Box<dyn std::error::Error + Send + Sync>),
+    Timeout(Duration),
 }
 
 impl Error {
@@ -56,12 +144,19 @@ impl Error {
     }
 
     pub fn internal<T>(provider: &str,
-                       err: Box<dyn std::error::Error>) -> Result<T> {
+                       err: Box<dyn std::error::Error + Send + Sync>) -> Result<T> {
         Err(Self {
             provider: provider.to_string(),
             etype: ErrorType::Internal(err),
         })
     }
+
+    pub fn timeout<T>(provider: &str, timeout: Duration) -> Result<T> {
+        Err(Self {
+            provider: provider.to_string(),
+            etype: ErrorType::Timeout(timeout),
+        })
+    }
 }
 
 impl From<Error> for ErrorType {
@@ -100,6 +195,9 @@ impl std::fmt::Display for ErrorType {
             Self::Internal(err) => {
                 write!(f, "Internal error occured\n{}", err)
             },
+            Self::Timeout(timeout) => {
+                write!(f, "No answer in {} ms", timeout.as_millis())
+            },
         }
     }
 }
@@ -0,0 +1,201 @@
+
+use std::str::FromStr;
+
+use crate::time::Moment;
+
+// Classic five field expression: minute, hour, day of month, month and day
+// of week. Fields take "*", values, ranges, lists and "/step"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    source: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+struct Field {
+    name: &'static str,
+    min: u32,
+    max: u32,
+}
+
+const FIELDS: [Field; 5] = [
+    Field { name: "minute", min: 0, max: 59 },
+    Field { name: "hour", min: 0, max: 23 },
+    Field { name: "day", min: 1, max: 31 },
+    Field { name: "month", min: 1, max: 12 },
+    Field { name: "weekday", min: 0, max: 7 },
+];
+
+impl Field {
+    fn value(self: &Self, value: &str) -> Result<u32, Error> {
+        value.parse::<u32>().ok()
+            .filter(|value| (self.min..=self.max).contains(value))
+            .ok_or_else(|| Error::new(format!(
+                "{} \"{}\" isn't in {}-{}", self.name, value, self.min,
+                self.max
+            )))
+    }
+
+    fn parse(self: &Self, field: &str) -> Result<u64, Error> {
+        field.split(',').try_fold(0, |mask, part| {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => (range, step.parse::<u32>().ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| Error::new(format!(
+                        "Incorrect {} step \"{}\"", self.name, step
+                    )))?),
+                None => (part, 1),
+            };
+
+            let (from, to) = match range.split_once('-') {
+                _ if range == "*" => (self.min, self.max),
+                Some((from, to)) => (self.value(from)?, self.value(to)?),
+                None if step > 1 => (self.value(range)?, self.max),
+                None => {
+                    let value = self.value(range)?;
+                    (value, value)
+                },
+            };
+
+            if from > to {
+                return Err(Error::new(format!(
+                    "Empty {} range \"{}\"", self.name, range
+                )));
+            }
+
+            Ok((from..=to).step_by(step as usize)
+                .fold(mask, |mask, value| mask | 1 << value))
+        })
+    }
+}
+
+impl Cron {
+    pub fn matches(self: &Self, moment: &Moment) -> bool {
+        let day = self.days & 1 << moment.date.day != 0;
+        let weekday = self.weekdays & 1 << moment.date.weekday() != 0;
+
+        // When both day fields are restricted either of them is enough
+        let date = match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        };
+
+        self.minutes & 1 << moment.minute != 0
+            && self.hours & 1 << moment.hour != 0
+            && self.months & 1 << moment.date.month != 0
+            && date
+    }
+}
+
+impl FromStr for Cron {
+    type Err = Error;
+
+    fn from_str(source: &str) -> Result<Self, Error> {
+        let fields: Vec<&str> = source.split_whitespace().collect();
+
+        if fields.len() != FIELDS.len() {
+            return Err(Error::new(format!(
+                "Expected {} fields, got {}", FIELDS.len(), fields.len()
+            )));
+        }
+
+        let masks = fields.iter().zip(FIELDS.iter())
+            .map(|(field, spec)| spec.parse(field))
+            .collect::<Result<Vec<u64>, Error>>()?;
+
+        // Both 0 and 7 stand for sunday
+        let weekdays = masks[4] & 0x7f | (masks[4] >> 7 & 1);
+
+        Ok(Self {
+            source: fields.join(" "),
+            minutes: masks[0],
+            hours: masks[1],
+            days: masks[2],
+            months: masks[3],
+            weekdays,
+            any_day: fields[2] == "*",
+            any_weekday: fields[4] == "*",
+        })
+    }
+}
+
+impl std::fmt::Display for Cron {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.source.fmt(f)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error(String);
+
+impl Error {
+    fn new(msg: String) -> Self {
+        Self(msg)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Incorrect cron expression: {}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::Date;
+
+    fn at(date: Date, hour: u32, minute: u32) -> Moment {
+        Moment { date, hour, minute }
+    }
+
+    #[test]
+    fn weekdays() {
+        let cron: Cron = "0 7 * * 1-5".parse().expect("Correct");
+        let monday = Date::new(2025, 3, 10);
+        let saturday = Date::new(2025, 3, 15);
+
+        assert!(cron.matches(&at(monday, 7, 0)));
+        assert!(!cron.matches(&at(monday, 7, 1)));
+        assert!(!cron.matches(&at(monday, 8, 0)));
+        assert!(!cron.matches(&at(saturday, 7, 0)));
+    }
+
+    #[test]
+    fn steps_and_lists() {
+        let cron: Cron = "*/15 9,18-20 * 1-3 *".parse().expect("Correct");
+        let date = Date::new(2025, 2, 1);
+
+        assert!(cron.matches(&at(date, 9, 45)));
+        assert!(cron.matches(&at(date, 19, 0)));
+        assert!(!cron.matches(&at(date, 19, 10)));
+        assert!(!cron.matches(&at(date, 12, 0)));
+        assert!(!cron.matches(&at(Date::new(2025, 4, 1), 9, 0)));
+    }
+
+    #[test]
+    fn sunday_and_day_of_month() {
+        let sunday: Cron = "0 0 * * 7".parse().expect("Correct");
+        let either: Cron = "0 0 1 * 0".parse().expect("Correct");
+
+        assert!(sunday.matches(&at(Date::new(2025, 3, 16), 0, 0)));
+        assert!(either.matches(&at(Date::new(2025, 3, 16), 0, 0)));
+        assert!(either.matches(&at(Date::new(2025, 4, 1), 0, 0)));
+        assert!(!either.matches(&at(Date::new(2025, 4, 2), 0, 0)));
+    }
+
+    #[test]
+    fn incorrect() {
+        for source in ["", "* * * *", "60 * * * *", "* 5-2 * * *",
+                       "*/0 * * * *", "a * * * *", "* * 0 * *"] {
+            assert!(source.parse::<Cron>().is_err(), "{}", source);
+        }
+    }
+}
@@ -0,0 +1,525 @@
+
+use std::time::Duration;
+
+use domain::light;
+use domain::brightness::Brightness;
+use logic::batch::Batch;
+use logic::facade::Facade;
+use logic::managers::{fetch, local};
+use logic::strategies::{Strategy, StrategyResult, history, list, power, sync};
+use logic::strategies::power::Power;
+use logic::strategies::sync::fetch_and_sync;
+use logic::group;
+
+use crate::rule::{Action, Rule, Rules, Target, Trigger};
+use crate::sun::{Event, Location};
+use crate::time::{Clock, Date, Moment, MINUTES_PER_DAY};
+
+// Rules missed while the daemon was asleep are fired only if it woke up
+// within this many minutes, older ones are dropped
+pub const CATCH_UP: i64 = 60;
+
+pub struct Scheduler {
+    rules: Rules,
+    last: Option<i64>,
+}
+
+// Histories which couldn't be saved don't fail the rule, lights were
+// synced already, so they come with the result
+#[derive(Debug)]
+pub struct Fired {
+    pub rule: String,
+    pub at: Moment,
+    pub result: Result<Vec<local::Error>, Error>,
+}
+
+impl Scheduler {
+    pub fn new(rules: Rules) -> Self {
+        Self { rules, last: None }
+    }
+
+    pub fn rules(self: &Self) -> &Rules {
+        &self.rules
+    }
+
+    // Already passed minutes aren't fired again by the new rules
+    pub fn set_rules(self: &mut Self, rules: Rules) {
+        self.rules = rules;
+    }
+
+    // Enabled rules to be fired at the minute since unix epoch
+    pub fn due(self: &Self, minute: i64) -> Vec<&Rule> {
+        let offset = self.rules.offset(minute);
+        let today = (minute + offset).div_euclid(MINUTES_PER_DAY);
+        let moment = Moment::from_minute(minute, offset);
+
+        self.rules.rules.iter()
+            .filter(|rule| rule.enabled)
+            .filter(|rule| match (&rule.trigger, self.rules.location) {
+                (Trigger::Cron(cron), _) => cron.matches(&moment),
+                (trigger, Some(location)) => {
+                    let (event, shift) = trigger.event()
+                        .expect("Only cron has no event");
+
+                    // Offset may move the event to another day
+                    (today - 1..=today + 1).any(|day| {
+                        sun_minute(&location, Date::from_days(day), event,
+                                   offset)
+                            .is_some_and(|at| at + shift == minute)
+                    })
+                },
+                _ => false,
+            })
+            .collect()
+    }
+
+    // Fires every rule due since the previous tick, the first tick only
+    // looks at the current minute
+    pub fn tick(self: &mut Self, facade: &mut dyn Facade,
+                now: i64) -> Vec<Fired> {
+        let minute = now.div_euclid(60);
+        let from = match self.last {
+            Some(last) => (last + 1).max(minute - CATCH_UP + 1),
+            None => minute,
+        };
+
+        let mut fired = Vec::new();
+
+        for minute in from..=minute {
+            for rule in self.due(minute) {
+                fired.push(Fired {
+                    rule: rule.name.clone(),
+                    at: Moment::from_minute(minute, self.rules.offset(minute)),
+                    result: execute(facade, &rule.action),
+                });
+            }
+        }
+
+        self.last = Some(self.last.map_or(minute, |last| last.max(minute)));
+        fired
+    }
+
+    // Ticks and sleeps till the start of the next minute
+    pub fn step(self: &mut Self, facade: &mut dyn Facade,
+                clock: &dyn Clock) -> Vec<Fired> {
+        let fired = self.tick(facade, clock.now());
+        let left = 60 - clock.now().rem_euclid(60);
+        clock.sleep(Duration::from_secs(left as u64));
+
+        fired
+    }
+}
+
+// Minute since unix epoch of the event on the local date
+fn sun_minute(location: &Location, date: Date, event: Event,
+              offset: i64) -> Option<i64> {
+    let start = date.days() * MINUTES_PER_DAY;
+    let mut at = start + location.event(date, event)?;
+
+    // Algorithm gives time of the day in UTC, which may belong to the
+    // neighbouring local day
+    if at + offset < start {
+        at += MINUTES_PER_DAY;
+    } else if at + offset >= start + MINUTES_PER_DAY {
+        at -= MINUTES_PER_DAY;
+    }
+
+    Some(at)
+}
+
+// Power and brightness change the current state of the lights, saved
+// names only point to them
+pub fn execute(facade: &mut dyn Facade,
+               action: &Action) -> Result<Vec<local::Error>, Error> {
+    match action {
+        Action::Power { on, target: Target::Names(names) } => {
+            let action = if *on { power::Action::On } else { power::Action::Off };
+
+            run(facade, Power::names(names.iter().map(String::as_str), action))
+                .map(|powered| powered.unrecorded)
+        },
+        Action::Power { on, target: Target::Group(name) } => {
+            let on = *on;
+
+            // Members without power capability are left as they are
+            run_group(facade, fetch_and_sync::group(name, move |light| {
+                light.set_power(on).unwrap_or_default()
+            }))
+        },
+        Action::Brightness { level, target: Target::Names(names) } => {
+            let level = *level;
+            let ids: Vec<_> = run_batch(facade, list::registry::dumps::get_by_names(
+                names.iter().map(String::as_str)
+            ))?.into_iter().map(|light| light.provider).collect();
+
+            run_batch(facade, fetch_and_sync::multiple(ids.iter(), move |light| {
+                light.set_brightness(Brightness::new(level)).unwrap_or_default()
+            })).map(unrecorded)
+        },
+        Action::Brightness { level, target: Target::Group(name) } => {
+            let level = *level;
+
+            run_group(facade, fetch_and_sync::group(name, move |light| {
+                light.set_brightness(Brightness::new(level)).unwrap_or_default()
+            }))
+        },
+        Action::Load { target: Target::Names(names) } => {
+            run_batch(facade, sync::load_and_sync::multiple(
+                names.iter().map(String::as_str), |_| {}
+            )).map(unrecorded)
+        },
+        Action::Load { target: Target::Group(name) } => {
+            run_group(facade, sync::load_and_sync::group(name, |_| {}))
+        },
+        Action::Default { target: Target::Names(names) } => {
+            run_batch(facade, sync::default_and_sync::multiple(
+                names.iter().map(String::as_str), |_| {}
+            )).map(unrecorded)
+        },
+        Action::Default { target: Target::Group(name) } => {
+            run_group(facade, sync::default_and_sync::group(name, |_| {}))
+        },
+    }
+}
+
+fn unrecorded(recorded: Vec<history::Recorded>) -> Vec<local::Error> {
+    recorded.into_iter().filter_map(Result::err).collect()
+}
+
+fn run<S, T, E>(facade: &mut dyn Facade, mut strategy: S) -> Result<T, Error>
+where S: Strategy + StrategyResult<Result = Result<T, E>>,
+      Error: From<E> {
+    facade.accept(&mut strategy);
+
+    strategy.result()
+        .ok_or(Error::NotExecuted)?
+        .map_err(Error::from)
+}
+
+fn run_batch<S, K, R, E>(facade: &mut dyn Facade,
+                         mut strategy: S) -> Result<Vec<R>, Error>
+where S: Strategy + StrategyResult<Result = Batch<K, R, E>>,
+      Error: From<E> {
+    facade.accept(&mut strategy);
+
+    strategy.result()
+        .ok_or(Error::NotExecuted)?
+        .into_result()
+        .map_err(Error::from)
+}
+
+fn run_group<S, K>(facade: &mut dyn Facade,
+                   mut strategy: S) -> Result<Vec<local::Error>, Error>
+where S: Strategy
+       + StrategyResult<Result = Result<
+           Batch<K, history::Recorded, group::Error>,
+           group::Error
+       >> {
+    facade.accept(&mut strategy);
+
+    strategy.result()
+        .ok_or(Error::NotExecuted)??
+        .into_result()
+        .map(unrecorded)
+        .map_err(Error::from)
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Fetch(fetch::Error),
+    Local(local::Error),
+    Group(group::Error),
+    Light(light::Error),
+    NotExecuted,
+}
+
+impl From<sync::Error> for Error {
+    fn from(err: sync::Error) -> Self {
+        match err {
+            sync::Error::Fetch(err) => Error::Fetch(err),
+            sync::Error::Local(err) => Error::Local(err),
+        }
+    }
+}
+
+impl From<fetch::Error> for Error {
+    fn from(err: fetch::Error) -> Self {
+        Error::Fetch(err)
+    }
+}
+
+impl From<local::Error> for Error {
+    fn from(err: local::Error) -> Self {
+        Error::Local(err)
+    }
+}
+
+impl From<power::Error> for Error {
+    fn from(err: power::Error) -> Self {
+        match err {
+            power::Error::Fetch(err) => Error::Fetch(err),
+            power::Error::Local(err) => Error::Local(err),
+            power::Error::Light(err) => Error::Light(err),
+        }
+    }
+}
+
+impl From<group::Error> for Error {
+    fn from(err: group::Error) -> Self {
+        Error::Group(err)
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Fetch(err) => Some(err),
+            Error::Local(err) => Some(err),
+            Error::Group(err) => Some(err),
+            Error::Light(err) => Some(err),
+            Error::NotExecuted => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Fetch(err) => err.fmt(f),
+            Error::Local(err) => err.fmt(f),
+            Error::Group(err) => err.fmt(f),
+            Error::Light(err) => err.fmt(f),
+            Error::NotExecuted => write!(f, "Strategy wasn't executed"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use std::cell::RefCell;
+
+    use domain::capabilities::Capability;
+    use domain::group::{Group, Member};
+    use domain::light::{Light, ProviderID};
+    use local_registry::Registry;
+    use logic::context::Context;
+    use logic::facade::default::DefaultFacade;
+    use memory_registry::MemoryRegistry;
+    use mock_provider::{MockProvider, VirtualLight};
+    use provider::Provider;
+
+    use super::*;
+    use crate::time::FakeClock;
+
+    const RULES: &str = r#"
+        utc_offset = 60
+
+        [location]
+        latitude = 51.5074
+        longitude = -0.1278
+
+        [[rules]]
+        name = "wake up"
+        trigger = { cron = "0 7 * * 1-5" }
+        action = { type = "power", on = true, target = { names = ["bed"] } }
+
+        [[rules]]
+        name = "night"
+        trigger = { cron = "0 22 * * *" }
+        action = { type = "brightness", level = 0.3, target = { group = "home" } }
+
+        [[rules]]
+        name = "evening"
+        trigger = { sunset = { offset = -30 } }
+        action = { type = "default", target = { names = ["bed"] } }
+    "#;
+
+    // Monday
+    fn monday() -> Date {
+        Date::new(2024, 6, 17)
+    }
+
+    fn facade() -> DefaultFacade {
+        let provider = MockProvider::new("mock")
+            .with_light(
+                VirtualLight::new("mock", "1", vec![Capability::Power,
+                                                    Capability::Brightness])
+                    .with_state(|light| {
+                        light.set_power(false)?;
+                        light.set_brightness(Brightness::new(0.5))
+                    })
+            )
+            .with_light(
+                VirtualLight::new("mock", "2", vec![Capability::Brightness])
+                    .with_state(|light| {
+                        light.set_brightness(Brightness::new(0.8))
+                    })
+            );
+
+        let mut registry = MemoryRegistry::new();
+        let mut bed = provider.state("1").expect("Light exists");
+        bed.name = "bed".to_string();
+        registry.dump(&bed).expect("Saved");
+
+        bed.set_power(true).expect("Capable");
+        bed.set_brightness(Brightness::new(0.9)).expect("Capable");
+        registry.default(&bed).expect("Saved");
+
+        registry.save_group(&Group::new("home".to_string(), vec![
+            Member::Saved("bed".to_string()),
+            Member::Light(ProviderID::new("mock".to_string(), "2".to_string())),
+        ])).expect("Saved");
+
+        let context = Context::new(vec![Box::new(provider) as Box<dyn Provider>],
+                                   Box::new(registry));
+
+        DefaultFacade::new(Rc::new(RefCell::new(context)))
+    }
+
+    fn lights(facade: &mut DefaultFacade) -> Vec<Light> {
+        let mut strategy = list::provider::Single::new("mock");
+        facade.accept(&mut strategy);
+
+        strategy.result().expect("Executed").expect("Listed")
+    }
+
+    fn fired(fired: &[Fired]) -> Vec<(String, String)> {
+        fired.iter()
+            .map(|fired| {
+                assert!(fired.result.is_ok(), "{:?}", fired.result);
+                (fired.rule.clone(), fired.at.to_string())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn cron() {
+        let rules = Rules::parse(RULES, false).expect("Correct rules");
+        let mut facade = facade();
+        let mut scheduler = Scheduler::new(rules);
+        let clock = FakeClock::at(monday(), 5, 59);
+
+        assert!(scheduler.step(&mut facade, &clock).is_empty());
+        assert!(!lights(&mut facade)[0].get_power().expect("Set"));
+
+        assert_eq!(fired(&scheduler.step(&mut facade, &clock)),
+                   [("wake up".to_string(), "2024-06-17 07:00".to_string())]);
+        assert!(lights(&mut facade)[0].get_power().expect("Set"));
+
+        clock.set(clock.now() + 15 * 3600);
+        assert_eq!(fired(&scheduler.step(&mut facade, &clock)),
+                   [("night".to_string(), "2024-06-17 22:00".to_string())]);
+
+        for light in lights(&mut facade) {
+            assert_eq!(**light.get_brightness().expect("Set"), 0.3);
+        }
+    }
+
+    #[test]
+    fn weekend() {
+        let rules = Rules::parse(RULES, false).expect("Correct rules");
+        let saturday = Date::from_days(monday().days() + 5);
+        let minute = (saturday.days() * MINUTES_PER_DAY) + 6 * 60;
+
+        assert!(Scheduler::new(rules).due(minute).is_empty());
+    }
+
+    #[test]
+    fn sunset() {
+        let rules = Rules::parse(RULES, false).expect("Correct rules");
+        let mut facade = facade();
+        let mut scheduler = Scheduler::new(rules);
+        let clock = FakeClock::at(monday(), 12, 0);
+
+        scheduler.step(&mut facade, &clock);
+        clock.set(clock.now() + 8 * 3600);
+
+        // Sunset is at about 20:21 UTC, 21:21 local
+        let fired = scheduler.step(&mut facade, &clock);
+        let at = fired[0].at;
+
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].rule, "evening");
+        assert!(at.hour == 20 && (45..=55).contains(&at.minute), "{}", at);
+        assert_eq!(**lights(&mut facade)[0].get_brightness().expect("Set"),
+                   0.9);
+    }
+
+    #[test]
+    fn missed_long_ago() {
+        let rules = Rules::parse(RULES, false).expect("Correct rules");
+        let mut facade = facade();
+        let mut scheduler = Scheduler::new(rules);
+        let clock = FakeClock::at(monday(), 5, 0);
+
+        scheduler.step(&mut facade, &clock);
+        clock.set(clock.now() + 3 * 3600);
+
+        assert!(scheduler.step(&mut facade, &clock).is_empty());
+    }
+
+    #[test]
+    fn failure_reported() {
+        let rules = Rules::parse(&RULES.replace("\"bed\"", "\"unknown\""),
+                                 false).expect("Correct rules");
+        let mut facade = facade();
+        let mut scheduler = Scheduler::new(rules);
+        let clock = FakeClock::at(monday(), 6, 0);
+
+        let fired = scheduler.tick(&mut facade, clock.now());
+
+        assert_eq!(fired.len(), 1);
+        assert!(matches!(fired[0].result, Err(Error::Local(_))));
+    }
+
+    #[test]
+    fn timezone() {
+        let rules = Rules::parse(&RULES.replace(
+            "utc_offset = 60", "timezone = \"GMT0BST,M3.5.0/1,M10.5.0\""
+        ), false).expect("Correct rules");
+        let scheduler = Scheduler::new(rules);
+        let at = |date: Date, hour: i64| {
+            date.days() * MINUTES_PER_DAY + hour * 60
+        };
+        let winter = Date::new(2024, 1, 15);
+
+        // Same local hour is an hour later in UTC once the clocks go back
+        assert!(!scheduler.due(at(monday(), 6)).is_empty());
+        assert!(scheduler.due(at(monday(), 7)).is_empty());
+        assert!(scheduler.due(at(winter, 6)).is_empty());
+        assert!(!scheduler.due(at(winter, 7)).is_empty());
+    }
+
+    #[test]
+    fn brightness_of_names() {
+        let mut facade = facade();
+        let target = Target::Names(vec!["bed".to_string()]);
+
+        execute(&mut facade, &Action::Brightness { level: 0.2, target })
+            .expect("Changed");
+
+        let lights = lights(&mut facade);
+        assert_eq!(**lights[0].get_brightness().expect("Set"), 0.2);
+        assert_eq!(**lights[1].get_brightness().expect("Set"), 0.8);
+        // Only the brightness is changed, saved power isn't restored
+        assert!(!lights[0].get_power().expect("Set"));
+    }
+
+    #[test]
+    fn power_recorded() {
+        let rules = Rules::parse(RULES, false).expect("Correct rules");
+        let mut facade = facade();
+        let clock = FakeClock::at(monday(), 6, 0);
+
+        Scheduler::new(rules).tick(&mut facade, clock.now());
+
+        let id = ProviderID::new("mock".to_string(), "1".to_string());
+        let mut strategy = history::Get::new(&id);
+        facade.accept(&mut strategy);
+        let history = strategy.result().expect("Executed").expect("Loaded");
+
+        assert_eq!(history.undo.len(), 1);
+        assert!(!history.undo[0].light.get_power().expect("Set"));
+    }
+}
@@ -0,0 +1,9 @@
+
+pub mod time;
+pub mod cron;
+pub mod sun;
+pub mod rule;
+pub mod engine;
+
+pub use engine::Scheduler;
+pub use rule::Rules;
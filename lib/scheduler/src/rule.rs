@@ -0,0 +1,341 @@
+
+use std::path::{Path, PathBuf};
+
+use serde::{Serialize, Deserialize};
+
+use crate::cron::Cron;
+use crate::sun::{Event, Location};
+use crate::time::Zone;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rules {
+    // Needed only by sun triggers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<Location>,
+    // Minutes local time is ahead of UTC, cron triggers are in local time.
+    // Fixed, so daylight saving time needs the time zone instead
+    #[serde(default)]
+    pub utc_offset: i64,
+    // Takes precedence over the fixed offset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<Zone>,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    pub name: String,
+    #[serde(default = "Rule::default_enabled")]
+    pub enabled: bool,
+    pub trigger: Trigger,
+    pub action: Action,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Trigger {
+    Cron(#[serde(with = "cron_string")] Cron),
+    // Offset in minutes, negative ones fire before the event
+    Sunrise {
+        #[serde(default)]
+        offset: i64,
+    },
+    Sunset {
+        #[serde(default)]
+        offset: i64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Action {
+    Power { on: bool, target: Target },
+    Brightness { level: f64, target: Target },
+    // Restores dumps of the saved lights
+    Load { target: Target },
+    // Restores defaults of the saved lights
+    Default { target: Target },
+}
+
+// Saved names, either listed or members of a group
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Target {
+    Names(Vec<String>),
+    Group(String),
+}
+
+mod cron_string {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use crate::cron::Cron;
+
+    pub fn serialize<S: Serializer>(
+        cron: &Cron,
+        serializer: S
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(cron)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D
+    ) -> Result<Cron, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl Rule {
+    fn default_enabled() -> bool {
+        true
+    }
+
+    pub fn new(name: &str, trigger: Trigger, action: Action) -> Self {
+        Self {
+            name: name.to_string(),
+            enabled: true,
+            trigger,
+            action,
+        }
+    }
+}
+
+impl Trigger {
+    pub fn event(self: &Self) -> Option<(Event, i64)> {
+        match self {
+            Trigger::Cron(_) => None,
+            Trigger::Sunrise { offset } => Some((Event::Sunrise, *offset)),
+            Trigger::Sunset { offset } => Some((Event::Sunset, *offset)),
+        }
+    }
+}
+
+impl std::fmt::Display for Trigger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self, self.event()) {
+            (Trigger::Cron(cron), _) => write!(f, "cron \"{}\"", cron),
+            (_, Some((event, 0))) => event.fmt(f),
+            (_, Some((event, offset))) => write!(f, "{} {:+} min", event,
+                                                 offset),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Rules {
+    // Minutes local time is ahead of UTC at the minute since unix epoch
+    pub fn offset(self: &Self, minute: i64) -> i64 {
+        match &self.timezone {
+            Some(zone) => zone.offset(minute),
+            None => self.utc_offset,
+        }
+    }
+
+    pub fn parse(content: &str, json: bool)
+        -> std::result::Result<Self, Box<dyn std::error::Error>> {
+        let rules: Self = if json {
+            serde_json::from_str(content)?
+        } else {
+            toml::from_str(content)?
+        };
+
+        rules.check()?;
+        Ok(rules)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+
+        let content = std::fs::read_to_string(path)
+            .map_err(|err| Error::Io(path.to_path_buf(), err))?;
+
+        Self::parse(&content, is_json(path))
+            .map_err(|err| Error::Parse(path.to_path_buf(), err))
+    }
+
+    pub fn save<P: AsRef<Path>>(self: &Self, path: P) -> Result<()> {
+        let path = path.as_ref();
+
+        let content = if is_json(path) {
+            serde_json::to_string_pretty(self)
+                .map_err(|err| Error::Parse(path.to_path_buf(), err.into()))?
+        } else {
+            toml::to_string_pretty(self)
+                .map_err(|err| Error::Parse(path.to_path_buf(), err.into()))?
+        };
+
+        std::fs::write(path, content)
+            .map_err(|err| Error::Io(path.to_path_buf(), err))
+    }
+
+    pub fn check(self: &Self) -> Result<()> {
+        for (i, rule) in self.rules.iter().enumerate() {
+            if self.rules[..i].iter().any(|other| other.name == rule.name) {
+                return Err(Error::DuplicateRule(rule.name.clone()));
+            }
+
+            if rule.trigger.event().is_some() && self.location.is_none() {
+                return Err(Error::NoLocation(rule.name.clone()));
+            }
+
+            if let Action::Brightness { level, .. } = rule.action {
+                if !(0.0..=1.0).contains(&level) {
+                    return Err(Error::Level(rule.name.clone(), level));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "json")
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, std::io::Error),
+    Parse(PathBuf, Box<dyn std::error::Error>),
+    DuplicateRule(String),
+    NoLocation(String),
+    Level(String, f64),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(_, err) => Some(err),
+            Error::Parse(_, err) => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(path, err) => {
+                write!(f, "Can't access rules \"{}\": {}", path.display(), err)
+            },
+            Error::Parse(path, err) => {
+                write!(f, "Can't parse rules \"{}\": {}", path.display(), err)
+            },
+            Error::DuplicateRule(name) => {
+                write!(f, "Rule \"{}\" defined twice", name)
+            },
+            Error::NoLocation(name) => {
+                write!(f, "Rule \"{}\" depends on the sun, but location \
+                           isn't set", name)
+            },
+            Error::Level(name, level) => {
+                write!(f, "Rule \"{}\": brightness {} isn't in 0-1", name,
+                       level)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::{Date, MINUTES_PER_DAY};
+
+    const RULES: &str = r#"
+        utc_offset = 180
+
+        [location]
+        latitude = 55.75
+        longitude = 37.62
+
+        [[rules]]
+        name = "wake up"
+        trigger = { cron = "0 7 * * 1-5" }
+        action = { type = "power", on = true, target = { names = ["bed"] } }
+
+        [[rules]]
+        name = "night"
+        trigger = { cron = "0 22 * * *" }
+        action = { type = "brightness", level = 0.3, target = { group = "home" } }
+
+        [[rules]]
+        name = "evening"
+        enabled = false
+        trigger = { sunset = { offset = -15 } }
+        action = { type = "default", target = { names = ["evening"] } }
+    "#;
+
+    #[test]
+    fn toml() {
+        let rules = Rules::parse(RULES, false).expect("Correct rules");
+
+        assert_eq!(rules.utc_offset, 180);
+        assert_eq!(rules.rules.len(), 3);
+        assert_eq!(rules.rules[0].trigger.to_string(), "cron \"0 7 * * 1-5\"");
+        assert_eq!(rules.rules[1].action, Action::Brightness {
+            level: 0.3,
+            target: Target::Group("home".to_string()),
+        });
+        assert!(!rules.rules[2].enabled);
+        assert_eq!(rules.rules[2].trigger, Trigger::Sunset { offset: -15 });
+    }
+
+    #[test]
+    fn persisted() {
+        let rules = Rules::parse(RULES, false).expect("Correct rules");
+        let dir = tempfile::tempdir().expect("Temporary directory");
+
+        for file in ["rules.toml", "rules.json"] {
+            let path = dir.path().join(file);
+            rules.save(&path).expect("Saved");
+
+            assert_eq!(Rules::load(&path).expect("Loaded"), rules);
+        }
+    }
+
+    #[test]
+    fn incorrect() {
+        let cron = r#"[[rules]]
+            name = "a"
+            trigger = { cron = "0 25 * * *" }
+            action = { type = "load", target = { names = ["a"] } }"#;
+        let sun = r#"[[rules]]
+            name = "a"
+            trigger = { sunrise = {} }
+            action = { type = "load", target = { names = ["a"] } }"#;
+        let level = r#"[[rules]]
+            name = "a"
+            trigger = { cron = "* * * * *" }
+            action = { type = "brightness", level = 2.0, target = { names = ["a"] } }"#;
+
+        assert!(Rules::parse(cron, false).is_err());
+        assert!(Rules::parse(sun, false).is_err());
+        assert!(Rules::parse(level, false).is_err());
+        assert!(Rules::parse(&format!("{}\n{}", cron.replace("25", "2"),
+                                      cron.replace("25", "3")), false)
+            .is_err());
+    }
+
+    #[test]
+    fn timezone() {
+        let rules = Rules::parse(&RULES.replace(
+            "utc_offset = 180", "timezone = \"EET-2EEST,M3.5.0/3,M10.5.0/4\""
+        ), false).expect("Correct rules");
+        let noon = |date: Date| date.days() * MINUTES_PER_DAY + 12 * 60;
+
+        assert_eq!(rules.timezone.as_ref().map(Zone::name),
+                   Some("EET-2EEST,M3.5.0/3,M10.5.0/4"));
+        assert_eq!(rules.offset(noon(Date::new(2025, 1, 15))), 120);
+        assert_eq!(rules.offset(noon(Date::new(2025, 7, 15))), 180);
+
+        let unknown = RULES.replace("utc_offset = 180",
+                                    "timezone = \"Nowhere/Atlantis\"");
+        assert!(Rules::parse(&unknown, false).is_err());
+    }
+}
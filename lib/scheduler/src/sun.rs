@@ -0,0 +1,138 @@
+
+use serde::{Serialize, Deserialize};
+
+use crate::time::Date;
+
+// Official zenith, accounts for refraction and size of the sun disk
+const ZENITH: f64 = 90.833;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Location {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Event {
+    Sunrise,
+    Sunset,
+}
+
+impl Location {
+    pub fn new(latitude: f64, longitude: f64) -> Self {
+        Self { latitude, longitude }
+    }
+
+    // Minutes since UTC midnight of the date, none during polar day or
+    // night. Almanac for Computers algorithm, good to a couple of minutes
+    pub fn event(self: &Self, date: Date, event: Event) -> Option<i64> {
+        let hour = self.longitude / 15.0;
+        let base = match event {
+            Event::Sunrise => 6.0,
+            Event::Sunset => 18.0,
+        };
+        let t = date.ordinal() as f64 + (base - hour) / 24.0;
+
+        let anomaly = 0.9856 * t - 3.289;
+        let longitude = (anomaly + 1.916 * sin(anomaly)
+                         + 0.020 * sin(2.0 * anomaly) + 282.634)
+            .rem_euclid(360.0);
+
+        let mut ascension = atan(0.91764 * tan(longitude)).rem_euclid(360.0);
+        ascension += (longitude / 90.0).floor() * 90.0
+            - (ascension / 90.0).floor() * 90.0;
+        ascension /= 15.0;
+
+        let sin_dec = 0.39782 * sin(longitude);
+        let cos_dec = sin_dec.asin().cos();
+        let cos_hour = (cos(ZENITH) - sin_dec * sin(self.latitude))
+            / (cos_dec * cos(self.latitude));
+
+        if !(-1.0..=1.0).contains(&cos_hour) {
+            return None;
+        }
+
+        let angle = match event {
+            Event::Sunrise => 360.0 - acos(cos_hour),
+            Event::Sunset => acos(cos_hour),
+        } / 15.0;
+
+        let local = angle + ascension - 0.06571 * t - 6.622;
+        let utc = (local - hour).rem_euclid(24.0);
+
+        Some((utc * 60.0).round() as i64)
+    }
+}
+
+fn sin(degrees: f64) -> f64 {
+    degrees.to_radians().sin()
+}
+
+fn cos(degrees: f64) -> f64 {
+    degrees.to_radians().cos()
+}
+
+fn tan(degrees: f64) -> f64 {
+    degrees.to_radians().tan()
+}
+
+fn atan(value: f64) -> f64 {
+    value.atan().to_degrees()
+}
+
+fn acos(value: f64) -> f64 {
+    value.acos().to_degrees()
+}
+
+impl std::fmt::Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Event::Sunrise => write!(f, "sunrise"),
+            Event::Sunset => write!(f, "sunset"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(actual: Option<i64>, hour: i64, minute: i64) {
+        let actual = actual.expect("Sun rises and sets");
+        let expected = hour * 60 + minute;
+
+        assert!((actual - expected).abs() <= 3,
+                "Expected {:02}:{:02}, got {:02}:{:02}",
+                hour, minute, actual / 60, actual % 60);
+    }
+
+    #[test]
+    fn london_midsummer() {
+        let london = Location::new(51.5074, -0.1278);
+        let date = Date::new(2024, 6, 21);
+
+        close(london.event(date, Event::Sunrise), 3, 43);
+        close(london.event(date, Event::Sunset), 20, 21);
+    }
+
+    #[test]
+    fn east_of_greenwich() {
+        let moscow = Location::new(55.7558, 37.6173);
+        let date = Date::new(2024, 12, 21);
+
+        // 08:59 and 15:57 local time, three hours ahead of UTC
+        close(moscow.event(date, Event::Sunrise), 5, 59);
+        close(moscow.event(date, Event::Sunset), 12, 57);
+    }
+
+    #[test]
+    fn polar() {
+        let svalbard = Location::new(78.2232, 15.6267);
+
+        assert_eq!(svalbard.event(Date::new(2024, 6, 21), Event::Sunset),
+                   None);
+        assert_eq!(svalbard.event(Date::new(2024, 12, 21), Event::Sunrise),
+                   None);
+    }
+}
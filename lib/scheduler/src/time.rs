@@ -0,0 +1,181 @@
+
+use std::cell::Cell;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use tz::TimeZone;
+
+pub use domain::date::Date;
+
+pub const MINUTES_PER_DAY: i64 = 24 * 60;
+
+// Wall time source, unlike transition clock it's bound to the calendar
+pub trait Clock {
+    // Seconds since unix epoch, UTC
+    fn now(self: &Self) -> i64;
+    fn sleep(self: &Self, duration: Duration);
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(self: &Self) -> i64 {
+        SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs() as i64)
+            .unwrap_or_default()
+    }
+
+    fn sleep(self: &Self, duration: Duration) {
+        std::thread::sleep(duration)
+    }
+}
+
+// Time only moves when someone sleeps or it's set explicitly
+pub struct FakeClock {
+    now: Cell<i64>,
+}
+
+impl FakeClock {
+    pub fn new(now: i64) -> Self {
+        Self { now: Cell::new(now) }
+    }
+
+    pub fn at(date: Date, hour: u32, minute: u32) -> Self {
+        Self::new(date.days() * 86400 + (hour * 3600 + minute * 60) as i64)
+    }
+
+    pub fn set(self: &Self, now: i64) {
+        self.now.set(now)
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(self: &Self) -> i64 {
+        self.now.get()
+    }
+
+    fn sleep(self: &Self, duration: Duration) {
+        self.now.set(self.now.get() + duration.as_secs() as i64)
+    }
+}
+
+// Time zone by name from the system database, e.g. "Europe/London", or
+// a POSIX TZ string. Offset is resolved for each minute, so it follows
+// daylight saving time
+#[derive(Debug, Clone, PartialEq)]
+pub struct Zone {
+    name: String,
+    zone: TimeZone,
+}
+
+impl Zone {
+    pub fn name(self: &Self) -> &str {
+        &self.name
+    }
+
+    // Minutes local time is ahead of UTC at the minute since unix epoch
+    pub fn offset(self: &Self, minute: i64) -> i64 {
+        self.zone.find_local_time_type(minute * 60)
+            .map_or(0, |local| local.ut_offset() as i64 / 60)
+    }
+}
+
+impl FromStr for Zone {
+    type Err = tz::Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(Self {
+            name: name.to_string(),
+            zone: TimeZone::from_posix_tz(name)?,
+        })
+    }
+}
+
+impl std::fmt::Display for Zone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.name.fmt(f)
+    }
+}
+
+impl Serialize for Zone {
+    fn serialize<S: Serializer>(
+        self: &Self,
+        serializer: S
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.name)
+    }
+}
+
+impl<'de> Deserialize<'de> for Zone {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D
+    ) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+
+        // Names not found in the database are parsed as TZ strings, so
+        // the reason tells little
+        name.parse().map_err(|_| {
+            serde::de::Error::custom(format!("Unknown time zone \"{}\"", name))
+        })
+    }
+}
+
+// Local calendar minute for the offset in effect at that minute
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Moment {
+    pub date: Date,
+    pub hour: u32,
+    pub minute: u32,
+}
+
+impl Moment {
+    // Minute since unix epoch in UTC and offset of the local time in minutes
+    pub fn from_minute(minute: i64, offset: i64) -> Self {
+        let local = minute + offset;
+        let of_day = local.rem_euclid(MINUTES_PER_DAY) as u32;
+
+        Self {
+            date: Date::from_days(local.div_euclid(MINUTES_PER_DAY)),
+            hour: of_day / 60,
+            minute: of_day % 60,
+        }
+    }
+}
+
+impl std::fmt::Display for Moment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {:02}:{:02}", self.date, self.hour, self.minute)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_moment() {
+        let minute = Date::new(2025, 1, 1).days() * MINUTES_PER_DAY + 30;
+
+        assert_eq!(Moment::from_minute(minute, 0).to_string(),
+                   "2025-01-01 00:30");
+        assert_eq!(Moment::from_minute(minute, -60).to_string(),
+                   "2024-12-31 23:30");
+        assert_eq!(Moment::from_minute(minute, 180).to_string(),
+                   "2025-01-01 03:30");
+    }
+
+    #[test]
+    fn zone_follows_dst() {
+        let zone: Zone = "CET-1CEST,M3.5.0,M10.5.0/3".parse().expect("Known");
+        let noon = |date: Date| date.days() * MINUTES_PER_DAY + 12 * 60;
+
+        assert_eq!(zone.offset(noon(Date::new(2025, 1, 15))), 60);
+        assert_eq!(zone.offset(noon(Date::new(2025, 7, 15))), 120);
+        // Clocks go forward at 01:00 UTC on the last sunday of march
+        let switch = Date::new(2025, 3, 30).days() * MINUTES_PER_DAY + 60;
+        assert_eq!(zone.offset(switch - 1), 60);
+        assert_eq!(zone.offset(switch), 120);
+        assert!("Nowhere/Atlantis".parse::<Zone>().is_err());
+    }
+}
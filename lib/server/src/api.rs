@@ -0,0 +1,153 @@
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+
+use domain::light::{Light, ProviderID};
+use domain::patch::LightPatch;
+use logic::facade::Facade;
+use logic::strategies::{Strategy, StrategyResult, list, sync};
+use logic::strategies::save::manage::{self, Rename};
+
+use crate::error::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NewName {
+    name: String,
+}
+
+impl From<Error> for Response {
+    fn from(err: Error) -> Self {
+        Self {
+            status: err.status,
+            body: err.body(),
+        }
+    }
+}
+
+// Routes request to the strategy, path is taken without the query
+pub fn handle(facade: &mut dyn Facade, method: &str, path: &str,
+              body: &str) -> Response {
+    route(facade, method, path, body).unwrap_or_else(Response::from)
+}
+
+fn route(facade: &mut dyn Facade, method: &str, path: &str,
+         body: &str) -> Result<Response> {
+    let path = path.split('?').next().unwrap_or_default();
+    let segments = path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(decode)
+        .collect::<Result<Vec<String>>>()?;
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+    match (segments.as_slice(), method) {
+        (["lights"], "GET") => ok(&run(facade, list::provider::All::new())?),
+        (["lights", provider, id], "GET") => {
+            ok(&run(facade, list::provider::get_by_id(&id_of(provider, id)))?)
+        },
+        (["lights", provider, id], "PUT") => {
+            let patch: LightPatch = serde_json::from_str(body)?;
+            ok(&put(facade, &id_of(provider, id), &patch)?)
+        },
+        (["registry", "dumps"], "GET") => {
+            ok(&run(facade, list::registry::dumps::All::new())?)
+        },
+        (["registry", "defaults"], "GET") => {
+            ok(&run(facade, list::registry::defaults::All::new())?)
+        },
+        (["registry", "saved", name, "rename"], "POST") => {
+            let new: NewName = serde_json::from_str(body)?;
+            let strategy = Rename::new(name, &new.name)
+                .ok_or_else(|| Error::bad_request("Empty name".to_string()))?;
+
+            run(facade, strategy).map(|_| no_content())
+        },
+        (["registry", "saved", name], "DELETE") => {
+            let strategy = manage::delete::Single::new(name)
+                .ok_or_else(|| Error::bad_request("Empty name".to_string()))?;
+
+            run(facade, strategy).map(|_| no_content())
+        },
+        (["lights"] | ["lights", _, _] | ["registry", "dumps" | "defaults"]
+         | ["registry", "saved", _] | ["registry", "saved", _, "rename"], _) => {
+            Err(Error::method(method, path))
+        },
+        _ => Err(Error::no_route(path)),
+    }
+}
+
+fn run<S, R, E>(facade: &mut dyn Facade, mut strategy: S) -> Result<R>
+where S: Strategy + StrategyResult<Result = std::result::Result<R, E>>,
+      Error: From<E> {
+    facade.accept(&mut strategy);
+
+    match strategy.result() {
+        Some(result) => result.map_err(Error::from),
+        None => Err(Error::not_executed()),
+    }
+}
+
+// Patch is checked against the current light first, so lights without the
+// capability are told apart from provider failures
+fn put(facade: &mut dyn Facade, id: &ProviderID,
+       patch: &LightPatch) -> Result<Light> {
+    let mut light = run(facade, list::provider::get_by_id(id))?;
+    patch.apply(&mut light)?;
+    run(facade, sync::Patch::new(id, patch))?;
+
+    Ok(light)
+}
+
+fn id_of(provider: &str, id: &str) -> ProviderID {
+    ProviderID::new(provider.to_string(), id.to_string())
+}
+
+fn ok<T: Serialize>(value: &T) -> Result<Response> {
+    Ok(Response {
+        status: 200,
+        body: serde_json::to_value(value)
+            .map_err(Error::unserializable)?,
+    })
+}
+
+fn no_content() -> Response {
+    Response {
+        status: 204,
+        body: Value::Null,
+    }
+}
+
+// Percent encoded segment, names may have spaces
+fn decode(segment: &str) -> Result<String> {
+    let bytes = segment.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let byte = segment.get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| Error::bad_request(format!(
+                    "Incorrect escape in \"{}\"", segment
+                )))?;
+
+            out.push(byte);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8(out).map_err(|_| {
+        Error::bad_request(format!("\"{}\" isn't UTF-8", segment))
+    })
+}
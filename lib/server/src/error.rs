@@ -0,0 +1,141 @@
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use domain::light;
+use logic::managers::{fetch, local};
+use logic::strategies::sync;
+
+// Every failure reaches the client as {"error": {...}} with the status
+// telling which side is to blame
+#[derive(Debug, Serialize)]
+pub struct Error {
+    #[serde(skip)]
+    pub status: u16,
+    pub kind: &'static str,
+    pub message: String,
+    // Provider or registry which produced the error
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+impl Error {
+    fn new(status: u16, kind: &'static str, message: String) -> Self {
+        Self {
+            status,
+            kind,
+            message,
+            source: None,
+        }
+    }
+
+    fn with_source(self: Self, source: &str) -> Self {
+        Self {
+            source: Some(source.to_string()),
+            ..self
+        }
+    }
+
+    pub fn bad_request(message: String) -> Self {
+        Self::new(400, "bad_request", message)
+    }
+
+    pub fn too_large(limit: u64) -> Self {
+        Self::new(413, "payload_too_large",
+                  format!("Body is longer than {} bytes", limit))
+    }
+
+    pub fn no_route(path: &str) -> Self {
+        Self::new(404, "no_route", format!("Nothing is served at \"{}\"", path))
+    }
+
+    pub fn method(method: &str, path: &str) -> Self {
+        Self::new(405, "method_not_allowed",
+                  format!("\"{}\" doesn't accept {}", path, method))
+    }
+
+    pub fn unserializable(err: serde_json::Error) -> Self {
+        Self::new(500, "internal",
+                  format!("Response isn't serializable: {}", err))
+    }
+
+    pub fn not_executed() -> Self {
+        Self::new(500, "internal", "Strategy wasn't executed".to_string())
+    }
+
+    pub fn body(self: &Self) -> Value {
+        json!({ "error": self })
+    }
+}
+
+impl From<fetch::Error> for Error {
+    fn from(err: fetch::Error) -> Self {
+        use provider::ErrorType;
+
+        let message = err.to_string();
+
+        match err {
+            fetch::Error::NotFound(name) => {
+                Self::new(404, "unknown_provider", message).with_source(&name)
+            },
+            fetch::Error::Provider(err) => {
+                let (status, kind) = match err.etype {
+                    ErrorType::NotFound(_) => (404, "not_found"),
+                    ErrorType::IncorrectLight(_)
+                    | ErrorType::IncorrectState(..)
+                    | ErrorType::ForeignLight(_) => (422, "incorrect_state"),
+                    ErrorType::Internal(_) => (502, "provider"),
+                    ErrorType::Timeout(_) => (504, "timeout"),
+                };
+
+                Self::new(status, kind, message).with_source(&err.provider)
+            },
+        }
+    }
+}
+
+impl From<local::Error> for Error {
+    fn from(err: local::Error) -> Self {
+        use local_registry::ErrorType;
+
+        let (status, kind) = match err.etype {
+            ErrorType::NotFound(_) => (404, "not_found"),
+            ErrorType::Exists(_) => (409, "exists"),
+            ErrorType::IncorrectLight(_)
+            | ErrorType::Unnamed => (422, "incorrect_light"),
+            ErrorType::IncorrectName(_) => (400, "bad_request"),
+            ErrorType::Internal(_) => (500, "registry"),
+        };
+
+        Self::new(status, kind, err.to_string()).with_source(&err.registry)
+    }
+}
+
+impl From<sync::Error> for Error {
+    fn from(err: sync::Error) -> Self {
+        match err {
+            sync::Error::Fetch(err) => err.into(),
+            sync::Error::Local(err) => err.into(),
+        }
+    }
+}
+
+impl From<light::Error> for Error {
+    fn from(err: light::Error) -> Self {
+        Self::new(422, "incapable", err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self::bad_request(format!("Incorrect body: {}", err))
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}: {}", self.status, self.kind, self.message)
+    }
+}
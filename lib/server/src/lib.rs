@@ -0,0 +1,76 @@
+
+use std::io::Read;
+use std::net::SocketAddr;
+
+use logic::facade::Facade;
+
+pub mod api;
+pub mod error;
+
+pub use api::{handle, Response};
+pub use error::Error;
+
+// Longest body accepted, requests carry small JSON objects only
+pub const MAX_BODY: u64 = 64 * 1024;
+
+// Requests are served one by one, so the facade doesn't need to be shared
+pub struct Server(tiny_http::Server);
+
+impl Server {
+    pub fn bind(address: &str) -> std::io::Result<Self> {
+        tiny_http::Server::http(address)
+            .map(Self)
+            .map_err(std::io::Error::other)
+    }
+
+    pub fn address(self: &Self) -> Option<SocketAddr> {
+        self.0.server_addr().to_ip()
+    }
+
+    // Blocks until unblocked from another thread
+    pub fn serve(self: &Self, facade: &mut dyn Facade) {
+        for request in self.0.incoming_requests() {
+            respond(facade, request);
+        }
+    }
+
+    pub fn unblock(self: &Self) {
+        self.0.unblock()
+    }
+}
+
+fn respond(facade: &mut dyn Facade, mut request: tiny_http::Request) {
+    let mut body = String::new();
+
+    // Length may be unknown, so the body is cut after the limit as well
+    let response = match request.body_length() {
+        Some(length) if length as u64 > MAX_BODY => {
+            Error::too_large(MAX_BODY).into()
+        },
+        _ => match request.as_reader().take(MAX_BODY + 1)
+            .read_to_string(&mut body) {
+            Ok(read) if read as u64 > MAX_BODY => {
+                Error::too_large(MAX_BODY).into()
+            },
+            Ok(_) => handle(facade, request.method().as_str(), request.url(),
+                            &body),
+            Err(err) => Error::bad_request(format!("Can't read body: {}", err))
+                .into(),
+        },
+    };
+
+    let content = match response.body {
+        serde_json::Value::Null => String::new(),
+        body => body.to_string(),
+    };
+    let header = tiny_http::Header::from_bytes("Content-Type",
+                                               "application/json")
+        .expect("Header is correct");
+
+    // Client may be gone already, nothing to do about it
+    let _ = request.respond(
+        tiny_http::Response::from_string(content)
+            .with_status_code(response.status)
+            .with_header(header)
+    );
+}
@@ -0,0 +1,239 @@
+
+use std::rc::Rc;
+use std::sync::Arc;
+use std::cell::RefCell;
+use std::thread::JoinHandle;
+
+use serde_json::{json, Value};
+
+use domain::capabilities::Capability;
+use domain::brightness::Brightness;
+use local_registry::Registry;
+use logic::context::Context;
+use json_registry::JSONRegistry;
+use logic::facade::default::DefaultFacade;
+use memory_registry::MemoryRegistry;
+use mock_provider::{MockProvider, VirtualLight};
+use provider::Provider;
+use server::Server;
+
+// Server runs in its own thread on a free port, facade isn't Send, so it's
+// built there as well
+struct Running {
+    server: Arc<Server>,
+    address: String,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Running {
+    fn start() -> Self {
+        let server = Arc::new(Server::bind("127.0.0.1:0").expect("Bound"));
+        let address = format!("http://{}", server.address().expect("IP"));
+        let serving = server.clone();
+
+        let thread = std::thread::spawn(move || {
+            serving.serve(&mut facade());
+        });
+
+        Self {
+            server,
+            address,
+            thread: Some(thread),
+        }
+    }
+
+    fn call(self: &Self, method: &str, path: &str,
+            body: Option<Value>) -> (u16, Value) {
+        let request = ureq::request(method, &format!("{}{}", self.address,
+                                                     path));
+        let result = match body {
+            Some(body) => request.send_json(body),
+            None => request.call(),
+        };
+
+        let response = match result {
+            Ok(response) => response,
+            Err(ureq::Error::Status(_, response)) => response,
+            Err(err) => panic!("Request failed: {}", err),
+        };
+        let status = response.status();
+        let body = response.into_string().expect("Body is read");
+
+        if body.is_empty() {
+            (status, Value::Null)
+        } else {
+            (status, serde_json::from_str(&body).expect("JSON body"))
+        }
+    }
+
+    fn get(self: &Self, path: &str) -> (u16, Value) {
+        self.call("GET", path, None)
+    }
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        self.server.unblock();
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn facade() -> DefaultFacade {
+    facade_with(MemoryRegistry::new())
+}
+
+fn facade_with<R: Registry + 'static>(mut registry: R) -> DefaultFacade {
+    let provider = MockProvider::new("mock")
+        .with_light(
+            VirtualLight::new("mock", "1", vec![Capability::Power,
+                                                Capability::Brightness])
+                .with_state(|light| {
+                    light.set_power(false)?;
+                    light.set_brightness(Brightness::new(0.2))
+                })
+        )
+        .with_light(
+            VirtualLight::new("mock", "2", vec![Capability::Brightness])
+                .with_state(|light| light.set_brightness(Brightness::new(0.8)))
+        );
+
+    let mut desk = provider.state("1").expect("Light exists");
+    desk.name = "desk lamp".to_string();
+    registry.dump(&desk).expect("Saved");
+    registry.default(&desk).expect("Saved");
+
+    let context = Context::new(vec![Box::new(provider) as Box<dyn Provider>],
+                               Box::new(registry));
+
+    DefaultFacade::new(Rc::new(RefCell::new(context)))
+}
+
+fn kind(body: &Value) -> &str {
+    body["error"]["kind"].as_str().expect("Error kind")
+}
+
+#[test]
+fn lights() {
+    let server = Running::start();
+
+    let (status, body) = server.get("/lights");
+    assert_eq!(status, 200);
+    assert_eq!(body.as_array().expect("List").len(), 2);
+
+    let (status, body) = server.get("/lights/mock/2");
+    assert_eq!(status, 200);
+    assert_eq!(body["provider"], json!({ "name": "mock", "id": "2" }));
+}
+
+#[test]
+fn partial_update() {
+    let server = Running::start();
+
+    let (status, body) = server.call("PUT", "/lights/mock/1",
+                                     Some(json!({ "power": true })));
+    assert_eq!(status, 200, "{}", body);
+
+    let (_, body) = server.get("/lights/mock/1");
+    let state = body["state"].as_array().expect("State");
+    assert!(state.contains(&json!({ "Power": true })));
+    assert!(state.contains(&json!({ "Brightness": 0.2 })));
+
+    let (status, body) = server.call("PUT", "/lights/mock/2",
+                                     Some(json!({ "power": true })));
+    assert_eq!(status, 422);
+    assert_eq!(kind(&body), "incapable");
+
+    let (status, body) = server.call("PUT", "/lights/mock/2",
+                                     Some(json!({ "volume": 11 })));
+    assert_eq!(status, 400);
+    assert_eq!(kind(&body), "bad_request");
+}
+
+#[test]
+fn provider_errors() {
+    let server = Running::start();
+
+    let (status, body) = server.get("/lights/mock/3");
+    assert_eq!(status, 404);
+    assert_eq!(kind(&body), "not_found");
+    assert_eq!(body["error"]["source"], "mock");
+
+    let (status, body) = server.get("/lights/hue/1");
+    assert_eq!(status, 404);
+    assert_eq!(kind(&body), "unknown_provider");
+}
+
+#[test]
+fn registry() {
+    let server = Running::start();
+
+    let (status, body) = server.get("/registry/dumps");
+    assert_eq!(status, 200);
+    assert_eq!(body[0]["name"], "desk lamp");
+
+    let (status, _) = server.call("POST", "/registry/saved/desk%20lamp/rename",
+                                  Some(json!({ "name": "desk" })));
+    assert_eq!(status, 204);
+
+    let (_, body) = server.get("/registry/defaults");
+    assert_eq!(body[0]["name"], "desk");
+
+    let (status, _) = server.call("DELETE", "/registry/saved/desk", None);
+    assert_eq!(status, 204);
+
+    let (status, body) = server.call("DELETE", "/registry/saved/desk", None);
+    assert_eq!(status, 404);
+    assert_eq!(kind(&body), "not_found");
+    assert_eq!(server.get("/registry/dumps").1, json!([]));
+}
+
+#[test]
+fn routes() {
+    let server = Running::start();
+
+    let (status, body) = server.get("/nothing");
+    assert_eq!(status, 404);
+    assert_eq!(kind(&body), "no_route");
+
+    let (status, body) = server.call("DELETE", "/lights", None);
+    assert_eq!(status, 405);
+    assert_eq!(kind(&body), "method_not_allowed");
+}
+
+#[test]
+fn names_outside_registry() {
+    // Memory registry takes any name, names are refused by the registry
+    // which stores them as files
+    let dir = tempfile::tempdir().expect("Temporary directory");
+    let mut facade = facade_with(JSONRegistry::new(dir.path()));
+
+    for path in ["/registry/saved/..%2Fdesk", "/registry/saved/..",
+                 "/registry/saved/%2E%2E"] {
+        let response = server::handle(&mut facade, "DELETE", path, "");
+        assert_eq!(response.status, 400, "{}", path);
+        assert_eq!(kind(&response.body), "bad_request");
+    }
+
+    let body = json!({ "name": "../desk" }).to_string();
+    let response = server::handle(&mut facade, "POST",
+                                  "/registry/saved/desk%20lamp/rename", &body);
+    assert_eq!(response.status, 400);
+
+    let response = server::handle(&mut facade, "GET", "/registry/dumps", "");
+    assert_eq!(response.body[0]["name"], "desk lamp");
+}
+
+#[test]
+fn body_too_large() {
+    let server = Running::start();
+    let name = "a".repeat(server::MAX_BODY as usize);
+
+    let (status, body) = server.call("POST", "/registry/saved/desk/rename",
+                                     Some(json!({ "name": name })));
+    assert_eq!(status, 413);
+    assert_eq!(kind(&body), "payload_too_large");
+    assert_eq!(server.get("/registry/dumps").1[0]["name"], "desk lamp");
+}
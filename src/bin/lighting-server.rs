@@ -0,0 +1,56 @@
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Parser;
+
+use config::{Config, Factory};
+use server::Server;
+
+#[derive(Debug, Parser)]
+#[command(name = "lighting-server", version,
+          about = "HTTP/JSON API for smarthouse lighting control")]
+struct Cli {
+    /// Address to listen on, loopback only by default
+    #[arg(short, long, default_value = "127.0.0.1:8080")]
+    address: String,
+
+    /// Configuration file, "~/.config/lighting/config.toml" by default
+    #[arg(short, long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Location of the local registry, overrides configuration
+    #[arg(long, value_name = "PATH")]
+    registry: Option<PathBuf>,
+}
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+fn run(args: Cli) -> Result<()> {
+    let mut config = match &args.config {
+        Some(path) => Config::load(path)?,
+        None => Config::load_default()?,
+    };
+
+    if let Some(registry) = args.registry {
+        config.registry.path = registry;
+    }
+
+    let mut facade = Factory::new().facade(&config)?;
+    let server = Server::bind(&args.address)?;
+
+    eprintln!("lighting-server: listening on {}", args.address);
+    server.serve(&mut facade);
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,114 @@
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::SystemTime;
+
+use clap::Parser;
+
+use config::{Config, Factory};
+use scheduler::{Rules, Scheduler};
+use scheduler::time::SystemClock;
+
+const RULES_FILE: &str = "rules.toml";
+
+#[derive(Debug, Parser)]
+#[command(name = "lightingd", version,
+          about = "Daemon switching lights by schedule")]
+struct Cli {
+    /// Configuration file, "~/.config/lighting/config.toml" by default
+    #[arg(short, long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Location of the local registry, overrides configuration
+    #[arg(long, value_name = "PATH")]
+    registry: Option<PathBuf>,
+
+    /// Rules file, "rules.toml" next to the configuration by default
+    #[arg(short, long, value_name = "PATH")]
+    rules: Option<PathBuf>,
+
+    /// Validate rules and exit
+    #[arg(long)]
+    check: bool,
+}
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+fn run(args: Cli) -> Result<()> {
+    let rules_path = args.rules.clone().unwrap_or_else(|| {
+        args.config.clone().unwrap_or_else(config::default_path)
+            .with_file_name(RULES_FILE)
+    });
+    let rules = Rules::load(&rules_path)?;
+
+    if args.check {
+        for rule in &rules.rules {
+            println!("{}: {}{}", rule.name, rule.trigger,
+                     if rule.enabled { "" } else { " (disabled)" });
+        }
+
+        return Ok(());
+    }
+
+    let mut config = match &args.config {
+        Some(path) => Config::load(path)?,
+        None => Config::load_default()?,
+    };
+
+    if let Some(registry) = args.registry {
+        config.registry.path = registry;
+    }
+
+    let mut facade = Factory::new().facade(&config)?;
+    let mut scheduler = Scheduler::new(rules);
+    let mut loaded = modified(&rules_path);
+
+    eprintln!("lightingd: {} rules from \"{}\"", scheduler.rules().rules.len(),
+              rules_path.display());
+
+    loop {
+        for fired in scheduler.step(&mut facade, &SystemClock) {
+            match fired.result {
+                Ok(unrecorded) => {
+                    println!("{} {}: done", fired.at, fired.rule);
+
+                    for err in unrecorded {
+                        eprintln!("{} {}: warning: {}", fired.at, fired.rule,
+                                  err);
+                    }
+                },
+                Err(err) => eprintln!("{} {}: {}", fired.at, fired.rule, err),
+            }
+        }
+
+        // Edited rules are picked up without restart, broken ones are
+        // reported and the previous ones are kept
+        let current = modified(&rules_path);
+
+        if current != loaded {
+            loaded = current;
+
+            match Rules::load(&rules_path) {
+                Ok(rules) => {
+                    eprintln!("lightingd: rules reloaded");
+                    scheduler.set_rules(rules);
+                },
+                Err(err) => eprintln!("lightingd: {}", err),
+            }
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,278 @@
+
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+
+use domain::light::ProviderID;
+
+#[derive(Debug, Parser)]
+#[command(name = "lighting", version, about = "CLI smarthouse lighting control")]
+pub struct Cli {
+    /// Print machine readable JSON instead of tables
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Configuration file, "~/.config/lighting/config.toml" by default
+    #[arg(short, long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Location of the local registry, overrides configuration
+    #[arg(long, global = true, value_name = "PATH")]
+    pub registry: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// List lights of providers or saved in the registry
+    List(ListArgs),
+    /// Show current state of a single light
+    Get {
+        #[arg(value_name = "ID@PROVIDER")]
+        id: ProviderID,
+    },
+    /// Change state of lights
+    Set(SetArgs),
+    /// Save current state of lights as both dump and default
+    Save(SaveArgs),
+    /// Save current state of a light as dump
+    Dump(NamedArgs),
+    /// Save current state of a light as default
+    Default(NamedArgs),
+    /// Sync lights with saved states
+    Load {
+        #[arg(required_unless_present = "group")]
+        names: Vec<String>,
+
+        /// Use defaults instead of dumps
+        #[arg(long)]
+        default: bool,
+
+        /// Load saved states of every light in a group
+        #[arg(short, long, conflicts_with = "names")]
+        group: Option<String>,
+    },
+    /// Show previous states of a light recorded by syncs
+    History {
+        #[arg(value_name = "ID@PROVIDER")]
+        id: ProviderID,
+    },
+    /// Sync a light back to the state it had before its last change
+    Undo {
+        #[arg(value_name = "ID@PROVIDER")]
+        id: ProviderID,
+    },
+    /// Sync a light to the state taken back by undo
+    Redo {
+        #[arg(value_name = "ID@PROVIDER")]
+        id: ProviderID,
+    },
+    /// List kept revisions of a saved light or compare two of them
+    Revisions {
+        name: String,
+
+        /// Revisions of the default instead of the dump
+        #[arg(long)]
+        default: bool,
+
+        /// Show what changed between two revisions
+        #[arg(long, num_args = 2, value_names = ["FROM", "TO"])]
+        diff: Option<Vec<u64>>,
+    },
+    /// Rename saved light
+    Rename {
+        old: String,
+        new: String,
+    },
+    /// Delete saved lights
+    Delete {
+        #[arg(required = true)]
+        names: Vec<String>,
+    },
+    /// Manage scenes, named states of several lights
+    #[command(subcommand)]
+    Scene(SceneCommand),
+    /// Manage groups, lights addressed by a single name
+    #[command(subcommand)]
+    Group(GroupCommand),
+    /// List modes supported by providers along with their parameters
+    Modes {
+        /// Only modes of this provider
+        provider: Option<String>,
+    },
+    /// Obtain username from a Hue bridge, press its link button first
+    Pair {
+        /// Bridge address, e.g. "192.168.1.2"
+        address: String,
+
+        /// Device type reported to the bridge
+        #[arg(long, default_value = "lighting")]
+        device: String,
+    },
+    /// Copy everything saved in a JSON registry into the configured SQLite one
+    Import {
+        /// Location of the JSON registry
+        source: PathBuf,
+    },
+    /// Move lights saved by earlier versions into the revision layout of
+    /// the configured JSON registry
+    Migrate,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SceneCommand {
+    /// List saved scenes
+    List,
+    /// Save current state of lights as a scene, all lights by default
+    Capture {
+        name: String,
+
+        #[arg(value_name = "ID@PROVIDER")]
+        ids: Vec<ProviderID>,
+    },
+    /// Sync every light of a scene
+    Apply {
+        name: String,
+    },
+    /// Delete saved scene
+    Delete {
+        name: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum GroupCommand {
+    /// List saved groups
+    List,
+    /// Save group, replacing existing one with the same name
+    Set {
+        name: String,
+
+        /// Light of a provider, may be repeated
+        #[arg(short, long = "light", value_name = "ID@PROVIDER")]
+        lights: Vec<ProviderID>,
+
+        /// Saved light, follows renames, may be repeated
+        #[arg(short, long = "saved", value_name = "NAME")]
+        saved: Vec<String>,
+
+        /// Nested group, may be repeated
+        #[arg(short, long = "group", value_name = "NAME")]
+        groups: Vec<String>,
+    },
+    /// Delete saved group
+    Delete {
+        name: String,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct ListArgs {
+    /// Limit listing to given providers
+    #[arg(short, long = "provider", value_name = "PROVIDER")]
+    pub providers: Vec<String>,
+
+    /// Limit listing to members of a group
+    #[arg(short, long, conflicts_with = "providers")]
+    pub group: Option<String>,
+
+    /// List saved dumps
+    #[arg(long, conflicts_with_all = ["providers", "defaults"])]
+    pub dumps: bool,
+
+    /// List saved defaults
+    #[arg(long, conflicts_with = "providers")]
+    pub defaults: bool,
+}
+
+#[derive(Debug, Args)]
+#[group(id = "color", multiple = false)]
+pub struct ColorArgs {
+    /// Color as "R,G,B" with 0-255 components
+    #[arg(long, value_name = "R,G,B", group = "color")]
+    pub rgb: Option<String>,
+
+    /// Color as "H,S,V" with 0-1 components
+    #[arg(long, value_name = "H,S,V", group = "color")]
+    pub hsv: Option<String>,
+
+    /// Color temperature in Kelvin
+    #[arg(long, value_name = "K", group = "color")]
+    pub temperature: Option<f64>,
+}
+
+#[derive(Debug, Args)]
+pub struct SetArgs {
+    #[arg(required_unless_present = "group", value_name = "ID@PROVIDER")]
+    pub ids: Vec<ProviderID>,
+
+    /// Change every light of a group
+    #[arg(short, long, conflicts_with = "ids")]
+    pub group: Option<String>,
+
+    #[command(flatten)]
+    pub color: ColorArgs,
+
+    /// Brightness in 0-1 range
+    #[arg(short, long)]
+    pub brightness: Option<f64>,
+
+    /// Provider mode name
+    #[arg(short, long)]
+    pub mode: Option<String>,
+
+    /// Mode parameter as "NAME=VALUE", may be repeated
+    #[arg(short, long = "param", value_name = "NAME=VALUE",
+          requires = "mode")]
+    pub params: Vec<String>,
+
+    /// Turn lights on
+    #[arg(long, conflicts_with = "off")]
+    pub on: bool,
+
+    /// Turn lights off
+    #[arg(long)]
+    pub off: bool,
+
+    /// Fade to the new state over this many milliseconds
+    #[arg(short, long, value_name = "MS")]
+    pub transition: Option<u64>,
+}
+
+#[derive(Debug, Args)]
+pub struct SaveArgs {
+    #[arg(value_name = "ID@PROVIDER",
+          required_unless_present_any = ["all", "providers"])]
+    pub id: Option<ProviderID>,
+
+    /// Name to save under, "<id>@<provider>" if omitted
+    pub name: Option<String>,
+
+    /// Save every light under its default name
+    #[arg(long, conflicts_with_all = ["id", "providers"])]
+    pub all: bool,
+
+    /// Save every light of given providers under its default name
+    #[arg(short, long = "provider", value_name = "PROVIDER",
+          conflicts_with = "id")]
+    pub providers: Vec<String>,
+
+    /// Note kept along with the saved revision
+    #[arg(short, long, requires = "id")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct NamedArgs {
+    #[arg(value_name = "ID@PROVIDER")]
+    pub id: ProviderID,
+
+    /// Name to save under, "<id>@<provider>" if omitted
+    pub name: Option<String>,
+
+    /// Note kept along with the saved revision
+    #[arg(short, long)]
+    pub message: Option<String>,
+}
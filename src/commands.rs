@@ -0,0 +1,587 @@
+
+use std::path::Path;
+use std::time::Duration;
+
+use domain::light::{self, Light, ProviderID};
+use domain::color::Color;
+use domain::color::rgb::RGB;
+use domain::color::hsv::HSV;
+use domain::color::temperature::Temperature;
+use domain::brightness::Brightness;
+use domain::group::{Group, Member};
+use domain::mode::Mode;
+use domain::mode::parameter::{Parameter, Value};
+use hue_provider::HueProvider;
+use sqlite_registry::SQLiteRegistry;
+use json_registry::JSONRegistry;
+use config::{RegistryConfig, expand_path};
+use logic::batch::Batch;
+use logic::facade::{Facade, Managers};
+use logic::managers::fetch;
+use logic::strategies::{Strategy, StrategyResult, list, sync, save};
+use logic::strategies::transition::Transition;
+use logic::strategies::scene;
+use logic::strategies::group;
+use logic::strategies::history;
+use logic::strategies::save::revisions::{self as revision, Diff, Store};
+use logic::managers::local::Section;
+
+use crate::cli::{Command, SceneCommand, GroupCommand, ListArgs, SetArgs,
+                 SaveArgs, NamedArgs, ColorArgs};
+use crate::output;
+
+pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+#[derive(Debug)]
+pub struct Error(String);
+
+impl std::error::Error for Error {}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+fn error<T>(msg: String) -> Result<T> {
+    Err(Box::new(Error(msg)))
+}
+
+fn run<S, R, E>(facade: &mut dyn Facade, mut strategy: S) -> Result<R>
+where S: Strategy + StrategyResult<Result = std::result::Result<R, E>>,
+      E: std::error::Error + 'static {
+    facade.accept(&mut strategy);
+
+    match strategy.result() {
+        Some(Ok(result)) => Ok(result),
+        Some(Err(err)) => Err(Box::new(err)),
+        None => error("Strategy wasn't executed".to_string()),
+    }
+}
+
+// Batches are run fail fast, so the first error is the only one
+fn run_batch<S, K, R, E>(facade: &mut dyn Facade, strategy: S) -> Result<Vec<R>>
+where S: Strategy + StrategyResult<Result = Batch<K, R, E>>,
+      E: std::error::Error + 'static {
+    run(facade, BatchResult(strategy))
+}
+
+// Groups are run fail fast too, failing to resolve the group comes first
+fn run_group<S, K, R, E>(facade: &mut dyn Facade, strategy: S) -> Result<Vec<R>>
+where S: Strategy
+       + StrategyResult<Result = std::result::Result<Batch<K, R, E>, E>>,
+      E: std::error::Error + 'static {
+    run(facade, strategy)?.into_result().map_err(|err| err.into())
+}
+
+// Adapts batch strategies to `run`
+struct BatchResult<S>(S);
+
+impl<S: Strategy> Strategy for BatchResult<S> {
+    fn execute(self: &mut Self, managers: Managers) {
+        self.0.execute(managers)
+    }
+}
+
+impl<S, K, R, E> StrategyResult for BatchResult<S>
+where S: StrategyResult<Result = Batch<K, R, E>> {
+    type Result = std::result::Result<Vec<R>, E>;
+
+    fn result(self: Self) -> Option<Self::Result> {
+        self.0.result().map(Batch::into_result)
+    }
+}
+
+// Failed providers are reported as warnings, unless all of them failed
+fn run_partial<S>(facade: &mut dyn Facade,
+                  mut strategy: S) -> Result<Vec<Light>>
+where S: Strategy + StrategyResult<Result = fetch::Partial> {
+    facade.accept(&mut strategy);
+
+    match strategy.result() {
+        Some(partial) => {
+            output::warnings(&partial.errors);
+
+            if partial.lights.is_empty() && !partial.is_complete() {
+                error("None of the providers could be reached".to_string())
+            } else {
+                Ok(partial.lights)
+            }
+        },
+        None => error("Strategy wasn't executed".to_string()),
+    }
+}
+
+fn unnamed<T>() -> Result<T> {
+    error("Light name can't be empty".to_string())
+}
+
+pub fn execute(facade: &mut dyn Facade, command: Command,
+               json: bool) -> Result<()> {
+    match command {
+        Command::List(args) => list(facade, args, json),
+        Command::Get { id } => get(facade, &id, json),
+        Command::Set(args) => set(facade, args, json),
+        Command::Save(args) => save(facade, args, json),
+        Command::Dump(args) => named(facade, args, Target::Dump, json),
+        Command::Default(args) => named(facade, args, Target::Default, json),
+        Command::Load { names, default, group } => {
+            load(facade, &names, group.as_deref(), default, json)
+        },
+        Command::History { id } => {
+            output::history(&run(facade, history::Get::new(&id))?, json);
+            Ok(())
+        },
+        Command::Undo { id } => {
+            output::light(&run(facade, history::Undo::new(&id))?, json);
+            Ok(())
+        },
+        Command::Redo { id } => {
+            output::light(&run(facade, history::Redo::new(&id))?, json);
+            Ok(())
+        },
+        Command::Revisions { name, default, diff } => {
+            revisions(facade, &name, default, diff, json)
+        },
+        Command::Rename { old, new } => rename(facade, &old, &new, json),
+        Command::Delete { names } => delete(facade, &names, json),
+        Command::Scene(command) => scenes(facade, command, json),
+        Command::Group(command) => groups(facade, command, json),
+        Command::Modes { provider } => modes(facade, provider.as_deref(), json),
+        Command::Pair { .. } => error("Pairing doesn't need providers".to_string()),
+        Command::Import { .. } => error("Import doesn't need providers".to_string()),
+        Command::Migrate => error("Migration doesn't need providers".to_string()),
+    }
+}
+
+// Runs before configuration is loaded, since it is used to fill it
+pub fn pair(address: &str, device: &str, json: bool) -> Result<()> {
+    let username = HueProvider::pair(address, device)?;
+
+    if json {
+        output::print_json(&serde_json::json!({ "username": username }));
+    } else {
+        println!("{}", username);
+    }
+
+    Ok(())
+}
+
+// Works on the registry alone, providers aren't created
+pub fn import(registry: &RegistryConfig, source: &Path, json: bool) -> Result<()> {
+    if registry.backend != "sqlite" {
+        return error(format!("Import needs the \"sqlite\" registry backend, \
+                              \"{}\" is configured", registry.backend));
+    }
+
+    let source = expand_path(source);
+
+    if !source.is_dir() {
+        return error(format!("No JSON registry at \"{}\"", source.display()));
+    }
+
+    let imported = SQLiteRegistry::open(expand_path(&registry.path))?
+        .import_json(source)?;
+
+    output::imported(&imported, json);
+    Ok(())
+}
+
+// Files which aren't lights are reported and left for the user to fix
+pub fn migrate(registry: &RegistryConfig, json: bool) -> Result<()> {
+    if registry.backend != "json" {
+        return error(format!("Migration needs the \"json\" registry backend, \
+                              \"{}\" is configured", registry.backend));
+    }
+
+    let migration = JSONRegistry::new(expand_path(&registry.path)).migrate()?;
+    let skipped: Vec<(String, _)> = migration.skipped.iter()
+        .map(|(path, err)| (path.display().to_string(), err))
+        .collect();
+
+    output::warnings(&skipped);
+    output::migrated(migration.moved, skipped.len(), json);
+    Ok(())
+}
+
+fn list(facade: &mut dyn Facade, args: ListArgs, json: bool) -> Result<()> {
+    let lights = if let Some(group) = &args.group {
+        if args.dumps {
+            run_group(facade, list::registry::dumps::get_by_group(group))?
+        } else if args.defaults {
+            run_group(facade,
+                      list::registry::defaults::get_by_group(group))?
+        } else {
+            run_group(facade, list::provider::get_by_group(group))?
+        }
+    } else if args.dumps {
+        run(facade, list::registry::dumps::All::new())?
+    } else if args.defaults {
+        run(facade, list::registry::defaults::All::new())?
+    } else {
+        // Unreachable providers don't hide the rest
+        match args.providers.as_slice() {
+            [] => run_partial(facade, list::provider::partial::All::new())?,
+            [provider] => run(facade, list::provider::Single::new(provider))?,
+            providers => run_partial(
+                facade,
+                list::provider::partial::Multiple::new(
+                    providers.iter().map(String::as_str)
+                )
+            )?,
+        }
+    };
+
+    output::lights(&lights, json);
+    Ok(())
+}
+
+fn fetch(facade: &mut dyn Facade, id: &ProviderID) -> Result<Light> {
+    run(facade, list::provider::get_by_id(id))
+}
+
+fn get(facade: &mut dyn Facade, id: &ProviderID, json: bool) -> Result<()> {
+    output::light(&fetch(facade, id)?, json);
+    Ok(())
+}
+
+fn parse_triple<T: std::str::FromStr>(value: &str) -> Result<(T, T, T)> {
+    let parts: Vec<&str> = value.split(',').map(str::trim).collect();
+
+    match parts.as_slice() {
+        [a, b, c] => match (a.parse(), b.parse(), c.parse()) {
+            (Ok(a), Ok(b), Ok(c)) => Ok((a, b, c)),
+            _ => error(format!("Can't parse components of \"{}\"", value)),
+        },
+        _ => error(format!("Expected three components in \"{}\"", value)),
+    }
+}
+
+fn parse_color(args: &ColorArgs) -> Result<Option<Color>> {
+    if let Some(rgb) = &args.rgb {
+        let (r, g, b) = parse_triple::<u8>(rgb)?;
+        Ok(Some(RGB::new(r, g, b).into()))
+    } else if let Some(hsv) = &args.hsv {
+        let (h, s, v) = parse_triple::<f64>(hsv)?;
+        Ok(Some(HSV::new(h, s, v).into()))
+    } else if let Some(temperature) = args.temperature {
+        Ok(Some(Temperature::new(temperature).into()))
+    } else {
+        Ok(None)
+    }
+}
+
+fn parse_value(value: &str) -> Value {
+    if let Ok(value) = value.parse::<i64>() {
+        Value::Int(value)
+    } else if let Ok(value) = value.parse::<u64>() {
+        Value::UInt(value)
+    } else if let Ok(value) = value.parse::<f64>() {
+        Value::Float(value)
+    } else {
+        Value::String(value.to_string())
+    }
+}
+
+fn parse_parameters(params: &[String]) -> Result<Vec<Parameter>> {
+    params.iter()
+        .map(|param| match param.split_once('=') {
+            Some((name, value)) if !name.is_empty() => {
+                Ok(Parameter::new(name.to_string(), parse_value(value)))
+            },
+            _ => error(format!("Expected \"NAME=VALUE\", got \"{}\"", param)),
+        })
+        .collect()
+}
+
+fn set(facade: &mut dyn Facade, args: SetArgs, json: bool) -> Result<()> {
+    let color = parse_color(&args.color)?;
+    let brightness = args.brightness.map(Brightness::new);
+    let mode = args.mode.as_ref()
+        .map(|name| parse_parameters(&args.params).map(|p| (name, p)))
+        .transpose()?;
+    let power = if args.on {
+        Some(true)
+    } else if args.off {
+        Some(false)
+    } else {
+        None
+    };
+
+    if power.is_none() && color.is_none() && brightness.is_none()
+        && mode.is_none() {
+        return error("Nothing to set, give a power, color, brightness \
+                      or mode".to_string());
+    }
+
+    let apply = |light: &mut Light| -> std::result::Result<(), light::Error> {
+        if let Some(power) = power {
+            light.set_power(power)?;
+        }
+
+        if let Some(color) = &color {
+            light.set_color(color.clone())?;
+        }
+
+        if let Some(brightness) = &brightness {
+            light.set_brightness(brightness.clone())?;
+        }
+
+        if let Some((name, parameters)) = &mode {
+            light.set_mode(Mode::new(light.provider.name.clone(),
+                                     name.to_string(), parameters.clone()))?;
+        }
+
+        Ok(())
+    };
+
+    if let Some(transition) = args.transition {
+        let duration = Duration::from_millis(transition);
+        let lights = match &args.group {
+            Some(group) => {
+                run_group(facade, list::provider::get_by_group(group))?
+            },
+            None => {
+                run_batch(facade, list::provider::get_by_ids(args.ids.iter()))?
+            },
+        };
+
+        let mut errors = Vec::new();
+
+        for light in lights.iter() {
+            let mut changed = light.clone();
+
+            match apply(&mut changed) {
+                Ok(_) => run(facade, Transition::new(&changed, duration)
+                             .with_start(light))?,
+                Err(err) => errors.push(err),
+            }
+        }
+
+        return unchanged(errors, json);
+    }
+
+    let mut errors = Vec::new();
+
+    // Changes are applied to a copy, so light isn't touched if any of them
+    // is unsupported, an untouched light isn't synced
+    let map = |light: &mut Light| {
+        let mut changed = light.clone();
+
+        match apply(&mut changed) {
+            Ok(_) => *light = changed,
+            Err(err) => errors.push(err),
+        }
+    };
+
+    let recorded = match &args.group {
+        Some(group) => {
+            run_group(facade, sync::fetch_and_sync::group(group, map))?
+        },
+        None => {
+            run_batch(facade, sync::fetch_and_sync::multiple(args.ids.iter(),
+                                                             map))?
+        },
+    };
+
+    unrecorded(recorded);
+    unchanged(errors, json)
+}
+
+// Lights are synced already when their history can't be saved, so it's
+// only a warning
+fn unrecorded(recorded: Vec<history::Recorded>) {
+    let errors: Vec<_> = recorded.into_iter()
+        .filter_map(|recorded| recorded.err())
+        .collect();
+    output::failures(&errors);
+}
+
+// Lights changes couldn't be applied to are left as they are, each of them
+// is reported
+fn unchanged(errors: Vec<light::Error>, json: bool) -> Result<()> {
+    if errors.is_empty() {
+        output::done(json);
+        return Ok(());
+    }
+
+    output::failures(&errors);
+    error(format!("{} of the lights couldn't be changed", errors.len()))
+}
+
+fn save(facade: &mut dyn Facade, args: SaveArgs, json: bool) -> Result<()> {
+    if args.all {
+        run(facade, save::load_and_save::all_default())?;
+    } else if !args.providers.is_empty() {
+        run(facade, save::load_and_save::providers_default(
+            args.providers.iter().map(String::as_str)
+        ))?;
+    } else if let Some(id) = args.id {
+        let args = NamedArgs { id, name: args.name, message: args.message };
+        return named(facade, args, Target::Both, json);
+    }
+
+    output::done(json);
+    Ok(())
+}
+
+enum Target {
+    Dump,
+    Default,
+    Both,
+}
+
+fn named(facade: &mut dyn Facade, args: NamedArgs, target: Target,
+         json: bool) -> Result<()> {
+    let mut light = fetch(facade, &args.id)?;
+    light.name = args.name.unwrap_or_else(|| args.id.to_string());
+
+    let sections: &[Section] = match target {
+        Target::Dump => &[Section::Dumps],
+        Target::Default => &[Section::Defaults],
+        Target::Both => &[Section::Dumps, Section::Defaults],
+    };
+
+    for section in sections {
+        let strategy = match (Store::new(*section, &light), &args.message) {
+            (Some(store), Some(message)) => store.with_message(message),
+            (Some(store), None) => store,
+            (None, _) => return unnamed(),
+        };
+
+        run(facade, strategy)?;
+    }
+
+    output::done(json);
+    Ok(())
+}
+
+fn revisions(facade: &mut dyn Facade, name: &str, default: bool,
+             diff: Option<Vec<u64>>, json: bool) -> Result<()> {
+    let section = if default { Section::Defaults } else { Section::Dumps };
+
+    match diff.as_deref() {
+        Some(&[from, to]) => {
+            let patch = run(facade, Diff::new(section, name, from, to))?;
+            output::patch(&patch, json);
+        },
+        _ => {
+            let revisions = run(facade, revision::All::new(section, name))?;
+            output::revisions(&revisions, json);
+        },
+    }
+
+    Ok(())
+}
+
+fn load(facade: &mut dyn Facade, names: &[String], group: Option<&str>,
+        default: bool, json: bool) -> Result<()> {
+    let names = names.iter().map(String::as_str);
+
+    let recorded = if let Some(group) = group {
+        if default {
+            run_group(facade, sync::default_and_sync::group(group, |_| {}))?
+        } else {
+            run_group(facade, sync::load_and_sync::group(group, |_| {}))?
+        }
+    } else if default {
+        run_batch(facade, sync::default_and_sync::multiple(names, |_| {}))?
+    } else {
+        run_batch(facade, sync::load_and_sync::multiple(names, |_| {}))?
+    };
+
+    unrecorded(recorded);
+    output::done(json);
+    Ok(())
+}
+
+fn rename(facade: &mut dyn Facade, old: &str, new: &str,
+          json: bool) -> Result<()> {
+    match save::manage::Rename::new(old, new) {
+        Some(strategy) => run(facade, strategy)?,
+        None => return unnamed(),
+    }
+
+    output::done(json);
+    Ok(())
+}
+
+fn delete(facade: &mut dyn Facade, names: &[String], json: bool) -> Result<()> {
+    match save::manage::delete::Multiple::new(names.iter().map(String::as_str)) {
+        Some(strategy) => { run_batch(facade, strategy)?; },
+        None => return unnamed(),
+    }
+
+    output::done(json);
+    Ok(())
+}
+
+fn modes(facade: &mut dyn Facade, provider: Option<&str>,
+         json: bool) -> Result<()> {
+    let modes = match provider {
+        Some(provider) => run(facade, list::provider::modes::Single::new(provider))?,
+        None => run(facade, list::provider::modes::All::new())?,
+    };
+
+    output::modes(&modes, json);
+    Ok(())
+}
+
+fn scenes(facade: &mut dyn Facade, command: SceneCommand,
+          json: bool) -> Result<()> {
+    match command {
+        SceneCommand::List => {
+            output::scenes(&run(facade, scene::All::new())?, json);
+            return Ok(());
+        },
+        SceneCommand::Capture { name, ids } => {
+            let strategy = if ids.is_empty() {
+                scene::Capture::new(&name)
+            } else {
+                scene::Capture::new(&name).with_ids(ids.iter())
+            };
+
+            let (_, errors) = run(facade, strategy.partial())?;
+            output::warnings(&errors);
+        },
+        SceneCommand::Apply { name } => {
+            let report = run(facade, scene::Apply::new(&name))?;
+            let failed = report.iter().filter(|(_, result)| result.is_err())
+                .count();
+            output::report(&report, json);
+
+            if 0 != failed {
+                return error(format!("{} of {} lights failed", failed,
+                                     report.len()));
+            }
+
+            return Ok(());
+        },
+        SceneCommand::Delete { name } => run(facade, scene::Delete::new(&name))?,
+    }
+
+    output::done(json);
+    Ok(())
+}
+
+fn groups(facade: &mut dyn Facade, command: GroupCommand,
+          json: bool) -> Result<()> {
+    match command {
+        GroupCommand::List => {
+            output::groups(&run(facade, group::All::new())?, json);
+            return Ok(());
+        },
+        GroupCommand::Set { name, lights, saved, groups } => {
+            let members = lights.into_iter().map(Member::Light)
+                .chain(saved.into_iter().map(Member::Saved))
+                .chain(groups.into_iter().map(Member::Group))
+                .collect();
+
+            run(facade, group::Save::new(&Group::new(name, members)))?;
+        },
+        GroupCommand::Delete { name } => run(facade, group::Delete::new(&name))?,
+    }
+
+    output::done(json);
+    Ok(())
+}
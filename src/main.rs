@@ -1,24 +1,47 @@
 
-use serde_json;
-
-use domain::light::Light;
-use domain::brightness::Brightness;
-use domain::capabilities::Capability;
-use domain::mode::Mode;
-use domain::color::rgb::RGB;
-
-fn main() {
-    let mut light = Light::new("test".to_string(), "1".to_string(),
-                               vec![Capability::Color, Capability::Brightness]);
-
-    light.set_color(RGB::new(255, 0, 0).into()).expect("aboba");
-    light.set_brightness(Brightness::new(-0.2)).expect("baoab");
-    light.set_mode(Mode::new_empty("test".to_string(), "123".to_string()))
-        .inspect_err(|err| {eprintln!("Ну да...\n{err}")})
-        .expect_err("Так быть не должно");
-
-    // println!("{:?}", light);
-    println!("{}", serde_json::to_string(&light).unwrap());
-}
+use std::process::ExitCode;
+
+use clap::Parser;
+
+use config::{Config, Factory};
+
+mod cli;
+mod commands;
+mod output;
+
+fn run(args: cli::Cli) -> commands::Result<()> {
+    if let cli::Command::Pair { address, device } = &args.command {
+        return commands::pair(address, device, args.json);
+    }
+
+    let mut config = match &args.config {
+        Some(path) => Config::load(path)?,
+        None => Config::load_default()?,
+    };
 
+    if let Some(registry) = args.registry {
+        config.registry.path = registry;
+    }
 
+    if let cli::Command::Import { source } = &args.command {
+        return commands::import(&config.registry, source, args.json);
+    }
+
+    if let cli::Command::Migrate = &args.command {
+        return commands::migrate(&config.registry, args.json);
+    }
+
+    let mut facade = Factory::new().facade(&config)?;
+
+    commands::execute(&mut facade, args.command, args.json)
+}
+
+fn main() -> ExitCode {
+    match run(cli::Cli::parse()) {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,400 @@
+
+use serde::Serialize;
+
+use domain::light::{self, Light, ProviderID};
+use domain::scene::Scene;
+use domain::group::Group;
+use domain::history::{Entry, History};
+use domain::date::timestamp;
+use domain::patch::LightPatch;
+use local_registry::Revision;
+use sqlite_registry::Imported;
+use domain::color::rgb::RGB;
+use domain::mode::parameter::Value;
+use domain::mode::descriptor::ModeDescriptor;
+use domain::mode::schema::{Field, ParameterSchema};
+
+const UNSUPPORTED: &str = "";
+const UNSET: &str = "-";
+
+fn cell<T, F: FnOnce(T) -> String>(
+    value: Result<T, light::Error>,
+    f: F
+) -> String {
+    match value {
+        Ok(value) => f(value),
+        Err(light::Error::Unset(..)) => UNSET.to_string(),
+        Err(_) => UNSUPPORTED.to_string(),
+    }
+}
+
+fn row(light: &Light) -> [String; 6] {
+    [
+        light.provider.to_string(),
+        light.name.clone(),
+        cell(light.get_power(), |power| {
+            if power { "on" } else { "off" }.to_string()
+        }),
+        cell(light.get_color(), |color| {
+            let rgb = RGB::from(color.clone());
+            format!("#{:02x}{:02x}{:02x}", rgb.red, rgb.green, rgb.blue)
+        }),
+        cell(light.get_brightness(), |brightness| {
+            format!("{:.0}%", **brightness * 100.0)
+        }),
+        cell(light.get_mode(), |mode| mode.name.clone()),
+    ]
+}
+
+// Columns are padded to the widest value, header included
+fn aligned<const N: usize>(header: [&str; N], rows: &[[String; N]]) -> String {
+    let mut widths = header.map(str::len);
+
+    for row in rows.iter() {
+        for (width, value) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+
+    let format = |values: &[&str]| {
+        values.iter()
+            .zip(widths.iter())
+            .map(|(value, width)| format!("{:<width$}", value, width = width))
+            .collect::<Vec<String>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut out = vec![format(&header)];
+
+    for row in rows.iter() {
+        out.push(format(&row.each_ref().map(String::as_str)));
+    }
+
+    out.join("\n")
+}
+
+pub fn table(lights: &[Light]) -> String {
+    const HEADER: [&str; 6] = ["ID", "NAME", "POWER", "COLOR", "BRIGHTNESS",
+                               "MODE"];
+
+    let rows: Vec<[String; 6]> = lights.iter().map(row).collect();
+
+    aligned(HEADER, &rows)
+}
+
+// Newest first, redo states above the undo ones, each with the number of
+// steps needed to get to it
+pub fn history_table(history: &History) -> String {
+    const HEADER: [&str; 6] = ["STEP", "REPLACED AT (UTC)", "POWER", "COLOR",
+                               "BRIGHTNESS", "MODE"];
+
+    let entry_row = |step: String, entry: &Entry| {
+        let [_, _, power, color, brightness, mode] = row(&entry.light);
+
+        [step, timestamp(entry.at), power, color, brightness, mode]
+    };
+
+    let redo = history.redo.iter().enumerate()
+        .map(|(index, entry)| {
+            entry_row(format!("redo {}", history.redo.len() - index), entry)
+        });
+    let undo = history.undo.iter().rev().enumerate()
+        .map(|(index, entry)| entry_row(format!("undo {}", index + 1), entry));
+    let rows: Vec<[String; 6]> = redo.chain(undo).collect();
+
+    aligned(HEADER, &rows)
+}
+
+pub fn lights(lights: &[Light], json: bool) {
+    if json {
+        print_json(&lights);
+    } else {
+        println!("{}", table(lights));
+    }
+}
+
+pub fn light(light: &Light, json: bool) {
+    if json {
+        print_json(light);
+    } else {
+        println!("{}", table(std::slice::from_ref(light)));
+    }
+}
+
+pub fn history(history: &History, json: bool) {
+    if json {
+        print_json(history);
+    } else if history.is_empty() {
+        println!("Nothing is recorded for {}", history.id);
+    } else {
+        println!("{}", history_table(history));
+    }
+}
+
+pub fn imported(imported: &Imported, json: bool) {
+    if json {
+        print_json(imported);
+    } else {
+        println!("Imported {} lights with {} revisions, {} scenes, {} groups \
+                  and {} histories", imported.lights, imported.revisions,
+                 imported.scenes, imported.groups, imported.histories);
+    }
+}
+
+pub fn migrated(moved: usize, skipped: usize, json: bool) {
+    if json {
+        print_json(&serde_json::json!({ "moved": moved, "skipped": skipped }));
+    } else {
+        println!("Moved {} lights, skipped {} files", moved, skipped);
+    }
+}
+
+pub fn revisions_table(revisions: &[Revision]) -> String {
+    const HEADER: [&str; 7] = ["REV", "SAVED AT (UTC)", "POWER", "COLOR",
+                               "BRIGHTNESS", "MODE", "MESSAGE"];
+
+    let rows: Vec<[String; 7]> = revisions.iter()
+        .map(|revision| {
+            let [_, _, power, color, brightness, mode] = row(&revision.light);
+
+            [revision.number.to_string(), timestamp(revision.at), power,
+             color, brightness, mode,
+             revision.message.clone().unwrap_or_default()]
+        })
+        .collect();
+
+    aligned(HEADER, &rows)
+}
+
+pub fn revisions(revisions: &[Revision], json: bool) {
+    if json {
+        print_json(revisions);
+    } else {
+        println!("{}", revisions_table(revisions));
+    }
+}
+
+// Changed parts one per line, nothing if revisions are the same
+pub fn patch(patch: &LightPatch, json: bool) {
+    if json {
+        print_json(patch);
+    } else if let Ok(serde_json::Value::Object(parts)) =
+        serde_json::to_value(patch) {
+        for (part, value) in parts {
+            println!("{}: {}", part, value);
+        }
+    }
+}
+
+pub fn scenes(scenes: &[Scene], json: bool) {
+    if json {
+        print_json(scenes);
+    } else {
+        for scene in scenes {
+            let ids: Vec<String> = scene.ids().iter()
+                .map(|id| id.to_string())
+                .collect();
+            println!("{}: {}", scene.name, ids.join(", "));
+        }
+    }
+}
+
+pub fn groups(groups: &[Group], json: bool) {
+    if json {
+        print_json(groups);
+    } else {
+        for group in groups {
+            let members: Vec<String> = group.members.iter()
+                .map(|member| member.to_string())
+                .collect();
+            println!("{}: {}", group.name, members.join(", "));
+        }
+    }
+}
+
+fn value(value: &Value) -> String {
+    match value {
+        Value::String(value) => format!("\"{}\"", value),
+        Value::Int(value) => value.to_string(),
+        Value::UInt(value) => value.to_string(),
+        Value::Float(value) => value.to_string(),
+        value => serde_json::to_string(value).unwrap_or_default(),
+    }
+}
+
+fn range<T: std::fmt::Display>(min: &Option<T>,
+                                max: &Option<T>) -> Option<String> {
+    match (min, max) {
+        (Some(min), Some(max)) => Some(format!("[{}, {}]", min, max)),
+        (Some(min), None) => Some(format!(">= {}", min)),
+        (None, Some(max)) => Some(format!("<= {}", max)),
+        (None, None) => None,
+    }
+}
+
+// Nested schemas are only shown in JSON output
+fn limits(schema: &ParameterSchema) -> Option<String> {
+    match schema {
+        ParameterSchema::String { values } if !values.is_empty() => {
+            Some(format!("({})", values.join(" | ")))
+        },
+        ParameterSchema::Int { min, max } => range(min, max),
+        ParameterSchema::UInt { min, max } => range(min, max),
+        ParameterSchema::Float { min, max } => range(min, max),
+        _ => None,
+    }
+}
+
+fn parameter(field: &Field) -> String {
+    let mut out = vec![field.name.clone(), field.schema.ptype().to_string()];
+
+    if let Some(limits) = limits(&field.schema) {
+        out.push(limits);
+    }
+
+    match &field.default {
+        Some(default) => out.push(format!("= {}", value(default))),
+        None if field.required => out.push("required".to_string()),
+        None => out.push("optional".to_string()),
+    }
+
+    if !field.doc.is_empty() {
+        out.push(format!("- {}", field.doc));
+    }
+
+    out.join(" ")
+}
+
+pub fn modes(modes: &[ModeDescriptor], json: bool) {
+    if json {
+        print_json(modes);
+    } else {
+        for mode in modes {
+            match mode.doc.as_str() {
+                "" => println!("{}@{}", mode.name, mode.provider),
+                doc => println!("{}@{}: {}", mode.name, mode.provider, doc),
+            }
+
+            for item in mode.fields() {
+                println!("    {}", parameter(item));
+            }
+        }
+    }
+}
+
+// Per light outcome of a change applied to several lights
+pub fn report<E: std::fmt::Display>(report: &[(ProviderID, Result<(), E>)],
+                                    json: bool) {
+    if json {
+        let out: Vec<serde_json::Value> = report.iter()
+            .map(|(id, result)| match result {
+                Ok(_) => serde_json::json!({
+                    "id": id.to_string(), "status": "ok"
+                }),
+                Err(err) => serde_json::json!({
+                    "id": id.to_string(), "status": "error",
+                    "error": err.to_string()
+                }),
+            })
+            .collect();
+        print_json(&out);
+    } else {
+        for (id, result) in report {
+            match result {
+                Ok(_) => println!("{}: ok", id),
+                Err(err) => println!("{}: {}", id, err),
+            }
+        }
+    }
+}
+
+// Goes to stderr, so JSON output stays parsable
+pub fn warnings<E: std::fmt::Display>(errors: &[(String, E)]) {
+    for (name, err) in errors {
+        eprintln!("warning: {}: {}", name, err);
+    }
+}
+
+// Errors naming the item themselves
+pub fn failures<E: std::fmt::Display>(errors: &[E]) {
+    for err in errors {
+        eprintln!("warning: {}", err);
+    }
+}
+
+pub fn done(json: bool) {
+    if json {
+        println!("{}", serde_json::json!({ "status": "ok" }));
+    }
+}
+
+pub fn print_json<T: Serialize + ?Sized>(value: &T) {
+    match serde_json::to_string_pretty(value) {
+        Ok(out) => println!("{}", out),
+        Err(err) => eprintln!("error: {}", err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::capabilities::Capability;
+    use domain::brightness::Brightness;
+
+    #[test]
+    fn aligned_table() {
+        let mut first = Light::named("mock".to_string(), "1".to_string(),
+                                     vec![Capability::Power,
+                                          Capability::Color,
+                                          Capability::Brightness],
+                                     "kitchen".to_string());
+        first.set_power(true).expect("Capable");
+        first.set_color(RGB::new(255, 0, 0).into()).expect("Capable");
+        first.set_brightness(Brightness::new(0.5)).expect("Capable");
+
+        let second = Light::new("mock".to_string(), "22".to_string(),
+                                vec![Capability::Brightness]);
+
+        assert_eq!(
+            table(&[first, second]),
+            "ID       NAME     POWER  COLOR    BRIGHTNESS  MODE\n\
+             1@mock   kitchen  on     #ff0000  50%\n\
+             22@mock                           -"
+        );
+    }
+
+    #[test]
+    fn history_steps() {
+        let mut light = Light::new("mock".to_string(), "1".to_string(),
+                                   vec![Capability::Power]);
+        light.set_power(true).expect("Capable");
+        let mut history = History::new(light.provider.clone());
+        history.record(Entry::new(60, light.clone()));
+        history.record(Entry::new(3_661, light.clone()));
+        history.undo(Entry::new(86_400, light)).expect("Recorded");
+
+        assert_eq!(
+            history_table(&history),
+            "STEP    REPLACED AT (UTC)    POWER  COLOR  BRIGHTNESS  MODE\n\
+             redo 1  1970-01-02 00:00:00  on\n\
+             undo 1  1970-01-01 00:01:00  on"
+        );
+    }
+
+    #[test]
+    fn parameter_line() {
+        let period = Field::new("period",
+                                ParameterSchema::uint(Some(100), Some(10000)),
+                                "Period in ms");
+        let action = Field::new("action",
+                                ParameterSchema::one_of(&["stay", "off"]), "")
+            .with_default(Value::String("stay".to_string()));
+
+        assert_eq!(parameter(&period),
+                   "period uint [100, 10000] required - Period in ms");
+        assert_eq!(parameter(&action),
+                   "action string (stay | off) = \"stay\"");
+    }
+}